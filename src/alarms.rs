@@ -0,0 +1,297 @@
+use crate::config::user_root;
+use crate::settings::{DefaultValue, Descriptor, Kind};
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf, sync::Mutex};
+
+// Alarms and countdown timers, per user, in `alarms.json` next to their
+// settings. The clock task asks `due` once a second; whatever it returns rings
+// (notification, optional chime) until it's dismissed or snoozed. Only the
+// signed-in user's alarms ring.
+
+const FILE: &str = "alarms.json";
+
+/// An alarm this much overdue (the app wasn't running) is skipped, not rung.
+const MISSED_AFTER: i64 = 30 * 60;
+
+/// Longest countdown timer, in seconds.
+pub const MAX_TIMER: i64 = 24 * 3600;
+
+/// Minutes a snoozed alarm or timer waits before ringing again.
+pub const SNOOZE_MINUTES: Descriptor = Descriptor {
+    section: "clock",
+    key: "snooze_minutes",
+    label: "Snooze (min)",
+    kind: Kind::Number { min: 1, max: 60 },
+    default: DefaultValue::Number(5),
+};
+
+/// Play a chime along with the notification.
+pub const SOUND: Descriptor = Descriptor {
+    section: "clock",
+    key: "sound",
+    label: "Alarm sound",
+    kind: Kind::Toggle,
+    default: DefaultValue::Toggle(true),
+};
+
+/// Clock settings for the registry in `settings.rs`.
+pub const SETTINGS: &[Descriptor] = &[SNOOZE_MINUTES, SOUND];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Alarm {
+    pub id: u64,
+    pub label: String,
+    pub hour: u32,
+    pub minute: u32,
+    /// Rings every day; otherwise it turns itself off after ringing.
+    pub daily: bool,
+    pub enabled: bool,
+    /// Unix seconds of the next ring (a snooze moves it).
+    pub next: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Timer {
+    pub id: u64,
+    pub label: String,
+    /// Unix seconds it ends.
+    pub ends: i64,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct Alarms {
+    #[serde(default)]
+    pub alarms: Vec<Alarm>,
+    #[serde(default)]
+    pub timers: Vec<Timer>,
+}
+
+/// Something that just went off.
+pub struct Ring {
+    pub timer: bool,
+    pub id: u64,
+    pub label: String,
+}
+
+/// The loaded user's alarms, so the clock task doesn't read the file every second.
+static LOADED: Mutex<Option<(String, Alarms)>> = Mutex::new(None);
+
+fn path(user: &str) -> io::Result<PathBuf> {
+    Ok(user_root(user)?.join(FILE))
+}
+
+fn read(user: &str) -> Alarms {
+    let Ok(text) = path(user).and_then(fs::read_to_string) else { return Alarms::default() };
+    serde_json::from_str(&text).unwrap_or_else(|e| {
+        eprintln!("Alarms for {user}: {e}; starting empty");
+        Alarms::default()
+    })
+}
+
+/// Run `f` on `user`'s alarms (loading them first if another user's are loaded).
+fn with<R>(user: &str, f: impl FnOnce(&mut Alarms) -> R) -> R {
+    let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+    if loaded.as_ref().is_none_or(|(u, _)| u != user) {
+        *loaded = Some((user.to_string(), read(user)));
+    }
+    let (_, alarms) = loaded.as_mut().expect("just loaded");
+    f(alarms)
+}
+
+/// Change `user`'s alarms and write them back.
+pub fn update<R>(user: &str, f: impl FnOnce(&mut Alarms) -> R) -> io::Result<R> {
+    with(user, |alarms| {
+        let out = f(alarms);
+        fs::write(path(user)?, serde_json::to_string_pretty(alarms)?)?;
+        Ok(out)
+    })
+}
+
+/// A copy of `user`'s alarms (soonest first) and timers (ending soonest first).
+pub fn list(user: &str) -> Alarms {
+    let mut all = with(user, |alarms| alarms.clone());
+    all.alarms.sort_by_key(|a| (a.hour, a.minute, a.id));
+    all.timers.sort_by_key(|t| (t.ends, t.id));
+    all
+}
+
+/// Forget the loaded copy (account renamed or deleted).
+pub fn forget() {
+    *LOADED.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+fn new_id() -> u64 {
+    chrono::Utc::now().timestamp_micros() as u64
+}
+
+/// Unix seconds of the first `hour:minute` after `after`, in local time.
+fn next_at(hour: u32, minute: u32, after: &DateTime<Local>) -> i64 {
+    let time = NaiveTime::from_hms_opt(hour, minute, 0).unwrap_or_default();
+    for day in 0..3 {
+        let date = after.date_naive() + Duration::days(day);
+        // a time skipped by a DST change rings an hour later
+        let local = Local
+            .from_local_datetime(&date.and_time(time))
+            .earliest()
+            .or_else(|| Local.from_local_datetime(&(date.and_time(time) + Duration::hours(1))).earliest());
+        if let Some(t) = local.filter(|t| t > after) {
+            return t.timestamp();
+        }
+    }
+    after.timestamp() + 86_400
+}
+
+pub fn add_alarm(user: &str, hour: u32, minute: u32, label: &str, daily: bool) -> io::Result<()> {
+    let next = next_at(hour, minute, &Local::now());
+    update(user, |a| {
+        a.alarms.push(Alarm { id: new_id(), label: label.trim().to_string(), hour, minute, daily, enabled: true, next })
+    })
+}
+
+/// Turn an alarm on (scheduled from now) or off.
+pub fn toggle_alarm(user: &str, id: u64) -> io::Result<()> {
+    let now = Local::now();
+    update(user, |a| {
+        if let Some(alarm) = a.alarms.iter_mut().find(|x| x.id == id) {
+            alarm.enabled = !alarm.enabled;
+            alarm.next = next_at(alarm.hour, alarm.minute, &now);
+        }
+    })
+}
+
+pub fn delete_alarm(user: &str, id: u64) -> io::Result<()> {
+    update(user, |a| a.alarms.retain(|x| x.id != id))
+}
+
+/// Start a timer of `seconds`, kept within 1s..`MAX_TIMER`.
+pub fn add_timer(user: &str, seconds: i64, label: &str) -> io::Result<()> {
+    let ends = Local::now().timestamp() + seconds.clamp(1, MAX_TIMER);
+    update(user, |a| a.timers.push(Timer { id: new_id(), label: label.trim().to_string(), ends }))
+}
+
+pub fn cancel_timer(user: &str, id: u64) -> io::Result<()> {
+    update(user, |a| a.timers.retain(|x| x.id != id))
+}
+
+/// Ring `ring` again in `minutes`: the alarm is moved, a timer is restarted.
+pub fn snooze(user: &str, ring: &Ring, minutes: i64) -> io::Result<()> {
+    let at = Local::now().timestamp() + minutes * 60;
+    update(user, |a| {
+        if ring.timer {
+            a.timers.push(Timer { id: ring.id, label: ring.label.clone(), ends: at });
+        } else if let Some(alarm) = a.alarms.iter_mut().find(|x| x.id == ring.id) {
+            alarm.enabled = true;
+            alarm.next = at;
+        }
+    })
+}
+
+/// `user`'s alarms and timers that ring at `now`. Rung alarms are moved to tomorrow (or turned
+/// off if they ring once), rung timers are removed; alarms missed while the app
+/// was closed are rescheduled without ringing, finished timers still ring.
+pub fn due(user: &str, now: &DateTime<Local>) -> Vec<Ring> {
+    let secs = now.timestamp();
+    let mut rings = Vec::new();
+    let changed = with(user, |a| {
+        let mut changed = false;
+        for alarm in a.alarms.iter_mut().filter(|x| x.enabled && x.next <= secs) {
+            if secs - alarm.next < MISSED_AFTER {
+                rings.push(Ring { timer: false, id: alarm.id, label: alarm.label.clone() });
+            }
+            alarm.enabled = alarm.daily;
+            alarm.next = next_at(alarm.hour, alarm.minute, now);
+            changed = true;
+        }
+        a.timers.retain(|t| {
+            if t.ends > secs {
+                return true;
+            }
+            rings.push(Ring { timer: true, id: t.id, label: t.label.clone() });
+            false
+        });
+        changed || rings.iter().any(|r| r.timer)
+    });
+    if changed {
+        if let Err(e) = update(user, |_| ()) {
+            eprintln!("Save alarms error: {e:?}");
+        }
+    }
+    rings
+}
+
+/// "7:30", "07:30", "7.30", "0730", "7:30 pm", "7pm" -> (hour, minute).
+pub fn parse_time(s: &str) -> Option<(u32, u32)> {
+    let s = s.trim().to_lowercase();
+    let (s, pm) = match s.strip_suffix("pm").or_else(|| s.strip_suffix("p")) {
+        Some(rest) => (rest.trim().to_string(), Some(true)),
+        None => match s.strip_suffix("am").or_else(|| s.strip_suffix("a")) {
+            Some(rest) => (rest.trim().to_string(), Some(false)),
+            None => (s.clone(), None),
+        },
+    };
+    let (h, m) = match s.split_once([':', '.', 'h']) {
+        Some((h, m)) => (h.trim().parse::<u32>().ok()?, if m.trim().is_empty() { 0 } else { m.trim().parse().ok()? }),
+        None if s.len() > 2 && s.chars().all(|c| c.is_ascii_digit()) => {
+            let (h, m) = s.split_at(s.len() - 2);
+            (h.parse().ok()?, m.parse().ok()?)
+        }
+        None => (s.parse().ok()?, 0),
+    };
+    let h = match pm {
+        Some(_) if !(1..=12).contains(&h) => return None,
+        Some(true) => h % 12 + 12,
+        Some(false) => h % 12,
+        None => h,
+    };
+    (h < 24 && m < 60).then_some((h, m))
+}
+
+/// Timer lengths: "10" (minutes), "90s", "1h30m", "25 min", "1:30" (m:ss), "1:00:00".
+/// `None` if it doesn't parse or doesn't fit in an `i64`; callers check `MAX_TIMER`.
+pub fn parse_duration(s: &str) -> Option<i64> {
+    let s = s.trim().to_lowercase();
+    if s.contains(':') {
+        let parts: Vec<i64> = s.split(':').map(|p| p.trim().parse().ok()).collect::<Option<_>>()?;
+        let secs = match parts[..] {
+            [m, sec] if (0..60).contains(&sec) => m.checked_mul(60)?.checked_add(sec)?,
+            [h, m, sec] if (0..60).contains(&m) && (0..60).contains(&sec) => h.checked_mul(3600)?.checked_add(m * 60 + sec)?,
+            _ => return None,
+        };
+        return (secs > 0).then_some(secs);
+    }
+    if let Ok(minutes) = s.parse::<i64>() {
+        return (minutes > 0).then_some(minutes.checked_mul(60)?);
+    }
+    let (mut total, mut number) = (0i64, String::new());
+    let mut rest = s.as_str();
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            rest = &rest[1..];
+            continue;
+        }
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let unit: String = rest.chars().take_while(|c| c.is_alphabetic()).collect();
+        rest = &rest[unit.len()..];
+        let scale = match unit.as_str() {
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60,
+            "s" | "sec" | "secs" | "second" | "seconds" => 1,
+            _ => return None,
+        };
+        total = total.checked_add(number.parse::<i64>().ok()?.checked_mul(scale)?)?;
+        number.clear();
+    }
+    (number.is_empty() && total > 0).then_some(total)
+}
+
+/// "4:59" or "1:02:03" left until `ends`.
+pub fn remaining(ends: i64, now: i64) -> String {
+    let left = (ends - now).max(0);
+    let (h, m, s) = (left / 3600, left / 60 % 60, left % 60);
+    if h > 0 { format!("{h}:{m:02}:{s:02}") } else { format!("{m}:{s:02}") }
+}
@@ -0,0 +1,160 @@
+use anyhow::Context;
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use std::{
+    fs::File,
+    io::{BufReader, Cursor},
+    path::Path,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+// Audio output shared by the alarm chime and the podcast player: the device is
+// opened on first use and kept for the rest of the run (the stream isn't
+// `Send`, so it lives on a thread of its own). One episode plays at a time.
+
+static OUTPUT: OnceLock<Option<OutputStreamHandle>> = OnceLock::new();
+
+/// The default output device, or `None` if there is none.
+pub fn output() -> Option<OutputStreamHandle> {
+    OUTPUT
+        .get_or_init(|| {
+            let (tx, rx) = std::sync::mpsc::channel();
+            let spawned = std::thread::Builder::new().name("audio".into()).spawn(move || match OutputStream::try_default() {
+                Ok((_stream, handle)) => {
+                    let _ = tx.send(Some(handle));
+                    // dropping the stream would silence every sink
+                    loop {
+                        std::thread::park();
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Audio output error: {e:?}");
+                    let _ = tx.send(None);
+                }
+            });
+            spawned.ok()?;
+            rx.recv().ok().flatten()
+        })
+        .clone()
+}
+
+struct Playing {
+    sink: Sink,
+    user: String,
+    episode: String,
+    title: String,
+    length: Duration,
+}
+
+static PLAYER: Mutex<Option<Playing>> = Mutex::new(None);
+
+fn with<R>(f: impl FnOnce(&mut Option<Playing>) -> R) -> R {
+    f(&mut PLAYER.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// What the player is doing.
+pub struct NowPlaying {
+    pub user: String,
+    pub episode: String,
+    pub title: String,
+    pub position: Duration,
+    /// Zero when neither the file nor the feed says.
+    pub length: Duration,
+    pub paused: bool,
+    /// Played to the end.
+    pub finished: bool,
+}
+
+/// Play `path` from `from`, replacing whatever was playing. `length` is the
+/// feed's duration, used when the file doesn't tell. An episode in an encrypted
+/// cache is decrypted into memory; plain ones play from the file.
+pub fn play(path: &Path, from: Duration, user: &str, episode: &str, title: &str, length: Duration) -> anyhow::Result<()> {
+    if crate::cachecrypt::enabled(user) {
+        let bytes = crate::cache::read_file_for(user, path).context("can't open this episode")?;
+        start(Decoder::new(Cursor::new(bytes)), from, user, episode, title, length)
+    } else {
+        start(Decoder::new(BufReader::new(File::open(path)?)), from, user, episode, title, length)
+    }
+}
+
+fn start<R>(
+    source: Result<Decoder<R>, rodio::decoder::DecoderError>,
+    from: Duration,
+    user: &str,
+    episode: &str,
+    title: &str,
+    length: Duration,
+) -> anyhow::Result<()>
+where
+    R: std::io::Read + std::io::Seek + Send + Sync + 'static,
+{
+    let output = output().context("no audio output")?;
+    let source = source.context("can't decode this episode")?;
+    let length = source.total_duration().unwrap_or(length);
+    let sink = Sink::try_new(&output)?;
+    sink.append(source);
+    if !from.is_zero() {
+        if let Err(e) = sink.try_seek(from) {
+            eprintln!("Seek error: {e:?}");
+        }
+    }
+    with(|p| {
+        *p = Some(Playing { sink, user: user.to_string(), episode: episode.to_string(), title: title.to_string(), length })
+    });
+    Ok(())
+}
+
+pub fn now_playing() -> Option<NowPlaying> {
+    with(|p| {
+        p.as_ref().map(|p| NowPlaying {
+            user: p.user.clone(),
+            episode: p.episode.clone(),
+            title: p.title.clone(),
+            position: p.sink.get_pos(),
+            length: p.length,
+            paused: p.sink.is_paused(),
+            finished: p.sink.empty(),
+        })
+    })
+}
+
+/// Pause or resume; whether it's playing now.
+pub fn toggle() -> bool {
+    with(|p| match p {
+        Some(p) if p.sink.is_paused() => {
+            p.sink.play();
+            true
+        }
+        Some(p) => {
+            p.sink.pause();
+            false
+        }
+        None => false,
+    })
+}
+
+/// Jump `secs` forward (or back, when negative).
+pub fn seek_by(secs: i64) {
+    with(|p| {
+        let Some(p) = p else { return };
+        let pos = p.sink.get_pos().as_secs() as i64 + secs;
+        let mut to = Duration::from_secs(pos.max(0) as u64);
+        if !p.length.is_zero() {
+            to = to.min(p.length.saturating_sub(Duration::from_secs(1)));
+        }
+        if let Err(e) = p.sink.try_seek(to) {
+            eprintln!("Seek error: {e:?}");
+        }
+    })
+}
+
+/// Stop playing; what was playing, to save where it got to.
+pub fn stop() -> Option<NowPlaying> {
+    let now = now_playing();
+    with(|p| {
+        if let Some(p) = p.take() {
+            p.sink.stop();
+        }
+    });
+    now
+}
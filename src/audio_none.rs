@@ -0,0 +1,37 @@
+use std::{path::Path, time::Duration};
+
+// Stand-in for `audio.rs` in builds without the `sound` feature: nothing ever
+// plays, and Play says why.
+
+/// What the player is doing (never built: nothing plays).
+#[allow(dead_code)]
+pub struct NowPlaying {
+    pub user: String,
+    pub episode: String,
+    pub title: String,
+    pub position: Duration,
+    /// Zero when neither the file nor the feed says.
+    pub length: Duration,
+    pub paused: bool,
+    /// Played to the end.
+    pub finished: bool,
+}
+
+pub fn play(_path: &Path, _from: Duration, _user: &str, _episode: &str, _title: &str, _length: Duration) -> anyhow::Result<()> {
+    anyhow::bail!("built without sound support")
+}
+
+pub fn now_playing() -> Option<NowPlaying> {
+    None
+}
+
+/// Pause or resume; whether it's playing now.
+pub fn toggle() -> bool {
+    false
+}
+
+pub fn seek_by(_secs: i64) {}
+
+pub fn stop() -> Option<NowPlaying> {
+    None
+}
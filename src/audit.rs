@@ -0,0 +1,96 @@
+use crate::config::base_dir;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+/// Once audit.log grows past this, only the newest half of the lines is kept.
+const MAX_AUDIT_BYTES: u64 = 256 * 1024;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+    Login,
+    LoginFailed,
+    Logout,
+    Locked,
+    Register,
+    PinChanged,
+    PinReset,
+    RecoveryCodes,
+    Renamed,
+    Deleted,
+    StorageChanged,
+}
+
+impl AuditEvent {
+    pub fn label(self) -> &'static str {
+        match self {
+            AuditEvent::Login => "Signed in",
+            AuditEvent::LoginFailed => "Failed sign-in",
+            AuditEvent::Logout => "Signed out",
+            AuditEvent::Locked => "Locked",
+            AuditEvent::Register => "Account created",
+            AuditEvent::PinChanged => "PIN changed",
+            AuditEvent::PinReset => "PIN reset with recovery code",
+            AuditEvent::RecoveryCodes => "New recovery codes",
+            AuditEvent::Renamed => "Account renamed",
+            AuditEvent::Deleted => "Account deleted",
+            AuditEvent::StorageChanged => "Storage changed",
+        }
+    }
+}
+
+/// One line of audit.log (JSON lines, oldest first).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditEntry {
+    pub ts: i64,
+    pub event: AuditEvent,
+    pub user: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub detail: String,
+}
+
+fn audit_path() -> io::Result<PathBuf> {
+    Ok(base_dir()?.join("audit.log"))
+}
+
+fn append(entry: &AuditEntry) -> io::Result<()> {
+    let path = audit_path()?;
+    if fs::metadata(&path).map(|m| m.len() > MAX_AUDIT_BYTES).unwrap_or(false) {
+        let data = fs::read_to_string(&path)?;
+        let lines: Vec<&str> = data.lines().collect();
+        let keep = lines[lines.len() / 2..].join("\n");
+        fs::write(&path, keep + "\n")?;
+    }
+    let mut f = fs::OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(f, "{}", serde_json::to_string(entry)?)
+}
+
+/// Append an event to the install's audit log. Failures are only logged: auditing
+/// must never block a sign-in.
+pub fn record(event: AuditEvent, user: &str, detail: &str) {
+    let entry = AuditEntry {
+        ts: chrono::Utc::now().timestamp(),
+        event,
+        user: user.to_string(),
+        detail: detail.to_string(),
+    };
+    if let Err(e) = append(&entry) {
+        eprintln!("Audit log error: {e:?}");
+    }
+}
+
+/// The newest `n` entries, newest first.
+pub fn recent(n: usize) -> Vec<AuditEntry> {
+    let Ok(data) = audit_path().and_then(fs::read_to_string) else {
+        return Vec::new();
+    };
+    data.lines()
+        .rev()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .take(n)
+        .collect()
+}
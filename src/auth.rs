@@ -2,9 +2,27 @@ use argon2::{
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
-use rand::rngs::OsRng;
+use lazy_static::lazy_static;
+use rand::{rngs::OsRng, Rng};
+use crate::{cache, config};
+use crate::secrets::{pin_key, KeyringStore, SecretBackend, SecretStore};
+use crate::userstore::{JsonUserStore, SqliteUserStore, UserStore};
 use serde::{Deserialize, Serialize};
-use std::{fs, io, path::PathBuf};
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+use zeroize::Zeroizing;
+
+/// Letters, digits, `.`, `-` and `_`, at most 32 characters, and not "guest":
+/// the name is used as the user's folder name.
+pub fn valid_username(name: &str) -> bool {
+    name != "guest"
+        && name.chars().count() <= 32
+        && name.chars().all(|c| c.is_alphanumeric() || c == '.' || c == '-' || c == '_')
+        && matches!(Path::new(name).components().collect::<Vec<_>>()[..], [Component::Normal(_)])
+}
 
 #[derive(Debug)]
 pub enum AuthError {
@@ -14,6 +32,31 @@ pub enum AuthError {
     AlreadyExists,
     InvalidPin,
     NoConfigDir,
+    /// New PIN is shorter than the policy minimum (carries the minimum).
+    PinTooShort(usize),
+    /// Policy asks for digits only.
+    PinNotDigits,
+    /// New PIN is on the banned list or a trivial pattern (1111, 1234, 4321…).
+    PinTooCommon,
+    /// The OS credential store failed (locked, missing service, …).
+    Secret(String),
+    Db(rusqlite::Error),
+    /// Recovery code unknown or already used.
+    InvalidRecoveryCode,
+    /// Not a usable username (`valid_username`).
+    InvalidUsername,
+    /// Unknown user or wrong PIN, deliberately not told apart (`unified_errors`).
+    WrongCredentials,
+    /// Only an admin account may do this.
+    NotAllowed,
+    /// The active provider has no such operation (e.g. storage settings on a remote backend).
+    Unsupported,
+    /// The blocking task running the call panicked or was cancelled.
+    Task(String),
+    /// The encrypted cache's key couldn't be resealed (`cachecrypt`).
+    CacheKey(String),
+    /// Too many wrong quick PINs in a row: it was turned off, the password is needed.
+    QuickPinDisabled,
 }
 
 impl std::fmt::Display for AuthError {
@@ -25,6 +68,19 @@ impl std::fmt::Display for AuthError {
             AuthError::AlreadyExists => write!(f, "User already exists"),
             AuthError::InvalidPin => write!(f, "Invalid PIN"),
             AuthError::NoConfigDir => write!(f, "No config dir"),
+            AuthError::PinTooShort(min) => write!(f, "PIN too short (at least {} characters)", min),
+            AuthError::PinNotDigits => write!(f, "PIN must be digits only"),
+            AuthError::PinTooCommon => write!(f, "PIN too common"),
+            AuthError::Secret(e) => write!(f, "Credential store error: {}", e),
+            AuthError::Db(e) => write!(f, "Database error: {}", e),
+            AuthError::InvalidRecoveryCode => write!(f, "Invalid or used recovery code"),
+            AuthError::InvalidUsername => write!(f, "Usernames are letters, digits, '.', '-' and '_', at most 32"),
+            AuthError::WrongCredentials => write!(f, "Wrong user or PIN"),
+            AuthError::NotAllowed => write!(f, "Only an admin can do that"),
+            AuthError::Unsupported => write!(f, "Not supported by this sign-in backend"),
+            AuthError::Task(e) => write!(f, "Background task failed: {}", e),
+            AuthError::CacheKey(e) => write!(f, "Cache key error: {}", e),
+            AuthError::QuickPinDisabled => write!(f, "Too many wrong quick PINs; unlock with your password"),
         }
     }
 }
@@ -34,6 +90,7 @@ impl std::error::Error for AuthError {
         match self {
             AuthError::Io(e) => Some(e),
             AuthError::Serde(e) => Some(e),
+            AuthError::Db(e) => Some(e),
             _ => None,
         }
     }
@@ -42,17 +99,296 @@ impl std::error::Error for AuthError {
 
 impl From<io::Error> for AuthError { fn from(e: io::Error) -> Self { AuthError::Io(e) } }
 impl From<serde_json::Error> for AuthError { fn from(e: serde_json::Error) -> Self { AuthError::Serde(e) } }
+impl From<rusqlite::Error> for AuthError { fn from(e: rusqlite::Error) -> Self { AuthError::Db(e) } }
+impl From<anyhow::Error> for AuthError { fn from(e: anyhow::Error) -> Self { AuthError::Secret(e.to_string()) } }
 
 #[derive(Serialize, Deserialize, Clone)]
-struct UserRecord {
-    username: String,
-    pin_phc: String,        // Argon2 PHC string (includes salt + params)
-    created_at: String,     // ISO8601
+pub(crate) struct UserRecord {
+    pub(crate) username: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub(crate) pin_phc: String,        // Argon2 PHC string (includes salt + params); empty when kept in the keyring
+    pub(crate) created_at: String,     // ISO8601
+    /// Argon2 hashes of the unused recovery codes; each one is removed once redeemed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) recovery_phc: Vec<String>,
+    /// ISO8601 time of the last successful sign-in (empty until the first one).
+    #[serde(default)]
+    pub(crate) last_login: String,
+    /// Optional numeric quick-unlock PIN (Argon2 PHC); only opens the lock screen.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub(crate) quick_pin_phc: String,
+    /// Wrong quick PINs since the last good unlock (`QUICK_PIN_MAX_FAILURES`).
+    #[serde(default)]
+    pub(crate) quick_pin_failures: u32,
+    #[serde(default)]
+    pub(crate) role: Role,
+    /// What a `Role::Child` may use; ignored for other roles.
+    #[serde(default)]
+    pub(crate) restrictions: Restrictions,
+}
+
+/// Account type. The first account on an install becomes the admin, who manages
+/// child profiles.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    Standard,
+    Admin,
+    Child,
+}
+
+/// Limits for child profiles (set by the admin, not editable by the child).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct Restrictions {
+    /// News page visible (always with safe-search filtering).
+    pub allow_news: bool,
+    /// Stories, discussions and share links may open the browser / mail client.
+    pub allow_links: bool,
+}
+
+impl Default for Restrictions {
+    fn default() -> Self {
+        Self { allow_news: true, allow_links: false }
+    }
+}
+
+/// What a signed-out ("guest") session may do: `guest_profile.json` next to
+/// users.json, set by the admin like any child profile. Without one, guests get
+/// a child's default limits once the install has a child, so a child can't
+/// lift theirs by signing out.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+struct GuestProfile {
+    role: Role,
+    restrictions: Restrictions,
+}
+
+impl Default for GuestProfile {
+    fn default() -> Self {
+        Self { role: Role::Child, restrictions: Restrictions::default() }
+    }
+}
+
+/// PIN hash of `rec`, from the record itself or the OS keyring.
+fn read_pin_phc(secrets: SecretBackend, rec: &UserRecord) -> Result<String, AuthError> {
+    match secrets {
+        SecretBackend::File => Ok(rec.pin_phc.clone()),
+        SecretBackend::Keyring => match KeyringStore.get(&pin_key(&rec.username))? {
+            Some(phc) => Ok(phc),
+            None => {
+                // a missing entry costs the same hashing time as a wrong PIN
+                let _ = verify_hash("", &DUMMY_PHC);
+                Err(AuthError::InvalidPin)
+            }
+        },
+    }
+}
+
+/// Store `phc` for `rec` in `secrets`.
+fn write_pin_phc(secrets: SecretBackend, rec: &mut UserRecord, phc: String) -> Result<(), AuthError> {
+    match secrets {
+        SecretBackend::File => rec.pin_phc = phc,
+        SecretBackend::Keyring => {
+            KeyringStore.set(&pin_key(&rec.username), &phc)?;
+            rec.pin_phc.clear();
+        }
+    }
+    Ok(())
+}
+
+/// Which file holds the user records.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UserStorage {
+    Json,
+    Sqlite,
+}
+
+/// Frequently used PINs / passphrases that are refused for new PINs.
+const COMMON_PINS: [&str; 24] = [
+    "0000", "1111", "1212", "1234", "1004", "2000", "2580", "4321", "6969", "1122", "1313", "0852",
+    "12345", "123456", "654321", "121212", "112233", "123123", "696969", "000000", "password",
+    "qwerty", "letmein", "iloveyou",
+];
+
+/// PIN rules for new PINs (registration and PIN change), read from
+/// `pin_policy.json` next to users.json; missing fields use the defaults.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PinPolicy {
+    pub min_len: usize,
+    /// true = numeric PIN only, false = any passphrase.
+    pub digits_only: bool,
+    /// Refuse `COMMON_PINS` and repeated / sequential digit runs.
+    pub ban_common: bool,
+    /// Answer sign-ins with one "wrong user or PIN" error, so the messages don't
+    /// reveal which usernames exist.
+    pub unified_errors: bool,
+}
+
+impl Default for PinPolicy {
+    fn default() -> Self {
+        Self { min_len: 4, digits_only: false, ban_common: true, unified_errors: false }
+    }
+}
+
+impl PinPolicy {
+    pub fn check(&self, pin: &str) -> Result<(), AuthError> {
+        if pin.chars().count() < self.min_len {
+            return Err(AuthError::PinTooShort(self.min_len));
+        }
+        if self.digits_only && !pin.chars().all(|c| c.is_ascii_digit()) {
+            return Err(AuthError::PinNotDigits);
+        }
+        if self.ban_common && is_common_pin(pin) {
+            return Err(AuthError::PinTooCommon);
+        }
+        Ok(())
+    }
 }
 
-#[derive(Serialize, Deserialize, Default)]
-struct UsersFile {
-    users: Vec<UserRecord>,
+/// On the banned list, one repeated digit, or a straight run up or down (e.g. 3456, 9876).
+fn is_common_pin(pin: &str) -> bool {
+    let lower = pin.to_lowercase();
+    if COMMON_PINS.contains(&lower.as_str()) {
+        return true;
+    }
+    let digits: Vec<i32> = pin.chars().filter_map(|c| c.to_digit(10)).map(|d| d as i32).collect();
+    if digits.len() != pin.chars().count() || digits.len() < 2 {
+        return false;
+    }
+    [0, 1, -1].iter().any(|step| digits.windows(2).all(|w| w[1] - w[0] == *step))
+}
+
+/// Quick-unlock PINs: digits only, at least this long.
+const QUICK_PIN_MIN: usize = 4;
+/// A quick PIN is turned off after this many wrong tries in a row; a short
+/// numeric PIN is easy to guess otherwise.
+const QUICK_PIN_MAX_FAILURES: u32 = 5;
+
+/// zxcvbn score (0 = very weak … 4 = very strong) and a label for the meter;
+/// the username counts as a known word.
+pub fn password_strength(password: &str, username: &str) -> (u8, &'static str) {
+    let score = zxcvbn::zxcvbn(password, &[username]).map(|e| e.score()).unwrap_or(0);
+    let label = match score {
+        0 => "Very weak",
+        1 => "Weak",
+        2 => "Fair",
+        3 => "Strong",
+        _ => "Very strong",
+    };
+    (score, label)
+}
+
+/// Recovery codes handed out per account.
+const RECOVERY_CODES: usize = 8;
+/// No 0/O or 1/I, so codes survive being copied by hand.
+const RECOVERY_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Random code shaped `XXXX-XXXX`.
+fn new_recovery_code() -> String {
+    let mut rng = OsRng;
+    let mut code = String::with_capacity(9);
+    for i in 0..8 {
+        if i == 4 {
+            code.push('-');
+        }
+        code.push(RECOVERY_ALPHABET[rng.gen_range(0..RECOVERY_ALPHABET.len())] as char);
+    }
+    code
+}
+
+/// The hash in `phcs` that `code` matches. Always runs `RECOVERY_CODES` Argon2
+/// checks (the dummy hash standing in for used-up codes), so response times
+/// don't reveal how many codes are left, which one matched or whether the
+/// account exists.
+fn find_recovery_code(code: &str, phcs: &[String]) -> Option<String> {
+    let mut found = None;
+    for i in 0..RECOVERY_CODES {
+        match phcs.get(i) {
+            Some(phc) => {
+                if verify_hash(code, phc) && found.is_none() {
+                    found = Some(phc.clone());
+                }
+            }
+            None => {
+                let _ = verify_hash(code, &DUMMY_PHC);
+            }
+        }
+    }
+    found
+}
+
+/// What gets hashed: dashes/spaces dropped and upper-cased, so `abcd efgh` matches `ABCD-EFGH`.
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_uppercase()).collect()
+}
+
+fn verify_hash(secret: &str, phc: &str) -> bool {
+    PasswordHash::new(phc)
+        .map(|parsed| Argon2::default().verify_password(secret.as_bytes(), &parsed).is_ok())
+        .unwrap_or(false)
+}
+
+lazy_static! {
+    /// Hash checked for unknown users, so a miss costs the same Argon2 run as a
+    /// wrong PIN and response times don't reveal which accounts exist.
+    static ref DUMMY_PHC: String = hash_pin("not-a-real-pin").unwrap_or_default();
+}
+
+/// Argon2 PHC string for `pin` with a freshly generated salt.
+fn hash_pin(pin: &str) -> Result<String, AuthError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default()
+        .hash_password(pin.as_bytes(), &salt)
+        .map_err(|_| AuthError::InvalidPin)?
+        .to_string())
+}
+
+/// An account backend: sign-in, PIN management and account profiles.
+/// `LocalAuth` (users.json / users.db on this computer) is the only one today;
+/// an OS account check or a remote server can implement this later.
+pub trait AuthProvider: Send + Sync {
+    fn register_user(&self, username: &str, pin: &str) -> Result<(), AuthError>;
+    /// Sign-in check; on success the user's `last_login` is bumped.
+    fn verify_login(&self, username: &str, pin: &str) -> Result<(), AuthError>;
+    /// Lock-screen unlock: the password, or the quick-unlock PIN if one is set.
+    /// Too many wrong quick PINs turn it off (`QuickPinDisabled`).
+    fn unlock(&self, username: &str, secret: &str) -> Result<(), AuthError>;
+    /// Set (or with an empty `quick_pin`, remove) the numeric quick-unlock PIN;
+    /// the account password confirms the change.
+    fn set_quick_pin(&self, username: &str, password: &str, quick_pin: &str) -> Result<(), AuthError>;
+    /// Re-confirm the current PIN without counting it as a sign-in.
+    fn confirm_pin(&self, username: &str, pin: &str) -> Result<(), AuthError>;
+    /// Replace a user's PIN after checking the current one; the new hash gets a fresh salt.
+    fn change_pin(&self, username: &str, old_pin: &str, new_pin: &str) -> Result<(), AuthError>;
+    /// Issue a fresh set of one-time recovery codes (replacing any old ones) and
+    /// return them in plain text; only their hashes are stored.
+    fn generate_recovery_codes(&self, username: &str) -> Result<Vec<String>, AuthError>;
+    /// Set a new PIN using a recovery code instead of the forgotten one. The code is
+    /// spent; returns how many codes are left.
+    fn reset_pin_with_recovery(&self, username: &str, code: &str, new_pin: &str) -> Result<usize, AuthError>;
+    /// Rename an account: its record (and keyring entry) plus the config and cache
+    /// folders, so settings and cached data follow the new name.
+    fn rename_user(&self, old: &str, new: &str) -> Result<(), AuthError>;
+    /// Role and child restrictions of `username`.
+    fn profile(&self, username: &str) -> Result<(Role, Restrictions), AuthError>;
+    fn has_admin(&self) -> Result<bool, AuthError>;
+    /// Change `target`'s role and restrictions on behalf of `by`, who must be an
+    /// admin. Installs from before roles existed have none: then a user may
+    /// claim admin for themselves. `target` "guest" sets what signed-out
+    /// sessions may do.
+    fn set_profile(&self, by: &str, target: &str, role: Role, restrictions: Restrictions) -> Result<(), AuthError>;
+    fn list_users(&self) -> Result<Vec<String>, AuthError>;
+    /// (username, last sign-in as ISO8601 or "" if never) for every account.
+    fn list_last_logins(&self) -> Result<Vec<(String, String)>, AuthError>;
+    fn delete_user(&self, username: &str) -> Result<(), AuthError>;
+    /// The on-disk store behind this provider, for storage settings and account
+    /// bundles; `None` for backends that keep no local records.
+    fn local(&self) -> Option<&LocalAuth> {
+        None
+    }
 }
 
 pub struct LocalAuth {
@@ -61,76 +397,462 @@ pub struct LocalAuth {
 
 impl LocalAuth {
     pub fn new() -> Result<Self, AuthError> {
-        use std::env;
-        let home = env::var("HOME").map(PathBuf::from).map_err(|_| AuthError::NoConfigDir)?;
-        let dir = home.join("tock-workshop").join("slint_rust");
-        fs::create_dir_all(&dir)?;
+        let dir = config::base_dir().map_err(|_| AuthError::NoConfigDir)?;
         Ok(Self { path: dir.join("users.json") })
     }
 
 
-    fn load(&self) -> Result<UsersFile, AuthError> {
-        if !self.path.exists() {
-            return Ok(UsersFile::default());
+    fn db_path(&self) -> PathBuf {
+        self.path.with_extension("db")
+    }
+
+    /// users.db (SQLite) once it exists, users.json otherwise.
+    fn store(&self) -> Box<dyn UserStore> {
+        match self.storage() {
+            UserStorage::Sqlite => Box::new(SqliteUserStore::new(self.db_path())),
+            UserStorage::Json => Box::new(JsonUserStore::new(self.path.clone())),
         }
-        let data = fs::read_to_string(&self.path)?;
-        Ok(serde_json::from_str(&data)?)
     }
 
-    fn save(&self, uf: &UsersFile) -> Result<(), AuthError> {
-        let data = serde_json::to_string_pretty(uf)?;
-        fs::write(&self.path, data)?;
+    pub fn storage(&self) -> UserStorage {
+        if self.db_path().exists() { UserStorage::Sqlite } else { UserStorage::Json }
+    }
+
+    /// Copy every record into the other store, then retire the old file as `*.bak`.
+    pub fn set_storage(&self, to: UserStorage) -> Result<(), AuthError> {
+        if self.storage() == to {
+            return Ok(());
+        }
+        let from = self.store();
+        let (secrets, users) = (from.secret_backend()?, from.list()?);
+        match to {
+            UserStorage::Sqlite => {
+                // build the database aside and move it in only once it is complete
+                let tmp = self.path.with_extension("db.tmp");
+                let _ = fs::remove_file(&tmp);
+                SqliteUserStore::new(tmp.clone()).replace_all(secrets, &users)?;
+                fs::rename(&tmp, self.db_path())?;
+                if self.path.exists() {
+                    fs::rename(&self.path, self.path.with_extension("json.bak"))?;
+                }
+            }
+            UserStorage::Json => {
+                JsonUserStore::new(self.path.clone()).replace_all(secrets, &users)?;
+                fs::rename(self.db_path(), self.path.with_extension("db.bak"))?;
+            }
+        }
         Ok(())
     }
 
+    /// The install's PIN policy (`pin_policy.json`); defaults if missing or unreadable.
+    pub fn policy(&self) -> PinPolicy {
+        fs::read_to_string(self.path.with_file_name("pin_policy.json"))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn guest_profile_path(&self) -> PathBuf {
+        self.path.with_file_name("guest_profile.json")
+    }
+
+    /// The guest profile the admin set, or the default: restricted once there
+    /// is a child account, unrestricted before.
+    fn guest_profile(&self) -> Result<(Role, Restrictions), AuthError> {
+        if let Some(g) = fs::read_to_string(self.guest_profile_path())
+            .ok()
+            .and_then(|s| serde_json::from_str::<GuestProfile>(&s).ok())
+        {
+            return Ok((g.role, g.restrictions));
+        }
+        let g = GuestProfile::default();
+        let has_child = self.store().list()?.iter().any(|u| u.role == Role::Child);
+        Ok(if has_child { (g.role, g.restrictions) } else { (Role::Standard, g.restrictions) })
+    }
+
+    /// Run `f` on every record under the store's write lock and save the
+    /// result, so concurrent read-modify-write cycles can't lose each other's
+    /// changes. Hash PINs before calling: the lock is held while `f` runs.
+    fn with_users<T>(&self, f: impl FnOnce(&mut Vec<UserRecord>) -> Result<T, AuthError>) -> Result<T, AuthError> {
+        let (mut f, mut out) = (Some(f), None);
+        self.store().update_with(&mut |users| {
+            out = Some(f.take().expect("update_with calls f once")(users)?);
+            Ok(())
+        })?;
+        Ok(out.expect("update_with called f"))
+    }
+
+    /// `with_users` on the one record named `username`.
+    fn with_user<T>(&self, username: &str, f: impl FnOnce(&mut UserRecord) -> Result<T, AuthError>) -> Result<T, AuthError> {
+        self.with_users(|users| f(users.iter_mut().find(|u| u.username == username).ok_or(AuthError::NotFound)?))
+    }
+
     #[allow(dead_code)]
     pub fn has_any_user(&self) -> Result<bool, AuthError> {
-        Ok(!self.load()?.users.is_empty())
+        Ok(!self.store().list()?.is_empty())
+    }
+
+    /// PIN check alone (re-confirming the PIN for a settings change is not a sign-in).
+    fn check_pin(&self, username: &str, pin: &str) -> Result<UserRecord, AuthError> {
+        let store = self.store();
+        let Some(rec) = store.get(username)? else {
+            // burn the same hashing time as a real check
+            let _ = verify_hash(pin, &DUMMY_PHC);
+            return Err(AuthError::NotFound);
+        };
+        let phc = read_pin_phc(store.secret_backend()?, &rec)?;
+        if !verify_hash(pin, &phc) {
+            return Err(AuthError::InvalidPin);
+        }
+        Ok(rec)
+    }
+
+    pub fn secret_backend(&self) -> Result<SecretBackend, AuthError> {
+        self.store().secret_backend()
+    }
+
+    /// Move every PIN hash to `to`. The new copies are written and the store saved
+    /// before the old keyring entries are removed, so a failure never loses a hash.
+    pub fn set_secret_backend(&self, to: SecretBackend) -> Result<(), AuthError> {
+        let store = self.store();
+        let from = store.secret_backend()?;
+        if from == to {
+            return Ok(());
+        }
+        let mut users = store.list()?;
+        for rec in users.iter_mut() {
+            let phc = read_pin_phc(from, rec)?;
+            write_pin_phc(to, rec, phc)?;
+        }
+        store.replace_all(to, &users)?;
+
+        if from == SecretBackend::Keyring {
+            for u in &users {
+                if let Err(e) = KeyringStore.delete(&pin_key(&u.username)) {
+                    eprintln!("Failed to remove keyring entry for {}: {:?}", u.username, e);
+                }
+            }
+        }
+        Ok(())
     }
 
-    pub fn register_user(&self, username: &str, pin: &str) -> Result<(), AuthError> {
-        let mut uf = self.load()?;
-        if uf.users.iter().any(|u| u.username == username) {
+    /// A user's record with the PIN hash filled in, wherever it is kept (for export).
+    pub(crate) fn export_record(&self, username: &str) -> Result<UserRecord, AuthError> {
+        let store = self.store();
+        let mut rec = store.get(username)?.ok_or(AuthError::NotFound)?;
+        rec.pin_phc = read_pin_phc(store.secret_backend()?, &rec)?;
+        Ok(rec)
+    }
+
+    /// Add an exported record, storing its PIN hash in this install's backend.
+    pub(crate) fn import_record(&self, mut rec: UserRecord) -> Result<(), AuthError> {
+        let secrets = self.store().secret_backend()?;
+        self.with_users(|users| {
+            if users.iter().any(|u| u.username == rec.username) {
+                return Err(AuthError::AlreadyExists);
+            }
+            if rec.role == Role::Admin && users.iter().any(|u| u.role == Role::Admin) {
+                rec.role = Role::Standard; // this install already has its admin
+            }
+            let phc = std::mem::take(&mut rec.pin_phc);
+            write_pin_phc(secrets, &mut rec, phc)?;
+            users.push(rec);
+            Ok(())
+        })
+    }
+}
+
+impl AuthProvider for LocalAuth {
+    fn register_user(&self, username: &str, pin: &str) -> Result<(), AuthError> {
+        if !valid_username(username) {
+            return Err(AuthError::InvalidUsername);
+        }
+        let store = self.store();
+        if store.get(username)?.is_some() {
             return Err(AuthError::AlreadyExists);
         }
-        let salt = SaltString::generate(&mut OsRng);
-        let argon = Argon2::default();
-        let pin_phc = argon
-            .hash_password(pin.as_bytes(), &salt)
-            .map_err(|_| AuthError::InvalidPin)?
-            .to_string();
-
-        let rec = UserRecord {
-            username: username.to_string(),
-            pin_phc,
-            created_at: chrono::Utc::now().to_rfc3339(),
+        self.policy().check(pin)?;
+        let (secrets, phc) = (store.secret_backend()?, hash_pin(pin)?);
+        self.with_users(|users| {
+            if users.iter().any(|u| u.username == username) {
+                return Err(AuthError::AlreadyExists);
+            }
+            let mut rec = UserRecord {
+                username: username.to_string(),
+                pin_phc: String::new(),
+                created_at: chrono::Utc::now().to_rfc3339(),
+                recovery_phc: Vec::new(),
+                last_login: String::new(),
+                quick_pin_phc: String::new(),
+                quick_pin_failures: 0,
+                // the very first account administers the install
+                role: if users.is_empty() { Role::Admin } else { Role::Standard },
+                restrictions: Restrictions::default(),
+            };
+            write_pin_phc(secrets, &mut rec, phc)?;
+            users.push(rec);
+            Ok(())
+        })
+    }
+
+    fn verify_login(&self, username: &str, pin: &str) -> Result<(), AuthError> {
+        match self.check_pin(username, pin) {
+            Err(AuthError::NotFound | AuthError::InvalidPin) if self.policy().unified_errors => {
+                return Err(AuthError::WrongCredentials);
+            }
+            res => res?,
+        };
+        self.with_user(username, |rec| {
+            rec.last_login = chrono::Utc::now().to_rfc3339();
+            Ok(())
+        })
+    }
+
+    fn unlock(&self, username: &str, secret: &str) -> Result<(), AuthError> {
+        match self.check_pin(username, secret) {
+            Err(AuthError::InvalidPin) => {
+                let rec = self.store().get(username)?.ok_or(AuthError::NotFound)?;
+                if rec.quick_pin_phc.is_empty() {
+                    return Err(AuthError::InvalidPin);
+                }
+                let good = verify_hash(secret, &rec.quick_pin_phc);
+                if good && rec.quick_pin_failures == 0 {
+                    return Ok(());
+                }
+                let disabled = self.with_user(username, |r| {
+                    if r.quick_pin_phc != rec.quick_pin_phc {
+                        // changed in the meantime: this try was against the old one
+                        return Ok(false);
+                    }
+                    r.quick_pin_failures = if good { 0 } else { r.quick_pin_failures + 1 };
+                    if r.quick_pin_failures < QUICK_PIN_MAX_FAILURES {
+                        return Ok(false);
+                    }
+                    r.quick_pin_phc.clear();
+                    r.quick_pin_failures = 0;
+                    Ok(true)
+                })?;
+                if disabled {
+                    Err(AuthError::QuickPinDisabled)
+                } else if good {
+                    Ok(())
+                } else {
+                    Err(AuthError::InvalidPin)
+                }
+            }
+            Ok(rec) if rec.quick_pin_failures > 0 => self.with_user(username, |r| {
+                r.quick_pin_failures = 0;
+                Ok(())
+            }),
+            res => res.map(|_| ()),
+        }
+    }
+
+    fn set_quick_pin(&self, username: &str, password: &str, quick_pin: &str) -> Result<(), AuthError> {
+        self.check_pin(username, password)?;
+        let phc = if quick_pin.is_empty() {
+            String::new()
+        } else {
+            if !quick_pin.chars().all(|c| c.is_ascii_digit()) {
+                return Err(AuthError::PinNotDigits);
+            }
+            if quick_pin.len() < QUICK_PIN_MIN {
+                return Err(AuthError::PinTooShort(QUICK_PIN_MIN));
+            }
+            if self.policy().ban_common && is_common_pin(quick_pin) {
+                return Err(AuthError::PinTooCommon);
+            }
+            hash_pin(quick_pin)?
         };
-        uf.users.push(rec);
-        self.save(&uf)
+        self.with_user(username, |rec| {
+            rec.quick_pin_phc = phc;
+            rec.quick_pin_failures = 0;
+            Ok(())
+        })
     }
 
-    pub fn verify_login(&self, username: &str, pin: &str) -> Result<(), AuthError> {
-        let uf = self.load()?;
-        let rec = uf.users.iter().find(|u| u.username == username).ok_or(AuthError::NotFound)?;
-        let parsed = PasswordHash::new(&rec.pin_phc).map_err(|_| AuthError::InvalidPin)?;
-        Argon2::default()
-            .verify_password(pin.as_bytes(), &parsed)
-            .map_err(|_| AuthError::InvalidPin)
+    fn confirm_pin(&self, username: &str, pin: &str) -> Result<(), AuthError> {
+        self.check_pin(username, pin).map(|_| ())
     }
 
-    pub fn list_users(&self) -> Result<Vec<String>, AuthError> {
-        let uf = self.load()?;
-        Ok(uf.users.into_iter().map(|u| u.username).collect())
+    fn change_pin(&self, username: &str, old_pin: &str, new_pin: &str) -> Result<(), AuthError> {
+        self.check_pin(username, old_pin)?;
+        self.policy().check(new_pin)?;
+        let (secrets, phc) = (self.store().secret_backend()?, hash_pin(new_pin)?);
+        self.with_user(username, |rec| write_pin_phc(secrets, rec, phc))
     }
 
-    pub fn delete_user(&self, username: &str) -> Result<(), AuthError> {
-        let mut uf = self.load()?;
-        let before = uf.users.len();
-        uf.users.retain(|u| u.username != username);
-        if uf.users.len() == before {
-            return Err(AuthError::NotFound);
+    fn generate_recovery_codes(&self, username: &str) -> Result<Vec<String>, AuthError> {
+        let codes: Vec<String> = (0..RECOVERY_CODES).map(|_| new_recovery_code()).collect();
+        let hashes = codes
+            .iter()
+            .map(|c| hash_pin(&normalize_recovery_code(c)))
+            .collect::<Result<_, _>>()?;
+        self.with_user(username, |rec| {
+            rec.recovery_phc = hashes;
+            Ok(())
+        })?;
+        Ok(codes)
+    }
+
+    fn reset_pin_with_recovery(&self, username: &str, code: &str, new_pin: &str) -> Result<usize, AuthError> {
+        self.policy().check(new_pin)?;
+        let store = self.store();
+        let code = Zeroizing::new(normalize_recovery_code(code));
+        let Some(rec) = store.get(username)? else {
+            let _ = find_recovery_code(&code, &[]);
+            return Err(if self.policy().unified_errors { AuthError::InvalidRecoveryCode } else { AuthError::NotFound });
+        };
+        let used = find_recovery_code(&code, &rec.recovery_phc).ok_or(AuthError::InvalidRecoveryCode)?;
+        let (secrets, phc) = (store.secret_backend()?, hash_pin(new_pin)?);
+        self.with_user(username, |rec| {
+            // gone if another instance used the same code in the meantime
+            let i = rec.recovery_phc.iter().position(|p| *p == used).ok_or(AuthError::InvalidRecoveryCode)?;
+            rec.recovery_phc.remove(i);
+            write_pin_phc(secrets, rec, phc)?;
+            Ok(rec.recovery_phc.len())
+        })
+    }
+
+    fn rename_user(&self, old: &str, new: &str) -> Result<(), AuthError> {
+        if !valid_username(new) {
+            return Err(AuthError::InvalidUsername);
+        }
+        // refuse before touching anything if leftovers already use the new name
+        if config::users_base_dir()?.join(new).exists() || cache::has_user_cache(new) {
+            return Err(AuthError::AlreadyExists);
+        }
+
+        let secrets = self.store().secret_backend()?;
+        // move both folder trees first, so a failure leaves the account as it was
+        config::rename_user_tree(old, new)?;
+        if let Err(e) = cache::rename_user_cache(old, new) {
+            if let Err(undo) = config::rename_user_tree(new, old) {
+                eprintln!("Failed to move the config folder back to {}: {:?}", old, undo);
+            }
+            return Err(e.into());
+        }
+
+        let renamed = self.with_users(|users| {
+            if users.iter().any(|u| u.username == new) {
+                return Err(AuthError::AlreadyExists);
+            }
+            let rec = users.iter_mut().find(|u| u.username == old).ok_or(AuthError::NotFound)?;
+            let phc = read_pin_phc(secrets, rec)?;
+            rec.username = new.to_string();
+            write_pin_phc(secrets, rec, phc)
+        });
+        if let Err(e) = renamed {
+            if let Err(undo) = cache::rename_user_cache(new, old) {
+                eprintln!("Failed to move the cache folder back to {}: {:?}", old, undo);
+            }
+            if let Err(undo) = config::rename_user_tree(new, old) {
+                eprintln!("Failed to move the config folder back to {}: {:?}", old, undo);
+            }
+            return Err(e);
+        }
+
+        crate::cachecrypt::rename_user(old, new);
+        crate::alarms::forget();
+        crate::worldclock::forget();
+        crate::podcasts::forget();
+        crate::calendar::forget();
+        if let Err(e) = crate::secrets::rename_api_keys(old, new) {
+            eprintln!("Failed to move keyring API keys for {}: {:?}", old, e);
+        }
+        // the record now points at the new entry; the old one can go
+        if secrets == SecretBackend::Keyring {
+            if let Err(e) = KeyringStore.delete(&pin_key(old)) {
+                eprintln!("Failed to remove keyring entry for {}: {:?}", old, e);
+            }
+        }
+        Ok(())
+    }
+
+    fn profile(&self, username: &str) -> Result<(Role, Restrictions), AuthError> {
+        if username == "guest" {
+            return self.guest_profile();
+        }
+        let rec = self.store().get(username)?.ok_or(AuthError::NotFound)?;
+        Ok((rec.role, rec.restrictions))
+    }
+
+    fn has_admin(&self) -> Result<bool, AuthError> {
+        Ok(self.store().list()?.iter().any(|u| u.role == Role::Admin))
+    }
+
+    fn set_profile(&self, by: &str, target: &str, role: Role, restrictions: Restrictions) -> Result<(), AuthError> {
+        if target == "guest" {
+            let by_admin = self.store().get(by)?.is_some_and(|u| u.role == Role::Admin);
+            if !by_admin || role == Role::Admin {
+                return Err(AuthError::NotAllowed);
+            }
+            let json = serde_json::to_string_pretty(&GuestProfile { role, restrictions })?;
+            return Ok(fs::write(self.guest_profile_path(), json)?);
+        }
+        self.with_users(|users| {
+            // decided on the records under the lock, so two claims can't both win
+            let by_admin = users.iter().any(|u| u.username == by && u.role == Role::Admin);
+            let claiming = by == target && role == Role::Admin && !users.iter().any(|u| u.role == Role::Admin);
+            if !by_admin && !claiming {
+                return Err(AuthError::NotAllowed);
+            }
+            if by == target && by_admin && role != Role::Admin {
+                // don't leave the install without an admin by accident
+                return Err(AuthError::NotAllowed);
+            }
+            let rec = users.iter_mut().find(|u| u.username == target).ok_or(AuthError::NotFound)?;
+            rec.role = role;
+            rec.restrictions = restrictions;
+            Ok(())
+        })
+    }
+
+    fn list_users(&self) -> Result<Vec<String>, AuthError> {
+        Ok(self.store().list()?.into_iter().map(|u| u.username).collect())
+    }
+
+    fn list_last_logins(&self) -> Result<Vec<(String, String)>, AuthError> {
+        Ok(self.store().list()?.into_iter().map(|u| (u.username, u.last_login)).collect())
+    }
+
+    fn delete_user(&self, username: &str) -> Result<(), AuthError> {
+        let store = self.store();
+        store.delete(username)?;
+        if store.secret_backend()? == SecretBackend::Keyring {
+            if let Err(e) = KeyringStore.delete(&pin_key(username)) {
+                eprintln!("Failed to remove keyring entry for {}: {:?}", username, e);
+            }
         }
-        self.save(&uf)
+        Ok(())
+    }
+
+    fn local(&self) -> Option<&LocalAuth> {
+        Some(self)
     }
 }
 
+/// Shared handle to the active provider, cheap to clone into UI callbacks.
+/// Calls hash PINs or touch the disk, so they only go through `run`, which
+/// moves them onto the blocking pool.
+#[derive(Clone)]
+pub struct Auth(Arc<dyn AuthProvider>);
+
+impl Auth {
+    /// The built-in provider: accounts stored on this computer.
+    pub fn new() -> Result<Self, AuthError> {
+        Ok(Self(Arc::new(LocalAuth::new()?)))
+    }
+
+    /// Run `f` against the provider on a blocking thread.
+    pub async fn run<T, F>(&self, f: F) -> Result<T, AuthError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn AuthProvider) -> Result<T, AuthError> + Send + 'static,
+    {
+        let provider = self.0.clone();
+        tokio::task::spawn_blocking(move || f(provider.as_ref()))
+            .await
+            .map_err(|e| AuthError::Task(e.to_string()))?
+    }
+}
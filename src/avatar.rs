@@ -0,0 +1,36 @@
+use crate::config::user_root;
+use slint::{Rgba8Pixel, SharedPixelBuffer};
+use std::path::{Path, PathBuf};
+
+/// Avatars are stored square and small; the UI only ever shows them at chip size.
+const AVATAR_SIZE: u32 = 64;
+
+fn avatar_path(user: &str) -> std::io::Result<PathBuf> {
+    Ok(user_root(user)?.join("avatar.png"))
+}
+
+/// Decode the picked image, crop/scale it to a square and store it as the user's avatar.png.
+pub fn save_avatar(user: &str, src: &Path) -> anyhow::Result<()> {
+    let img = image::open(src)?;
+    let square = img.resize_to_fill(AVATAR_SIZE, AVATAR_SIZE, image::imageops::FilterType::Triangle);
+    square.to_rgba8().save_with_format(avatar_path(user)?, image::ImageFormat::Png)?;
+    Ok(())
+}
+
+pub fn load_avatar(user: &str) -> Option<SharedPixelBuffer<Rgba8Pixel>> {
+    let path = avatar_path(user).ok()?;
+    if !path.exists() {
+        return None;
+    }
+    let rgba = image::open(path).ok()?.to_rgba8();
+    let (w, h) = rgba.dimensions();
+    Some(SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(rgba.as_raw(), w, h))
+}
+
+pub fn remove_avatar(user: &str) -> std::io::Result<()> {
+    let path = avatar_path(user)?;
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
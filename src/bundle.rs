@@ -0,0 +1,210 @@
+use crate::auth::{LocalAuth, UserRecord};
+use crate::podcasts::DOWNLOADS;
+use crate::{cache, config};
+use anyhow::{anyhow, bail, Context};
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, Key, KeyInit, Nonce};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+/// Marker in every bundle, so random JSON files are refused on import.
+const FORMAT: &str = "slint_rust-account";
+const VERSION: u32 = 1;
+
+/// What sits on disk: the account, as JSON sealed with a PIN-derived key.
+#[derive(Serialize, Deserialize)]
+struct BundleFile {
+    format: String,
+    version: u32,
+    user: String,
+    /// Always set now; plain bundles (older versions) are refused on import.
+    encrypted: bool,
+    #[serde(default)]
+    salt: String,
+    #[serde(default)]
+    nonce: String,
+    payload: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Payload {
+    record: UserRecord,
+    files: Vec<PackedFile>,
+}
+
+/// One file of the account's config or cache folder.
+#[derive(Serialize, Deserialize)]
+struct PackedFile {
+    /// "config" or "cache"
+    root: String,
+    /// Path inside the user's folder, `/`-separated.
+    path: String,
+    data: String,
+}
+
+/// Pack the files under `dir`, leaving out the top-level entries named in `skip`.
+fn pack_dir(root: &str, dir: &Path, rel: &str, skip: &[&str], out: &mut Vec<PackedFile>) -> io::Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)?.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if rel.is_empty() && skip.contains(&name.as_str()) {
+            continue;
+        }
+        let path = if rel.is_empty() { name } else { format!("{rel}/{name}") };
+        if entry.file_type()?.is_dir() {
+            pack_dir(root, &entry.path(), &path, &[], out)?;
+        } else {
+            out.push(PackedFile { root: root.to_string(), path, data: B64.encode(fs::read(entry.path())?) });
+        }
+    }
+    Ok(())
+}
+
+/// 256-bit key from the PIN (Argon2id, per-bundle salt); wiped when dropped.
+pub fn derive_key(pin: &str, salt: &[u8]) -> anyhow::Result<Zeroizing<[u8; 32]>> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    argon2::Argon2::default()
+        .hash_password_into(pin.as_bytes(), salt, &mut key[..])
+        .map_err(|e| anyhow!("key derivation failed: {e}"))?;
+    Ok(key)
+}
+
+/// Write `user`'s record, config folder and cache folder (without podcast
+/// downloads) to `dest`, sealed with ChaCha20-Poly1305 under `pin`: the record
+/// carries the PIN and recovery code hashes, and the folders API keys, so
+/// bundles are never written in the clear.
+pub fn export_account(auth: &LocalAuth, user: &str, pin: &str, dest: &Path) -> anyhow::Result<()> {
+    let mut files = Vec::new();
+    // downloaded episodes are only a cache, and can be large: they're fetched again
+    pack_dir("config", &config::users_base_dir()?.join(user), "", &[DOWNLOADS], &mut files)?;
+    pack_dir("cache", &cache::users_cache_base().join(user), "", &[DOWNLOADS], &mut files)?;
+    let payload = Zeroizing::new(serde_json::to_vec(&Payload { record: auth.export_record(user)?, files })?);
+
+    let (mut salt, mut nonce) = ([0u8; 16], [0u8; 12]);
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&derive_key(pin, &salt)?[..]));
+    let sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce), payload.as_slice())
+        .map_err(|_| anyhow!("encryption failed"))?;
+    let bundle = BundleFile {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        user: user.to_string(),
+        encrypted: true,
+        salt: B64.encode(salt),
+        nonce: B64.encode(nonce),
+        payload: B64.encode(sealed),
+    };
+    fs::write(dest, serde_json::to_string(&bundle)?)?;
+    Ok(())
+}
+
+/// Relative path from a bundle, refusing anything that could leave the user's folder.
+fn safe_rel(path: &str) -> anyhow::Result<PathBuf> {
+    let rel = PathBuf::from(path);
+    if rel.components().any(|c| !matches!(c, Component::Normal(_))) {
+        bail!("bad path in bundle: {path}");
+    }
+    Ok(rel)
+}
+
+/// A folder an import is unpacked into before it's renamed into place; removed
+/// when dropped unless `keep` was called.
+struct Staged {
+    dir: PathBuf,
+    keep: bool,
+}
+
+impl Staged {
+    fn new(base: &Path, user: &str) -> io::Result<Self> {
+        fs::create_dir_all(base)?;
+        let dir = base.join(format!(".import-{user}-{:08x}", OsRng.next_u32()));
+        fs::create_dir(&dir)?;
+        Ok(Staged { dir, keep: false })
+    }
+
+    /// Rename the folder to `dest`; from then on it stays.
+    fn commit(mut self, dest: &Path) -> io::Result<()> {
+        fs::rename(&self.dir, dest)?;
+        self.keep = true;
+        Ok(())
+    }
+}
+
+impl Drop for Staged {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+/// Recreate the account stored in `src`, opening it with `pin`. Fails if the
+/// name is already taken here. The folders are unpacked next to where they go
+/// and renamed into place, and the record is added last, so a failed import
+/// leaves nothing behind. Returns the imported username.
+pub fn import_account(auth: &LocalAuth, src: &Path, pin: &str) -> anyhow::Result<String> {
+    let bundle: BundleFile = serde_json::from_str(&fs::read_to_string(src)?).context("not an account bundle")?;
+    if bundle.format != FORMAT || bundle.version > VERSION {
+        bail!("not an account bundle (or made by a newer version)");
+    }
+    if !bundle.encrypted {
+        bail!("unencrypted bundles aren't accepted; export the account again");
+    }
+    let salt = B64.decode(&bundle.salt)?;
+    let nonce = B64.decode(&bundle.nonce)?;
+    if nonce.len() != 12 {
+        bail!("damaged bundle");
+    }
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&derive_key(pin, &salt)?[..]));
+    let payload = Zeroizing::new(
+        cipher
+            .decrypt(Nonce::from_slice(&nonce), B64.decode(&bundle.payload)?.as_slice())
+            .map_err(|_| anyhow!("wrong PIN or damaged bundle"))?,
+    );
+    let payload: Payload = serde_json::from_slice(&payload)?;
+    let user = payload.record.username.clone();
+    if !crate::auth::valid_username(&user) {
+        bail!("bad username in bundle");
+    }
+    let (config_dest, cache_dest) = (config::users_base_dir()?.join(&user), cache::users_cache_base().join(&user));
+    if config_dest.exists() || cache_dest.exists() {
+        bail!("{user} already has data on this computer");
+    }
+
+    let config_stage = Staged::new(&config::users_base_dir()?, &user)?;
+    let cache_stage = Staged::new(&cache::users_cache_base(), &user)?;
+    for f in payload.files {
+        let base = match f.root.as_str() {
+            "config" => &config_stage.dir,
+            "cache" => &cache_stage.dir,
+            other => bail!("unknown folder in bundle: {other}"),
+        };
+        let dest = base.join(safe_rel(&f.path)?);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(dest, B64.decode(&f.data)?)?;
+    }
+
+    config_stage.commit(&config_dest)?;
+    let placed = cache_stage.commit(&cache_dest).map_err(anyhow::Error::from).and_then(|()| {
+        auth.import_record(payload.record).map_err(|e| {
+            let _ = fs::remove_dir_all(&cache_dest);
+            anyhow::Error::from(e)
+        })
+    });
+    if let Err(e) = placed {
+        let _ = fs::remove_dir_all(&config_dest);
+        return Err(e);
+    }
+    Ok(user)
+}
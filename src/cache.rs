@@ -1,14 +1,50 @@
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use crate::cachecrypt::{self, Opened};
+use crate::cachestore::{self, CacheStorage, CacheStore};
+use crate::news::{cached_favicon, canonical_url, topic_key, Article, OpenStats};
+use crate::config::{cache_root, move_entries};
+use crate::geocode::Place;
+use crate::reader::ReaderDoc;
 use slint::{Rgba8Pixel, SharedPixelBuffer};
-use std::{fs, io, path::PathBuf};
+use std::{
+    any::Any,
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
 use chrono::Utc;
 
 // Global cache for guest
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct WeatherRow { pub time: String, pub temp: String, pub summary: String }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StockDay { pub date: String, pub close: f64 }
+
+/// Daily closes of one watchlist symbol, oldest first.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StockCache {
+    pub ts: i64,
+    pub symbol: String,
+    pub days: Vec<StockDay>,
+}
+
+/// Exchange rates against the euro (which is in the map as 1.0).
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RatesCache {
+    pub ts: i64,
+    /// Day the rates were published (YYYY-MM-DD).
+    pub date: String,
+    pub rates: BTreeMap<String, f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct WeatherCache {
     pub ts: i64,
     #[serde(default)] pub units: String,   // "C" or "F" (default for old files)
@@ -16,11 +52,47 @@ pub struct WeatherCache {
     pub rows: Vec<WeatherRow>,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct NewsRow { pub title: String, pub source: String, pub published: String, pub url: String}
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NewsRow {
+    pub title: String,
+    pub source: String,
+    pub published: String,
+    pub url: String,
+    #[serde(default)] pub object_id: String,
+    #[serde(default)] pub points: i64,
+    #[serde(default)] pub comments: i64,
+    #[serde(default)] pub thumb: String,   // path to the PNG thumbnail ("" if none)
+    #[serde(default)] pub paywalled: bool,
+    #[serde(default)] pub author: String,
+}
 
-#[derive(Serialize, Deserialize)]
-pub struct NewsCache { pub ts: i64, pub rows: Vec<NewsRow> }
+impl NewsRow {
+    /// Rebuild an article from a cached row (thumbnail reloaded from its PNG, if any).
+    pub fn into_article(self, user: &str) -> Article {
+        let thumbnail = load_thumb(user, &self.thumb).unwrap_or_else(|| SharedPixelBuffer::new(10, 10));
+        let favicon = cached_favicon(&self.source);
+        Article {
+            title: self.title,
+            source: self.source,
+            published: self.published,
+            url: self.url,
+            object_id: self.object_id,
+            points: self.points,
+            comments: self.comments,
+            thumbnail,
+            favicon,
+            paywalled: self.paywalled,
+            author: self.author,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NewsCache {
+    pub ts: i64,
+    #[serde(default)] pub topic: String,   // lowercase topic key
+    pub rows: Vec<NewsRow>,
+}
 
 /// Returns true if `ts` is within `ttl_secs` of now.
 pub fn is_fresh(ts: i64, ttl_secs: i64) -> bool {
@@ -36,16 +108,476 @@ pub fn age_minutes(ts: i64) -> i64 {
 
 // Post Login cache
 
+pub fn users_cache_base() -> PathBuf {
+    cache_root().join("users")
+}
+
+/// Downloaded weather icons and source favicons (shared by all users).
+pub fn icons_dir() -> PathBuf {
+    cache_root().join("icons")
+}
+
+pub fn has_user_cache(user: &str) -> bool {
+    users_cache_base().join(user).exists()
+}
+
 fn user_cache_dir(user: &str) -> io::Result<PathBuf> {
-    let dir = PathBuf::from("cache")
-        .join("users")
-        .join(user);
+    let dir = users_cache_base().join(user);
     fs::create_dir_all(&dir)?;
     Ok(dir)
 }
 
-fn weather_path_for(user: &str) -> io::Result<PathBuf> { Ok(user_cache_dir(user)?.join("weather.json")) }
-fn news_path_for(user: &str)    -> io::Result<PathBuf> { Ok(user_cache_dir(user)?.join("news.json")) }
+/// Read one of the user's cache documents from its store (files or `cache.db`).
+fn load_doc<T: Cached>(user: &str, kind: &str) -> Option<T> {
+    load_entry(user, kind, "")
+}
+
+fn save_doc<T: Serialize>(user: &str, kind: &str, value: &T) -> io::Result<()> {
+    save_entry(user, kind, "", value)
+}
+
+/// Like `load_doc`, for kinds that keep several entries (one per city, topic, …).
+fn load_entry<T: Cached>(user: &str, kind: &str, key: &str) -> Option<T> {
+    load_stored(user, kind, &stored_key(user, key).ok()?)
+}
+
+/// `load_entry` by the key an entry is stored under (as listed by `CacheStore::keys`).
+fn load_stored<T: Cached>(user: &str, kind: &str, key: &str) -> Option<T> {
+    if let Some(value) = remembered(user, kind, key) {
+        return Some(value);
+    }
+    let dir = user_cache_dir(user).ok()?;
+    let data = read_checked(user, &dir, cachestore::open(&dir).as_ref(), kind, key)?;
+    let value: T = serde_json::from_str(&data).ok()?;
+    remember(user, kind, key, value.clone());
+    Some(value)
+}
+
+fn save_entry<T: Serialize>(user: &str, kind: &str, key: &str, value: &T) -> io::Result<()> {
+    let key = stored_key(user, key)?;
+    forget(user, kind, &key);
+    let data = encrypt_for(user, seal(value)?)?;
+    cachestore::open(&user_cache_dir(user)?).put(kind, &key, &data)
+}
+
+/// The key an entry is stored under. An encrypted user's keys (cities, topics,
+/// symbols) would name what they looked up in file names and `lru.json`, so
+/// they're hashed with the user's cache key instead.
+fn stored_key(user: &str, key: &str) -> io::Result<String> {
+    if key.is_empty() || !cachecrypt::enabled(user) {
+        return Ok(key.to_string());
+    }
+    cachecrypt::entry_name(user, key)
+}
+
+/// The key a keyed entry belongs under, read from the document itself (its
+/// stored key may be a hash).
+fn key_of(kind: &str, data: &str) -> Option<String> {
+    match kind {
+        "weather" => serde_json::from_str::<WeatherCache>(data).ok().map(|w| weather_key(&w.city, &w.units)),
+        "news" => serde_json::from_str::<NewsCache>(data).ok().map(|n| n.topic),
+        "stocks" => serde_json::from_str::<StockCache>(data).ok().map(|s| s.symbol.to_lowercase()),
+        _ => None,
+    }
+}
+
+/// `data` as it's stored for `user`: encrypted if they turned that on.
+fn encrypt_for(user: &str, data: String) -> io::Result<String> {
+    if cachecrypt::enabled(user) { cachecrypt::encrypt(user, &data) } else { Ok(data) }
+}
+
+/// Write a cached file (thumbnail, offline image, episode) for `user`: sealed
+/// with their cache key if they turned encryption on.
+pub fn write_file_for(user: &str, path: &Path, bytes: &[u8]) -> io::Result<()> {
+    if cachecrypt::enabled(user) {
+        fs::write(path, cachecrypt::seal_bytes(user, bytes)?)
+    } else {
+        fs::write(path, bytes)
+    }
+}
+
+/// A cached file's contents, decrypted if needed. `None` if it's missing,
+/// damaged, or sealed while `user`'s key isn't open.
+pub fn read_file_for(user: &str, path: &Path) -> Option<Vec<u8>> {
+    cachecrypt::open_bytes(user, fs::read(path).ok()?).ok()
+}
+
+/// The document in a stored entry: decrypted and checked. `None` if it's
+/// damaged, or encrypted while `user`'s key isn't open.
+fn open_entry(user: &str, raw: &str) -> Option<String> {
+    match cachecrypt::open(user, raw) {
+        Opened::Plain(text) => unseal(&text),
+        Opened::Locked | Opened::Damaged => None,
+    }
+}
+
+// In-memory layer: documents already parsed in this run, per user, kind and key,
+// so switching pages doesn't read and parse the same JSON again. Anything in this
+// module that rewrites or removes an entry drops its copy first.
+
+/// What the in-memory layer can hold.
+trait Cached: DeserializeOwned + Clone + Send + Sync + 'static {}
+impl<T: DeserializeOwned + Clone + Send + Sync + 'static> Cached for T {}
+
+type MemoryKey = (String, String, String);
+
+static MEMORY: Mutex<BTreeMap<MemoryKey, Arc<dyn Any + Send + Sync>>> = Mutex::new(BTreeMap::new());
+
+fn memory_key(user: &str, kind: &str, key: &str) -> MemoryKey {
+    (user.to_string(), kind.to_string(), key.to_string())
+}
+
+fn remembered<T: Cached>(user: &str, kind: &str, key: &str) -> Option<T> {
+    let value = MEMORY.lock().ok()?.get(&memory_key(user, kind, key)).cloned()?;
+    value.downcast::<T>().ok().map(|v| (*v).clone())
+}
+
+fn remember<T: Cached>(user: &str, kind: &str, key: &str, value: T) {
+    if let Ok(mut memory) = MEMORY.lock() {
+        memory.insert(memory_key(user, kind, key), Arc::new(value));
+    }
+}
+
+fn forget(user: &str, kind: &str, key: &str) {
+    if let Ok(mut memory) = MEMORY.lock() {
+        memory.remove(&memory_key(user, kind, key));
+    }
+}
+
+/// Drop `user`'s copies, of one kind or (`None`) all of them.
+fn forget_all(user: &str, kind: Option<&str>) {
+    if let Ok(mut memory) = MEMORY.lock() {
+        memory.retain(|(u, k, _), _| u != user || kind.is_some_and(|kind| k != kind));
+    }
+}
+
+// Integrity: entries are saved with a checksum of their contents. One that no
+// longer matches (or isn't JSON at all) is moved to `quarantine/` with a warning
+// and treated as missing, so the page fetches fresh data instead of showing nothing.
+
+const QUARANTINE_DIR: &str = "quarantine";
+
+#[derive(Serialize, Deserialize)]
+struct Sealed {
+    fnv1a: String,
+    data: Value,
+}
+
+fn checksum(data: &Value) -> String {
+    format!("{:016x}", fnv1a(&data.to_string()))
+}
+
+fn seal<T: Serialize>(value: &T) -> io::Result<String> {
+    let data = serde_json::to_value(value)?;
+    Ok(serde_json::to_string_pretty(&Sealed { fnv1a: checksum(&data), data })?)
+}
+
+/// The document inside a stored entry, or `None` if it's damaged. Entries
+/// written before checksums existed pass as they are.
+fn unseal(raw: &str) -> Option<String> {
+    let value: Value = serde_json::from_str(raw).ok()?;
+    match serde_json::from_value::<Sealed>(value.clone()) {
+        Ok(sealed) if sealed.fnv1a == checksum(&sealed.data) => Some(sealed.data.to_string()),
+        Ok(_) => None,
+        Err(_) => Some(value.to_string()),
+    }
+}
+
+/// Read, decrypt and verify an entry; a damaged one is quarantined. One that's
+/// encrypted while the key isn't open is only skipped.
+fn read_checked(user: &str, dir: &Path, store: &dyn CacheStore, kind: &str, key: &str) -> Option<String> {
+    let raw = store.get(kind, key).ok()??;
+    let data = match cachecrypt::open(user, &raw) {
+        Opened::Plain(text) => unseal(&text),
+        Opened::Locked => return None,
+        Opened::Damaged => None,
+    };
+    if data.is_none() {
+        quarantine(dir, store, kind, key, &raw);
+    }
+    data
+}
+
+fn quarantine(dir: &Path, store: &dyn CacheStore, kind: &str, key: &str, raw: &str) {
+    let name = format!(
+        "{kind}{}{}-{}.json",
+        if key.is_empty() { "" } else { "-" },
+        urlencoding::encode(key),
+        Utc::now().format("%Y%m%d-%H%M%S")
+    );
+    let target = dir.join(QUARANTINE_DIR).join(name);
+    let moved = fs::create_dir_all(dir.join(QUARANTINE_DIR)).and_then(|()| fs::write(&target, raw));
+    let _ = store.delete(kind, key);
+    match moved {
+        Ok(()) => eprintln!("Cache entry {kind} {key:?} is corrupt (checksum mismatch); moved to {}", target.display()),
+        Err(e) => eprintln!("Cache entry {kind} {key:?} is corrupt; dropped it (quarantine failed: {e:?})"),
+    }
+}
+
+/// Where an entry would live as a file, which is also its key in the usage index.
+fn doc_path(user: &str, kind: &str, key: &str) -> PathBuf {
+    let key = stored_key(user, key).unwrap_or_default();
+    cachestore::FileCacheStore::new(users_cache_base().join(user)).path(kind, &key)
+}
+
+#[derive(Deserialize)]
+struct Stamp {
+    ts: i64,
+}
+
+/// Keep the `keep` most recently fetched entries of `kind`, dropping the rest.
+fn prune_entries(user: &str, kind: &str, keep: usize) -> io::Result<()> {
+    let store = cachestore::open(&user_cache_dir(user)?);
+    let mut stamped: Vec<(i64, String)> = store
+        .keys(kind)?
+        .into_iter()
+        .map(|key| {
+            let ts = store
+                .get(kind, &key)
+                .ok()
+                .flatten()
+                .and_then(|raw| open_entry(user, &raw))
+                .and_then(|d| serde_json::from_str::<Stamp>(&d).ok())
+                .map_or(0, |s| s.ts);
+            (ts, key)
+        })
+        .collect();
+    stamped.sort_by_key(|s| std::cmp::Reverse(s.0));
+    for (_, key) in stamped.into_iter().skip(keep) {
+        forget(user, kind, &key);
+        store.delete(kind, &key)?;
+    }
+    Ok(())
+}
+
+/// Which backend holds `user`'s cache documents.
+pub fn cache_storage(user: &str) -> CacheStorage {
+    cachestore::storage(&users_cache_base().join(user))
+}
+
+/// Move `user`'s cache documents to `to` (thumbnails and offline copies stay files).
+pub fn set_cache_storage(user: &str, to: CacheStorage) -> io::Result<()> {
+    cachestore::set_storage(&user_cache_dir(user)?, to)
+}
+
+/// Move a user's cache folder to a new name. Thumbnail and offline image paths
+/// embed the folder, so the stored ones are repointed too.
+pub fn rename_user_cache(old: &str, new: &str) -> io::Result<()> {
+    let (from, to) = (users_cache_base().join(old), users_cache_base().join(new));
+    if !from.exists() {
+        return Ok(());
+    }
+    if to.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("cache folder for {new} exists")));
+    }
+    fs::rename(&from, &to)?;
+    forget_all(old, None);
+    repoint_user_paths(new, &from, &to)
+}
+
+/// Remove a deleted user's cache folder: documents, thumbnails, offline copies
+/// and quarantined entries.
+pub fn delete_user_cache(user: &str) -> io::Result<()> {
+    let dir = users_cache_base().join(user);
+    if !dir.exists() {
+        return Ok(());
+    }
+    let _guard = LRU_LOCK.lock();
+    forget_all(user, None);
+    fs::remove_dir_all(&dir)?;
+    // like the config side: drop `users/` once it's empty
+    let _ = fs::remove_dir(users_cache_base());
+    Ok(())
+}
+
+/// Turn encryption of `user`'s cache on or off with their (already confirmed)
+/// PIN, rewriting every document, offline copy, thumbnail and downloaded
+/// episode to match.
+pub fn set_cache_encryption(user: &str, pin: &str, on: bool) -> anyhow::Result<()> {
+    if on {
+        cachecrypt::enable(user, pin)?;
+    } else {
+        cachecrypt::unlock(user, pin)?;
+        cachecrypt::disable(user)?; // new writes are plain from here; the key stays open to read the old ones
+    }
+    let dir = user_cache_dir(user)?;
+    {
+        let _guard = LRU_LOCK.lock();
+        let store = cachestore::open(&dir);
+        let mut entries = Vec::new();
+        for (kind, key, raw) in store.all()? {
+            // entries that don't decrypt any more are dropped
+            let Opened::Plain(text) = cachecrypt::open(user, &raw) else { continue };
+            // keyed entries are refiled under hashed keys, or back under plain ones
+            let key = match key.is_empty() {
+                true => key,
+                false => match unseal(&text).and_then(|d| key_of(&kind, &d)) {
+                    Some(plain) => stored_key(user, &plain)?,
+                    None => continue,
+                },
+            };
+            entries.push((kind, key, encrypt_for(user, text)?));
+        }
+        store.replace_all(&entries)?;
+        forget_all(user, None);
+        // the usage index names the old keys; file times stand in until it's rebuilt
+        let _ = fs::remove_file(dir.join(LRU_FILE));
+    }
+    for entry in fs::read_dir(dir.join("offline")).into_iter().flatten().flatten() {
+        if let Some(doc) = read_offline_doc(user, &entry.path()) {
+            write_offline_doc(user, &entry.path(), &doc)?;
+        }
+    }
+    for folder in ["thumbs", "offline", crate::podcasts::DOWNLOADS] {
+        rewrite_files(user, &dir.join(folder))?;
+    }
+    if !on {
+        cachecrypt::lock(user);
+    }
+    Ok(())
+}
+
+/// Rewrite the cached files under `dir` (images, episodes) sealed or plain to
+/// match `user`'s setting. Ones that don't decrypt any more are removed; offline
+/// texts and partial downloads are left alone.
+fn rewrite_files(user: &str, dir: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            rewrite_files(user, &path)?;
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == "doc.json" || name.ends_with(".part") {
+            continue;
+        }
+        match read_file_for(user, &path) {
+            Some(bytes) => write_file_for(user, &path, &bytes)?,
+            None => {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rewrite the thumbnail and offline image paths stored in `user`'s cache that
+/// start with `from` so they start with `to` (after the folder moved).
+fn repoint_user_paths(user: &str, from: &Path, to: &Path) -> io::Result<()> {
+    let (old_prefix, new_prefix) = (from.to_string_lossy().into_owned(), to.to_string_lossy().into_owned());
+    let repoint = |p: &mut String| {
+        if let Some(rest) = p.strip_prefix(&old_prefix) {
+            *p = format!("{new_prefix}{rest}");
+        }
+    };
+    let mut keys = cachestore::open(to).keys("news")?;
+    keys.push(String::new()); // a single news.json from older versions
+    for key in keys {
+        if let Some(mut n) = load_stored::<NewsCache>(user, "news", &key) {
+            n.rows.iter_mut().for_each(|r| repoint(&mut r.thumb));
+            forget(user, "news", &key);
+            let data = encrypt_for(user, seal(&n)?)?;
+            cachestore::open(to).put("news", &key, &data)?;
+        }
+    }
+    if let Ok(entries) = fs::read_dir(to.join("offline")) {
+        for entry in entries.flatten() {
+            let Some(mut doc) = read_offline_doc(user, &entry.path()) else { continue };
+            doc.images.iter_mut().for_each(&repoint);
+            write_offline_doc(user, &entry.path(), &doc)?;
+        }
+    }
+    Ok(())
+}
+
+/// Folders older versions may have left `cache/` and `icons_cache/` in: the
+/// launch directory, and the executable's folder (launchers often start it
+/// from elsewhere).
+fn legacy_cache_roots() -> Vec<PathBuf> {
+    let mut roots = vec![PathBuf::from(".")];
+    if let Some(dir) = std::env::current_exe().ok().and_then(|p| p.parent().map(Path::to_path_buf)) {
+        let same = matches!((fs::canonicalize(&dir), fs::canonicalize(".")), (Ok(a), Ok(b)) if a == b);
+        if !same {
+            roots.push(dir);
+        }
+    }
+    roots
+}
+
+/// One-time move of the old `cache/` and `icons_cache/` folders (relative to the
+/// launch directory or next to the executable) into the platform cache folder,
+/// repointing stored image paths. Users already present there win.
+pub fn migrate_legacy_cache() {
+    // resolved places moved into each user's cache; the shared copy named everyone's lookups
+    let _ = fs::remove_file(crate::geocode::shared_cache_path());
+    if cache_root() == Path::new("cache") {
+        return; // no platform folder: still using ./cache
+    }
+    for root in legacy_cache_roots() {
+        migrate_cache_from(&root);
+    }
+}
+
+fn migrate_cache_from(root: &Path) {
+    let legacy_cache = root.join("cache");
+    let legacy_users = legacy_cache.join("users");
+    if legacy_users.is_dir() {
+        let users: Vec<String> = fs::read_dir(&legacy_users)
+            .map(|rd| rd.flatten().map(|e| e.file_name().to_string_lossy().into_owned()).collect())
+            .unwrap_or_default();
+        match move_entries(&legacy_users, &users_cache_base()) {
+            Ok(()) => {
+                for user in users {
+                    if legacy_users.join(&user).exists() {
+                        continue; // already had a platform copy; left in place
+                    }
+                    if let Err(e) = repoint_user_paths(&user, &legacy_users.join(&user), &users_cache_base().join(&user)) {
+                        eprintln!("Cache migration error ({user}): {e:?}");
+                    }
+                }
+                let _ = fs::remove_dir(&legacy_users);
+                let _ = fs::remove_dir(&legacy_cache);
+            }
+            Err(e) => eprintln!("Cache migration error: {e:?}"),
+        }
+    }
+    let legacy_icons = root.join("icons_cache");
+    if legacy_icons.is_dir() {
+        match move_entries(&legacy_icons, &icons_dir()) {
+            Ok(()) => {
+                let _ = fs::remove_dir(&legacy_icons);
+            }
+            Err(e) => eprintln!("Icon cache migration error: {e:?}"),
+        }
+    }
+}
+
+// Weather and news keep a few recent entries each, so switching back to a city
+// or topic finds its data still there.
+
+const MAX_WEATHER_ENTRIES: usize = 5;
+const MAX_NEWS_ENTRIES: usize = 8;
+const MAX_STOCK_ENTRIES: usize = 30;
+
+fn weather_key(city: &str, units: &str) -> String {
+    format!("{}|{units}", city.to_lowercase())
+}
+
+/// Older versions kept one weather.json / news.json; file it under its own key.
+fn migrate_single_entry(user: &str, kind: &str) -> io::Result<()> {
+    let key = match kind {
+        "weather" => load_doc::<WeatherCache>(user, kind).map(|w| weather_key(&w.city, &w.units)),
+        _ => load_doc::<NewsCache>(user, kind).map(|n| n.topic),
+    };
+    let Some(key) = key else { return Ok(()) };
+    let key = stored_key(user, &key)?;
+    let store = cachestore::open(&user_cache_dir(user)?);
+    if let (Some(data), None) = (store.get(kind, "")?, store.get(kind, &key)?) {
+        store.put(kind, &key, &data)?;
+    }
+    forget(user, kind, "");
+    store.delete(kind, "")
+}
 
 pub fn save_weather_for(
     user: &str,
@@ -61,32 +593,947 @@ pub fn save_weather_for(
             time: t.clone(), temp: temp.clone(), summary: s.clone()
         }).collect(),
     };
-    fs::write(weather_path_for(user)?, serde_json::to_string_pretty(&w)?)?;
+    migrate_single_entry(user, "weather")?;
+    let key = weather_key(city, units);
+    save_entry(user, "weather", &key, &w)?;
+    prune_entries(user, "weather", MAX_WEATHER_ENTRIES)?;
+    touch(user, &[&doc_path(user, "weather", &key)]);
+    enforce_size_limit(user);
+    Ok(())
+}
+
+/// The cached weather for `city` in `units` ("C" / "F"), however old.
+pub fn load_weather_for(user: &str, city: &str, units: &str) -> Option<WeatherCache> {
+    let key = weather_key(city, units);
+    let w = load_entry(user, "weather", &key).or_else(|| {
+        load_doc::<WeatherCache>(user, "weather").filter(|w| weather_key(&w.city, &w.units) == key)
+    })?;
+    touch(user, &[&doc_path(user, "weather", &key)]);
+    Some(w)
+}
+
+pub fn save_stock_for(user: &str, symbol: &str, days: Vec<StockDay>) -> io::Result<()> {
+    let s = StockCache { ts: Utc::now().timestamp(), symbol: symbol.to_uppercase(), days };
+    let key = symbol.to_lowercase();
+    save_entry(user, "stocks", &key, &s)?;
+    prune_entries(user, "stocks", MAX_STOCK_ENTRIES)?;
+    touch(user, &[&doc_path(user, "stocks", &key)]);
+    enforce_size_limit(user);
+    Ok(())
+}
+
+/// The cached closes for `symbol`, however old.
+pub fn load_stock_for(user: &str, symbol: &str) -> Option<StockCache> {
+    let key = symbol.to_lowercase();
+    let s = load_entry(user, "stocks", &key)?;
+    touch(user, &[&doc_path(user, "stocks", &key)]);
+    Some(s)
+}
+
+pub fn save_rates_for(user: &str, date: &str, rates: BTreeMap<String, f64>) -> io::Result<()> {
+    let r = RatesCache { ts: Utc::now().timestamp(), date: date.to_string(), rates };
+    save_doc(user, "rates", &r)?;
+    touch(user, &[&doc_path(user, "rates", "")]);
     Ok(())
 }
 
-pub fn load_weather_for(user: &str) -> Option<WeatherCache> {
-    let p = weather_path_for(user).ok()?;
-    let s = fs::read_to_string(p).ok()?;
-    serde_json::from_str(&s).ok()
+/// The cached exchange rates, however old.
+pub fn load_rates_for(user: &str) -> Option<RatesCache> {
+    let r = load_doc(user, "rates")?;
+    touch(user, &[&doc_path(user, "rates", "")]);
+    Some(r)
 }
 
-pub fn save_news_for(user: &str, rows: &[(String, String, String, String, SharedPixelBuffer<Rgba8Pixel>)]) -> io::Result<()> {
+/// Stable 64-bit FNV-1a, used for thumbnail file names.
+pub fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+fn thumbs_dir_for(user: &str) -> io::Result<PathBuf> {
+    let dir = user_cache_dir(user)?.join("thumbs");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Encode a thumbnail as PNG (sealed for an encrypted cache); returns the path,
+/// or "" for the tiny dummy buffers.
+fn save_thumb(user: &str, dir: &Path, url: &str, buf: &SharedPixelBuffer<Rgba8Pixel>) -> String {
+    if buf.width() <= 10 || buf.height() <= 10 {
+        return String::new();
+    }
+    let path = dir.join(format!("{:016x}.png", fnv1a(url)));
+    let Some(img) = image::RgbaImage::from_raw(buf.width(), buf.height(), buf.as_bytes().to_vec()) else {
+        return String::new();
+    };
+    match encode_png(&img).and_then(|png| write_file_for(user, &path, &png)) {
+        Ok(()) => path.to_string_lossy().into_owned(),
+        Err(e) => {
+            eprintln!("Failed to cache thumbnail for {}: {:?}", url, e);
+            String::new()
+        }
+    }
+}
+
+/// `img` as PNG bytes.
+pub fn encode_png(img: &image::RgbaImage) -> io::Result<Vec<u8>> {
+    let mut png = Vec::new();
+    img.write_to(&mut io::Cursor::new(&mut png), image::ImageFormat::Png).map_err(io::Error::other)?;
+    Ok(png)
+}
+
+fn load_thumb(user: &str, path: &str) -> Option<SharedPixelBuffer<Rgba8Pixel>> {
+    if path.is_empty() {
+        return None;
+    }
+    let rgba = image::load_from_memory(&read_file_for(user, Path::new(path))?).ok()?.to_rgba8();
+    let (w, h) = rgba.dimensions();
+    Some(SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(rgba.as_raw(), w, h))
+}
+
+pub fn save_news_for(user: &str, topic: &str, rows: &[Article]) -> io::Result<()> {
+    let thumbs = thumbs_dir_for(user)?;
     let n = NewsCache {
         ts: Utc::now().timestamp(),
-        rows: rows.iter().map(|(title, source, published, url, _thumbnail)| NewsRow {
-            title: title.clone(), source: source.clone(), published: published.clone(), url: url.clone()
+        topic: topic_key(topic),
+        rows: rows.iter().map(|a| NewsRow {
+            title: a.title.clone(), source: a.source.clone(), published: a.published.clone(), url: a.url.clone(),
+            object_id: a.object_id.clone(), points: a.points, comments: a.comments,
+            thumb: save_thumb(user, &thumbs, &a.url, &a.thumbnail),
+            paywalled: a.paywalled,
+            author: a.author.clone(),
         }).collect(),
     };
-    fs::write(news_path_for(user)?, serde_json::to_string_pretty(&n)?)?;
+    migrate_single_entry(user, "news")?;
+    save_entry(user, "news", &n.topic, &n)?;
+    prune_entries(user, "news", MAX_NEWS_ENTRIES)?;
+
+    // drop thumbnails no cached topic refers to any more
+    let kept: std::collections::HashSet<String> = load_all_news_for(user)
+        .into_iter()
+        .flat_map(|c| c.rows.into_iter().map(|r| r.thumb))
+        .collect();
+    if let Ok(entries) = fs::read_dir(&thumbs) {
+        for entry in entries.flatten() {
+            if !kept.contains(entry.path().to_string_lossy().as_ref()) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+    touch_news(user, &n);
+    enforce_size_limit(user);
+    Ok(())
+}
+
+/// The cached stories for `topic` (a feed key), however old.
+pub fn load_news_for(user: &str, topic: &str) -> Option<NewsCache> {
+    let key = topic_key(topic);
+    let n = load_entry(user, "news", &key)
+        .or_else(|| load_doc::<NewsCache>(user, "news").filter(|n| n.topic == key))?;
+    touch_news(user, &n);
+    Some(n)
+}
+
+/// Read `user`'s cached cities, the news entries under `news_keys` and the lists
+/// the news cards use into the in-memory layer (start-up, behind the splash).
+pub fn warm(user: &str, news_keys: &[String]) {
+    let Ok(dir) = user_cache_dir(user) else { return };
+    for key in cachestore::open(&dir).keys("weather").unwrap_or_default() {
+        let _ = load_stored::<WeatherCache>(user, "weather", &key);
+    }
+    for key in news_keys {
+        let _ = load_entry::<NewsCache>(user, "news", &topic_key(key));
+    }
+    for key in cachestore::open(&dir).keys("stocks").unwrap_or_default() {
+        let _ = load_stored::<StockCache>(user, "stocks", &key);
+    }
+    let _ = (load_seen_for(user), load_read_for(user), load_bookmarks_for(user), load_open_stats_for(user));
+    let _ = load_locations_for(user);
+}
+
+/// Every cached topic (for the offline search).
+pub fn load_all_news_for(user: &str) -> Vec<NewsCache> {
+    let Ok(dir) = user_cache_dir(user) else { return Vec::new() };
+    let mut keys = cachestore::open(&dir).keys("news").unwrap_or_default();
+    keys.push(String::new());
+    keys.iter().filter_map(|k| load_stored(user, "news", k)).collect()
+}
+
+// Stale-while-revalidate: show what's cached at once, refetch in the background
+
+/// A cache entry that knows when it was fetched.
+pub trait Stamped {
+    fn ts(&self) -> i64;
+}
+
+impl Stamped for WeatherCache {
+    fn ts(&self) -> i64 { self.ts }
+}
+
+impl Stamped for NewsCache {
+    fn ts(&self) -> i64 { self.ts }
+}
+
+impl Stamped for StockCache {
+    fn ts(&self) -> i64 { self.ts }
+}
+
+impl Stamped for RatesCache {
+    fn ts(&self) -> i64 { self.ts }
+}
+
+/// Names one cache entry and knows how to read it.
+pub trait CacheKey {
+    type Entry: Stamped;
+    /// Cache kind, for the lookup counters in `cache_stats`.
+    const KIND: &'static str;
+    fn load(&self) -> Option<Self::Entry>;
+}
+
+/// The user's weather for a city in "C" or "F".
+pub struct WeatherKey<'a> {
+    pub user: &'a str,
+    pub city: &'a str,
+    pub units: &'a str,
+}
+
+impl CacheKey for WeatherKey<'_> {
+    type Entry = WeatherCache;
+    const KIND: &'static str = "weather";
+    fn load(&self) -> Option<WeatherCache> {
+        load_weather_for(self.user, self.city, self.units)
+    }
+}
+
+/// The user's stories for a feed key (see `news::feed_key`).
+pub struct NewsKey<'a> {
+    pub user: &'a str,
+    pub topic: &'a str,
+}
+
+impl CacheKey for NewsKey<'_> {
+    type Entry = NewsCache;
+    const KIND: &'static str = "news";
+    fn load(&self) -> Option<NewsCache> {
+        load_news_for(self.user, self.topic)
+    }
+}
+
+/// The user's closes for a watchlist symbol.
+pub struct StockKey<'a> {
+    pub user: &'a str,
+    pub symbol: &'a str,
+}
+
+impl CacheKey for StockKey<'_> {
+    type Entry = StockCache;
+    const KIND: &'static str = "stocks";
+    fn load(&self) -> Option<StockCache> {
+        load_stock_for(self.user, self.symbol)
+    }
+}
+
+/// The user's exchange rates.
+pub struct RatesKey<'a> {
+    pub user: &'a str,
+}
+
+impl CacheKey for RatesKey<'_> {
+    type Entry = RatesCache;
+    const KIND: &'static str = "rates";
+    fn load(&self) -> Option<RatesCache> {
+        load_rates_for(self.user)
+    }
+}
+
+/// A cached entry, and whether it's past its TTL.
+pub struct Tagged<T> {
+    pub entry: T,
+    pub stale: bool,
+}
+
+/// Return the cached entry for `key` right away, tagged stale once it's older
+/// than `ttl_secs` (0 counts everything as stale, for Refresh buttons). Unless
+/// it was fresh, `fetch` runs on `handle` and its result arrives on the returned
+/// channel; saving it to the cache is up to the caller.
+pub fn cached_or_refresh<K, T, Fut>(
+    handle: &tokio::runtime::Handle,
+    key: &K,
+    ttl_secs: i64,
+    fetch: impl FnOnce() -> Fut,
+) -> (Option<Tagged<K::Entry>>, Option<tokio::sync::oneshot::Receiver<T>>)
+where
+    K: CacheKey,
+    T: Send + 'static,
+    Fut: std::future::Future<Output = T> + Send + 'static,
+{
+    let cached = key.load().map(|entry| {
+        let stale = ttl_secs <= 0 || !is_fresh(entry.ts(), ttl_secs);
+        Tagged { entry, stale }
+    });
+    count_lookup(K::KIND, cached.as_ref().map(|c| c.stale));
+    if cached.as_ref().is_some_and(|c| !c.stale) {
+        return (cached, None);
+    }
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let fetching = fetch();
+    handle.spawn(async move {
+        let _ = tx.send(fetching.await);
+    });
+    (cached, Some(rx))
+}
+
+/// A news cache and its thumbnails are used together.
+fn touch_news(user: &str, n: &NewsCache) {
+    let path = doc_path(user, "news", &n.topic);
+    let mut paths = vec![path.as_path()];
+    paths.extend(n.rows.iter().filter(|r| !r.thumb.is_empty()).map(|r| Path::new(&r.thumb)));
+    touch(user, &paths);
+}
+
+// URLs already seen by the background news poll (oldest first, capped)
+
+const MAX_SEEN: usize = 500;
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct SeenCache { pub urls: Vec<String> }
+
+impl SeenCache {
+    /// Record `url`; returns false if it was already known.
+    pub fn insert(&mut self, url: String) -> bool {
+        if self.urls.contains(&url) {
+            return false;
+        }
+        self.urls.push(url);
+        if self.urls.len() > MAX_SEEN {
+            let extra = self.urls.len() - MAX_SEEN;
+            self.urls.drain(..extra);
+        }
+        true
+    }
+}
+
+pub fn load_seen_for(user: &str) -> SeenCache {
+    load_doc(user, "seen").unwrap_or_default()
+}
+
+pub fn save_seen_for(user: &str, seen: &SeenCache) -> io::Result<()> {
+    save_doc(user, "seen", seen)
+}
+
+// Stories the user opened (canonical URLs, oldest first, capped)
+
+const MAX_READ: usize = 2000;
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct ReadCache { pub urls: Vec<String> }
+
+impl ReadCache {
+    pub fn contains(&self, url: &str) -> bool {
+        self.urls.contains(&canonical_url(url))
+    }
+
+    /// Mark `url` as read; returns false if it already was.
+    pub fn insert(&mut self, url: &str) -> bool {
+        let key = canonical_url(url);
+        if self.urls.contains(&key) {
+            return false;
+        }
+        self.urls.push(key);
+        if self.urls.len() > MAX_READ {
+            let extra = self.urls.len() - MAX_READ;
+            self.urls.drain(..extra);
+        }
+        true
+    }
+}
+
+pub fn load_read_for(user: &str) -> ReadCache {
+    load_doc(user, "read").unwrap_or_default()
+}
+
+pub fn save_read_for(user: &str, read: &ReadCache) -> io::Result<()> {
+    save_doc(user, "read", read)
+}
+
+// Bookmarked stories (newest first)
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Bookmark {
+    pub title: String,
+    pub source: String,
+    pub published: String,
+    pub url: String,
+    #[serde(default)] pub object_id: String,
+    pub saved_at: i64,
+    #[serde(default)] pub offline: bool,   // an offline reader copy was stored
+    #[serde(default)] pub paywalled: bool,
+}
+
+impl Bookmark {
+    /// Rebuild an article card; the thumbnail is the offline copy's lead image, if any.
+    pub fn to_article(&self, user: &str) -> Article {
+        let thumbnail = load_offline_for(user, &self.url)
+            .and_then(|d| d.images.first().and_then(|p| load_thumb(user, p)))
+            .unwrap_or_else(|| SharedPixelBuffer::new(10, 10));
+        Article {
+            title: self.title.clone(),
+            source: self.source.clone(),
+            published: self.published.clone(),
+            url: self.url.clone(),
+            object_id: self.object_id.clone(),
+            points: 0,
+            comments: 0,
+            thumbnail,
+            favicon: cached_favicon(&self.source),
+            paywalled: self.paywalled,
+            author: String::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct BookmarkStore { pub items: Vec<Bookmark> }
+
+impl BookmarkStore {
+    pub fn contains(&self, url: &str) -> bool {
+        self.items.iter().any(|b| b.url == url)
+    }
+
+    /// Remove the bookmark for `url`; returns false if there was none.
+    pub fn remove(&mut self, url: &str) -> bool {
+        let before = self.items.len();
+        self.items.retain(|b| b.url != url);
+        self.items.len() != before
+    }
+
+    pub fn add(&mut self, b: Bookmark) {
+        self.remove(&b.url);
+        self.items.insert(0, b);
+    }
+}
+
+pub fn load_bookmarks_for(user: &str) -> BookmarkStore {
+    load_doc(user, "bookmarks").unwrap_or_default()
+}
+
+pub fn save_bookmarks_for(user: &str, store: &BookmarkStore) -> io::Result<()> {
+    save_doc(user, "bookmarks", store)
+}
+
+// Offline reader copies: one folder per article (doc.json + images)
+
+fn offline_path_for(user: &str, url: &str) -> PathBuf {
+    users_cache_base()
+        .join(user)
+        .join("offline")
+        .join(format!("{:016x}", fnv1a(url)))
+}
+
+/// Folder for the offline copy of `url` (created if missing).
+pub fn offline_dir_for(user: &str, url: &str) -> io::Result<PathBuf> {
+    let dir = offline_path_for(user, url);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+pub fn save_offline_for(user: &str, doc: &ReaderDoc) -> io::Result<()> {
+    let dir = offline_dir_for(user, &doc.url)?;
+    write_offline_doc(user, &dir, doc)?;
+    touch(user, &[&dir]);
+    enforce_size_limit(user);
+    Ok(())
+}
+
+pub fn load_offline_for(user: &str, url: &str) -> Option<ReaderDoc> {
+    let dir = offline_path_for(user, url);
+    let doc = read_offline_doc(user, &dir)?;
+    touch(user, &[&dir]);
+    Some(doc)
+}
+
+/// An offline copy's text (`doc.json`), decrypted if needed.
+fn read_offline_doc(user: &str, dir: &Path) -> Option<ReaderDoc> {
+    let raw = fs::read_to_string(dir.join("doc.json")).ok()?;
+    match cachecrypt::open(user, &raw) {
+        Opened::Plain(text) => serde_json::from_str(&text).ok(),
+        Opened::Locked | Opened::Damaged => None,
+    }
+}
+
+fn write_offline_doc(user: &str, dir: &Path, doc: &ReaderDoc) -> io::Result<()> {
+    fs::write(dir.join("doc.json"), encrypt_for(user, serde_json::to_string_pretty(doc)?)?)
+}
+
+pub fn remove_offline_for(user: &str, url: &str) {
+    let _ = fs::remove_dir_all(offline_path_for(user, url));
+}
+
+// Article summaries, keyed by URL
+
+fn load_summaries_for(user: &str) -> HashMap<String, Vec<String>> {
+    load_doc(user, "summaries").unwrap_or_default()
+}
+
+pub fn load_summary_for(user: &str, url: &str) -> Option<Vec<String>> {
+    let summary = load_summaries_for(user).remove(url)?;
+    touch(user, &[&doc_path(user, "summaries", "")]);
+    Some(summary)
+}
+
+pub fn save_summary_for(user: &str, url: &str, bullets: &[String]) -> io::Result<()> {
+    let mut all = load_summaries_for(user);
+    all.insert(url.to_string(), bullets.to_vec());
+    save_doc(user, "summaries", &all)?;
+    touch(user, &[&doc_path(user, "summaries", "")]);
+    enforce_size_limit(user);
+    Ok(())
+}
+
+// Per-topic / per-source open counts for the "For You" feed
+
+pub fn load_open_stats_for(user: &str) -> OpenStats {
+    load_doc(user, "opens").unwrap_or_default()
+}
+
+pub fn save_open_stats_for(user: &str, stats: &OpenStats) -> io::Result<()> {
+    save_doc(user, "opens", stats)
+}
+
+// Favorite and recent weather locations (quick switching on the weather page and in Settings)
+
+/// Recent locations kept; favorites aren't limited.
+const MAX_RECENT_LOCATIONS: usize = 6;
+
+/// A city the user looked at: what they typed and the place it resolved to.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SavedLocation {
+    pub city: String,
+    pub place: Place,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct LocationStore {
+    #[serde(default)] pub favorites: Vec<SavedLocation>,
+    /// Most recent first.
+    #[serde(default)] pub recent: Vec<SavedLocation>,
+}
+
+impl LocationStore {
+    pub fn is_favorite(&self, label: &str) -> bool {
+        self.favorites.iter().any(|l| l.place.label == label)
+    }
+
+    /// A favorite or recent location by its place label.
+    pub fn find(&self, label: &str) -> Option<&SavedLocation> {
+        self.favorites.iter().chain(&self.recent).find(|l| l.place.label == label)
+    }
+
+    /// Move `loc` to the front of the recent list (one entry per place).
+    pub fn note(&mut self, loc: SavedLocation) {
+        self.recent.retain(|l| l.place.label != loc.place.label);
+        self.recent.insert(0, loc);
+        self.recent.truncate(MAX_RECENT_LOCATIONS);
+    }
+
+    /// Star or unstar `loc`; returns whether it's a favorite now.
+    pub fn toggle_favorite(&mut self, loc: SavedLocation) -> bool {
+        if self.is_favorite(&loc.place.label) {
+            self.favorites.retain(|l| l.place.label != loc.place.label);
+            false
+        } else {
+            self.favorites.push(loc);
+            true
+        }
+    }
+
+    /// Favorites first, then the recent ones that aren't favorites.
+    pub fn quick_list(&self) -> Vec<(&SavedLocation, bool)> {
+        let recent = self.recent.iter().filter(|l| !self.is_favorite(&l.place.label));
+        self.favorites.iter().map(|l| (l, true)).chain(recent.map(|l| (l, false))).collect()
+    }
+}
+
+pub fn load_locations_for(user: &str) -> LocationStore {
+    load_doc(user, "locations").unwrap_or_default()
+}
+
+pub fn save_locations_for(user: &str, store: &LocationStore) -> io::Result<()> {
+    save_doc(user, "locations", store)
+}
+
+// Resolved places (geocoding), by lookup: `places.json` in the user's cache, so
+// it's encrypted and counted against the size limit like the rest
+
+/// Lookups kept; the ones resolved longest ago go first.
+const MAX_PLACE_LOOKUPS: usize = 200;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CachedPlaces {
+    pub ts: i64,
+    pub places: Vec<Place>,
+}
+
+/// The places stored for lookup `key`, however old.
+pub fn load_places_for(user: &str, key: &str) -> Option<CachedPlaces> {
+    let mut all: BTreeMap<String, CachedPlaces> = load_doc(user, "places")?;
+    let found = all.remove(key)?;
+    touch(user, &[&doc_path(user, "places", "")]);
+    Some(found)
+}
+
+pub fn save_places_for(user: &str, key: &str, places: &[Place]) -> io::Result<()> {
+    let mut all: BTreeMap<String, CachedPlaces> = load_doc(user, "places").unwrap_or_default();
+    all.insert(key.to_string(), CachedPlaces { ts: Utc::now().timestamp(), places: places.to_vec() });
+    if all.len() > MAX_PLACE_LOOKUPS {
+        let mut stamps: Vec<i64> = all.values().map(|c| c.ts).collect();
+        stamps.sort_unstable();
+        let cutoff = stamps[all.len() - MAX_PLACE_LOOKUPS];
+        all.retain(|_, c| c.ts >= cutoff);
+    }
+    save_doc(user, "places", &all)?;
+    touch(user, &[&doc_path(user, "places", "")]);
+    enforce_size_limit(user);
+    Ok(())
+}
+
+// Size limit: past it, the least recently used entries are evicted. Use times
+// live in `lru.json` in the user's cache folder (file times when missing).
+
+/// Per-user cache limit in bytes (0 = no limit); set from the user's settings.
+static CACHE_LIMIT_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Serializes updates of the usage index (loads and saves run on several tasks).
+static LRU_LOCK: Mutex<()> = Mutex::new(());
+
+const LRU_FILE: &str = "lru.json";
+
+/// The user's own state rather than re-fetchable data: never evicted.
+/// `cache.db` holds bookmarks too, so with the SQLite store only files outside it are evicted.
+const PINNED: [&str; 9] = [
+    "bookmarks.json", "read.json", "seen.json", "opens.json", "locations.json", LRU_FILE,
+    cachestore::DB_FILE, "cache.db-wal", "cache.db-shm",
+];
+
+/// Folders whose entries are evicted one at a time (a thumbnail, an offline copy,
+/// one city's weather, one topic's news or one downloaded episode).
+const SPLIT_DIRS: [&str; 7] =
+    ["thumbs", "offline", "weather", "news", "stocks", QUARANTINE_DIR, crate::podcasts::DOWNLOADS];
+
+#[derive(Serialize, Deserialize, Default)]
+struct LruIndex {
+    /// Entry (path relative to the user's cache folder) → last use, Unix millis.
+    #[serde(default)]
+    used: HashMap<String, i64>,
+}
+
+pub fn set_size_limit(bytes: u64) {
+    CACHE_LIMIT_BYTES.store(bytes, Ordering::Relaxed);
+}
+
+fn load_lru(dir: &Path) -> LruIndex {
+    fs::read_to_string(dir.join(LRU_FILE))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_lru(dir: &Path, idx: &LruIndex) -> io::Result<()> {
+    fs::write(dir.join(LRU_FILE), serde_json::to_string_pretty(idx)?)?;
     Ok(())
 }
 
-pub fn load_news_for(user: &str) -> Option<NewsCache> {
-    let p = news_path_for(user).ok()?;
-    let s = fs::read_to_string(p).ok()?;
-    serde_json::from_str(&s).ok()
+/// The evictable entry `path` belongs to, relative to the user's cache folder.
+fn lru_key(user: &str, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(users_cache_base().join(user)).ok()?;
+    let mut parts = rel.components().map(|c| c.as_os_str().to_string_lossy().into_owned());
+    let first = parts.next()?;
+    if SPLIT_DIRS.contains(&first.as_str()) {
+        Some(format!("{first}/{}", parts.next()?))
+    } else {
+        Some(first)
+    }
 }
 
+/// Record that `paths` were just used.
+fn touch(user: &str, paths: &[&Path]) {
+    let Ok(_guard) = LRU_LOCK.lock() else { return };
+    let dir = users_cache_base().join(user);
+    let mut idx = load_lru(&dir);
+    let now = Utc::now().timestamp_millis();
+    for key in paths.iter().filter_map(|p| lru_key(user, p)) {
+        idx.used.insert(key, now);
+    }
+    let _ = save_lru(&dir, &idx);
+}
 
+/// Record that a downloaded podcast episode was just played or fetched.
+pub fn touch_download(user: &str, path: &Path) {
+    touch(user, &[path]);
+}
 
+/// Bytes on disk under `path` (a file or a whole folder).
+pub fn disk_size(path: &Path) -> u64 {
+    match fs::read_dir(path) {
+        Ok(entries) => entries.flatten().map(|e| disk_size(&e.path())).sum(),
+        Err(_) => fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+    }
+}
+
+fn modified_millis(path: &Path) -> i64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(|t| chrono::DateTime::<Utc>::from(t).timestamp_millis())
+        .unwrap_or(0)
+}
+
+/// Entries that may be evicted: top-level files other than `PINNED`, plus each
+/// thumbnail and offline copy on its own.
+fn evictable_entries(dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut out = Vec::new();
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if SPLIT_DIRS.contains(&name.as_str()) {
+            for sub in fs::read_dir(entry.path()).into_iter().flatten().flatten() {
+                out.push((format!("{name}/{}", sub.file_name().to_string_lossy()), sub.path()));
+            }
+        } else if !PINNED.contains(&name.as_str()) && entry.path().is_file() {
+            out.push((name, entry.path()));
+        }
+    }
+    out
+}
+
+/// Evict `user`'s least recently used entries until the cache folder fits in
+/// `limit` bytes; returns how many bytes were freed.
+pub fn trim_user_cache(user: &str, limit: u64) -> u64 {
+    let dir = users_cache_base().join(user);
+    let Ok(_guard) = LRU_LOCK.lock() else { return 0 };
+    let mut total = disk_size(&dir);
+    if total <= limit {
+        return 0;
+    }
+    let mut idx = load_lru(&dir);
+    let mut entries: Vec<(i64, String, PathBuf)> = evictable_entries(&dir)
+        .into_iter()
+        .map(|(key, path)| (idx.used.get(&key).copied().unwrap_or_else(|| modified_millis(&path)), key, path))
+        .collect();
+    entries.sort_by_key(|e| e.0);
+
+    let mut freed = 0;
+    for (_, key, path) in entries {
+        if total <= limit {
+            break;
+        }
+        let size = disk_size(&path);
+        let removed = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+        if removed.is_ok() {
+            total = total.saturating_sub(size);
+            freed += size;
+            idx.used.remove(&key);
+        }
+    }
+    idx.used.retain(|key, _| dir.join(key).exists());
+    let _ = save_lru(&dir, &idx);
+    if freed > 0 {
+        forget_all(user, None);
+    }
+    freed
+}
+
+/// Apply the configured limit (if any) to `user`'s cache.
+pub fn enforce_size_limit(user: &str) -> u64 {
+    match CACHE_LIMIT_BYTES.load(Ordering::Relaxed) {
+        0 => 0,
+        limit => trim_user_cache(user, limit),
+    }
+}
+
+// Clear-cache actions (Settings)
+
+/// What a clear-cache action removes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClearScope {
+    Weather,
+    News,
+    /// Both, plus summaries, stock quotes, exchange rates, resolved places, podcast downloads, the usage index and the icons and favicons shared by all users.
+    All,
+}
+
+/// Remove `user`'s cached data in `scope`; returns the bytes freed. Bookmarks,
+/// their offline copies, saved locations and the read / seen / open lists are never touched.
+pub fn clear_cache(user: &str, scope: ClearScope) -> io::Result<u64> {
+    let dir = user_cache_dir(user)?;
+    let size = || disk_size(&dir) + if scope == ClearScope::All { disk_size(&icons_dir()) } else { 0 };
+    let before = size();
+    let _guard = LRU_LOCK.lock();
+    let store = cachestore::open(&dir);
+    if matches!(scope, ClearScope::Weather | ClearScope::All) {
+        forget_all(user, Some("weather"));
+        store.clear("weather")?;
+    }
+    if matches!(scope, ClearScope::News | ClearScope::All) {
+        forget_all(user, Some("news"));
+        store.clear("news")?;
+        let _ = fs::remove_dir_all(dir.join("thumbs"));
+    }
+    if scope == ClearScope::All {
+        forget_all(user, Some("summaries"));
+        store.clear("summaries")?;
+        forget_all(user, Some("stocks"));
+        store.clear("stocks")?;
+        forget_all(user, Some("rates"));
+        store.clear("rates")?;
+        forget_all(user, Some("places"));
+        store.clear("places")?;
+        let _ = fs::remove_file(dir.join(LRU_FILE));
+        let _ = fs::remove_dir_all(dir.join(QUARANTINE_DIR));
+        let _ = fs::remove_dir_all(dir.join(crate::podcasts::DOWNLOADS));
+        let _ = fs::remove_dir_all(icons_dir());
+        crate::httpcache::clear();
+    }
+    Ok(before.saturating_sub(size()))
+}
+
+/// `bytes` for status lines ("512 bytes", "3.4 KB", "12.0 MB").
+pub fn human_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    match bytes as f64 {
+        b if b >= KB * KB => format!("{:.1} MB", b / (KB * KB)),
+        b if b >= KB => format!("{:.1} KB", b / KB),
+        _ => format!("{bytes} bytes"),
+    }
+}
+
+// Statistics (Settings → Diagnostics): what's on disk, plus lookup counters
+// collected since start-up
+
+#[derive(Clone, Copy, Default)]
+struct Lookups {
+    hits: u64,
+    stale: u64,
+    misses: u64,
+}
+
+static LOOKUPS: Mutex<std::collections::BTreeMap<&'static str, Lookups>> =
+    Mutex::new(std::collections::BTreeMap::new());
+
+/// Record a lookup: `Some(false)` fresh, `Some(true)` stale, `None` nothing cached.
+fn count_lookup(kind: &'static str, stale: Option<bool>) {
+    if let Ok(mut all) = LOOKUPS.lock() {
+        let l = all.entry(kind).or_default();
+        match stale {
+            Some(false) => l.hits += 1,
+            Some(true) => l.stale += 1,
+            None => l.misses += 1,
+        }
+    }
+}
+
+/// One group of cached data (weather, thumbnails, …).
+pub struct CacheStat {
+    pub name: &'static str,
+    pub entries: usize,
+    pub bytes: u64,
+    /// Unix seconds of the newest and oldest entry, if known.
+    pub newest: Option<i64>,
+    pub oldest: Option<i64>,
+    lookups: Option<Lookups>,
+}
+
+impl CacheStat {
+    fn new(name: &'static str) -> Self {
+        CacheStat { name, entries: 0, bytes: 0, newest: None, oldest: None, lookups: None }
+    }
+
+    fn add(&mut self, bytes: u64, ts: Option<i64>) {
+        self.entries += 1;
+        self.bytes += bytes;
+        if let Some(ts) = ts {
+            self.newest = Some(self.newest.map_or(ts, |n| n.max(ts)));
+            self.oldest = Some(self.oldest.map_or(ts, |o| o.min(ts)));
+        }
+    }
+
+    /// "Weather: 3 entries • 12.4 KB • newest 5m, oldest 2h • 4 fresh / 1 stale / 2 missing"
+    pub fn summary(&self) -> String {
+        let mut line = format!("{}: {} entr{} • {}", self.name, self.entries, if self.entries == 1 { "y" } else { "ies" }, human_size(self.bytes));
+        if let (Some(newest), Some(oldest)) = (self.newest, self.oldest) {
+            line.push_str(&format!(" • newest {}, oldest {}", age_label(newest), age_label(oldest)));
+        }
+        if let Some(l) = self.lookups {
+            line.push_str(&format!(" • {} fresh / {} stale / {} missing", l.hits, l.stale, l.misses));
+        }
+        line
+    }
+}
+
+/// Age of a Unix timestamp, shortest unit ("40s", "5m", "3h", "2d").
+fn age_label(ts: i64) -> String {
+    match Utc::now().timestamp().saturating_sub(ts).max(0) {
+        s if s < 60 => format!("{s}s"),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86_400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86_400),
+    }
+}
+
+fn modified_secs(path: &Path) -> Option<i64> {
+    Some(modified_millis(path) / 1000).filter(|&s| s > 0)
+}
+
+/// Entries of a folder of files / sub-folders, by size and modification time.
+fn folder_stat(name: &'static str, dir: &Path) -> CacheStat {
+    let mut stat = CacheStat::new(name);
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        stat.add(disk_size(&entry.path()), modified_secs(&entry.path()));
+    }
+    stat
+}
+
+/// Everything in `user`'s cache folder, bookmarks and database overhead included.
+pub fn user_cache_size(user: &str) -> u64 {
+    disk_size(&users_cache_base().join(user))
+}
+
+/// Walk `user`'s cache (and the shared icons) for the diagnostics view.
+pub fn cache_stats(user: &str) -> Vec<CacheStat> {
+    let Ok(dir) = user_cache_dir(user) else { return Vec::new() };
+    let store = cachestore::open(&dir);
+    let lookups = LOOKUPS.lock().map(|l| l.clone()).unwrap_or_default();
+    let mut out = Vec::new();
+
+    for (name, kind) in [("Weather", "weather"), ("News", "news"), ("Stocks", "stocks")] {
+        let mut stat = CacheStat::new(name);
+        let mut keys = store.keys(kind).unwrap_or_default();
+        keys.push(String::new());
+        for key in keys {
+            if let Ok(Some(data)) = store.get(kind, &key) {
+                let ts = open_entry(user, &data).and_then(|d| serde_json::from_str::<Stamp>(&d).ok()).map(|s| s.ts);
+                stat.add(data.len() as u64, ts);
+            }
+        }
+        stat.lookups = Some(lookups.get(kind).copied().unwrap_or_default());
+        out.push(stat);
+    }
+    out.push(folder_stat("Thumbnails", &dir.join("thumbs")));
+    out.push(folder_stat("Offline copies", &dir.join("offline")));
+    out.push(folder_stat("Podcast downloads", &dir.join(crate::podcasts::DOWNLOADS)));
+    let documents: [(&'static str, &[&str]); 5] = [
+        ("Summaries", &["summaries"]),
+        ("Exchange rates", &["rates"]),
+        ("Bookmarks", &["bookmarks"]),
+        ("Locations", &["locations"]),
+        ("Read / seen lists", &["read", "seen", "opens"]),
+    ];
+    for (name, kinds) in documents {
+        let mut stat = CacheStat::new(name);
+        for kind in kinds {
+            if let Ok(Some(data)) = store.get(kind, "") {
+                stat.add(data.len() as u64, None);
+            }
+        }
+        out.push(stat);
+    }
+    out.push(folder_stat("Shared icons", &icons_dir()));
+    out
+}
@@ -0,0 +1,272 @@
+use crate::{bundle::derive_key, config};
+use anyhow::{anyhow, bail, Context};
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use blake2::{
+    digest::{consts::U16, Mac},
+    Blake2bMac,
+};
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, Key, KeyInit, Nonce};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::PathBuf, sync::Mutex};
+use zeroize::Zeroizing;
+
+// Encrypted cache at rest: a user who turns it on gets a random cache key, kept
+// in their config folder sealed with a key derived from their PIN. Signing in
+// with the PIN opens it for the rest of the run; until then their encrypted
+// entries read as missing and nothing new is cached. Cached files (thumbnails,
+// offline images, episodes) are sealed with the same key.
+
+/// Next to `config.json`; its presence means the user's cache is encrypted.
+const KEY_FILE: &str = "cache_key.json";
+
+/// The cache key, sealed with ChaCha20-Poly1305 under an Argon2id key from the PIN.
+#[derive(Serialize, Deserialize)]
+struct WrappedKey {
+    salt: String,
+    nonce: String,
+    key: String,
+}
+
+/// How an encrypted entry is stored; plain ones are JSON without `sealed`.
+#[derive(Serialize, Deserialize)]
+struct Encrypted {
+    nonce: String,
+    sealed: String,
+}
+
+/// Starts a file sealed with `seal_bytes`; no image or audio format begins so.
+const FILE_MAGIC: &[u8] = b"DSKCRYPT";
+
+/// Cache keys opened in this run, per user; wiped when dropped.
+static UNLOCKED: Mutex<BTreeMap<String, Zeroizing<[u8; 32]>>> = Mutex::new(BTreeMap::new());
+
+fn key_path(user: &str) -> io::Result<PathBuf> {
+    Ok(config::users_base_dir()?.join(user).join(KEY_FILE))
+}
+
+/// Whether `user` keeps their cache encrypted.
+pub fn enabled(user: &str) -> bool {
+    key_path(user).is_ok_and(|p| p.exists())
+}
+
+fn unlocked_key(user: &str) -> Option<Zeroizing<[u8; 32]>> {
+    UNLOCKED.lock().ok()?.get(user).cloned()
+}
+
+/// Whether `user`'s cache key is open in this run.
+pub fn is_open(user: &str) -> bool {
+    unlocked_key(user).is_some()
+}
+
+fn wrap(pin: &str, key: &[u8; 32]) -> anyhow::Result<WrappedKey> {
+    let (mut salt, mut nonce) = ([0u8; 16], [0u8; 12]);
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&derive_key(pin, &salt)?[..]));
+    let sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce), &key[..])
+        .map_err(|_| anyhow!("encryption failed"))?;
+    Ok(WrappedKey { salt: B64.encode(salt), nonce: B64.encode(nonce), key: B64.encode(sealed) })
+}
+
+fn unwrap(pin: &str, wrapped: &WrappedKey) -> anyhow::Result<Zeroizing<[u8; 32]>> {
+    let nonce = B64.decode(&wrapped.nonce)?;
+    if nonce.len() != 12 {
+        bail!("damaged cache key");
+    }
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&derive_key(pin, &B64.decode(&wrapped.salt)?)?[..]));
+    let plain = Zeroizing::new(
+        cipher
+            .decrypt(Nonce::from_slice(&nonce), B64.decode(&wrapped.key)?.as_slice())
+            .map_err(|_| anyhow!("the cache key doesn't open with this PIN"))?,
+    );
+    let mut key = Zeroizing::new([0u8; 32]);
+    if plain.len() != key.len() {
+        bail!("damaged cache key");
+    }
+    key.copy_from_slice(&plain);
+    Ok(key)
+}
+
+fn write_wrapped(user: &str, pin: &str, key: &[u8; 32]) -> anyhow::Result<()> {
+    let path = config::user_root(user)?.join(KEY_FILE);
+    config::write_private(&path, serde_json::to_string_pretty(&wrap(pin, key)?)?.as_bytes())?;
+    Ok(())
+}
+
+fn read_wrapped(user: &str) -> anyhow::Result<WrappedKey> {
+    let text = fs::read_to_string(key_path(user)?)?;
+    serde_json::from_str(&text).context("damaged cache key")
+}
+
+/// Open `user`'s cache key with the PIN they just signed in with (no-op if their
+/// cache isn't encrypted or the key is open already). A missing or damaged key,
+/// or one the PIN doesn't open, is an error and the cache stays closed.
+pub fn unlock(user: &str, pin: &str) -> anyhow::Result<()> {
+    if !enabled(user) || unlocked_key(user).is_some() {
+        return Ok(());
+    }
+    let key = unwrap(pin, &read_wrapped(user)?)?;
+    if let Ok(mut unlocked) = UNLOCKED.lock() {
+        unlocked.insert(user.to_string(), key);
+    }
+    Ok(())
+}
+
+/// Forget `user`'s opened key (log out, account deleted).
+pub fn lock(user: &str) {
+    if let Ok(mut unlocked) = UNLOCKED.lock() {
+        unlocked.remove(user);
+    }
+}
+
+/// Create and open a cache key for `user`, sealed with `pin`. Existing entries
+/// stay as they are until `cache::set_cache_encryption` rewrites them.
+pub fn enable(user: &str, pin: &str) -> anyhow::Result<()> {
+    if enabled(user) {
+        return unlock(user, pin);
+    }
+    let mut key = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(&mut key[..]);
+    write_wrapped(user, pin, &key)?;
+    if let Ok(mut unlocked) = UNLOCKED.lock() {
+        unlocked.insert(user.to_string(), key);
+    }
+    Ok(())
+}
+
+/// Stop encrypting new entries for `user`. The opened key stays in memory so
+/// the existing ones can still be decrypted; `lock` drops it afterwards.
+pub fn disable(user: &str) -> io::Result<()> {
+    match fs::remove_file(key_path(user)?) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Seal the cache key with a new PIN after a PIN change.
+pub fn rewrap(user: &str, old_pin: &str, new_pin: &str) -> anyhow::Result<()> {
+    if !enabled(user) {
+        return Ok(());
+    }
+    let key = unwrap(old_pin, &read_wrapped(user)?)?;
+    write_wrapped(user, new_pin, &key)
+}
+
+/// Seal the cache key with the PIN just set from a recovery code. Without the
+/// old PIN the key only carries over if it's open in this run (the account was
+/// locked, not signed out); otherwise a new key is started and the old entries
+/// are dropped as damaged when next read. Whether the old key carried over.
+pub fn reseal(user: &str, new_pin: &str) -> anyhow::Result<bool> {
+    if !enabled(user) {
+        return Ok(true);
+    }
+    if let Some(key) = unlocked_key(user) {
+        write_wrapped(user, new_pin, &key)?;
+        return Ok(true);
+    }
+    let mut key = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(&mut key[..]);
+    write_wrapped(user, new_pin, &key)?;
+    if let Ok(mut unlocked) = UNLOCKED.lock() {
+        unlocked.insert(user.to_string(), key);
+    }
+    Ok(false)
+}
+
+/// Keep a renamed user's opened key under the new name (the key file moves
+/// with their config folder).
+pub fn rename_user(old: &str, new: &str) {
+    if let Ok(mut unlocked) = UNLOCKED.lock() {
+        if let Some(key) = unlocked.remove(old) {
+            unlocked.insert(new.to_string(), key);
+        }
+    }
+}
+
+/// `user`'s opened key, or the error for a closed one.
+fn opened_key(user: &str) -> io::Result<Zeroizing<[u8; 32]>> {
+    unlocked_key(user).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::PermissionDenied, "cache is encrypted; sign in with your PIN to use it")
+    })
+}
+
+/// Encrypt a cache entry with `user`'s opened key; fails while it's closed, so
+/// nothing is ever written in the clear for a user who turned encryption on.
+pub fn encrypt(user: &str, plain: &str) -> io::Result<String> {
+    let key = opened_key(user)?;
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    let sealed = ChaCha20Poly1305::new(Key::from_slice(&key[..]))
+        .encrypt(Nonce::from_slice(&nonce), plain.as_bytes())
+        .map_err(|_| io::Error::other("encryption failed"))?;
+    Ok(serde_json::to_string(&Encrypted { nonce: B64.encode(nonce), sealed: B64.encode(sealed) })?)
+}
+
+/// Encrypt a cached file's bytes with `user`'s opened key: the magic, the
+/// nonce, then the sealed bytes. Fails while the key is closed, like `encrypt`.
+pub fn seal_bytes(user: &str, plain: &[u8]) -> io::Result<Vec<u8>> {
+    let key = opened_key(user)?;
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    let sealed = ChaCha20Poly1305::new(Key::from_slice(&key[..]))
+        .encrypt(Nonce::from_slice(&nonce), plain)
+        .map_err(|_| io::Error::other("encryption failed"))?;
+    Ok([FILE_MAGIC, &nonce[..], &sealed].concat())
+}
+
+/// A cached file's bytes: plain ones as they are, sealed ones decrypted. Fails
+/// for a sealed file while `user`'s key is closed, or one that doesn't decrypt.
+pub fn open_bytes(user: &str, raw: Vec<u8>) -> io::Result<Vec<u8>> {
+    let Some(rest) = raw.strip_prefix(FILE_MAGIC) else {
+        return Ok(raw);
+    };
+    let key = opened_key(user)?;
+    let damaged = || io::Error::new(io::ErrorKind::InvalidData, "cached file doesn't decrypt");
+    if rest.len() < 12 {
+        return Err(damaged());
+    }
+    let (nonce, sealed) = rest.split_at(12);
+    ChaCha20Poly1305::new(Key::from_slice(&key[..]))
+        .decrypt(Nonce::from_slice(nonce), sealed)
+        .map_err(|_| damaged())
+}
+
+/// A name for the cache entry `key` (a city, a topic) that doesn't give it
+/// away: its BLAKE2b MAC under `user`'s opened key, in hex.
+pub fn entry_name(user: &str, key: &str) -> io::Result<String> {
+    let mut mac = <Blake2bMac<U16> as Mac>::new_from_slice(&opened_key(user)?[..]).map_err(io::Error::other)?;
+    mac.update(key.as_bytes());
+    Ok(mac.finalize().into_bytes().iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// A stored entry, as far as it can be read.
+pub enum Opened {
+    /// Plain text: written unencrypted, or decrypted.
+    Plain(String),
+    /// Encrypted, and the user's key isn't open: treat as missing.
+    Locked,
+    /// Encrypted, but doesn't decrypt with the user's key.
+    Damaged,
+}
+
+pub fn open(user: &str, raw: &str) -> Opened {
+    let Ok(enc) = serde_json::from_str::<Encrypted>(raw) else {
+        return Opened::Plain(raw.to_string());
+    };
+    let Some(key) = unlocked_key(user) else {
+        return Opened::Locked;
+    };
+    let (Ok(nonce), Ok(sealed)) = (B64.decode(&enc.nonce), B64.decode(&enc.sealed)) else {
+        return Opened::Damaged;
+    };
+    if nonce.len() != 12 {
+        return Opened::Damaged;
+    }
+    ChaCha20Poly1305::new(Key::from_slice(&key[..]))
+        .decrypt(Nonce::from_slice(&nonce), sealed.as_slice())
+        .ok()
+        .and_then(|p| String::from_utf8(p).ok())
+        .map_or(Opened::Damaged, Opened::Plain)
+}
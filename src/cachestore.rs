@@ -0,0 +1,266 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Storage for a user's cache documents (weather, news, bookmarks, …), each a
+/// JSON string under a kind and a key ("" for single documents). Thumbnails and
+/// offline copies stay plain files either way. `cache.rs` only talks to this trait.
+pub trait CacheStore {
+    fn get(&self, kind: &str, key: &str) -> io::Result<Option<String>>;
+    fn put(&self, kind: &str, key: &str, data: &str) -> io::Result<()>;
+    /// Removing a missing entry is not an error.
+    fn delete(&self, kind: &str, key: &str) -> io::Result<()>;
+    /// Keys stored under `kind`.
+    fn keys(&self, kind: &str) -> io::Result<Vec<String>>;
+    /// Remove every entry of `kind` and give the space back.
+    fn clear(&self, kind: &str) -> io::Result<()>;
+    /// Every entry as (kind, key, data), for backend switches.
+    fn all(&self) -> io::Result<Vec<(String, String, String)>>;
+    /// Replace everything in one go (backend switches).
+    fn replace_all(&self, entries: &[(String, String, String)]) -> io::Result<()>;
+}
+
+/// Where a user's cache documents live.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheStorage {
+    /// One JSON file per document (`<kind>.json`, keyed ones in `<kind>/<key>.json`).
+    Files,
+    /// A single `cache.db` in the user's cache folder.
+    Sqlite,
+}
+
+pub const DB_FILE: &str = "cache.db";
+
+/// Which backend `dir` (a user's cache folder) uses: SQLite once `cache.db` exists.
+pub fn storage(dir: &Path) -> CacheStorage {
+    if dir.join(DB_FILE).exists() { CacheStorage::Sqlite } else { CacheStorage::Files }
+}
+
+pub fn open(dir: &Path) -> Box<dyn CacheStore> {
+    match storage(dir) {
+        CacheStorage::Sqlite => Box::new(SqliteCacheStore::new(dir.join(DB_FILE))),
+        CacheStorage::Files => Box::new(FileCacheStore::new(dir.to_path_buf())),
+    }
+}
+
+/// Move every document of `dir` to the other backend. The new store is filled
+/// completely before the old one is removed, so a failure leaves the old one in use.
+pub fn set_storage(dir: &Path, to: CacheStorage) -> io::Result<()> {
+    if storage(dir) == to {
+        return Ok(());
+    }
+    let files = FileCacheStore::new(dir.to_path_buf());
+    match to {
+        CacheStorage::Sqlite => {
+            let entries = files.all()?;
+            let tmp = dir.join("cache.db.tmp");
+            let _ = fs::remove_file(&tmp);
+            SqliteCacheStore::new(tmp.clone()).replace_all(&entries)?;
+            fs::rename(&tmp, dir.join(DB_FILE))?;
+            files.replace_all(&[])
+        }
+        CacheStorage::Files => {
+            let db = SqliteCacheStore::new(dir.join(DB_FILE));
+            files.replace_all(&db.all()?)?;
+            drop(db);
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = fs::remove_file(dir.join(format!("{DB_FILE}{suffix}")));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Kinds the file store knows about; `all` only looks at these, so unrelated
+/// files in the cache folder (thumbnails, the usage index) are left alone.
+pub const KINDS: [&str; 11] =
+    ["weather", "news", "seen", "read", "bookmarks", "summaries", "opens", "locations", "stocks", "rates", "places"];
+
+/// The original layout: `<kind>.json`, or `<kind>/<url-encoded key>.json`.
+pub struct FileCacheStore {
+    dir: PathBuf,
+}
+
+impl FileCacheStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn path(&self, kind: &str, key: &str) -> PathBuf {
+        if key.is_empty() {
+            self.dir.join(format!("{kind}.json"))
+        } else {
+            self.dir.join(kind).join(format!("{}.json", urlencoding::encode(key)))
+        }
+    }
+}
+
+impl CacheStore for FileCacheStore {
+    fn get(&self, kind: &str, key: &str) -> io::Result<Option<String>> {
+        match fs::read_to_string(self.path(kind, key)) {
+            Ok(s) => Ok(Some(s)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn put(&self, kind: &str, key: &str, data: &str) -> io::Result<()> {
+        let path = self.path(kind, key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, data)
+    }
+
+    fn delete(&self, kind: &str, key: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(kind, key)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn keys(&self, kind: &str) -> io::Result<Vec<String>> {
+        let mut out = Vec::new();
+        for entry in fs::read_dir(self.dir.join(kind)).into_iter().flatten().flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Some(key) = name.strip_suffix(".json").and_then(|k| urlencoding::decode(k).ok()) {
+                out.push(key.into_owned());
+            }
+        }
+        Ok(out)
+    }
+
+    fn clear(&self, kind: &str) -> io::Result<()> {
+        self.delete(kind, "")?;
+        match fs::remove_dir_all(self.dir.join(kind)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn all(&self) -> io::Result<Vec<(String, String, String)>> {
+        let mut out = Vec::new();
+        for kind in KINDS {
+            if let Some(data) = self.get(kind, "")? {
+                out.push((kind.to_string(), String::new(), data));
+            }
+            for key in self.keys(kind)? {
+                if let Some(data) = self.get(kind, &key)? {
+                    out.push((kind.to_string(), key, data));
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    fn replace_all(&self, entries: &[(String, String, String)]) -> io::Result<()> {
+        for kind in KINDS {
+            self.delete(kind, "")?;
+            let _ = fs::remove_dir_all(self.dir.join(kind));
+        }
+        for (kind, key, data) in entries {
+            self.put(kind, key, data)?;
+        }
+        Ok(())
+    }
+}
+
+/// SQLite store (`cache.db`): one row per document, indexed by kind and key
+/// (city, topic), written in transactions instead of many small files.
+pub struct SqliteCacheStore {
+    path: PathBuf,
+}
+
+fn db_err(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+impl SqliteCacheStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn open(&self) -> io::Result<Connection> {
+        let conn = Connection::open(&self.path).map_err(db_err)?;
+        conn.busy_timeout(Duration::from_secs(5)).map_err(db_err)?;
+        conn.pragma_update(None, "journal_mode", "WAL").map_err(db_err)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS entries (
+                 kind TEXT NOT NULL,
+                 key  TEXT NOT NULL,
+                 data TEXT NOT NULL,
+                 PRIMARY KEY (kind, key)
+             );",
+        )
+        .map_err(db_err)?;
+        Ok(conn)
+    }
+}
+
+impl CacheStore for SqliteCacheStore {
+    fn get(&self, kind: &str, key: &str) -> io::Result<Option<String>> {
+        self.open()?
+            .query_row("SELECT data FROM entries WHERE kind = ?1 AND key = ?2", params![kind, key], |r| r.get(0))
+            .optional()
+            .map_err(db_err)
+    }
+
+    fn put(&self, kind: &str, key: &str, data: &str) -> io::Result<()> {
+        self.open()?
+            .execute(
+                "INSERT OR REPLACE INTO entries (kind, key, data) VALUES (?1, ?2, ?3)",
+                params![kind, key, data],
+            )
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    fn delete(&self, kind: &str, key: &str) -> io::Result<()> {
+        self.open()?
+            .execute("DELETE FROM entries WHERE kind = ?1 AND key = ?2", params![kind, key])
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    fn keys(&self, kind: &str) -> io::Result<Vec<String>> {
+        let conn = self.open()?;
+        let mut stmt = conn
+            .prepare("SELECT key FROM entries WHERE kind = ?1 AND key != '' ORDER BY key")
+            .map_err(db_err)?;
+        let rows = stmt.query_map(params![kind], |r| r.get::<_, String>(0)).map_err(db_err)?;
+        rows.collect::<Result<_, _>>().map_err(db_err)
+    }
+
+    fn clear(&self, kind: &str) -> io::Result<()> {
+        let conn = self.open()?;
+        conn.execute("DELETE FROM entries WHERE kind = ?1", params![kind]).map_err(db_err)?;
+        // deleted rows only become free pages; VACUUM shrinks the file
+        conn.execute_batch("VACUUM").map_err(db_err)
+    }
+
+    fn all(&self) -> io::Result<Vec<(String, String, String)>> {
+        let conn = self.open()?;
+        let mut stmt = conn.prepare("SELECT kind, key, data FROM entries").map_err(db_err)?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .map_err(db_err)?;
+        rows.collect::<Result<_, _>>().map_err(db_err)
+    }
+
+    fn replace_all(&self, entries: &[(String, String, String)]) -> io::Result<()> {
+        let mut conn = self.open()?;
+        let tx = conn.transaction().map_err(db_err)?;
+        tx.execute("DELETE FROM entries", []).map_err(db_err)?;
+        for (kind, key, data) in entries {
+            tx.execute(
+                "INSERT INTO entries (kind, key, data) VALUES (?1, ?2, ?3)",
+                params![kind, key, data],
+            )
+            .map_err(db_err)?;
+        }
+        tx.commit().map_err(db_err)
+    }
+}
@@ -0,0 +1,507 @@
+use crate::config::user_root;
+use crate::settings::{DefaultValue, Descriptor, Kind};
+use anyhow::{bail, Context};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use quick_xml::{events::Event as Xml, Reader};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, io, path::PathBuf, sync::Mutex, time::Duration};
+
+// Calendar: events per user in `calendar.json` next to their settings, from
+// .ics files they import and from one CalDAV calendar synced on a schedule.
+// Sync only reads the server: it asks for the events from a month back to a
+// year ahead, with repeating events expanded by the server. An imported event
+// and a server event with the same UID are the same event; the copy with the
+// newer LAST-MODIFIED is kept (the server's on a tie). The password lives in
+// the OS credential store (`secrets::caldav_password`).
+
+const FILE: &str = "calendar.json";
+
+/// The calendar collection's URL, e.g. "https://dav.example.com/calendars/me/personal/";
+/// empty = sync is off.
+pub const URL: Descriptor = Descriptor {
+    section: "calendar",
+    key: "url",
+    label: "CalDAV calendar URL",
+    kind: Kind::Text,
+    default: DefaultValue::Text(""),
+};
+
+pub const USERNAME: Descriptor = Descriptor {
+    section: "calendar",
+    key: "username",
+    label: "CalDAV username",
+    kind: Kind::Text,
+    default: DefaultValue::Text(""),
+};
+
+/// Minutes between background syncs; 0 = only when the page is opened or Sync is pressed.
+pub const SYNC_MINUTES: Descriptor = Descriptor {
+    section: "calendar",
+    key: "sync_minutes",
+    label: "Sync calendar every (min)",
+    kind: Kind::Number { min: 0, max: 1440 },
+    default: DefaultValue::Number(30),
+};
+
+/// Calendar settings for the registry in `settings.rs`.
+pub const SETTINGS: &[Descriptor] = &[URL, USERNAME, SYNC_MINUTES];
+
+/// Days before today and after it that a sync asks the server for.
+const SYNC_PAST_DAYS: i64 = 30;
+const SYNC_AHEAD_DAYS: i64 = 365;
+
+/// An unchanged collection tag skips the download, but not for longer than
+/// this: the sync window moves with the date.
+const CTAG_TRUST_SECS: i64 = 24 * 3600;
+
+/// Each request to the server gives up after this long.
+const DAV_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// From an .ics file.
+    Imported,
+    /// From the CalDAV server; replaced on every sync.
+    Server,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Event {
+    pub uid: String,
+    /// RECURRENCE-ID of one occurrence of a repeating event; empty otherwise.
+    #[serde(default)]
+    pub recurrence: String,
+    pub summary: String,
+    #[serde(default)]
+    pub location: String,
+    /// Unix seconds; all-day events start at midnight UTC of their first day.
+    pub start: i64,
+    /// Unix seconds, exclusive.
+    pub end: i64,
+    #[serde(default)]
+    pub all_day: bool,
+    /// LAST-MODIFIED (or DTSTAMP) in unix seconds, 0 if the file didn't say.
+    #[serde(default)]
+    pub modified: i64,
+    pub source: Source,
+}
+
+impl Event {
+    /// The first day, in the local zone for timed events.
+    pub fn first_day(&self) -> Option<NaiveDate> {
+        let start = DateTime::from_timestamp(self.start, 0)?;
+        Some(if self.all_day { start.date_naive() } else { start.with_timezone(&Local).date_naive() })
+    }
+
+    /// Whether it's over at `now`; all-day events last to the end of their last day here.
+    pub fn ended(&self, now: &DateTime<Local>) -> bool {
+        if self.all_day {
+            DateTime::from_timestamp(self.end - 1, 0).is_some_and(|last| last.date_naive() < now.date_naive())
+        } else {
+            self.end <= now.timestamp()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct Calendar {
+    /// By start time.
+    #[serde(default)]
+    pub events: Vec<Event>,
+    /// The server's collection tag at the last sync (empty if it keeps none).
+    #[serde(default)]
+    pub ctag: String,
+    /// Unix seconds of the last sync that reached the server, 0 = never.
+    #[serde(default)]
+    pub synced: i64,
+}
+
+/// The loaded user's calendar, so the page doesn't read the file on every visit.
+static LOADED: Mutex<Option<(String, Calendar)>> = Mutex::new(None);
+
+fn path(user: &str) -> io::Result<PathBuf> {
+    Ok(user_root(user)?.join(FILE))
+}
+
+fn read(user: &str) -> Calendar {
+    let Ok(text) = path(user).and_then(fs::read_to_string) else { return Calendar::default() };
+    serde_json::from_str(&text).unwrap_or_else(|e| {
+        eprintln!("Calendar for {user}: {e}; starting empty");
+        Calendar::default()
+    })
+}
+
+fn with<R>(user: &str, f: impl FnOnce(&mut Calendar) -> R) -> R {
+    let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+    if loaded.as_ref().is_none_or(|(u, _)| u != user) {
+        *loaded = Some((user.to_string(), read(user)));
+    }
+    let (_, calendar) = loaded.as_mut().expect("just loaded");
+    f(calendar)
+}
+
+fn update<R>(user: &str, f: impl FnOnce(&mut Calendar) -> R) -> io::Result<R> {
+    with(user, |calendar| {
+        let out = f(calendar);
+        calendar.events.sort_by_key(|e| e.start);
+        fs::write(path(user)?, serde_json::to_string_pretty(calendar)?)?;
+        Ok(out)
+    })
+}
+
+/// A copy of `user`'s calendar.
+pub fn list(user: &str) -> Calendar {
+    with(user, |calendar| calendar.clone())
+}
+
+/// Forget the loaded copy (account renamed or deleted).
+pub fn forget() {
+    *LOADED.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Drop the server's events and sync state (signed out); imported events stay.
+pub fn clear_server(user: &str) -> io::Result<()> {
+    update(user, |calendar| {
+        calendar.events.retain(|e| e.source == Source::Imported);
+        calendar.ctag.clear();
+        calendar.synced = 0;
+    })
+}
+
+/// `user`'s events that haven't ended by `now`, by day and each day's
+/// all-day events first.
+pub fn upcoming(user: &str, now: &DateTime<Local>, limit: usize) -> Vec<Event> {
+    let mut events: Vec<Event> = with(user, |calendar| calendar.events.iter().filter(|e| !e.ended(now)).cloned().collect());
+    events.sort_by_key(|e| (e.first_day(), !e.all_day, e.start));
+    events.truncate(limit);
+    events
+}
+
+/// Add the events of an .ics file. Events already there under the same UID
+/// are replaced unless theirs is the newer copy. Returns how many were taken.
+pub fn import(user: &str, ics: &str) -> anyhow::Result<usize> {
+    let events = parse_ics(ics, Source::Imported);
+    if events.is_empty() {
+        bail!("no events in this file");
+    }
+    Ok(update(user, |calendar| merge(&mut calendar.events, events))?)
+}
+
+/// Put `incoming` into `events`, a UID at a time: the newer side keeps all of
+/// its occurrences (the server's copy on a tie). Returns how many were taken.
+fn merge(events: &mut Vec<Event>, incoming: Vec<Event>) -> usize {
+    let mut by_uid: HashMap<String, Vec<Event>> = HashMap::new();
+    for e in incoming {
+        by_uid.entry(e.uid.clone()).or_default().push(e);
+    }
+    let mut taken = 0;
+    for (uid, new) in by_uid {
+        let old: Vec<&Event> = events.iter().filter(|e| e.uid == uid).collect();
+        let old_newest = old.iter().map(|e| e.modified).max();
+        let new_newest = new.iter().map(|e| e.modified).max().unwrap_or(0);
+        let old_server = old.iter().any(|e| e.source == Source::Server);
+        let new_server = new.iter().any(|e| e.source == Source::Server);
+        let wins = match old_newest {
+            None => true,
+            Some(m) if new_newest != m => new_newest > m,
+            Some(_) => new_server || !old_server,
+        };
+        if wins {
+            events.retain(|e| e.uid != uid);
+            taken += new.len();
+            events.extend(new);
+        }
+    }
+    taken
+}
+
+/// Where to sync from; `None` until the user filled in the URL and username.
+pub struct Account {
+    pub url: String,
+    pub username: String,
+}
+
+impl Account {
+    pub fn from_config(cfg: &crate::config::AppConfig) -> Option<Self> {
+        let url = URL.text(cfg).trim().to_string();
+        let username = USERNAME.text(cfg).trim().to_string();
+        if url.is_empty() || username.is_empty() {
+            return None;
+        }
+        Some(Account { url, username })
+    }
+}
+
+/// What a sync did, for the page's status line.
+pub enum Synced {
+    /// The collection tag hadn't changed, so nothing was downloaded.
+    Unchanged,
+    /// How many events the server has in the sync window.
+    Fetched(usize),
+}
+
+/// Bring `user`'s server events up to date with `account`. Events the server
+/// no longer has are dropped; imported ones stay unless the server's copy is newer.
+pub async fn sync(user: &str, account: &Account, password: &str) -> anyhow::Result<Synced> {
+    let client = reqwest::Client::builder().timeout(DAV_TIMEOUT).build()?;
+    let now = Utc::now().timestamp();
+    let ctag = fetch_ctag(&client, account, password).await?;
+    let previous = list(user);
+    if !ctag.is_empty() && ctag == previous.ctag && now - previous.synced < CTAG_TRUST_SECS {
+        update(user, |calendar| calendar.synced = now)?;
+        return Ok(Synced::Unchanged);
+    }
+    let remote = fetch_events(&client, account, password, now).await?;
+    let fetched = remote.len();
+    update(user, |calendar| {
+        calendar.events.retain(|e| e.source == Source::Imported);
+        merge(&mut calendar.events, remote);
+        calendar.ctag = ctag;
+        calendar.synced = now;
+    })?;
+    Ok(Synced::Fetched(fetched))
+}
+
+/// Send a WebDAV request (`PROPFIND`, `REPORT`) and return the multistatus body.
+async fn dav(client: &reqwest::Client, account: &Account, password: &str, method: &str, depth: &str, body: String) -> anyhow::Result<String> {
+    let method = reqwest::Method::from_bytes(method.as_bytes())?;
+    let resp = client
+        .request(method, &account.url)
+        .basic_auth(&account.username, Some(password))
+        .header("Depth", depth)
+        .header(reqwest::header::CONTENT_TYPE, "application/xml; charset=utf-8")
+        .body(body)
+        .send()
+        .await
+        .with_context(|| format!("can't reach {}", account.url))?;
+    if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+        bail!("sign-in failed");
+    }
+    Ok(resp.error_for_status()?.text().await?)
+}
+
+/// The collection's `getctag` (changes whenever an event does); empty when
+/// the server doesn't keep one.
+async fn fetch_ctag(client: &reqwest::Client, account: &Account, password: &str) -> anyhow::Result<String> {
+    let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:" xmlns:cs="http://calendarserver.org/ns/">
+  <d:prop><cs:getctag/></d:prop>
+</d:propfind>"#;
+    let xml = dav(client, account, password, "PROPFIND", "0", body.to_string()).await?;
+    Ok(element_texts(&xml, "getctag")?.into_iter().next().unwrap_or_default().trim().to_string())
+}
+
+/// The events in the sync window around `now`, repeats expanded by the server.
+async fn fetch_events(client: &reqwest::Client, account: &Account, password: &str, now: i64) -> anyhow::Result<Vec<Event>> {
+    let stamp = |days: i64| {
+        DateTime::from_timestamp(now + days * 86400, 0).unwrap_or_default().format("%Y%m%dT%H%M%SZ").to_string()
+    };
+    let (from, to) = (stamp(-SYNC_PAST_DAYS), stamp(SYNC_AHEAD_DAYS));
+    let body = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>
+    <c:calendar-data><c:expand start="{from}" end="{to}"/></c:calendar-data>
+  </d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR">
+      <c:comp-filter name="VEVENT"><c:time-range start="{from}" end="{to}"/></c:comp-filter>
+    </c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#
+    );
+    let xml = dav(client, account, password, "REPORT", "1", body).await?;
+    Ok(element_texts(&xml, "calendar-data")?.iter().flat_map(|ics| parse_ics(ics, Source::Server)).collect())
+}
+
+/// The text of every element named `local` (any namespace) in `xml`.
+fn element_texts(xml: &str, local: &str) -> anyhow::Result<Vec<String>> {
+    let mut reader = Reader::from_str(xml);
+    let mut out = Vec::new();
+    let mut current: Option<String> = None;
+    loop {
+        match reader.read_event().context("bad answer from the server")? {
+            Xml::Start(e) if e.local_name().as_ref() == local.as_bytes() => current = Some(String::new()),
+            Xml::End(e) if e.local_name().as_ref() == local.as_bytes() => out.extend(current.take()),
+            Xml::Text(t) => {
+                if let Some(text) = current.as_mut() {
+                    text.push_str(&t.unescape()?);
+                }
+            }
+            Xml::CData(t) => {
+                if let Some(text) = current.as_mut() {
+                    text.push_str(&String::from_utf8_lossy(&t));
+                }
+            }
+            Xml::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+/// One content line of an .ics file: "DTSTART;TZID=Europe/Paris:20240102T090000".
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Unfold the lines (a leading space or tab continues the previous one) and
+/// split each into name, parameters and value.
+fn properties(ics: &str) -> Vec<Property> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+        .iter()
+        .filter_map(|line| {
+            // the value starts at the first ':' outside a quoted parameter
+            let mut quoted = false;
+            let colon = line.char_indices().find_map(|(i, c)| match c {
+                '"' => {
+                    quoted = !quoted;
+                    None
+                }
+                ':' if !quoted => Some(i),
+                _ => None,
+            })?;
+            let (head, value) = (&line[..colon], &line[colon + 1..]);
+            let mut parts = head.split(';');
+            let name = parts.next()?.trim().to_ascii_uppercase();
+            let params = parts
+                .filter_map(|p| p.split_once('='))
+                .map(|(k, v)| (k.trim().to_string(), v.trim().trim_matches('"').to_string()))
+                .collect();
+            Some(Property { name, params, value: value.to_string() })
+        })
+        .collect()
+}
+
+/// The VEVENTs of an .ics file. Cancelled events and ones without a start are
+/// left out; a repeating event in a file shows once, at its first date.
+fn parse_ics(ics: &str, source: Source) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut current: Option<Vec<Property>> = None;
+    // components inside the event (VALARM) whose lines aren't the event's
+    let mut nested = 0;
+    for prop in properties(ics) {
+        let value = prop.value.trim().to_ascii_uppercase();
+        match (prop.name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value == "VEVENT" => current = Some(Vec::new()),
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(_)) if nested > 0 => nested -= 1,
+            ("END", Some(_)) if value == "VEVENT" => {
+                events.extend(current.take().and_then(|props| event(&props, source)));
+            }
+            (_, Some(props)) if nested == 0 => props.push(prop),
+            _ => {}
+        }
+    }
+    events
+}
+
+fn event(props: &[Property], source: Source) -> Option<Event> {
+    let get = |name: &str| props.iter().find(|p| p.name == name);
+    if get("STATUS").is_some_and(|p| p.value.trim().eq_ignore_ascii_case("CANCELLED")) {
+        return None;
+    }
+    let (start, all_day) = get("DTSTART").and_then(when)?;
+    let end = match (get("DTEND").and_then(when), get("DURATION").and_then(|p| parse_duration(&p.value))) {
+        (Some((end, _)), _) => end,
+        (None, Some(secs)) => start.checked_add(secs)?,
+        (None, None) if all_day => start + 86400,
+        (None, None) => start,
+    };
+    let stamp = |name: &str| get(name).and_then(when).map(|(t, _)| t);
+    let summary = get("SUMMARY").map(|p| unescape(&p.value)).unwrap_or_default();
+    Some(Event {
+        uid: get("UID").map(|p| p.value.trim().to_string()).unwrap_or_else(|| format!("{start}-{summary}")),
+        recurrence: get("RECURRENCE-ID").map(|p| p.value.trim().to_string()).unwrap_or_default(),
+        summary: if summary.trim().is_empty() { "(no title)".to_string() } else { summary },
+        location: get("LOCATION").map(|p| unescape(&p.value)).unwrap_or_default(),
+        start,
+        end: end.max(start),
+        all_day,
+        modified: stamp("LAST-MODIFIED").or_else(|| stamp("DTSTAMP")).unwrap_or(0),
+        source,
+    })
+}
+
+/// A DATE or DATE-TIME value -> (unix seconds, all day). Times with a TZID
+/// that isn't an IANA zone, and floating times, are taken as local.
+fn when(p: &Property) -> Option<(i64, bool)> {
+    let value = p.value.trim();
+    if p.param("VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE")) || value.len() == 8 {
+        let day = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((day.and_hms_opt(0, 0, 0)?.and_utc().timestamp(), true));
+    }
+    if let Some(utc) = value.strip_suffix(['Z', 'z']) {
+        return Some((NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?.and_utc().timestamp(), false));
+    }
+    let t = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let at = match p.param("TZID").and_then(|z| z.parse::<Tz>().ok()) {
+        Some(tz) => tz.from_local_datetime(&t).earliest()?.timestamp(),
+        None => Local.from_local_datetime(&t).earliest()?.timestamp(),
+    };
+    Some((at, false))
+}
+
+/// A DURATION ("PT1H30M", "P1D", "P2W") -> seconds; `None` if it doesn't
+/// parse or doesn't fit.
+fn parse_duration(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let mut total: i64 = 0;
+    let mut number = String::new();
+    for c in s.strip_prefix('P')?.chars() {
+        let unit = match c {
+            '0'..='9' => {
+                number.push(c);
+                continue;
+            }
+            'T' => continue,
+            'W' => 7 * 86400,
+            'D' => 86400,
+            'H' => 3600,
+            'M' => 60,
+            'S' => 1,
+            _ => return None,
+        };
+        let n: i64 = std::mem::take(&mut number).parse().ok()?;
+        total = total.checked_add(n.checked_mul(unit)?)?;
+    }
+    Some(if negative { -total } else { total })
+}
+
+/// TEXT values escape `\`, `;`, `,` and newlines with a backslash.
+fn unescape(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.trim().chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
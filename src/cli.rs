@@ -0,0 +1,53 @@
+use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+
+/// Command-line options. Each one can also come from an `APP_*` environment
+/// variable (the flag wins); they layer over the stored settings for this run.
+#[derive(Parser, Debug)]
+#[command(version, about = "Hourly weather and latest news")]
+pub struct Cli {
+    /// City to show instead of the stored one
+    #[arg(long, env = "APP_CITY")]
+    pub city: Option<String>,
+
+    /// Start signed in as this existing account, like "Remember me"
+    #[arg(long, env = "APP_USER")]
+    pub user: Option<String>,
+
+    /// Page to open on
+    #[arg(long, env = "APP_PAGE", value_enum, ignore_case = true)]
+    pub page: Option<StartPage>,
+
+    /// Keep settings and accounts here instead of the platform config folder
+    #[arg(long, env = "APP_CONFIG_DIR")]
+    pub config_dir: Option<PathBuf>,
+
+    /// Keep caches (weather, news, thumbnails, icons) here instead of the
+    /// platform cache folder or the one set in Settings
+    #[arg(long, env = "APP_CACHE_DIR")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Never use the network: show cached weather and news only
+    #[arg(long, env = "APP_OFFLINE")]
+    pub offline: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum StartPage {
+    Weather,
+    News,
+    Notes,
+    Settings,
+}
+
+impl StartPage {
+    /// Same labels as the `start_page` setting.
+    pub fn label(self) -> &'static str {
+        match self {
+            StartPage::Weather => "Weather",
+            StartPage::News => "News",
+            StartPage::Notes => "Notes",
+            StartPage::Settings => "Settings",
+        }
+    }
+}
@@ -1,14 +1,196 @@
+use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
-use std::{fs, io, path::PathBuf};
+use serde_json::{Map, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
 
-fn base_dir() -> io::Result<PathBuf> {
-    let home = std::env::var("HOME")
-        .map_err(|_| io::Error::new(io::ErrorKind::Other, "HOME not set"))?;
-    let dir = PathBuf::from(home).join("tock-workshop").join("slint_rust");
+/// `--config-dir` / `APP_CONFIG_DIR`: replaces the platform config folder for this run.
+static CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `dir` instead of the platform config folder. Call once, before anything
+/// reads settings or accounts.
+pub fn set_config_dir(dir: PathBuf) {
+    let _ = CONFIG_DIR.set(dir);
+}
+
+/// `--cache-dir` / `APP_CACHE_DIR`, or the stored `cache_dir` setting: replaces
+/// the platform cache folder for this run.
+static CACHE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `dir` instead of the platform cache folder. Call once, before anything
+/// reads or writes the cache (after `prepare_cache_dir` accepted it).
+pub fn set_cache_dir(dir: PathBuf) {
+    let _ = CACHE_DIR.set(dir);
+}
+
+/// Last contents this process wrote to each settings file, so the file watcher
+/// can tell our own saves from external edits.
+static OWN_WRITES: Mutex<BTreeMap<PathBuf, String>> = Mutex::new(BTreeMap::new());
+
+fn write_settings(path: PathBuf, data: String) -> io::Result<()> {
+    fs::write(&path, &data)?;
+    if let Ok(mut own) = OWN_WRITES.lock() {
+        own.insert(path, data);
+    }
+    Ok(())
+}
+
+/// True if `path` still holds exactly what this process last wrote there.
+pub fn is_own_write(path: &Path) -> bool {
+    let Ok(current) = fs::read_to_string(path) else {
+        return false;
+    };
+    OWN_WRITES.lock().map(|own| own.get(path) == Some(&current)).unwrap_or(false)
+}
+
+/// The app's platform folders (XDG dirs on Linux, Application Support on macOS,
+/// AppData on Windows).
+fn project_dirs() -> io::Result<ProjectDirs> {
+    ProjectDirs::from("", "", "slint_rust")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))
+}
+
+/// Install-level config folder: users.json / users.db, pin_policy.json,
+/// session.json, audit.log and the per-user settings under `users/`.
+pub fn base_dir() -> io::Result<PathBuf> {
+    let dir = match CONFIG_DIR.get() {
+        Some(dir) => dir.clone(),
+        None => project_dirs()?.config_dir().to_path_buf(),
+    };
     fs::create_dir_all(&dir)?;
     Ok(dir)
 }
 
+/// Cache folder (per-user caches, thumbnails, icons): the one from `set_cache_dir`,
+/// else the platform's; `./cache` if the platform has none.
+pub fn cache_root() -> PathBuf {
+    if let Some(dir) = CACHE_DIR.get() {
+        return dir.clone();
+    }
+    project_dirs()
+        .map(|d| d.cache_dir().to_path_buf())
+        .unwrap_or_else(|_| PathBuf::from("cache"))
+}
+
+/// Create `dir` if needed and check a file can be written there; the error says
+/// which folder failed and why.
+pub fn prepare_cache_dir(dir: &Path) -> io::Result<()> {
+    let describe = |e: io::Error| io::Error::new(e.kind(), format!("cache folder {}: {e}", dir.display()));
+    if !dir.is_absolute() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("cache folder {}: use an absolute path", dir.display()),
+        ));
+    }
+    fs::create_dir_all(dir).map_err(describe)?;
+    let probe = dir.join(".write-test");
+    fs::write(&probe, b"ok").map_err(describe)?;
+    let _ = fs::remove_file(probe);
+    Ok(())
+}
+
+/// Store the cache folder setting in the machine-wide `config.json`: the cache
+/// is shared by every account, so it isn't a per-user setting. The folder is
+/// created and checked first (`prepare_cache_dir`); it takes effect at the next
+/// start. Only for the admin: the caller checks.
+pub fn save_cache_dir(dir: &str) -> io::Result<()> {
+    if !dir.is_empty() {
+        prepare_cache_dir(Path::new(dir))?;
+    }
+    let path = config_path()?;
+    let mut machine = read_layer(&path).unwrap_or_default();
+    if dir.is_empty() {
+        machine.remove("cache_dir");
+    } else {
+        machine.insert("cache_dir".into(), Value::from(dir));
+    }
+    write_settings(path, serde_json::to_string_pretty(&Value::Object(machine))?)
+}
+
+/// Write a file only its owner may read (mode 0600 on Unix): a sibling temp file
+/// created with that mode, then renamed over `path`, so the secret is never
+/// readable by others nor left half-written.
+pub fn write_private(path: &Path, data: &[u8]) -> io::Result<()> {
+    use std::io::Write;
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp = PathBuf::from(tmp_name);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp)?;
+    #[cfg(unix)]
+    {
+        // a temp file left by an older run keeps its mode; tighten it
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp, path)
+}
+
+/// Move every entry of `from` into `to` (rename, or copy + delete across
+/// filesystems). Entries that already exist in `to` are left where they were.
+pub fn move_entries(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)?.flatten() {
+        let dest = to.join(entry.file_name());
+        if dest.exists() {
+            continue;
+        }
+        if fs::rename(entry.path(), &dest).is_err() {
+            copy_tree(&entry.path(), &dest)?;
+            if entry.file_type()?.is_dir() {
+                fs::remove_dir_all(entry.path())?;
+            } else {
+                fs::remove_file(entry.path())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn copy_tree(from: &Path, to: &Path) -> io::Result<()> {
+    if !from.is_dir() {
+        fs::copy(from, to)?;
+        return Ok(());
+    }
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)?.flatten() {
+        copy_tree(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// One-time move from the old `$HOME/tock-workshop/slint_rust` folder into the
+/// platform config folder; the old folder is removed once empty.
+pub fn migrate_legacy_config() {
+    let Some(home) = std::env::var_os("HOME") else { return };
+    let legacy = PathBuf::from(home).join("tock-workshop").join("slint_rust");
+    if !legacy.is_dir() {
+        return;
+    }
+    match base_dir().and_then(|dir| move_entries(&legacy, &dir)) {
+        Ok(()) => {
+            let _ = fs::remove_dir(&legacy);
+            if let Some(parent) = legacy.parent() {
+                let _ = fs::remove_dir(parent);
+            }
+        }
+        Err(e) => eprintln!("Config migration error: {e:?}"),
+    }
+}
+
 pub fn users_base_dir() -> io::Result<PathBuf> {
     let dir = base_dir()?.join("users");
     fs::create_dir_all(&dir)?;
@@ -24,7 +206,262 @@ pub fn config_path() -> io::Result<PathBuf> {
 pub struct AppConfig {
     pub city: String,
     pub news_topic: String,
+    /// Topics shown as tabs on the News page.
+    #[serde(default = "default_pinned_topics")]
+    pub pinned_topics: Vec<String>,
+    /// Recently searched news topics, newest first.
+    #[serde(default)]
+    pub topic_history: Vec<String>,
+    /// The place picked for an ambiguous city ("springfield" → Illinois), by
+    /// `geocode::query_key`; used instead of the best match.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub city_choices: BTreeMap<String, crate::geocode::Place>,
+    /// News sort label ("Relevance", "Newest", "Most points", "Most comments").
+    #[serde(default)]
+    pub news_sort: String,
+    /// HN item type label ("All", "Ask HN", "Show HN", "Jobs", "Polls").
+    #[serde(default)]
+    pub news_category: String,
+    /// Hide stories with this many points or fewer (0 = off).
+    #[serde(default)]
+    pub news_min_points: u32,
+    /// News date range label ("Any time", "Today", "This week", "This month", "Custom").
+    #[serde(default)]
+    pub news_range: String,
+    /// Custom range bounds, `YYYY-MM-DD` (either may be empty).
+    #[serde(default)]
+    pub news_range_from: String,
+    #[serde(default)]
+    pub news_range_to: String,
     pub units_celsius: bool,
+    /// Theme label ("System", "Light", "Dark"); empty follows the system.
+    #[serde(default)]
+    pub theme: String,
+    /// "24h" or "12h" clock, used for every time the app shows.
+    #[serde(default = "default_time_format")]
+    pub time_format: String,
+    /// Date order label (see `timefmt::DateStyle::LABELS`).
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    /// Page shown at start-up and after login / account switch ("Weather", "News", "Notes", "Settings").
+    #[serde(default = "default_start_page")]
+    pub start_page: String,
+    /// Accent color as `#rrggbb` (nav, buttons, highlights); invalid values use the default.
+    #[serde(default = "default_accent_color")]
+    pub accent_color: String,
+    /// News hosts that are never shown (matches subdomains too).
+    #[serde(default)]
+    pub blocked_sources: Vec<String>,
+    /// If non-empty, only these news hosts are shown.
+    #[serde(default)]
+    pub allowed_sources: Vec<String>,
+    /// Stories whose title contains one of these words/phrases are hidden.
+    #[serde(default)]
+    pub muted_keywords: Vec<String>,
+    /// Fresh stories matching one of these trigger a desktop notification.
+    #[serde(default)]
+    pub watch_keywords: Vec<String>,
+    /// Story title languages to show (names or ISO 639-3 codes); empty = any.
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// HN usernames whose submissions fill the "Following" tab.
+    #[serde(default)]
+    pub followed_authors: Vec<String>,
+    /// Raise a desktop notification when a followed author posts (checked by the poll).
+    #[serde(default)]
+    pub notify_followed: bool,
+    /// How long cached weather counts as fresh, in minutes (see `clamp_ttl`).
+    #[serde(default = "default_cache_ttl_minutes")]
+    pub weather_ttl_minutes: u32,
+    /// How long cached news counts as fresh, in minutes (see `clamp_ttl`).
+    #[serde(default = "default_cache_ttl_minutes")]
+    pub news_ttl_minutes: u32,
+    /// Largest the user's cache folder may grow, in MB (0 = no limit); least
+    /// recently used entries are evicted past it (see `cache::trim_user_cache`).
+    #[serde(default = "default_cache_limit_mb")]
+    pub cache_limit_mb: u32,
+    /// Cache folder instead of the platform one (empty = platform folder). Only
+    /// the machine-wide `config.json` value is used, read at start-up.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub cache_dir: String,
+    /// Reload the Weather page every N minutes (0 = manual only).
+    #[serde(default = "default_weather_refresh_minutes")]
+    pub weather_refresh_minutes: u32,
+    /// Reload the News page every N minutes (0 = manual only).
+    #[serde(default = "default_news_refresh_minutes")]
+    pub news_refresh_minutes: u32,
+    /// Background news poll interval in minutes (0 = off).
+    #[serde(default = "default_news_poll_minutes")]
+    pub news_poll_minutes: u32,
+    /// Lock the app after this many idle minutes (0 = never).
+    #[serde(default)]
+    pub lock_after_minutes: u32,
+    /// Trusted "fast switch": switching into this account skips the PIN while it
+    /// still has a session from signing in with the PIN earlier in this run.
+    #[serde(default)]
+    pub fast_switch: bool,
+    /// Store an offline reader copy (text + images) when bookmarking a story.
+    #[serde(default)]
+    pub offline_bookmarks: bool,
+    /// Extra paywalled hosts (on top of the built-in list); marked with a lock, not hidden.
+    #[serde(default)]
+    pub paywalled_sources: Vec<String>,
+    /// Leave stories the user already opened out of the news list.
+    #[serde(default)]
+    pub hide_read: bool,
+    /// OpenAI-compatible endpoint for article summaries (empty = summarizer off).
+    #[serde(default)]
+    pub summary_endpoint: String,
+    /// Legacy: the summarizer key used to live here; `load_config_for` moves it
+    /// to the user's secrets (see `secrets::api_key`).
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub summary_api_key: String,
+    #[serde(default = "default_summary_model")]
+    pub summary_model: String,
+    /// Settings registered by pages (`settings::registry`): section → key → value.
+    /// Read them through their `settings::Descriptor`, which checks type and range.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sections: BTreeMap<String, BTreeMap<String, Value>>,
+}
+
+fn default_pinned_topics() -> Vec<String> {
+    vec!["Top Stories".into()]
+}
+
+fn default_time_format() -> String {
+    "24h".into()
+}
+
+fn default_date_format() -> String {
+    "YYYY-MM-DD".into()
+}
+
+fn default_start_page() -> String {
+    "Weather".into()
+}
+
+fn default_news_poll_minutes() -> u32 {
+    10
+}
+
+fn default_weather_refresh_minutes() -> u32 {
+    30
+}
+
+fn default_news_refresh_minutes() -> u32 {
+    15
+}
+
+/// Upper bounds for the interval settings, matching the Settings spin boxes.
+pub const MAX_POLL_MINUTES: u32 = 240;
+pub const MAX_REFRESH_MINUTES: u32 = 24 * 60;
+pub const MAX_LOCK_MINUTES: u32 = 240;
+
+/// Bounds for the cache TTLs: at least a minute, at most a day.
+pub const MIN_CACHE_TTL_MINUTES: u32 = 1;
+pub const MAX_CACHE_TTL_MINUTES: u32 = 24 * 60;
+
+fn default_cache_ttl_minutes() -> u32 {
+    15
+}
+
+/// Upper bound for the cache size limit (MB), matching its spin box.
+pub const MAX_CACHE_LIMIT_MB: u32 = 10_000;
+
+fn default_cache_limit_mb() -> u32 {
+    200
+}
+
+/// A TTL in minutes pulled into the supported range.
+pub fn clamp_ttl(minutes: u32) -> u32 {
+    minutes.clamp(MIN_CACHE_TTL_MINUTES, MAX_CACHE_TTL_MINUTES)
+}
+
+/// Settings form fields that `validate` can complain about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingsField {
+    City,
+    Accent,
+    Poll,
+    Refresh,
+    Cache,
+    CacheSize,
+    CacheDir,
+    Lock,
+    Summary,
+}
+
+/// A setting that didn't pass `validate`, and what to tell the user about it.
+#[derive(Clone, Debug)]
+pub struct FieldError {
+    pub field: SettingsField,
+    pub message: String,
+}
+
+/// Check settings before they're saved; empty means the config is fine.
+pub fn validate(cfg: &AppConfig) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let mut fail = |field, message: String| errors.push(FieldError { field, message });
+
+    if cfg.city.trim().is_empty() {
+        fail(SettingsField::City, "Enter a city".into());
+    }
+    if crate::theme::parse_hex(&cfg.accent_color).is_none() {
+        fail(SettingsField::Accent, "Use a hex color such as #2563eb".into());
+    }
+    if cfg.news_poll_minutes > MAX_POLL_MINUTES {
+        fail(SettingsField::Poll, format!("At most {MAX_POLL_MINUTES} minutes"));
+    }
+    if cfg.weather_refresh_minutes > MAX_REFRESH_MINUTES || cfg.news_refresh_minutes > MAX_REFRESH_MINUTES {
+        fail(SettingsField::Refresh, format!("At most {MAX_REFRESH_MINUTES} minutes"));
+    }
+    let ttl_ok = |m: u32| (MIN_CACHE_TTL_MINUTES..=MAX_CACHE_TTL_MINUTES).contains(&m);
+    if !ttl_ok(cfg.weather_ttl_minutes) || !ttl_ok(cfg.news_ttl_minutes) {
+        fail(
+            SettingsField::Cache,
+            format!("Between {MIN_CACHE_TTL_MINUTES} and {MAX_CACHE_TTL_MINUTES} minutes"),
+        );
+    }
+    if cfg.cache_limit_mb > MAX_CACHE_LIMIT_MB {
+        fail(SettingsField::CacheSize, format!("At most {MAX_CACHE_LIMIT_MB} MB"));
+    }
+    if !cfg.cache_dir.is_empty() && !Path::new(&cfg.cache_dir).is_absolute() {
+        fail(SettingsField::CacheDir, "Use an absolute path".into());
+    }
+    if cfg.lock_after_minutes > MAX_LOCK_MINUTES {
+        fail(SettingsField::Lock, format!("At most {MAX_LOCK_MINUTES} minutes"));
+    }
+    if !cfg.summary_endpoint.is_empty() {
+        match reqwest::Url::parse(&cfg.summary_endpoint) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") && url.host().is_some() => {
+                if cfg.summary_model.trim().is_empty() {
+                    fail(SettingsField::Summary, "Enter a model name".into());
+                }
+            }
+            _ => fail(SettingsField::Summary, "Enter an http(s) URL such as https://api.openai.com/v1".into()),
+        }
+    }
+    errors
+}
+
+impl AppConfig {
+    /// Weather cache TTL in seconds, as `is_fresh` takes it.
+    pub fn weather_ttl_secs(&self) -> i64 {
+        clamp_ttl(self.weather_ttl_minutes) as i64 * 60
+    }
+
+    /// News cache TTL in seconds, as `is_fresh` takes it.
+    pub fn news_ttl_secs(&self) -> i64 {
+        clamp_ttl(self.news_ttl_minutes) as i64 * 60
+    }
+}
+
+fn default_accent_color() -> String {
+    "#2563eb".into()
+}
+
+fn default_summary_model() -> String {
+    "gpt-4o-mini".into()
 }
 
 impl Default for AppConfig {
@@ -32,39 +469,483 @@ impl Default for AppConfig {
         Self {
             city: "Bucharest".into(),
             news_topic: "Top Stories".into(),
+            pinned_topics: default_pinned_topics(),
+            topic_history: Vec::new(),
+            city_choices: BTreeMap::new(),
+            news_sort: "Relevance".into(),
+            news_range: "Any time".into(),
+            news_category: "All".into(),
+            news_min_points: 0,
+            news_range_from: String::new(),
+            news_range_to: String::new(),
             units_celsius: true,
+            theme: "System".into(),
+            start_page: default_start_page(),
+            time_format: default_time_format(),
+            date_format: default_date_format(),
+            accent_color: default_accent_color(),
+            blocked_sources: Vec::new(),
+            allowed_sources: Vec::new(),
+            muted_keywords: Vec::new(),
+            watch_keywords: Vec::new(),
+            followed_authors: Vec::new(),
+            notify_followed: false,
+            weather_ttl_minutes: default_cache_ttl_minutes(),
+            news_ttl_minutes: default_cache_ttl_minutes(),
+            cache_limit_mb: default_cache_limit_mb(),
+            cache_dir: String::new(),
+            weather_refresh_minutes: default_weather_refresh_minutes(),
+            news_refresh_minutes: default_news_refresh_minutes(),
+            news_poll_minutes: default_news_poll_minutes(),
+            offline_bookmarks: false,
+            lock_after_minutes: 0,
+            fast_switch: false,
+            hide_read: false,
+            paywalled_sources: Vec::new(),
+            languages: Vec::new(),
+            summary_endpoint: String::new(),
+            summary_api_key: String::new(),
+            summary_model: default_summary_model(),
+            sections: BTreeMap::new(),
         }
     }
 }
 
-pub fn load_config() -> AppConfig {
-    match config_path().and_then(fs::read_to_string) {
-        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
-        Err(_) => AppConfig::default(),
+const MAX_TOPIC_HISTORY: usize = 20;
+const MAX_TOPIC_SUGGESTIONS: usize = 6;
+
+impl AppConfig {
+    /// Move `topic` to the front of the search history (case-insensitive dedupe).
+    pub fn remember_topic(&mut self, topic: &str) {
+        let topic = topic.trim();
+        if topic.is_empty() {
+            return;
+        }
+        self.topic_history.retain(|t| !t.eq_ignore_ascii_case(topic));
+        self.topic_history.insert(0, topic.to_string());
+        self.topic_history.truncate(MAX_TOPIC_HISTORY);
+    }
+
+    /// History entries containing `query` (all recent ones for an empty query).
+    pub fn topic_suggestions(&self, query: &str) -> Vec<String> {
+        let q = query.trim().to_lowercase();
+        self.topic_history
+            .iter()
+            .filter(|t| q.is_empty() || t.to_lowercase().contains(&q))
+            .take(MAX_TOPIC_SUGGESTIONS)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Split a comma-separated settings field into trimmed, non-empty entries.
+pub fn split_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty())
+        .collect()
+}
+
+/// Install-wide login memory for "Remember me". Only the user name is kept, never the PIN.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Session {
+    #[serde(default)]
+    pub remembered_user: Option<String>,
+}
+
+fn session_path() -> io::Result<PathBuf> {
+    Ok(base_dir()?.join("session.json"))
+}
+
+pub fn load_session() -> Session {
+    session_path()
+        .and_then(fs::read_to_string)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_session(session: &Session) -> io::Result<()> {
+    fs::write(session_path()?, serde_json::to_string_pretty(session)?)?;
+    Ok(())
+}
+
+/// Forget the remembered user (all of them, or only if it is `user`).
+pub fn forget_session(user: Option<&str>) {
+    let mut session = load_session();
+    if user.is_none() || session.remembered_user.as_deref() == user {
+        session.remembered_user = None;
+        if let Err(e) = save_session(&session) {
+            eprintln!("Save session error: {e:?}");
+        }
+    }
+}
+
+/// Install-wide main window placement (physical pixels), saved on close.
+/// While maximized only the flag changes, so un-maximizing returns to the old size.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+/// Smaller saved sizes are treated as broken and ignored.
+const MIN_WINDOW_SIZE: (u32, u32) = (320, 240);
+
+fn window_path() -> io::Result<PathBuf> {
+    Ok(base_dir()?.join("window.json"))
+}
+
+pub fn load_window_geometry() -> Option<WindowGeometry> {
+    let g: WindowGeometry = serde_json::from_str(&fs::read_to_string(window_path().ok()?).ok()?).ok()?;
+    (g.width >= MIN_WINDOW_SIZE.0 && g.height >= MIN_WINDOW_SIZE.1).then_some(g)
+}
+
+pub fn save_window_geometry(g: &WindowGeometry) -> io::Result<()> {
+    fs::write(window_path()?, serde_json::to_string_pretty(g)?)
+}
+
+// Settings are layered, lowest first:
+//   1. built-in defaults (`AppConfig::default`)
+//   2. the machine-wide `config.json` in the config folder
+//   3. the user's active profile (`users/<user>/config.json` or `profiles/<name>.json`)
+// Each layer only holds the fields it changes and is merged field by field, so a
+// field that doesn't parse is skipped with a warning instead of discarding the
+// whole file. Per-user saves write only what differs from layers 1-2, which lets
+// later machine-wide changes still reach users who never touched that field.
+
+/// Warnings already printed, so a bad field isn't reported on every load.
+static WARNED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+fn warn_once(msg: String) {
+    if WARNED.lock().map(|mut w| w.insert(msg.clone())).unwrap_or(true) {
+        eprintln!("{msg}");
     }
 }
 
+fn read_layer(path: &Path) -> Option<Map<String, Value>> {
+    let text = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&text) {
+        Ok(Value::Object(map)) => Some(map),
+        Ok(_) => {
+            warn_once(format!("{}: not a JSON object, ignored", path.display()));
+            None
+        }
+        Err(e) => {
+            warn_once(format!("{}: {e}, ignored", path.display()));
+            None
+        }
+    }
+}
+
+/// Lay `path` over `merged`, keeping the lower value for any field that doesn't parse.
+fn merge_layer(merged: &mut Map<String, Value>, path: &Path) {
+    let Some(layer) = read_layer(path) else { return };
+    for (key, value) in layer {
+        let value = match merged.get(&key) {
+            Some(lower) => merge_value(lower, value),
+            None => value,
+        };
+        let previous = merged.insert(key.clone(), value);
+        if serde_json::from_value::<AppConfig>(Value::Object(merged.clone())).is_err() {
+            warn_once(format!("{}: bad value for \"{key}\", ignored", path.display()));
+            match previous {
+                Some(v) => merged.insert(key, v),
+                None => merged.remove(&key),
+            };
+        }
+    }
+}
+
+/// Objects (the registered page `sections`) merge entry by entry; anything else is replaced.
+fn merge_value(lower: &Value, upper: Value) -> Value {
+    match (lower, upper) {
+        (Value::Object(lower), Value::Object(upper)) => {
+            let mut out = lower.clone();
+            for (k, v) in upper {
+                let v = match out.get(&k) {
+                    Some(prev) => merge_value(prev, v),
+                    None => v,
+                };
+                out.insert(k, v);
+            }
+            Value::Object(out)
+        }
+        (_, upper) => upper,
+    }
+}
+
+/// Layers 1-2: built-in defaults with the machine-wide config.json on top.
+fn machine_layers() -> Map<String, Value> {
+    let mut merged = match serde_json::to_value(AppConfig::default()) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    if let Ok(path) = config_path() {
+        merge_layer(&mut merged, &path);
+    }
+    merged
+}
+
+fn from_layers(merged: Map<String, Value>) -> AppConfig {
+    serde_json::from_value(Value::Object(merged)).unwrap_or_default()
+}
+
+/// The fields of `cfg` that differ from the machine-wide layers, as a JSON object.
+fn user_layer_json(cfg: &AppConfig) -> io::Result<String> {
+    let below = machine_layers();
+    let own: Map<String, Value> = match serde_json::to_value(cfg)? {
+        Value::Object(all) => all.into_iter().filter(|(k, v)| below.get(k) != Some(v)).collect(),
+        _ => Map::new(),
+    };
+    Ok(serde_json::to_string_pretty(&Value::Object(own))?)
+}
+
+/// Settings before any user layer (start-up without a signed-in account).
+pub fn load_config() -> AppConfig {
+    from_layers(machine_layers())
+}
+
 pub fn user_root(user: &str) -> io::Result<PathBuf> {
     let dir = base_dir()?.join("users").join(user);
     fs::create_dir_all(&dir)?;
     Ok(dir)
 }
 
+/// Settings profiles ("Home", "Work", …): `config.json` holds the default one,
+/// the others live in `profiles/<name>.json` and `profiles/active` names the one in use.
+pub const DEFAULT_PROFILE: &str = "Default";
+
+fn profile_path(user: &str, name: &str) -> io::Result<PathBuf> {
+    if name == DEFAULT_PROFILE {
+        Ok(user_root(user)?.join("config.json"))
+    } else {
+        Ok(user_root(user)?.join("profiles").join(format!("{name}.json")))
+    }
+}
+
+fn set_active_profile(user: &str, name: &str) -> io::Result<()> {
+    let dir = user_root(user)?.join("profiles");
+    fs::create_dir_all(&dir)?;
+    write_settings(dir.join("active"), name.to_string())
+}
+
+/// Letters, digits, spaces, `-` and `_`, at most 32 characters.
+pub fn valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= 32
+        && name.chars().all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_')
+}
+
+/// The profile in use; falls back to the default one if its file is gone.
+pub fn active_profile(user: &str) -> String {
+    user_root(user)
+        .and_then(|root| fs::read_to_string(root.join("profiles").join("active")))
+        .map(|s| s.trim().to_string())
+        .ok()
+        .filter(|name| valid_profile_name(name) && profile_path(user, name).is_ok_and(|p| p.exists()))
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+/// "Default" first, then the other profiles by name.
+pub fn list_profiles(user: &str) -> Vec<String> {
+    let mut names: Vec<String> = user_root(user)
+        .and_then(|root| fs::read_dir(root.join("profiles")))
+        .map(|rd| {
+            rd.flatten()
+                .filter_map(|e| e.file_name().to_str()?.strip_suffix(".json").map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names.insert(0, DEFAULT_PROFILE.to_string());
+    names
+}
+
+/// Start a new profile as a copy of the current settings and switch to it.
+pub fn create_profile(user: &str, name: &str) -> io::Result<()> {
+    if !valid_profile_name(name) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "use letters, digits, spaces, - or _"));
+    }
+    let path = profile_path(user, name)?;
+    if path.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("profile {name} exists")));
+    }
+    let cfg = load_config_for(user);
+    fs::create_dir_all(user_root(user)?.join("profiles"))?;
+    write_settings(path, user_layer_json(&cfg)?)?;
+    set_active_profile(user, name)
+}
+
+pub fn switch_profile(user: &str, name: &str) -> io::Result<()> {
+    if !valid_profile_name(name) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("no profile named {name}")));
+    }
+    if !profile_path(user, name)?.exists() && name != DEFAULT_PROFILE {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("no profile named {name}")));
+    }
+    set_active_profile(user, name)
+}
+
+/// Remove a profile (never the default one); deleting the active one switches to the default.
+pub fn delete_profile(user: &str, name: &str) -> io::Result<()> {
+    if !valid_profile_name(name) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("no profile named {name}")));
+    }
+    if name == DEFAULT_PROFILE {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "the default profile can't be deleted"));
+    }
+    let was_active = active_profile(user) == name;
+    fs::remove_file(profile_path(user, name)?)?;
+    if was_active {
+        set_active_profile(user, DEFAULT_PROFILE)?;
+    }
+    Ok(())
+}
+
+/// The active profile's settings file.
 pub fn config_path_for(user: &str) -> io::Result<PathBuf> {
-    Ok(user_root(user)?.join("config.json"))
+    profile_path(user, &active_profile(user))
 }
 
+/// All three layers for `user` (see the layering notes above `load_config`).
 pub fn load_config_for(user: &str) -> AppConfig {
-    match config_path_for(user).and_then(fs::read_to_string) {
-        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
-        Err(_) => AppConfig::default(),
+    let mut merged = machine_layers();
+    if let Ok(path) = config_path_for(user) {
+        merge_layer(&mut merged, &path);
+    }
+    let mut cfg = from_layers(merged);
+    if !cfg.summary_api_key.is_empty() {
+        match crate::secrets::set_api_key(user, crate::secrets::ApiService::Summarizer, &cfg.summary_api_key) {
+            Ok(()) => {
+                cfg.summary_api_key.clear();
+                if let Err(e) = save_config_for(user, &cfg) {
+                    eprintln!("Save config error: {e:?}");
+                }
+            }
+            Err(e) => eprintln!("Moving the summarizer key out of config.json failed: {e:?}"),
+        }
     }
+    cfg
 }
 
+/// Save `cfg` as the user's layer: only the fields that differ from the machine-wide settings.
+/// The previous file is kept as a backup first.
 pub fn save_config_for(user: &str, cfg: &AppConfig) -> io::Result<()> {
     let path = config_path_for(user)?;
-    let data = serde_json::to_string_pretty(cfg)?;
-    fs::write(path, data)
+    let data = user_layer_json(cfg)?;
+    backup_settings(user, &data)?;
+    write_settings(path, data)
+}
+
+/// How many backups of each profile's settings file are kept.
+const BACKUPS_KEPT: usize = 10;
+/// Backup file names: save time, sortable, millisecond precision.
+const BACKUP_STAMP: &str = "%Y-%m-%d_%H-%M-%S%.3f";
+
+/// `users/<user>/backups/<profile>/`
+fn backup_dir(user: &str) -> io::Result<PathBuf> {
+    Ok(user_root(user)?.join("backups").join(active_profile(user)))
+}
+
+/// Backups of the active profile, newest first (file stems).
+fn backup_stems(user: &str) -> Vec<String> {
+    let mut stems: Vec<String> = backup_dir(user)
+        .and_then(fs::read_dir)
+        .map(|rd| {
+            rd.flatten()
+                .filter_map(|e| e.file_name().to_str()?.strip_suffix(".json").map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    stems.sort_unstable_by(|a, b| b.cmp(a));
+    stems
+}
+
+/// Settings fields that may hold secrets (older files); never copied into backups.
+const SECRET_FIELDS: [&str; 1] = ["summary_api_key"];
+
+/// A settings file's JSON without `SECRET_FIELDS` (as it is if it isn't a JSON object).
+fn without_secrets(json: &str) -> String {
+    let Ok(serde_json::Value::Object(mut fields)) = serde_json::from_str(json) else {
+        return json.to_string();
+    };
+    let before = fields.len();
+    fields.retain(|k, _| !SECRET_FIELDS.contains(&k.as_str()));
+    if fields.len() == before {
+        return json.to_string();
+    }
+    serde_json::to_string_pretty(&fields).unwrap_or_default()
+}
+
+/// Copy the active settings file (minus secrets) into the backups before it's
+/// replaced by `next`, then drop the oldest beyond `BACKUPS_KEPT`. Nothing is
+/// copied if the file is missing, unchanged, or identical to the newest backup.
+fn backup_settings(user: &str, next: &str) -> io::Result<()> {
+    let Ok(current) = fs::read_to_string(config_path_for(user)?) else {
+        return Ok(());
+    };
+    let current = without_secrets(&current);
+    let dir = backup_dir(user)?;
+    let stems = backup_stems(user);
+    let newest = stems.first().and_then(|s| fs::read_to_string(dir.join(format!("{s}.json"))).ok());
+    if current == next || newest.as_deref() == Some(current.as_str()) {
+        return Ok(());
+    }
+    fs::create_dir_all(&dir)?;
+    let stamp = chrono::Local::now().format(BACKUP_STAMP).to_string();
+    fs::write(dir.join(format!("{stamp}.json")), current)?;
+    for old in backup_stems(user).iter().skip(BACKUPS_KEPT) {
+        let _ = fs::remove_file(dir.join(format!("{old}.json")));
+    }
+    Ok(())
+}
+
+/// Backups of the active profile for the restore picker, newest first, as
+/// labels in the user's date and time format.
+pub fn list_backups(user: &str) -> Vec<String> {
+    backup_stems(user)
+        .into_iter()
+        .map(|s| match chrono::NaiveDateTime::parse_from_str(&s, BACKUP_STAMP) {
+            Ok(t) => crate::timefmt::date_time_secs(t),
+            Err(_) => s,
+        })
+        .collect()
+}
+
+/// Put back the `index`-th newest backup (as listed by `list_backups`); the
+/// settings it replaces are backed up too, so a restore can be undone.
+pub fn restore_backup(user: &str, index: usize) -> io::Result<()> {
+    let stem = backup_stems(user)
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such backup"))?;
+    let data = fs::read_to_string(backup_dir(user)?.join(format!("{stem}.json")))?;
+    backup_settings(user, &data)?;
+    write_settings(config_path_for(user)?, data)
+}
+
+/// Drop the user's own settings for the active profile, back to the machine-wide
+/// defaults (the old file goes to the backups first).
+pub fn reset_config(user: &str) -> io::Result<()> {
+    save_config_for(user, &load_config())
+}
+
+/// Move a user's config folder (config.json, avatar, …) to a new name.
+pub fn rename_user_tree(old: &str, new: &str) -> io::Result<()> {
+    let base = users_base_dir()?;
+    let (from, to) = (base.join(old), base.join(new));
+    if !from.exists() {
+        return Ok(());
+    }
+    if to.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("config folder for {new} exists")));
+    }
+    fs::rename(from, to)
 }
 
 pub fn delete_user_tree(user: &str) -> io::Result<()> {
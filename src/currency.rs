@@ -0,0 +1,89 @@
+use crate::config::{split_list, AppConfig};
+use crate::settings::{DefaultValue, Descriptor, Kind};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+// Currency converter: the European Central Bank's reference rates from
+// frankfurter.app, published once per working day. They're fetched against
+// the euro and cached like the weather, so conversions between any two
+// currencies work offline from the last copy.
+
+/// Favorite pairs shown with their rate, comma-separated ("EUR/USD, GBP/RON").
+pub const FAVORITE_PAIRS: Descriptor = Descriptor {
+    section: "currency",
+    key: "pairs",
+    label: "Favorite pairs",
+    kind: Kind::Text,
+    default: DefaultValue::Text("EUR/USD, USD/JPY"),
+};
+
+/// Currency settings for the registry in `settings.rs`.
+pub const SETTINGS: &[Descriptor] = &[FAVORITE_PAIRS];
+
+/// The rates change once a day; a fetched copy counts as fresh this long.
+pub const RATES_TTL_SECS: i64 = 6 * 3600;
+
+#[derive(Deserialize, Clone)]
+struct Latest {
+    date: String,
+    rates: BTreeMap<String, f64>,
+}
+
+/// Today's rates against the euro (the euro included, as 1.0) and their date.
+pub async fn fetch_rates() -> anyhow::Result<(String, BTreeMap<String, f64>)> {
+    let url = "https://api.frankfurter.app/latest?from=EUR";
+    let mut latest: Latest = crate::httpcache::get(&reqwest::Client::new(), url).await?.json()?;
+    latest.rates.insert("EUR".to_string(), 1.0);
+    Ok((latest.date, latest.rates))
+}
+
+/// `amount` of `from` in `to`, through the euro rates.
+pub fn convert(rates: &BTreeMap<String, f64>, amount: f64, from: &str, to: &str) -> Option<f64> {
+    let (from, to) = (rates.get(&from.to_uppercase())?, rates.get(&to.to_uppercase())?);
+    (*from > 0.0).then(|| amount / from * to)
+}
+
+/// "1,234.5", "1 234,5", "12,5" -> the number.
+pub fn parse_amount(s: &str) -> Option<f64> {
+    let s: String = s.chars().filter(|c| !c.is_whitespace() && *c != '_' && *c != '\'').collect();
+    // a lone comma is the decimal mark, next to a dot it groups thousands
+    let s = if s.contains('.') { s.replace(',', "") } else if s.matches(',').count() == 1 { s.replace(',', ".") } else { s.replace(',', "") };
+    s.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+/// Two decimals, more for small values so a rate like 0.0061 doesn't read 0.00.
+pub fn format_amount(v: f64) -> String {
+    let decimals = match v.abs() {
+        a if a == 0.0 || a >= 1.0 => 2,
+        a if a >= 0.01 => 4,
+        _ => 6,
+    };
+    format!("{v:.decimals$}")
+}
+
+/// The user's favorite pairs, uppercased, without repeats.
+pub fn pairs(cfg: &AppConfig) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    for p in split_list(&FAVORITE_PAIRS.text(cfg)) {
+        let Some((from, to)) = p.split_once('/') else { continue };
+        let pair = (from.trim().to_uppercase(), to.trim().to_uppercase());
+        if !pair.0.is_empty() && !pair.1.is_empty() && !out.contains(&pair) {
+            out.push(pair);
+        }
+    }
+    out
+}
+
+/// The favorite pairs with `from`/`to` added, or taken out if it's there, as
+/// the setting stores them.
+pub fn toggle_pair(cfg: &AppConfig, from: &str, to: &str) -> String {
+    let pair = (from.trim().to_uppercase(), to.trim().to_uppercase());
+    let mut list = pairs(cfg);
+    match list.iter().position(|p| *p == pair) {
+        Some(i) => {
+            list.remove(i);
+        }
+        None => list.push(pair),
+    }
+    list.iter().map(|(f, t)| format!("{f}/{t}")).collect::<Vec<_>>().join(", ")
+}
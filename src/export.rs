@@ -0,0 +1,50 @@
+use serde::Serialize;
+use std::{fs, io, path::Path};
+
+/// One exported story.
+#[derive(Serialize)]
+pub struct ExportRow {
+    pub title: String,
+    pub source: String,
+    pub url: String,
+    pub published: String,
+    pub read: bool,
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+pub fn to_csv(rows: &[ExportRow]) -> String {
+    let mut out = String::from("title,source,url,published,read\n");
+    for r in rows {
+        let fields = [
+            csv_field(&r.title),
+            csv_field(&r.source),
+            csv_field(&r.url),
+            csv_field(&r.published),
+            r.read.to_string(),
+        ];
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Write `rows` to `path`: JSON for a `.json` extension, CSV otherwise.
+pub fn write_export(path: &Path, rows: &[ExportRow]) -> io::Result<()> {
+    let is_json = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("json"));
+    let body = if is_json {
+        serde_json::to_string_pretty(rows)?
+    } else {
+        to_csv(rows)
+    };
+    fs::write(path, body)
+}
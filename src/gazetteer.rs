@@ -0,0 +1,77 @@
+use crate::geocode::{place_label, Place};
+use lazy_static::lazy_static;
+
+// A gazetteer built into the binary (`cities.tsv`): city names resolve from it
+// first, so they resolve the same with the network or the geocoding API down.
+// The online search only refines its answer (see `geocode::fetch_places`).
+
+const DATA: &str = include_str!("../cities.tsv");
+
+struct City {
+    /// `fold`ed name, for matching
+    key: String,
+    name: &'static str,
+    region: &'static str,
+    country: &'static str,
+    /// ISO 3166 code
+    code: &'static str,
+    lat: f64,
+    lon: f64,
+}
+
+lazy_static! {
+    /// Largest first, like the file.
+    static ref CITIES: Vec<City> = DATA
+        .lines()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+        .filter_map(|l| {
+            let mut f = l.split('\t');
+            let (name, region, country) = (f.next()?, f.next()?, f.next()?);
+            let (lat, lon) = (f.next()?.trim().parse().ok()?, f.next()?.trim().parse().ok()?);
+            let code = f.next().unwrap_or("").trim();
+            Some(City { key: fold(name), name, region, country, code, lat, lon })
+        })
+        .collect();
+}
+
+/// Lowercase without the common diacritics, so "sao paulo" finds "São Paulo"
+/// and "timisoara" finds "Timișoara".
+fn fold(s: &str) -> String {
+    s.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ä' | 'ã' | 'å' | 'ă' => 'a',
+            'ç' | 'č' | 'ć' => 'c',
+            'é' | 'è' | 'ê' | 'ë' | 'ě' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ł' => 'l',
+            'ñ' | 'ń' => 'n',
+            'ó' | 'ò' | 'ô' | 'ö' | 'õ' => 'o',
+            'ș' | 'ş' | 'š' | 'ś' => 's',
+            'ț' | 'ţ' => 't',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ý' => 'y',
+            'ž' | 'ź' | 'ż' => 'z',
+            c => c,
+        })
+        .collect()
+}
+
+/// Built-in places called `name`, largest first (empty if it isn't listed).
+/// `within` (an ISO 3166 code, a country or a region name) keeps the ones there.
+pub fn lookup(name: &str, within: Option<&str>) -> Vec<Place> {
+    let key = fold(name);
+    let within = within.map(fold);
+    CITIES
+        .iter()
+        .filter(|c| c.key == key)
+        .filter(|c| within.as_ref().is_none_or(|w| [c.code, c.country, c.region].iter().any(|s| fold(s) == *w)))
+        .map(|c| Place {
+            lat: c.lat,
+            lon: c.lon,
+            label: place_label(c.name, c.region, c.country),
+            timezone: String::new(),
+        })
+        .collect()
+}
@@ -1,4 +1,9 @@
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 #[derive(Debug)]
 pub enum GeocodeError {
     Http(reqwest::Error),
@@ -31,28 +36,416 @@ impl std::error::Error for GeocodeError {
 impl From<reqwest::Error> for GeocodeError { fn from(e: reqwest::Error) -> Self { Self::Http(e) } }
 impl From<serde_json::Error> for GeocodeError { fn from(e: serde_json::Error) -> Self { Self::Json(e) } }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 struct SearchResp { results: Option<Vec<ResultItem>> }
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 struct ResultItem {
     name: String,
     latitude: f64,
     longitude: f64,
     #[serde(default)] country: String,
     #[serde(default)] admin1: String,
+    #[serde(default)] timezone: String,
 }
 
-/// Return (lat, lon, display_label)
-pub async fn fetch_coords(query: &str) -> Result<(f64, f64, String), GeocodeError> {
-    let url = format!(
-        "https://geocoding-api.open-meteo.com/v1/search?name={}&count=1&language=en&format=json",
+/// Candidates asked for per search; the API returns them best match first.
+const MAX_PLACES: usize = 10;
+
+/// One geocoding result.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Place {
+    pub lat: f64,
+    pub lon: f64,
+    /// "Springfield — Illinois, United States"
+    pub label: String,
+    /// IANA zone ("America/Chicago") when the geocoder gave one; empty = let
+    /// the forecast API work it out from the coordinates.
+    #[serde(default)]
+    pub timezone: String,
+}
+
+pub fn place_label(name: &str, admin1: &str, country: &str) -> String {
+    if country.is_empty() { name.to_string() }
+    else if admin1.is_empty() { format!("{name} ({country})") }
+    else { format!("{name} — {admin1}, {country}") }
+}
+
+/// How a typed city is looked up in the remembered choices.
+pub fn query_key(query: &str) -> String {
+    query.trim().to_lowercase()
+}
+
+// Resolved places are kept in the user's cache (what they looked up is theirs):
+// a city doesn't move, so refreshes skip the API for a month, and a city that
+// was ever resolved still resolves when the network is down.
+
+/// How long a resolved query is reused before asking the API again.
+const PLACES_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Where older versions kept resolved places for everyone; removed at startup.
+pub fn shared_cache_path() -> PathBuf {
+    crate::config::cache_root().join("geocode.json")
+}
+
+fn remember_places(user: &str, query: &str, places: &[Place]) {
+    if let Err(e) = crate::cache::save_places_for(user, &query_key(query), places) {
+        eprintln!("Geocode cache error: {e:?}");
+    }
+}
+
+/// Places stored for `query` and when they were resolved, whatever their age.
+fn cached_places(user: &str, query: &str) -> Option<(i64, Vec<Place>)> {
+    crate::cache::load_places_for(user, &query_key(query)).map(|c| (c.ts, c.places))
+}
+
+/// Coordinates typed instead of a city: "44.43, 26.10", "44.43 26.10",
+/// "44.43N 26.10E", "33.9° S, 18.4° E". Latitude first, unless the hemisphere
+/// letters say otherwise.
+pub fn parse_coords(input: &str) -> Option<(f64, f64)> {
+    // "44.43 N" -> "44.43N": a lone hemisphere letter belongs to the number before it
+    let mut parts: Vec<String> = Vec::new();
+    for token in input.replace('°', " ").split(|c: char| c == ',' || c == ';' || c.is_whitespace()) {
+        match (token.len(), parts.last_mut()) {
+            (0, _) => {}
+            (1, Some(last)) if token.chars().all(|c| "NSEWnsew".contains(c)) => last.push_str(token),
+            _ => parts.push(token.to_string()),
+        }
+    }
+    let [a, b] = parts.as_slice() else { return None };
+    let axis = |s: &str| -> Option<(f64, Option<bool>)> {
+        // value, and Some(true) for a latitude letter / Some(false) for a longitude one
+        let (num, sign, lat) = match s.chars().last()?.to_ascii_uppercase() {
+            'N' => (&s[..s.len() - 1], 1.0, Some(true)),
+            'S' => (&s[..s.len() - 1], -1.0, Some(true)),
+            'E' => (&s[..s.len() - 1], 1.0, Some(false)),
+            'W' => (&s[..s.len() - 1], -1.0, Some(false)),
+            _ => (s, 1.0, None),
+        };
+        let v: f64 = num.parse().ok()?;
+        (v.is_finite() && (lat.is_none() || v >= 0.0)).then_some((v * sign, lat))
+    };
+    let ((lat, a_is_lat), (lon, b_is_lat)) = match (axis(a)?, axis(b)?) {
+        // "26.10E 44.43N"
+        ((x, Some(false)), (y, Some(true))) => ((y, Some(true)), (x, Some(false))),
+        pair => pair,
+    };
+    if a_is_lat == Some(false) || b_is_lat == Some(true) {
+        return None;
+    }
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+/// "RO", "de": an ISO 3166 alpha-2 country code.
+pub fn is_country_code(s: &str) -> bool {
+    s.len() == 2 && s.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// The country at the end of a place label ("… — Illinois, United States",
+/// "Paris (France)").
+fn label_country(label: &str) -> &str {
+    match label.strip_suffix(')').and_then(|l| l.rsplit_once('(')) {
+        Some((_, country)) => country,
+        None => label.rsplit_once(", ").map_or("", |(_, country)| country),
+    }
+}
+
+/// A postal code typed instead of a city: "10115, DE", "DE-10115", "SW1A 1AA, GB",
+/// or digits alone ("90210"). Returns the code and the ISO 3166 country, if given.
+pub fn parse_postal(input: &str) -> Option<(String, Option<String>)> {
+    let input = input.trim();
+    let (code, country) = if let Some((a, b)) = input.split_once(',') {
+        let (a, b) = (a.trim(), b.trim());
+        if is_country_code(b) { (a, Some(b)) } else if is_country_code(a) { (b, Some(a)) } else { return None }
+    } else if let Some(rest) = input.get(..2).filter(|p| is_country_code(p)).and_then(|_| input[2..].strip_prefix(['-', ' '])) {
+        (rest.trim(), Some(&input[..2]))
+    } else {
+        (input, None)
+    };
+    let digits = code.chars().filter(char::is_ascii_digit).count();
+    let ok = digits >= 2
+        && (3..=10).contains(&code.len())
+        && code.chars().all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-')
+        // without a country only all-digit codes: "Route 66" is a name
+        && (country.is_some() || code.chars().all(|c| c.is_ascii_digit() || c == '-'));
+    ok.then(|| (code.to_uppercase(), country.map(str::to_uppercase)))
+}
+
+/// Every place matching `query`, best match first (never empty): from `user`'s
+/// cache while it's fresh, else from the built-in gazetteer (the API refines that
+/// answer in the background, for the next lookup), else from the API, else
+/// (offline) from the cache at any age.
+/// Typed coordinates skip the search and are named by a reverse lookup (or shown as
+/// they are when that fails), for spots that aren't in the place database.
+/// Postal codes are looked up as such and labelled with the code.
+/// Labels are in `lang` (ISO 639-1) where the services know the name.
+/// `prefer` (a country code, or empty) is searched first, unless the query names
+/// its own country ("Springfield, US", "Paris, France").
+pub async fn fetch_places(user: &str, query: &str, lang: &str, prefer: &str) -> Result<Vec<Place>, GeocodeError> {
+    if let Some((lat, lon)) = parse_coords(query) {
+        let place = fetch_place(user, lat, lon, lang)
+            .await
+            .unwrap_or_else(|_| Place { lat, lon, label: format!("{lat:.4}, {lon:.4}"), timezone: String::new() });
+        return Ok(vec![place]);
+    }
+    let prefer = Some(prefer.trim()).filter(|c| is_country_code(c)).map(str::to_uppercase);
+    // labels differ per language and results per preferred country: separate entries
+    let key = format!("{lang}|{}|{query}", prefer.as_deref().unwrap_or(""));
+    let cached = cached_places(user, &key);
+    if let Some((ts, places)) = &cached {
+        if crate::cache::is_fresh(*ts, PLACES_TTL_SECS) {
+            return Ok(places.clone());
+        }
+    }
+    let known = builtin_places(query, prefer.as_deref());
+    if !known.is_empty() {
+        // the API adds labels in `lang`, time zones and smaller places of the
+        // same name; an older refined answer is still better than the list's
+        let (user, query, lang) = (user.to_string(), query.to_string(), lang.to_string());
+        tokio::spawn(async move {
+            if let Ok(places) = search_online(&query, prefer.as_deref(), &lang).await {
+                remember_places(&user, &key, &places);
+            }
+        });
+        return Ok(cached.map_or(known, |(_, places)| places));
+    }
+    match search_online(query, prefer.as_deref(), lang).await {
+        Ok(places) => {
+            remember_places(user, &key, &places);
+            Ok(places)
+        }
+        Err(GeocodeError::NotFound) => Err(GeocodeError::NotFound),
+        Err(e) => cached.map(|(_, places)| places).ok_or(e),
+    }
+}
+
+/// "City, Country" split at the last comma, both parts given.
+fn typed_country(query: &str) -> Option<(&str, &str)> {
+    query
+        .rsplit_once(',')
+        .map(|(city, country)| (city.trim(), country.trim()))
+        .filter(|(city, country)| !city.is_empty() && !country.is_empty())
+}
+
+/// `query` in the built-in gazetteer, read the way `search_online` reads it
+/// (postal codes aren't listed).
+fn builtin_places(query: &str, prefer: Option<&str>) -> Vec<Place> {
+    use crate::gazetteer::lookup;
+    if parse_postal(query).is_some() {
+        return Vec::new();
+    }
+    match typed_country(query) {
+        Some((city, country)) => match lookup(city, Some(country)) {
+            // "Washington, DC" isn't a country: then the whole text is the name
+            places if places.is_empty() && is_country_code(country) => lookup(query, None),
+            places if places.is_empty() => lookup(city, None),
+            places => places,
+        },
+        None => match prefer.map(|cc| lookup(query, Some(cc))) {
+            Some(places) if !places.is_empty() => places,
+            _ => lookup(query, None),
+        },
+    }
+}
+
+/// The API search for `query`: a postal code, "City, Country", or a name looked
+/// for in `prefer` first.
+async fn search_online(query: &str, prefer: Option<&str>, lang: &str) -> Result<Vec<Place>, GeocodeError> {
+    if let Some((code, country)) = parse_postal(query) {
+        search_places(&code, country.as_deref(), lang).await.map(|places| {
+            places.into_iter().map(|p| Place { label: format!("{code} {}", p.label), ..p }).collect()
+        })
+    } else if let Some((city, country)) = typed_country(query) {
+        if is_country_code(country) {
+            // "Washington, DC" isn't a country: then the whole text is the name
+            match search_places(city, Some(&country.to_uppercase()), lang).await {
+                Err(GeocodeError::NotFound) => search_places(query, None, lang).await,
+                found => found,
+            }
+        } else {
+            search_places(city, None, lang).await.map(|places| {
+                let wanted = country.to_lowercase();
+                let (ours, others): (Vec<Place>, Vec<Place>) =
+                    places.into_iter().partition(|p| label_country(&p.label).to_lowercase() == wanted);
+                // "Cluj-Napoca, Cluj": a region, not a country
+                if ours.is_empty() { others } else { ours }
+            })
+        }
+    } else if let Some(prefer) = prefer {
+        match search_places(query, Some(prefer), lang).await {
+            Err(GeocodeError::NotFound) => search_places(query, None, lang).await,
+            found => found,
+        }
+    } else {
+        search_places(query, None, lang).await
+    }
+}
+
+/// Open-Meteo first; OSM knows more small villages, so it gets a second try.
+async fn search_places(query: &str, country: Option<&str>, lang: &str) -> Result<Vec<Place>, GeocodeError> {
+    match lookup_places(query, country, lang).await {
+        Err(first) => nominatim_places(query, country, lang).await.map_err(|second| match first {
+            GeocodeError::NotFound => second,
+            e => e,
+        }),
+        ok => ok,
+    }
+}
+
+/// `country`: ISO 3166 code to search in (any when `None`).
+async fn lookup_places(query: &str, country: Option<&str>, lang: &str) -> Result<Vec<Place>, GeocodeError> {
+    let mut url = format!(
+        "https://geocoding-api.open-meteo.com/v1/search?name={}&count={MAX_PLACES}&language={lang}&format=json",
+        urlencoding::encode(query)
+    );
+    if let Some(cc) = country {
+        url.push_str(&format!("&countryCode={}", urlencoding::encode(cc)));
+    }
+    let data: SearchResp = crate::httpcache::get(&reqwest::Client::new(), &url).await?.json()?;
+    let places: Vec<Place> = data
+        .results
+        .unwrap_or_default()
+        .into_iter()
+        .map(|item| Place {
+            lat: item.latitude,
+            lon: item.longitude,
+            label: place_label(&item.name, &item.admin1, &item.country),
+            timezone: item.timezone,
+        })
+        .collect();
+    if places.is_empty() {
+        return Err(GeocodeError::NotFound);
+    }
+    Ok(places)
+}
+
+// OSM Nominatim: reverse lookups (coordinates -> place name) and the fallback
+// search. Its usage policy asks for an identifying User-Agent and at most one
+// request a second.
+
+const NOMINATIM_USER_AGENT: &str = "slint_rust-weather/1.0";
+const NOMINATIM_INTERVAL: Duration = Duration::from_secs(1);
+const NOMINATIM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// When the next Nominatim request may go out.
+static NOMINATIM_NEXT: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Wait for this request's slot; each caller reserves the one after the last.
+async fn nominatim_turn() {
+    let wait = match NOMINATIM_NEXT.lock() {
+        Ok(mut next) => {
+            let now = Instant::now();
+            let at = next.map_or(now, |n| n.max(now));
+            *next = Some(at + NOMINATIM_INTERVAL);
+            at - now
+        }
+        Err(_) => NOMINATIM_INTERVAL,
+    };
+    tokio::time::sleep(wait).await;
+}
+
+async fn nominatim_get<T: DeserializeOwned + Clone + Send + Sync + 'static>(url: &str) -> Result<T, GeocodeError> {
+    nominatim_turn().await;
+    let client = reqwest::Client::builder()
+        .user_agent(NOMINATIM_USER_AGENT)
+        .timeout(NOMINATIM_TIMEOUT)
+        .build()?;
+    Ok(crate::httpcache::get(&client, url).await?.json()?)
+}
+
+#[derive(Deserialize, Clone)]
+struct SearchHit {
+    lat: String,
+    lon: String,
+    #[serde(default)] name: String,
+    #[serde(default)] address: Option<Address>,
+}
+
+#[derive(Deserialize, Clone)]
+struct ReverseResp {
+    #[serde(default)] address: Option<Address>,
+}
+#[derive(Deserialize, Clone)]
+struct Address {
+    city: Option<String>,
+    town: Option<String>,
+    village: Option<String>,
+    hamlet: Option<String>,
+    municipality: Option<String>,
+    county: Option<String>,
+    #[serde(default)] state: String,
+    #[serde(default)] country: String,
+}
+
+impl Address {
+    /// The settlement's name, the most specific kind first.
+    fn place_name(&self) -> Option<String> {
+        [&self.city, &self.town, &self.village, &self.hamlet, &self.municipality, &self.county]
+            .into_iter()
+            .flatten()
+            .next()
+            .cloned()
+    }
+}
+
+async fn nominatim_places(query: &str, country: Option<&str>, lang: &str) -> Result<Vec<Place>, GeocodeError> {
+    let mut url = format!(
+        "https://nominatim.openstreetmap.org/search?q={}&format=jsonv2&addressdetails=1&limit={MAX_PLACES}&accept-language={lang}",
         urlencoding::encode(query)
     );
-    let resp = reqwest::Client::new().get(&url).send().await?.error_for_status()?;
-    let data: SearchResp = resp.json().await?;
-    let item = data.results.and_then(|mut v| v.pop()).ok_or(GeocodeError::NotFound)?;
-    let label = if item.country.is_empty() { item.name.clone() }
-    else if item.admin1.is_empty() { format!("{} ({})", item.name, item.country) }
-    else { format!("{} — {}, {}", item.name, item.admin1, item.country) };
-    Ok((item.latitude, item.longitude, label))
+    if let Some(cc) = country {
+        url.push_str(&format!("&countrycodes={}", urlencoding::encode(&cc.to_lowercase())));
+    }
+    let hits: Vec<SearchHit> = nominatim_get(&url).await?;
+    let places: Vec<Place> = hits
+        .into_iter()
+        .filter_map(|hit| {
+            let (lat, lon) = (hit.lat.parse().ok()?, hit.lon.parse().ok()?);
+            let (name, state, country) = match &hit.address {
+                Some(a) => (a.place_name().unwrap_or(hit.name), a.state.as_str(), a.country.as_str()),
+                None => (hit.name, "", ""),
+            };
+            (!name.is_empty()).then(|| Place {
+                lat,
+                lon,
+                label: place_label(&name, state, country),
+                timezone: String::new(),
+            })
+        })
+        .collect();
+    if places.is_empty() {
+        return Err(GeocodeError::NotFound);
+    }
+    Ok(places)
+}
+
+/// The place at `lat`/`lon` ("Cluj-Napoca — Cluj, Romania"), for coordinates
+/// that didn't come from a city search. Cached like searches.
+pub async fn fetch_place(user: &str, lat: f64, lon: f64, lang: &str) -> Result<Place, GeocodeError> {
+    // ~100 m grid: nearby points share a lookup
+    let key = format!("{lang}|{lat:.3},{lon:.3}");
+    let cached = cached_places(user, &key);
+    if let Some((ts, places)) = &cached {
+        if let (true, Some(place)) = (crate::cache::is_fresh(*ts, PLACES_TTL_SECS), places.first()) {
+            return Ok(Place { lat, lon, ..place.clone() });
+        }
+    }
+    let url = format!(
+        "https://nominatim.openstreetmap.org/reverse?lat={lat}&lon={lon}&zoom=10&format=jsonv2&accept-language={lang}"
+    );
+    let found = nominatim_get::<ReverseResp>(&url).await.and_then(|resp| {
+        let a = resp.address.ok_or(GeocodeError::NotFound)?;
+        let name = a.place_name().ok_or(GeocodeError::NotFound)?;
+        Ok(Place { lat, lon, label: place_label(&name, &a.state, &a.country), timezone: String::new() })
+    });
+    match found {
+        Ok(place) => {
+            remember_places(user, &key, std::slice::from_ref(&place));
+            Ok(place)
+        }
+        Err(GeocodeError::NotFound) => Err(GeocodeError::NotFound),
+        Err(e) => cached
+            .and_then(|(_, places)| places.into_iter().next())
+            .map(|p| Place { lat, lon, ..p })
+            .ok_or(e),
+    }
 }
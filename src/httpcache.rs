@@ -0,0 +1,185 @@
+use crate::config::cache_root;
+use lazy_static::lazy_static;
+use reqwest::{header, Client, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    any::Any,
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::SystemTime,
+};
+
+/// Stored responses kept on disk; the least recently written go first.
+const MAX_ENTRIES: usize = 200;
+
+/// A response body with the validators the server sent for it. Weather,
+/// news and geocoding answers aren't user data, so these are shared (except
+/// while `PRIVATE` is set).
+#[derive(Clone, Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(default)]
+    last_modified: Option<String>,
+    body: String,
+}
+
+lazy_static! {
+    /// In-memory copies of the entries on disk; `prune` drops the ones whose
+    /// file is gone.
+    static ref ENTRIES: Mutex<HashMap<String, Entry>> = Mutex::new(HashMap::new());
+    /// Last parse of each stored URL's body, reused while the server answers 304.
+    static ref PARSED: Mutex<HashMap<String, Arc<dyn Any + Send + Sync>>> = Mutex::new(HashMap::new());
+}
+
+/// Set while the signed-in user encrypts their cache: what they look up
+/// (cities, topics, feeds) is neither stored here nor read back.
+static PRIVATE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_private(on: bool) {
+    PRIVATE.store(on, Ordering::Relaxed);
+}
+
+pub fn dir() -> PathBuf {
+    cache_root().join("http")
+}
+
+fn path(url: &str) -> PathBuf {
+    dir().join(format!("{:016x}.json", crate::cache::fnv1a(url)))
+}
+
+fn stored(url: &str) -> Option<Entry> {
+    if let Some(e) = ENTRIES.lock().ok()?.get(url) {
+        return Some(e.clone());
+    }
+    let entry: Entry = serde_json::from_str(&fs::read_to_string(path(url)).ok()?).ok()?;
+    // two URLs with the same hash: treat as a miss
+    if entry.url != url {
+        return None;
+    }
+    if let Ok(mut entries) = ENTRIES.lock() {
+        entries.insert(url.to_string(), entry.clone());
+    }
+    Some(entry)
+}
+
+fn store(entry: Entry) {
+    let _ = fs::create_dir_all(dir());
+    if let Ok(json) = serde_json::to_string(&entry) {
+        if let Err(e) = fs::write(path(&entry.url), json) {
+            eprintln!("HTTP cache: could not store {}: {e}", entry.url);
+        }
+    }
+    if let Ok(mut parsed) = PARSED.lock() {
+        parsed.remove(&entry.url);
+    }
+    if let Ok(mut entries) = ENTRIES.lock() {
+        entries.insert(entry.url.clone(), entry);
+    }
+    prune();
+}
+
+/// Keep the `MAX_ENTRIES` newest files, and only the in-memory copies and
+/// parses of those, so memory stays as bounded as the disk.
+fn prune() {
+    let mut files: Vec<(SystemTime, PathBuf)> = fs::read_dir(dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    if files.len() > MAX_ENTRIES {
+        files.sort();
+        for (_, p) in &files[..files.len() - MAX_ENTRIES] {
+            let _ = fs::remove_file(p);
+        }
+    }
+    // another instance may have pruned too
+    if let Ok(mut entries) = ENTRIES.lock() {
+        entries.retain(|url, _| path(url).is_file());
+        if let Ok(mut parsed) = PARSED.lock() {
+            parsed.retain(|url, _| entries.contains_key(url));
+        }
+    }
+}
+
+/// Forget every stored response (Settings → Clear cache → Everything).
+pub fn clear() {
+    if let Ok(mut entries) = ENTRIES.lock() {
+        entries.clear();
+    }
+    if let Ok(mut parsed) = PARSED.lock() {
+        parsed.clear();
+    }
+    let _ = fs::remove_dir_all(dir());
+}
+
+/// A response body, fresh or confirmed unchanged by a 304.
+pub struct Body {
+    url: String,
+    pub text: String,
+    /// The server answered 304 Not Modified; `text` is the stored copy.
+    pub not_modified: bool,
+}
+
+impl Body {
+    /// Parse the body. After a 304 the previous parse is cloned instead, so an
+    /// unchanged payload isn't deserialized again.
+    pub fn json<T: DeserializeOwned + Clone + Send + Sync + 'static>(&self) -> serde_json::Result<T> {
+        if self.not_modified {
+            let memo = PARSED.lock().ok().and_then(|p| p.get(&self.url).cloned());
+            if let Some(v) = memo.and_then(|m| m.downcast::<T>().ok()) {
+                return Ok((*v).clone());
+            }
+        }
+        let value: T = serde_json::from_str(&self.text)?;
+        // only a stored body can come back as a 304
+        let stored = ENTRIES.lock().is_ok_and(|e| e.contains_key(&self.url));
+        if stored {
+            if let Ok(mut parsed) = PARSED.lock() {
+                parsed.insert(self.url.clone(), Arc::new(value.clone()));
+            }
+        }
+        Ok(value)
+    }
+}
+
+/// GET `url`, sending `If-None-Match` / `If-Modified-Since` when a copy is
+/// stored. A 304 returns that copy without downloading it again; callers still
+/// save their own cache entry, which is what refreshes its TTL.
+pub async fn get(client: &Client, url: &str) -> reqwest::Result<Body> {
+    let private = PRIVATE.load(Ordering::Relaxed);
+    let previous = if private { None } else { stored(url) };
+    let mut req = client.get(url);
+    if let Some(prev) = &previous {
+        if let Some(etag) = &prev.etag {
+            req = req.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(modified) = &prev.last_modified {
+            req = req.header(header::IF_MODIFIED_SINCE, modified);
+        }
+    }
+    let resp = req.send().await?;
+    if resp.status() == StatusCode::NOT_MODIFIED {
+        if let Some(prev) = previous {
+            return Ok(Body { url: url.to_string(), text: prev.body, not_modified: true });
+        }
+    }
+    let resp = resp.error_for_status()?;
+    let validator = |name: header::HeaderName| {
+        resp.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+    };
+    let etag = validator(header::ETAG);
+    let last_modified = validator(header::LAST_MODIFIED);
+    let text = resp.text().await?;
+    if !private && (etag.is_some() || last_modified.is_some()) {
+        store(Entry { url: url.to_string(), etag, last_modified, body: text.clone() });
+    }
+    Ok(Body { url: url.to_string(), text, not_modified: false })
+}
@@ -0,0 +1,255 @@
+use crate::settings::{DefaultValue, Descriptor, Kind};
+use anyhow::{anyhow, bail, Context};
+use base64::Engine;
+use chrono::{DateTime, FixedOffset};
+use std::{
+    io,
+    net::{TcpStream, ToSocketAddrs},
+    sync::Arc,
+    time::Duration,
+};
+
+// Inbox preview over IMAP (TLS only): the newest messages' sender, subject and
+// date, and the unread count for the nav badge. The mailbox is opened
+// read-only (EXAMINE) and only headers are fetched, so nothing is marked as
+// read; replies go through the default mail client (mailto:). The password
+// lives in the OS credential store (`secrets::mail_password`).
+
+/// IMAP server, e.g. "imap.gmail.com"; empty = mail is off.
+pub const SERVER: Descriptor = Descriptor {
+    section: "mail",
+    key: "server",
+    label: "IMAP server",
+    kind: Kind::Text,
+    default: DefaultValue::Text(""),
+};
+
+pub const PORT: Descriptor = Descriptor {
+    section: "mail",
+    key: "port",
+    label: "IMAP port (TLS)",
+    kind: Kind::Number { min: 1, max: 65535 },
+    default: DefaultValue::Number(993),
+};
+
+/// Login name, usually the address itself.
+pub const USERNAME: Descriptor = Descriptor {
+    section: "mail",
+    key: "username",
+    label: "Mail username",
+    kind: Kind::Text,
+    default: DefaultValue::Text(""),
+};
+
+/// Minutes between background checks for the badge; 0 = only when the page is opened.
+pub const POLL_MINUTES: Descriptor = Descriptor {
+    section: "mail",
+    key: "poll_minutes",
+    label: "Check mail every (min)",
+    kind: Kind::Number { min: 0, max: 240 },
+    default: DefaultValue::Number(10),
+};
+
+/// Mail settings for the registry in `settings.rs`.
+pub const SETTINGS: &[Descriptor] = &[SERVER, PORT, USERNAME, POLL_MINUTES];
+
+/// How many of the newest messages are listed.
+pub const SHOWN: u32 = 25;
+
+/// Connecting, and every read or write after it, gives up after this long, so
+/// an unresponsive server can't hold a blocking thread forever.
+const MAIL_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// One message in the list.
+pub struct Message {
+    /// Display name, or the address when there's none.
+    pub from: String,
+    pub address: String,
+    pub subject: String,
+    pub date: Option<DateTime<FixedOffset>>,
+    pub unread: bool,
+}
+
+/// The newest messages (newest first) and the unread count of the whole inbox.
+pub struct Inbox {
+    pub messages: Vec<Message>,
+    pub unread: usize,
+}
+
+/// Where to connect; `None` until the user filled in the server and username.
+pub struct Account {
+    pub server: String,
+    pub port: u16,
+    pub username: String,
+}
+
+impl Account {
+    pub fn from_config(cfg: &crate::config::AppConfig) -> Option<Self> {
+        let server = SERVER.text(cfg).trim().to_string();
+        let username = USERNAME.text(cfg).trim().to_string();
+        if server.is_empty() || username.is_empty() {
+            return None;
+        }
+        Some(Account { server, port: u16::try_from(PORT.number(cfg)).unwrap_or(993), username })
+    }
+}
+
+type TlsStream = rustls::StreamOwned<rustls::ClientConnection, TcpStream>;
+
+/// TCP to the first address of `account` that answers within `MAIL_TIMEOUT`.
+fn connect_tcp(account: &Account) -> io::Result<TcpStream> {
+    let mut last = io::Error::new(io::ErrorKind::NotFound, format!("no address for {}", account.server));
+    for addr in (account.server.as_str(), account.port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, MAIL_TIMEOUT) {
+            Ok(tcp) => return Ok(tcp),
+            Err(e) => last = e,
+        }
+    }
+    Err(last)
+}
+
+/// TLS (rustls, checked against the webpki roots) over `connect_tcp`, with
+/// read and write timeouts.
+fn connect(account: &Account) -> anyhow::Result<TlsStream> {
+    let tcp = connect_tcp(account)?;
+    tcp.set_read_timeout(Some(MAIL_TIMEOUT))?;
+    tcp.set_write_timeout(Some(MAIL_TIMEOUT))?;
+
+    let mut roots = rustls::RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let name = rustls::pki_types::ServerName::try_from(account.server.clone())?;
+    let conn = rustls::ClientConnection::new(Arc::new(config), name)?;
+    Ok(rustls::StreamOwned::new(conn, tcp))
+}
+
+/// Sign in and read the inbox. Blocking: run it on `spawn_blocking`.
+pub fn fetch_inbox(account: &Account, password: &str) -> anyhow::Result<Inbox> {
+    let stream = connect(account).with_context(|| format!("can't reach {}:{}", account.server, account.port))?;
+    let mut client = imap::Client::new(stream);
+    client.read_greeting().with_context(|| format!("no IMAP greeting from {}", account.server))?;
+    let mut session = client.login(&account.username, password).map_err(|(e, _)| anyhow!("sign-in failed: {e}"))?;
+
+    let inbox = session.examine("INBOX")?;
+    let unread = session.search("UNSEEN")?.len();
+    let mut messages = Vec::new();
+    if inbox.exists > 0 {
+        let first = inbox.exists.saturating_sub(SHOWN - 1).max(1);
+        let fetches = session.fetch(format!("{first}:{}", inbox.exists), "(FLAGS ENVELOPE INTERNALDATE)")?;
+        for f in fetches.iter() {
+            let Some(env) = f.envelope() else { continue };
+            let sender = env.from.as_ref().and_then(|list| list.first());
+            let address = sender
+                .map(|a| {
+                    let part = |p: Option<&[u8]>| p.map(|b| String::from_utf8_lossy(b).into_owned()).unwrap_or_default();
+                    format!("{}@{}", part(a.mailbox), part(a.host))
+                })
+                .filter(|a| a.len() > 1)
+                .unwrap_or_default();
+            let name = sender.and_then(|a| a.name).map(decode_header).unwrap_or_default();
+            let date = env
+                .date
+                .and_then(|d| DateTime::parse_from_rfc2822(String::from_utf8_lossy(d).trim()).ok())
+                .or_else(|| f.internal_date());
+            messages.push(Message {
+                from: if name.trim().is_empty() { address.clone() } else { name },
+                address,
+                subject: env.subject.map(decode_header).unwrap_or_default(),
+                date,
+                unread: !f.flags().iter().any(|flag| matches!(flag, imap::types::Flag::Seen)),
+            });
+        }
+    }
+    let _ = session.logout();
+    messages.reverse();
+    Ok(Inbox { messages, unread })
+}
+
+/// A header as sent, with its RFC 2047 encoded words ("=?UTF-8?B?...?=")
+/// decoded. Unknown charsets come out as lossy UTF-8.
+pub fn decode_header(raw: &[u8]) -> String {
+    let text = String::from_utf8_lossy(raw).replace(['\r', '\n'], "");
+    let mut out = String::new();
+    let mut rest = text.as_str();
+    // whitespace between two encoded words is folding, not content
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let (before, word) = rest.split_at(start);
+        match decode_word(word) {
+            Some((decoded, len)) => {
+                if !(after_word && before.trim().is_empty()) {
+                    out.push_str(before);
+                }
+                out.push_str(&decoded);
+                rest = &word[len..];
+                after_word = true;
+            }
+            None => {
+                out.push_str(before);
+                out.push_str("=?");
+                rest = &word[2..];
+                after_word = false;
+            }
+        }
+    }
+    out.push_str(rest);
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// "=?charset?enc?text?=" at the start of `s` -> (decoded, bytes used).
+fn decode_word(s: &str) -> Option<(String, usize)> {
+    let body = s.strip_prefix("=?")?;
+    let (charset, body) = body.split_once('?')?;
+    let (encoding, body) = body.split_once('?')?;
+    let end = body.find("?=")?;
+    let payload = &body[..end];
+    if payload.contains(char::is_whitespace) {
+        return None;
+    }
+    let bytes = match encoding {
+        "B" | "b" => {
+            let trimmed = payload.trim_end_matches('=');
+            base64::engine::general_purpose::STANDARD_NO_PAD.decode(trimmed).ok()?
+        }
+        "Q" | "q" => {
+            let mut bytes = Vec::new();
+            let mut it = payload.bytes();
+            while let Some(b) = it.next() {
+                match b {
+                    b'_' => bytes.push(b' '),
+                    b'=' => {
+                        let hex = [it.next()?, it.next()?];
+                        bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+                    }
+                    _ => bytes.push(b),
+                }
+            }
+            bytes
+        }
+        _ => return None,
+    };
+    // "utf-8*en" carries an RFC 2231 language tag
+    let charset = charset.split('*').next().unwrap_or(charset).to_ascii_lowercase();
+    let decoded = match charset.as_str() {
+        "iso-8859-1" | "latin1" | "windows-1252" | "cp1252" => bytes.iter().map(|&b| char::from(b)).collect(),
+        _ => String::from_utf8_lossy(&bytes).into_owned(),
+    };
+    Some((decoded, s.len() - body.len() + end + 2))
+}
+
+/// A reply in the default mail client.
+pub fn reply_link(address: &str, subject: &str) -> anyhow::Result<String> {
+    if address.is_empty() {
+        bail!("no sender address");
+    }
+    let subject = subject.trim();
+    let subject = if subject.get(..3).is_some_and(|p| p.eq_ignore_ascii_case("re:")) {
+        subject.to_string()
+    } else {
+        format!("Re: {subject}")
+    };
+    Ok(format!("mailto:{address}?subject={}", urlencoding::encode(&subject)))
+}
@@ -1,26 +1,68 @@
+mod alarms;
+#[cfg_attr(not(feature = "sound"), path = "audio_none.rs")]
+mod audio;
 mod auth;
 mod weather;
 mod news;
+mod mail;
+mod calendar;
 mod config;
+mod currency;
 mod cache;
+mod cachestore;
+mod cachecrypt;
+mod httpcache;
 mod geocode;
+mod gazetteer;
+mod notes;
+mod podcasts;
+mod worldclock;
+mod notify;
+mod reader;
+mod summarize;
+mod export;
+mod speech;
+mod stopwatch;
+mod stocks;
+mod secrets;
+mod userstore;
+mod avatar;
+mod audit;
+mod bundle;
+mod theme;
+mod watch;
+mod cli;
+mod settings;
+mod timefmt;
 
 use weather::fetch_next_hours_at;
-use geocode::fetch_coords;
+use geocode::{fetch_places, Place};
 
 
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
-use auth::{LocalAuth, AuthError};
+use auth::{Auth, AuthError, AuthProvider, UserStorage, Role, Restrictions};
+use secrets::{ApiService, SecretBackend};
+use audit::AuditEvent;
+use zeroize::Zeroizing;
+use clap::Parser;
+use theme::Theme;
 
-use config::{AppConfig, load_config, load_config_for, save_config_for};
+use config::{AppConfig, load_config, load_config_for, save_config_for, split_list, load_session, save_session, forget_session, Session};
 
 use cache::{
-    is_fresh, age_minutes,
-    load_weather_for, save_weather_for,
-    load_news_for, save_news_for,
+    age_minutes, CacheKey,
+    save_weather_for,
+    load_all_news_for, save_news_for,
+    load_seen_for, save_seen_for,
+    load_bookmarks_for, save_bookmarks_for, load_offline_for, remove_offline_for,
+    load_summary_for, save_summary_for,
+    load_open_stats_for, save_open_stats_for,
+    load_read_for, save_read_for, ReadCache,
+    Bookmark, BookmarkStore,
 };
 
-use slint::{ComponentHandle, Image, SharedPixelBuffer};
+use slint::{ComponentHandle, Image, Model};
 
 slint::include_modules!();
 
@@ -30,6 +72,21 @@ struct AppState {
     current_page: Page,
     clock_text: String,
     current_user: Option<String>,
+    /// Canonical URLs of stories already shown on the News page.
+    news_known: HashSet<String>,
+    /// Stories found by the background poll since the News page was last opened.
+    news_unread: usize,
+    /// Last time the pointer moved over the app (drives the idle auto-lock).
+    last_activity: Option<std::time::Instant>,
+    last_pointer: (f32, f32),
+    /// Account whose session is behind the lock screen.
+    locked_user: Option<String>,
+    /// Session tokens: accounts that entered their PIN in this run, and when.
+    sessions: HashMap<String, std::time::Instant>,
+    /// The page's Refresh button was pressed: the next refresh refetches even
+    /// if the cache is still fresh.
+    force_weather_refresh: bool,
+    force_news_refresh: bool,
 }
 
 type State = Arc<Mutex<AppState>>;
@@ -50,6 +107,17 @@ fn set_page(state: &State, app_weak: &slint::Weak<MainWindow>, page: Page) {
     ui(app_weak, move |app| app.set_current_page(page));
 }
 
+/// Page named by the user's `start_page` setting; News falls back to Weather
+/// when the profile may not see it (call after `apply_profile`).
+fn start_page(app: &MainWindow, cfg: &AppConfig) -> Page {
+    match cfg.start_page.as_str() {
+        "News" if app.get_allow_news() => Page::News,
+        "Notes" => Page::Notes,
+        "Settings" => Page::Settings,
+        _ => Page::Weather,
+    }
+}
+
 fn set_login(state: &State, app_weak: &slint::Weak<MainWindow>, logged_in: bool) {
     if let Ok(mut s) = state.lock() { s.is_logged_in = logged_in; }
     ui(app_weak, move |app| {
@@ -71,6 +139,59 @@ fn set_login_error(app_weak: &slint::Weak<MainWindow>, msg: String) {
     ui(app_weak, move |app| app.set_login_error_text(msg.into()));
 }
 
+/// How long a PIN sign-in keeps an account eligible for fast switching.
+const SESSION_TTL: std::time::Duration = std::time::Duration::from_secs(8 * 60 * 60);
+
+/// Switching is fast (no PIN) only for accounts that opted in and still hold a session.
+fn can_fast_switch(s: &AppState, user: &str) -> bool {
+    load_config_for(user).fast_switch
+        && s.sessions.get(user).is_some_and(|t| t.elapsed() < SESSION_TTL)
+}
+
+/// Back to the login overlay with the pages kept as they are; the PIN resumes.
+/// Guest has no PIN to unlock with, so it is never locked.
+fn lock_session(app: &MainWindow, s: &mut AppState) {
+    let Some(user) = s.current_user.clone().filter(|u| u != "guest") else { return };
+    audit::record(AuditEvent::Locked, &user, "");
+    s.is_logged_in = false;
+    s.locked_user = Some(user.clone());
+    app.set_locked(true);
+    app.set_user_menu_open(false);
+    app.set_login_user(user.into());
+    app.set_login_pin("".into());
+    app.set_login_error_text("".into());
+    app.set_is_logged_in(false);
+}
+
+/// Ask for `target`'s PIN before switching. The current account stays behind a
+/// lock, so signing in as it again resumes where it was.
+fn prompt_switch(app: &MainWindow, s: &mut AppState, target: &str) {
+    s.locked_user = s.current_user.clone().filter(|u| u != "guest");
+    s.is_logged_in = false;
+    app.set_user_menu_open(false);
+    app.set_login_user(target.into());
+    app.set_login_pin("".into());
+    app.set_login_error_text(format!("Enter the PIN for {target}").into());
+    app.set_is_logged_in(false);
+}
+
+/// Remember stories the user has had on screen (they no longer count as unread).
+fn mark_news_seen<'a>(state: &State, urls: impl IntoIterator<Item = &'a str>) {
+    if let Ok(mut s) = state.lock() {
+        for u in urls {
+            s.news_known.insert(news::canonical_url(u));
+        }
+    }
+}
+
+/// Read a one-shot flag from the state and clear it.
+fn take_flag(state: &State, flag: impl FnOnce(&mut AppState) -> &mut bool) -> bool {
+    state.lock().map(|mut s| std::mem::take(flag(&mut s))).unwrap_or(false)
+}
+
+/// Appended to "Cached …" statuses while a stale copy is being refetched.
+const STALE_NOTE: &str = " • stale, refreshing…";
+
 fn current_user(state: &State) -> String {
     state
         .lock()
@@ -82,25 +203,1053 @@ fn current_user(state: &State) -> String {
 fn set_current_user(state: &State, app_weak: &slint::Weak<MainWindow>, user: Option<String>) {
     if let Ok(mut s) = state.lock() { s.current_user = user.clone(); }
     let label = user.clone().unwrap_or_else(|| "guest".into());
-    ui(app_weak, move |app| app.set_current_user(label.into()));
+    httpcache::set_private(cachecrypt::enabled(&label));
+    ui(app_weak, move |app| {
+        if app.get_current_user() != label.as_str() {
+            // another account's notes: close the open one (its pending save still lands)
+            app.set_note_id("".into());
+            app.set_note_text("".into());
+            app.set_notes_query("".into());
+            push_notes(&app, &label, "");
+            // only the signed-in user's alarms ring
+            app.set_clock_rings(slint::ModelRc::default());
+            push_clock(&app, &label);
+            push_world(&app, &label);
+            // another account's mailbox: nothing shows until it's checked
+            MAIL_FETCHES.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            app.set_mail_items(slint::ModelRc::default());
+            app.set_mail_unread(0);
+            app.set_mail_status("".into());
+            push_mail_account(&app, &label);
+            app.invoke_refresh_mail();
+            // and calendar: the local copy at once, the server's after a sync
+            app.set_calendar_status("".into());
+            push_calendar_account(&app, &label);
+            push_calendar(&app, &label);
+            app.invoke_sync_calendar();
+            // another account's podcasts: stop the episode (its position is saved)
+            stop_playback();
+            push_player(&app);
+            app.set_podcast_feed("".into());
+            push_podcasts(&app, &label);
+        }
+        app.set_current_user(label.into());
+    });
 }
 
-fn push_users_to_ui(app_weak: &slint::Weak<MainWindow>, auth: &LocalAuth) {
-    let list = auth.list_users().unwrap_or_default();
-    ui(app_weak, move |app| {
-        let list_ss: Vec<slint::SharedString> = list.into_iter().map(Into::into).collect();
-        let model = slint::VecModel::from(list_ss);
-        app.set_users(slint::ModelRc::new(model));
+/// Bumped on every note edit; a delayed save only runs if nothing came after it.
+static NOTE_EDITS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// How long typing has to pause before a note is saved.
+const NOTE_AUTOSAVE: std::time::Duration = std::time::Duration::from_millis(700);
+
+/// The user's notes matching `query` (all when empty) into the Notes list.
+fn push_notes(app: &MainWindow, user: &str, query: &str) {
+    let items: Vec<NoteItem> = notes::search(user, query)
+        .into_iter()
+        .map(|n| NoteItem {
+            title: n.title().into(),
+            snippet: notes::snippet(&n.text, query).into(),
+            updated: chrono::DateTime::from_timestamp(n.modified, 0)
+                .map(|t| timefmt::date_time(&t.with_timezone(&chrono::Local)))
+                .unwrap_or_default()
+                .into(),
+            id: n.id.into(),
+        })
+        .collect();
+    app.set_notes(slint::ModelRc::new(slint::VecModel::from(items)));
+}
+
+/// Save the open note now (switching notes, deleting), cancelling a pending autosave.
+fn save_open_note(app: &MainWindow, user: &str) {
+    let id = app.get_note_id();
+    if id.is_empty() {
+        return;
+    }
+    NOTE_EDITS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    if let Err(e) = notes::save(user, &id, &app.get_note_text()) {
+        eprintln!("Save note error: {e:?}");
+    }
+}
+
+/// `user`'s alarms and running timers into the Clock page.
+fn push_clock(app: &MainWindow, user: &str) {
+    let all = alarms::list(user);
+    let now = chrono::Local::now().timestamp();
+    let alarms: Vec<AlarmItem> = all
+        .alarms
+        .into_iter()
+        .map(|a| AlarmItem {
+            id: a.id.to_string().into(),
+            time: chrono::NaiveTime::from_hms_opt(a.hour, a.minute, 0).map(timefmt::time).unwrap_or_default().into(),
+            label: a.label.into(),
+            repeat: if a.daily { "Daily" } else { "Once" }.into(),
+            enabled: a.enabled,
+        })
+        .collect();
+    let timers: Vec<TimerItem> = all
+        .timers
+        .into_iter()
+        .map(|t| TimerItem { id: t.id.to_string().into(), label: t.label.into(), remaining: alarms::remaining(t.ends, now).into() })
+        .collect();
+    app.set_clock_alarms(slint::ModelRc::new(slint::VecModel::from(alarms)));
+    app.set_clock_timers(slint::ModelRc::new(slint::VecModel::from(timers)));
+}
+
+/// `user`'s world-clock places, as they read now, into the Clock page.
+fn push_world(app: &MainWindow, user: &str) {
+    let now = chrono::Utc::now();
+    let items: Vec<WorldItem> = worldclock::list(user)
+        .into_iter()
+        .filter_map(|zone| {
+            let r = worldclock::read_zone(&zone, &now)?;
+            let day = match r.day_shift {
+                1.. => "Tomorrow",
+                ..=-1 => "Yesterday",
+                _ => "",
+            };
+            let note: Vec<String> =
+                [day.to_string(), worldclock::offset_label(r.ahead)].into_iter().filter(|s| !s.is_empty()).collect();
+            Some(WorldItem {
+                label: zone.label.into(),
+                time: timefmt::time(r.local.time()).into(),
+                note: note.join(" · ").into(),
+                daytime: r.daytime,
+            })
+        })
+        .collect();
+    app.set_clock_world(slint::ModelRc::new(slint::VecModel::from(items)));
+}
+
+/// `symbols` as cached for `user` into the Stocks page (UI thread: the
+/// sparklines become images here).
+fn push_stocks(app: &MainWindow, user: &str, symbols: &[String]) {
+    let items: Vec<StockItem> = symbols
+        .iter()
+        .map(|symbol| {
+            let cached = cache::load_stock_for(user, symbol);
+            match cached.as_ref().and_then(|c| Some((c, stocks::quote(&c.days)?))) {
+                Some((c, (price, change, pct))) => {
+                    let closes: Vec<f64> = c.days.iter().map(|d| d.close).collect();
+                    StockItem {
+                        symbol: symbol.as_str().into(),
+                        price: format!("{price:.2}").into(),
+                        change: stocks::change_label(change, pct).into(),
+                        up: change >= 0.0,
+                        chart: Image::from_rgba8(stocks::sparkline(&closes)),
+                        note: c.days.last().map(|d| d.date.clone()).unwrap_or_default().into(),
+                    }
+                }
+                None => StockItem {
+                    symbol: symbol.as_str().into(),
+                    price: "—".into(),
+                    change: "".into(),
+                    up: true,
+                    chart: Image::default(),
+                    note: "no quotes yet".into(),
+                },
+            }
+        })
+        .collect();
+    app.set_stocks(slint::ModelRc::new(slint::VecModel::from(items)));
+}
+
+/// The converter's result for the amount and currencies in the form, and the
+/// favorite pairs with their rates, from the cached rates.
+fn push_currency(app: &MainWindow, user: &str) {
+    let pairs = currency::pairs(&load_config_for(user));
+    let (from, to) = (app.get_conv_from().to_string(), app.get_conv_to().to_string());
+    app.set_conv_favorite(pairs.iter().any(|(f, t)| *f == from && *t == to));
+    let Some(cached) = cache::load_rates_for(user) else {
+        app.set_conv_result("".into());
+        app.set_rates_note(if offline() { "Offline mode • no rates cached" } else { "Loading rates…" }.into());
+        return;
+    };
+    let codes: Vec<slint::SharedString> = cached.rates.keys().map(|c| c.as_str().into()).collect();
+    app.set_currencies(slint::ModelRc::new(slint::VecModel::from(codes)));
+    let result = match currency::parse_amount(&app.get_conv_amount()) {
+        None => "Enter an amount".to_string(),
+        Some(amount) => match currency::convert(&cached.rates, amount, &from, &to) {
+            Some(v) => format!("{} {from} = {} {to}", currency::format_amount(amount), currency::format_amount(v)),
+            None => format!("No rate for {from} / {to}"),
+        },
+    };
+    app.set_conv_result(result.into());
+    app.set_rates_note(format!("ECB reference rates of {} • updated {}m ago", cached.date, age_minutes(cached.ts)).into());
+    let items: Vec<PairItem> = pairs
+        .into_iter()
+        .map(|(from, to)| PairItem {
+            rate: currency::convert(&cached.rates, 1.0, &from, &to).map(currency::format_amount).unwrap_or_else(|| "—".into()).into(),
+            from: from.into(),
+            to: to.into(),
+        })
+        .collect();
+    app.set_currency_pairs(slint::ModelRc::new(slint::VecModel::from(items)));
+}
+
+/// Bumped on every inbox check and account switch; only the latest check is shown.
+static MAIL_FETCHES: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// How long an inbox check may take before it's given up.
+const MAIL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(45);
+
+/// The sign-in form: server and username from the settings. Whether `user` is
+/// signed in takes a keyring read, so `refresh_mail` sets that.
+fn push_mail_account(app: &MainWindow, user: &str) {
+    let cfg = load_config_for(user);
+    app.set_mail_server(mail::SERVER.text(&cfg).into());
+    app.set_mail_username(mail::USERNAME.text(&cfg).into());
+}
+
+/// Check `user`'s inbox in the background, then show the newest messages and
+/// the unread count (nav badge). The password is read from the keyring off the
+/// UI thread; whether there is one also marks the account signed in.
+fn refresh_mail(h: &tokio::runtime::Handle, app_weak: &slint::Weak<MainWindow>, user: String) {
+    let Some(account) = mail::Account::from_config(&load_config_for(&user)) else {
+        ui(app_weak, |app| app.set_mail_signed_in(false));
+        return;
+    };
+    let fetch = MAIL_FETCHES.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+    let latest = move || MAIL_FETCHES.load(std::sync::atomic::Ordering::SeqCst) == fetch;
+    let aw = app_weak.clone();
+    h.spawn(async move {
+        let u = user.clone();
+        let password = tokio::task::spawn_blocking(move || secrets::mail_password(&u).map(Zeroizing::new))
+            .await
+            .ok()
+            .flatten();
+        let signed_in = password.is_some();
+        ui(&aw, move |app| {
+            if latest() {
+                app.set_mail_signed_in(signed_in);
+            }
+        });
+        let Some(password) = password else { return };
+        if offline() {
+            ui(&aw, |app| app.set_mail_status("Offline mode".into()));
+            return;
+        }
+        ui(&aw, |app| app.set_mail_status("Checking…".into()));
+        let job = tokio::task::spawn_blocking(move || mail::fetch_inbox(&account, &password));
+        let result = match tokio::time::timeout(MAIL_TIMEOUT, job).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => Err(e.into()),
+            Err(_) => Err(anyhow::anyhow!("the server didn't answer")),
+        };
+        if let Err(e) = &result {
+            eprintln!("Mail check for {user}: {e:#}");
+        }
+        ui(&aw, move |app| {
+            if !latest() {
+                return;
+            }
+            let inbox = match result {
+                Ok(inbox) => inbox,
+                Err(e) => {
+                    app.set_mail_status(format!("Mail: {e:#}").into());
+                    return;
+                }
+            };
+            let today = chrono::Local::now().date_naive();
+            let items: Vec<MailItem> = inbox
+                .messages
+                .into_iter()
+                .map(|m| MailItem {
+                    date: m
+                        .date
+                        .map(|d| d.with_timezone(&chrono::Local))
+                        .map(|d| if d.date_naive() == today { timefmt::time(d.time()) } else { timefmt::date_time(&d) })
+                        .unwrap_or_default()
+                        .into(),
+                    from: m.from.into(),
+                    address: m.address.into(),
+                    subject: if m.subject.is_empty() { "(no subject)".into() } else { m.subject.into() },
+                    unread: m.unread,
+                })
+                .collect();
+            app.set_mail_items(slint::ModelRc::new(slint::VecModel::from(items)));
+            app.set_mail_unread(inbox.unread as i32);
+            app.set_mail_status(
+                format!("{} unread • checked {}", inbox.unread, timefmt::time(chrono::Local::now().time())).into(),
+            );
+        });
+    });
+}
+
+/// How many upcoming events the Calendar page lists.
+const CALENDAR_SHOWN: usize = 200;
+
+/// The sign-in form: calendar URL and username from the settings;
+/// `sync_calendar` sets whether `user` is signed in.
+fn push_calendar_account(app: &MainWindow, user: &str) {
+    let cfg = load_config_for(user);
+    app.set_calendar_url(calendar::URL.text(&cfg).into());
+    app.set_calendar_username(calendar::USERNAME.text(&cfg).into());
+}
+
+/// `user`'s upcoming events into the Calendar page; the first event of each
+/// day carries the day (ones that started earlier and still run count as today).
+fn push_calendar(app: &MainWindow, user: &str) {
+    let now = chrono::Local::now();
+    let today = now.date_naive();
+    let local = |t: i64| chrono::DateTime::from_timestamp(t, 0).map(|t| t.with_timezone(&chrono::Local));
+    let mut last_day = None;
+    let items: Vec<EventItem> = calendar::upcoming(user, &now, CALENDAR_SHOWN)
+        .into_iter()
+        .map(|e| {
+            let day = e.first_day().map(|d| d.max(today));
+            let heading = match day {
+                _ if day == last_day => String::new(),
+                Some(d) if d == today => "Today".to_string(),
+                Some(d) if Some(d) == today.succ_opt() => "Tomorrow".to_string(),
+                Some(d) => timefmt::weekday_date(d),
+                None => String::new(),
+            };
+            last_day = day;
+            let time = match (local(e.start), local(e.end)) {
+                _ if e.all_day => "All day".to_string(),
+                (Some(start), Some(end)) if end > start && end.date_naive() == start.date_naive() => {
+                    format!("{} – {}", timefmt::time(start.time()), timefmt::time(end.time()))
+                }
+                (Some(start), Some(end)) if end > start => format!("{} – {}", timefmt::time(start.time()), timefmt::date(&end)),
+                (Some(start), _) => timefmt::time(start.time()),
+                _ => String::new(),
+            };
+            EventItem {
+                day: heading.into(),
+                time: time.into(),
+                title: e.summary.into(),
+                location: e.location.into(),
+                imported: e.source == calendar::Source::Imported,
+            }
+        })
+        .collect();
+    app.set_calendar_events(slint::ModelRc::new(slint::VecModel::from(items)));
+}
+
+/// Sync `user`'s calendar with their CalDAV account in the background, then
+/// show it. The password is read from the keyring off the UI thread, and
+/// whether there is one marks the account signed in.
+fn sync_calendar(h: &tokio::runtime::Handle, app_weak: &slint::Weak<MainWindow>, user: String) {
+    let Some(account) = calendar::Account::from_config(&load_config_for(&user)) else {
+        ui(app_weak, |app| app.set_calendar_signed_in(false));
+        return;
+    };
+    let aw = app_weak.clone();
+    h.spawn(async move {
+        let u = user.clone();
+        let password = tokio::task::spawn_blocking(move || secrets::caldav_password(&u).map(Zeroizing::new))
+            .await
+            .ok()
+            .flatten();
+        let signed_in = password.is_some();
+        let u = user.clone();
+        ui(&aw, move |app| {
+            if app.get_current_user() == u.as_str() {
+                app.set_calendar_signed_in(signed_in);
+            }
+        });
+        let Some(password) = password else { return };
+        if offline() {
+            ui(&aw, |app| app.set_calendar_status("Offline mode".into()));
+            return;
+        }
+        ui(&aw, |app| app.set_calendar_status("Syncing…".into()));
+        let result = calendar::sync(&user, &account, &password).await;
+        if let Err(e) = &result {
+            eprintln!("Calendar sync for {user}: {e:#}");
+        }
+        ui(&aw, move |app| {
+            // the account was switched meanwhile: its own sync shows its calendar
+            if app.get_current_user() != user.as_str() {
+                return;
+            }
+            let at = timefmt::time(chrono::Local::now().time());
+            let status = match result {
+                Ok(calendar::Synced::Unchanged) => format!("Up to date • synced {at}"),
+                Ok(calendar::Synced::Fetched(n)) => format!("{n} events on the server • synced {at}"),
+                Err(e) => format!("Calendar: {e:#}"),
+            };
+            push_calendar(&app, &user);
+            app.set_calendar_status(status.into());
+        });
+    });
+}
+
+/// Bumped on every episode start and stop; a player loop stops once it's not the latest.
+static PLAYBACK_RUNS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Seconds between saves of where the playing episode got to.
+const PROGRESS_SAVE_EVERY: u32 = 10;
+
+/// `user`'s subscriptions, and the selected one's episodes (the first
+/// subscription when none is selected), into the Podcasts page.
+fn push_podcasts(app: &MainWindow, user: &str) {
+    let all = podcasts::list(user);
+    let mut selected = app.get_podcast_feed().to_string();
+    if !all.feeds.iter().any(|f| f.url == selected) {
+        selected = all.feeds.first().map(|f| f.url.clone()).unwrap_or_default();
+        app.set_podcast_feed(selected.as_str().into());
+    }
+    let playing = audio::now_playing().filter(|n| n.user == user).map(|n| n.episode);
+    let feeds: Vec<FeedItem> = all
+        .feeds
+        .iter()
+        .map(|f| {
+            let unplayed = f.episodes.iter().filter(|e| !all.progress(&e.id).listened).count();
+            FeedItem {
+                url: f.url.as_str().into(),
+                title: f.title.as_str().into(),
+                note: format!("{} episodes · {unplayed} unplayed", f.episodes.len()).into(),
+            }
+        })
+        .collect();
+    let episodes: Vec<EpisodeItem> = all
+        .feeds
+        .iter()
+        .find(|f| f.url == selected)
+        .map(|f| f.episodes.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .map(|e| {
+            let p = all.progress(&e.id);
+            let length = if e.duration > 0 { podcasts::format_secs(e.duration) } else { String::new() };
+            let progress = match (p.listened, p.position) {
+                (true, _) => "Played".to_string(),
+                (false, 0) => length,
+                (false, pos) if length.is_empty() => format!("{} in", podcasts::format_secs(pos)),
+                (false, pos) => format!("{} / {length}", podcasts::format_secs(pos)),
+            };
+            EpisodeItem {
+                id: e.id.as_str().into(),
+                title: e.title.as_str().into(),
+                date: chrono::DateTime::from_timestamp(e.published, 0)
+                    .filter(|_| e.published > 0)
+                    .map(|t| timefmt::date(&t.with_timezone(&chrono::Local)))
+                    .unwrap_or_default()
+                    .into(),
+                progress: progress.into(),
+                listened: p.listened,
+                downloaded: podcasts::downloaded(user, e).is_some(),
+                playing: playing.as_deref() == Some(e.id.as_str()),
+            }
+        })
+        .collect();
+    app.set_podcast_feeds(slint::ModelRc::new(slint::VecModel::from(feeds)));
+    app.set_podcast_episodes(slint::ModelRc::new(slint::VecModel::from(episodes)));
+}
+
+/// The player bar: what's playing and how far along it is.
+fn push_player(app: &MainWindow) {
+    let Some(now) = audio::now_playing() else {
+        app.set_player_active(false);
+        return;
+    };
+    let position = podcasts::format_secs(now.position.as_secs());
+    app.set_player_active(true);
+    app.set_player_paused(now.paused);
+    app.set_player_title(now.title.into());
+    app.set_player_time(
+        if now.length.is_zero() { position } else { format!("{position} / {}", podcasts::format_secs(now.length.as_secs())) }.into(),
+    );
+}
+
+/// Save where an episode got to (played to the end counts as listened).
+fn remember_progress(now: &audio::NowPlaying) {
+    let progress = if now.finished {
+        podcasts::Progress { position: 0, listened: true }
+    } else {
+        podcasts::Progress { position: now.position.as_secs(), listened: false }
+    };
+    if let Err(e) = podcasts::set_progress(&now.user, &now.episode, progress) {
+        eprintln!("Save podcast progress error: {e:?}");
+    }
+}
+
+/// Stop the episode that's playing, remembering where it got to.
+fn stop_playback() {
+    PLAYBACK_RUNS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    if let Some(now) = audio::stop() {
+        remember_progress(&now);
+    }
+}
+
+/// Follow the episode that just started: update the player bar every second,
+/// save the position now and then, and mark it listened when it ends.
+fn watch_playback(h: &tokio::runtime::Handle, app_weak: &slint::Weak<MainWindow>) {
+    let run = PLAYBACK_RUNS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+    let aw = app_weak.clone();
+    h.spawn(async move {
+        let mut tick = tokio::time::interval(std::time::Duration::from_secs(1));
+        let mut ticks = 0u32;
+        loop {
+            tick.tick().await;
+            if PLAYBACK_RUNS.load(std::sync::atomic::Ordering::SeqCst) != run {
+                break;
+            }
+            let Some(now) = audio::now_playing() else { break };
+            ticks += 1;
+            if now.finished {
+                audio::stop();
+                remember_progress(&now);
+                ui(&aw, move |app| {
+                    push_player(&app);
+                    push_podcasts(&app, &now.user);
+                });
+                break;
+            }
+            if ticks % PROGRESS_SAVE_EVERY == 0 && !now.paused {
+                remember_progress(&now);
+            }
+            ui(&aw, |app| push_player(&app));
+        }
+    });
+}
+
+/// Bumped on every stopwatch start; a display loop stops once it's not the latest.
+static STOPWATCH_RUNS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// How often the stopwatch display is redrawn while it runs.
+const STOPWATCH_FRAME: std::time::Duration = std::time::Duration::from_millis(31);
+
+/// Stopwatch time, state and laps (newest first, best / worst split marked).
+fn push_stopwatch(app: &MainWindow) {
+    let (text, running, laps) = stopwatch::with(|w| (stopwatch::format(w.elapsed()), w.running(), w.laps()));
+    let splits: Vec<_> = laps.iter().map(|l| l.split).collect();
+    let (best, worst) = (splits.iter().min().copied(), splits.iter().max().copied());
+    let items: Vec<LapItem> = laps
+        .iter()
+        .rev()
+        .map(|l| LapItem {
+            number: l.number as i32,
+            split: stopwatch::format(l.split).into(),
+            total: stopwatch::format(l.total).into(),
+            mark: match splits.len() >= 3 {
+                true if Some(l.split) == best => "best",
+                true if Some(l.split) == worst => "worst",
+                _ => "",
+            }
+            .into(),
+        })
+        .collect();
+    app.set_stopwatch_text(text.into());
+    app.set_stopwatch_running(running);
+    app.set_stopwatch_laps(slint::ModelRc::new(slint::VecModel::from(items)));
+}
+
+/// Take `ring` off the Clock page's ringing list.
+fn drop_ring(app: &MainWindow, ring: &RingItem) {
+    let rest: Vec<RingItem> = app.get_clock_rings().iter().filter(|r| !(r.timer == ring.timer && r.id == ring.id)).collect();
+    app.set_clock_rings(slint::ModelRc::new(slint::VecModel::from(rest)));
+}
+
+fn note_blocks(text: &str) -> Vec<NoteBlock> {
+    notes::render(text)
+        .into_iter()
+        .map(|b| NoteBlock { kind: b.kind.into(), text: b.text.into() })
+        .collect()
+}
+
+/// Push a loaded config into the UI properties (call on the UI thread).
+fn apply_config(app: &MainWindow, cfg: &AppConfig) {
+    app.set_settings_errors(SettingsErrors::default());
+    app.set_weather_city(cfg.city.clone().into());
+    app.set_news_topic(cfg.news_topic.clone().into());
+    app.set_news_sort(news_sort_label(&cfg.news_sort).into());
+    app.set_news_category(news_category_label(&cfg.news_category).into());
+    app.set_news_min_points(cfg.news_min_points as i32);
+    app.set_news_range(news_range_label(&cfg.news_range).into());
+    app.set_news_range_from(cfg.news_range_from.clone().into());
+    app.set_news_range_to(cfg.news_range_to.clone().into());
+    let tabs: Vec<slint::SharedString> = cfg.pinned_topics.iter().map(|t| t.as_str().into()).collect();
+    app.set_news_tabs(slint::ModelRc::new(slint::VecModel::from(tabs)));
+    app.set_use_celsius(cfg.units_celsius);
+    let theme = Theme::from_label(&cfg.theme);
+    app.set_theme(Theme::LABELS[theme as usize].into());
+    app.set_start_page(cfg.start_page.clone().into());
+    let date = timefmt::DateStyle::from_label(&cfg.date_format);
+    timefmt::set(timefmt::TimeFormat { hour12: cfg.time_format == "12h", date });
+    app.set_time_format(if cfg.time_format == "12h" { "12h" } else { "24h" }.into());
+    app.set_date_format(timefmt::DateStyle::LABELS[date as usize].into());
+    // stories already listed switch to the new format too
+    let stories = app.get_news_items();
+    for i in 0..stories.row_count() {
+        let Some(mut a) = stories.row_data(i) else { continue };
+        let published = timefmt::stored_stamp(&a.stamp);
+        if a.published != published.as_str() {
+            a.published = published.into();
+            stories.set_row_data(i, a);
+        }
+    }
+    theme::apply(app, theme.is_dark());
+    let accent = theme::normalize_accent(&cfg.accent_color);
+    theme::apply_accent(app, &accent);
+    app.set_accent_color(accent.into());
+    app.set_news_pinned_topics(cfg.pinned_topics.join(", ").into());
+    set_topic_suggestions(app, &cfg.topic_suggestions(""));
+    app.set_news_blocked_sources(cfg.blocked_sources.join(", ").into());
+    app.set_news_muted_keywords(cfg.muted_keywords.join(", ").into());
+    app.set_news_watch_keywords(cfg.watch_keywords.join(", ").into());
+    app.set_news_languages(cfg.languages.join(", ").into());
+    app.set_news_followed_authors(cfg.followed_authors.join(", ").into());
+    app.set_news_notify_followed(cfg.notify_followed);
+    app.set_news_has_following(!cfg.followed_authors.is_empty());
+    app.set_news_poll_minutes(cfg.news_poll_minutes as i32);
+    app.set_weather_refresh_minutes(cfg.weather_refresh_minutes as i32);
+    app.set_news_refresh_minutes(cfg.news_refresh_minutes as i32);
+    app.set_weather_ttl_minutes(config::clamp_ttl(cfg.weather_ttl_minutes) as i32);
+    app.set_news_ttl_minutes(config::clamp_ttl(cfg.news_ttl_minutes) as i32);
+    news::set_cache_ttl(std::time::Duration::from_secs(cfg.news_ttl_secs() as u64));
+    app.set_cache_limit_mb(cfg.cache_limit_mb as i32);
+    cache::set_size_limit(cfg.cache_limit_mb as u64 * 1024 * 1024);
+    app.set_cache_dir(cfg.cache_dir.clone().into());
+    app.set_lock_after_minutes(cfg.lock_after_minutes as i32);
+    app.set_fast_switch(cfg.fast_switch);
+    app.set_news_offline_bookmarks(cfg.offline_bookmarks);
+    app.set_news_hide_read(cfg.hide_read);
+    app.set_news_paywalled_sources(cfg.paywalled_sources.join(", ").into());
+    app.set_summary_endpoint(cfg.summary_endpoint.clone().into());
+    app.set_summary_model(cfg.summary_model.clone().into());
+
+    let user = app.get_current_user().to_string();
+    let profiles: Vec<slint::SharedString> = config::list_profiles(&user).into_iter().map(Into::into).collect();
+    app.set_config_profiles(slint::ModelRc::new(slint::VecModel::from(profiles)));
+    app.set_active_profile(config::active_profile(&user).into());
+    app.set_cache_in_sqlite(cache::cache_storage(&user) == cachestore::CacheStorage::Sqlite);
+    app.set_cache_encrypted(cachecrypt::enabled(&user));
+    let backups: Vec<slint::SharedString> = config::list_backups(&user).into_iter().map(Into::into).collect();
+    app.set_config_backups(slint::ModelRc::new(slint::VecModel::from(backups)));
+    push_api_keys(app, &user);
+    push_locations(app, &cache::load_locations_for(&user));
+    app.set_page_settings(slint::ModelRc::new(slint::VecModel::from(page_setting_rows(cfg))));
+}
+
+/// One generic Settings row per registered page setting, headed by its section.
+fn page_setting_rows(cfg: &AppConfig) -> Vec<SettingItem> {
+    let mut section = "";
+    settings::registry()
+        .into_iter()
+        .map(|d| {
+            let heading = if d.section != section {
+                section = d.section;
+                let mut c = d.section.chars();
+                c.next().map(|f| f.to_uppercase().chain(c).collect()).unwrap_or_default()
+            } else {
+                String::new()
+            };
+            let (kind, minimum, maximum, choices) = match d.kind {
+                settings::Kind::Toggle => ("toggle", 0, 0, Vec::new()),
+                settings::Kind::Number { min, max } => ("number", min as i32, max as i32, Vec::new()),
+                settings::Kind::Text => ("text", 0, 0, Vec::new()),
+                settings::Kind::Choice(options) => ("choice", 0, 0, options.to_vec()),
+            };
+            let value = match d.value(cfg) {
+                serde_json::Value::String(s) => s,
+                v => v.to_string(),
+            };
+            SettingItem {
+                section: d.section.into(),
+                key: d.key.into(),
+                heading: heading.into(),
+                label: d.label.into(),
+                kind: kind.into(),
+                value: value.into(),
+                minimum,
+                maximum,
+                choices: slint::ModelRc::new(slint::VecModel::from(
+                    choices.into_iter().map(slint::SharedString::from).collect::<Vec<_>>(),
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Validation messages for the settings form, one slot per field.
+fn settings_errors(errors: &[config::FieldError]) -> SettingsErrors {
+    use config::SettingsField as F;
+    let mut out = SettingsErrors::default();
+    for e in errors {
+        let slot = match e.field {
+            F::City => &mut out.city,
+            F::Accent => &mut out.accent,
+            F::Poll => &mut out.poll,
+            F::Refresh => &mut out.refresh,
+            F::Cache => &mut out.cache,
+            F::CacheSize => &mut out.cache_size,
+            F::CacheDir => &mut out.cache_dir,
+            F::Lock => &mut out.lock,
+            F::Summary => &mut out.summary,
+        };
+        *slot = e.message.as_str().into();
+    }
+    out
+}
+
+/// Settings rows for the user's API keys: masked, never the key itself.
+fn push_api_keys(app: &MainWindow, user: &str) {
+    let rows: Vec<ApiKeyItem> = ApiService::ALL
+        .into_iter()
+        .map(|s| ApiKeyItem {
+            id: s.id().into(),
+            label: match s {
+                ApiService::NewsApi => "NewsAPI key",
+                ApiService::OpenWeatherMap => "OWM key",
+                ApiService::Summarizer => "Summary key",
+            }
+            .into(),
+            masked: secrets::api_key(user, s).map(|k| secrets::mask(&k)).unwrap_or_default().into(),
+        })
+        .collect();
+    app.set_api_keys(slint::ModelRc::new(slint::VecModel::from(rows)));
+    app.set_api_keys_in_keyring(secrets::api_key_backend(user) == SecretBackend::Keyring);
+}
+
+/// Favorite and recent locations as chips (weather page and Settings).
+fn push_locations(app: &MainWindow, store: &cache::LocationStore) {
+    let items: Vec<LocationItem> = store
+        .quick_list()
+        .into_iter()
+        .map(|(l, favorite)| LocationItem { city: l.city.as_str().into(), label: l.place.label.as_str().into(), favorite })
+        .collect();
+    app.set_weather_locations(slint::ModelRc::new(slint::VecModel::from(items)));
+}
+
+/// After a settings profile change: push the now active profile's settings and
+/// refresh both pages, or show why it failed.
+fn reload_profile(app_weak: &slint::Weak<MainWindow>, user: &str, res: std::io::Result<String>) {
+    let cfg = load_config_for(user);
+    ui(app_weak, move |app| match res {
+        Ok(msg) => {
+            apply_config(&app, &cfg);
+            app.set_new_profile("".into());
+            app.set_pin_status(msg.into());
+            app.invoke_refresh_weather();
+            app.invoke_refresh_news();
+        }
+        Err(e) => app.set_pin_status(format!("Settings error: {e}").into()),
+    });
+}
+
+fn set_topic_suggestions(app: &MainWindow, list: &[String]) {
+    let list: Vec<slint::SharedString> = list.iter().map(|t| t.as_str().into()).collect();
+    app.set_news_topic_suggestions(slint::ModelRc::new(slint::VecModel::from(list)));
+}
+
+/// Normalise a stored sort label (empty/unknown -> "Relevance").
+fn news_sort_label(label: &str) -> &'static str {
+    let sort = news::NewsSort::from_label(label);
+    news::NewsSort::LABELS[sort as usize]
+}
+
+/// Normalise a stored category label (empty/unknown -> "All").
+fn news_category_label(label: &str) -> &'static str {
+    let category = news::NewsCategory::from_label(label);
+    news::NewsCategory::LABELS[category as usize]
+}
+
+/// Sort, date range, category and score threshold as currently set on the News page.
+fn news_query(app: &MainWindow) -> news::NewsQuery {
+    news::NewsQuery {
+        sort: news::NewsSort::from_label(&app.get_news_sort()),
+        range: news::DateRange::from_label(&app.get_news_range(), &app.get_news_range_from(), &app.get_news_range_to()),
+        category: news::NewsCategory::from_label(&app.get_news_category()),
+        min_points: app.get_news_min_points().max(0) as u32,
+    }
+}
+
+/// The same options as stored in a user's config (for background work).
+fn news_query_for(cfg: &AppConfig) -> news::NewsQuery {
+    news::NewsQuery {
+        sort: news::NewsSort::from_label(&cfg.news_sort),
+        range: news::DateRange::from_label(&cfg.news_range, &cfg.news_range_from, &cfg.news_range_to),
+        category: news::NewsCategory::from_label(&cfg.news_category),
+        min_points: cfg.news_min_points,
+    }
+}
+
+/// Normalise a stored date range label (empty/unknown -> "Any time").
+fn news_range_label(label: &str) -> &'static str {
+    news::DateRange::LABELS
+        .iter()
+        .find(|l| **l == label)
+        .copied()
+        .unwrap_or(news::DateRange::LABELS[0])
+}
+
+/// Build the news filter (sources, muted keywords, languages) from a user's config;
+/// `child` profiles always get safe-search filtering on top.
+fn news_filter(cfg: &AppConfig, child: bool) -> news::NewsFilter {
+    news::NewsFilter {
+        blocked: cfg.blocked_sources.clone(),
+        allowed: cfg.allowed_sources.clone(),
+        muted: cfg.muted_keywords.clone(),
+        languages: cfg.languages.clone(),
+        safe: child,
+    }
+}
+
+/// Role and restrictions of `user` (unknown users are standard).
+async fn profile_of(auth: &Auth, user: &str) -> (Role, Restrictions) {
+    let u = user.to_string();
+    auth.run(move |a| a.profile(&u)).await.unwrap_or((Role::Standard, Restrictions::default()))
+}
+
+/// What `apply_profile` shows for an account.
+#[derive(Clone, Copy, Default)]
+struct ProfileView {
+    role: Role,
+    rules: Restrictions,
+    /// The install has no admin yet, so this account may claim it.
+    can_claim_admin: bool,
+}
+
+/// `user`'s `ProfileView`; reads the store, so call it inside `Auth::run`.
+fn profile_view(a: &dyn AuthProvider, user: &str) -> ProfileView {
+    let (role, rules) = a.profile(user).unwrap_or((Role::Standard, Restrictions::default()));
+    ProfileView { role, rules, can_claim_admin: user != "guest" && !a.has_admin().unwrap_or(true) }
+}
+
+/// `--offline` / `APP_OFFLINE`: refreshes show cached data only and never fetch.
+static OFFLINE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+fn offline() -> bool {
+    OFFLINE.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// First loads the splash waits for (`WARM_WEATHER | WARM_NEWS`), so the pages
+/// behind it aren't still "Loading…" when it goes.
+static WARMING: std::sync::atomic::AtomicU8 = std::sync::atomic::AtomicU8::new(0);
+const WARM_WEATHER: u8 = 1;
+const WARM_NEWS: u8 = 2;
+/// The splash shows for at least `SPLASH_MIN`, and waits for warming at most `SPLASH_MAX`.
+const SPLASH_MIN: std::time::Duration = std::time::Duration::from_millis(1200);
+const SPLASH_MAX: std::time::Duration = std::time::Duration::from_secs(6);
+
+/// Clears its `WARMING` bit when dropped: a refresh holds one until its rows are
+/// on screen (cached or fetched) or it gave up.
+struct Warmed(u8);
+
+impl Drop for Warmed {
+    fn drop(&mut self) {
+        WARMING.fetch_and(!self.0, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Set while a child profile without link access is active; `open_url` refuses then.
+static LINKS_BLOCKED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Push the current user's profile type to the UI (pages, admin controls) and the
+/// link guard. Call on the UI thread after `current_user` changed.
+fn apply_profile(app: &MainWindow, profile: ProfileView) {
+    let ProfileView { role, rules, can_claim_admin } = profile;
+    let child = role == Role::Child;
+    LINKS_BLOCKED.store(child && !rules.allow_links, std::sync::atomic::Ordering::Relaxed);
+    app.set_is_child(child);
+    app.set_allow_news(!child || rules.allow_news);
+    app.set_is_admin(role == Role::Admin);
+    app.set_can_claim_admin(can_claim_admin);
+    if child && !rules.allow_news && app.get_current_page() == Page::News {
+        app.set_current_page(Page::Weather);
+    }
+}
+
+/// Convert a fetched story into its UI row (call on the UI thread: builds an Image).
+fn article_item(a: news::Article) -> ArticleItem {
+    ArticleItem {
+        title: a.title.into(),
+        source: a.source.into(),
+        published: timefmt::stored_stamp(&a.published).into(),
+        stamp: a.published.into(),
+        url: a.url.into(),
+        object_id: a.object_id.into(),
+        points: a.points as i32,
+        comments: a.comments as i32,
+        thumbnail: Image::from_rgba8(a.thumbnail),
+        favicon: a.favicon.map(Image::from_rgba8).unwrap_or_default(),
+        bookmarked: false,
+        read: false,
+        paywalled: a.paywalled,
+        author: a.author.into(),
+        related: 0,
+        group: Default::default(),
+        expanded: false,
+        comments_open: false,
+        top_comments: Default::default(),
+        comments_note: Default::default(),
+    }
+}
+
+/// Clustering pass: near-duplicate stories move right behind their lead card,
+/// which gets a "N related" counter; the others stay collapsed until expanded.
+fn cluster_items(items: Vec<ArticleItem>) -> Vec<ArticleItem> {
+    let groups = {
+        let keys: Vec<(&str, &str)> = items.iter().map(|a| (a.title.as_str(), a.url.as_str())).collect();
+        news::cluster_stories(&keys)
+    };
+    let mut slots: Vec<Option<ArticleItem>> = items.into_iter().map(Some).collect();
+    let mut out = Vec::with_capacity(slots.len());
+    for g in groups {
+        let Some(mut lead) = slots[g[0]].take() else { continue };
+        lead.related = (g.len() - 1) as i32;
+        lead.group = Default::default();
+        lead.expanded = false;
+        let lead_url = lead.url.clone();
+        out.push(lead);
+        for &i in &g[1..] {
+            if let Some(mut a) = slots[i].take() {
+                a.related = 0;
+                a.group = lead_url.clone();
+                a.expanded = false;
+                out.push(a);
+            }
+        }
+    }
+    out
+}
+
+/// Per-user card state: bookmarks, opened stories (and whether those are hidden),
+/// extra paywalled sites.
+struct CardMarks {
+    bookmarks: BookmarkStore,
+    read: ReadCache,
+    hide_read: bool,
+    paywalled: Vec<String>,
+}
+
+impl CardMarks {
+    fn load(user: &str) -> Self {
+        let cfg = load_config_for(user);
+        Self {
+            bookmarks: load_bookmarks_for(user),
+            read: load_read_for(user),
+            hide_read: cfg.hide_read,
+            paywalled: cfg.paywalled_sources,
+        }
+    }
+
+    /// Set the bookmarked/read/paywall flags on every card.
+    fn flag(&self, items: &mut [ArticleItem]) {
+        for item in items {
+            item.bookmarked = self.bookmarks.contains(&item.url);
+            item.read = self.read.contains(&item.url);
+            item.paywalled |= news::is_paywalled_host(&item.source, &self.paywalled);
+        }
+    }
+
+    /// Flag the cards, drop read ones if the user hides them, then cluster.
+    fn apply(&self, mut items: Vec<ArticleItem>) -> Vec<ArticleItem> {
+        self.flag(&mut items);
+        if self.hide_read {
+            items.retain(|a| !a.read);
+        }
+        cluster_items(items)
+    }
+}
+
+/// Flag the card for `url` (if it is on screen) with the paywall lock.
+fn flag_paywalled(app: &MainWindow, url: &str) {
+    let items = app.get_news_items();
+    if let Some(i) = items.iter().position(|a| a.url == url) {
+        if let Some(mut row) = items.row_data(i) {
+            row.paywalled = true;
+            items.set_row_data(i, row);
+        }
+    }
+}
+
+/// Record `url` as read and flag its card (if it is on screen).
+fn mark_read(app: &MainWindow, user: &str, url: &str) {
+    let mut read = load_read_for(user);
+    if read.insert(url) {
+        if let Err(e) = save_read_for(user, &read) {
+            eprintln!("Save read list error: {e:?}");
+        }
+    }
+    let items = app.get_news_items();
+    if let Some(i) = items.iter().position(|a| a.url == url) {
+        if let Some(mut row) = items.row_data(i) {
+            row.read = true;
+            items.set_row_data(i, row);
+        }
+    }
+}
+
+/// Open `url` in the default browser, off the UI thread (opening can block a bit).
+/// Child profiles without link access never leave the app.
+fn open_url(h: &tokio::runtime::Handle, url: String) {
+    if LINKS_BLOCKED.load(std::sync::atomic::Ordering::Relaxed) {
+        return;
+    }
+    h.spawn(async move {
+        let _ = tokio::task::spawn_blocking(move || {
+            let _ = open::that(url);
+        }).await;
+    });
+}
+
+/// "last seen" label for the account switcher: "5m ago", "3h ago", "2d ago", "never".
+fn last_seen_label(last_login: &str) -> String {
+    let Ok(t) = chrono::DateTime::parse_from_rfc3339(last_login) else {
+        return "never".into();
+    };
+    let mins = age_minutes(t.timestamp());
+    match mins {
+        0 => "just now".into(),
+        1..=59 => format!("{mins}m ago"),
+        60..=1439 => format!("{}h ago", mins / 60),
+        _ => format!("{}d ago", mins / 1440),
+    }
+}
+
+/// Refresh the account switcher, the current user's avatar chip and profile.
+/// Queues its UI update before returning, so UI work queued after awaiting it
+/// sees the profile applied.
+async fn push_users_to_ui(app_weak: slint::Weak<MainWindow>, auth: Auth) {
+    // every account's profile, for whichever is current once on the UI thread
+    let (logins, profiles) = auth
+        .run(|a| {
+            let logins = a.list_last_logins()?;
+            let profiles: HashMap<String, ProfileView> = logins
+                .iter()
+                .map(|(u, _)| u.as_str())
+                .chain(["guest"])
+                .map(|u| (u.to_string(), profile_view(a, u)))
+                .collect();
+            Ok((logins, profiles))
+        })
+        .await
+        .unwrap_or_default();
+    let (list, seen): (Vec<String>, Vec<String>) = logins.into_iter().unzip();
+    let avatars: Vec<_> = list.iter().map(|u| avatar::load_avatar(u)).collect();
+    ui(&app_weak, move |app| {
+        apply_profile(&app, profiles.get(app.get_current_user().as_str()).copied().unwrap_or_default());
+
+        let mine = avatar::load_avatar(&app.get_current_user());
+        app.set_has_avatar(mine.is_some());
+        app.set_current_avatar(mine.map(Image::from_rgba8).unwrap_or_default());
+
+        let entries: Vec<UserEntry> = list
+            .into_iter()
+            .zip(avatars)
+            .zip(seen)
+            .map(|((name, pic), last)| UserEntry {
+                name: name.into(),
+                has_avatar: pic.is_some(),
+                avatar: pic.map(Image::from_rgba8).unwrap_or_default(),
+                last_seen: last_seen_label(&last).into(),
+            })
+            .collect();
+        app.set_users(slint::ModelRc::new(slint::VecModel::from(entries)));
     });
 }
 async fn cache_icon_to_path(url: &str) -> Option<std::path::PathBuf> {
-    use std::{fs, path::PathBuf};
+    use std::fs;
 
     if url.is_empty() {
         return None;
     }
 
-    let cache_dir = PathBuf::from("icons_cache");
+    let cache_dir = cache::icons_dir();
     let _ = fs::create_dir_all(&cache_dir);
 
     let filename = url.split('/').last().unwrap_or("icon.png");
@@ -121,6 +1270,38 @@ async fn cache_icon_to_path(url: &str) -> Option<std::path::PathBuf> {
     Some(path)
 }
 fn main() -> Result<(), slint::PlatformError> {
+    let cli = cli::Cli::parse();
+    OFFLINE.store(cli.offline, std::sync::atomic::Ordering::Relaxed);
+
+    // older versions kept data in ~/tock-workshop and ./cache; an explicit
+    // config dir is left alone
+    match &cli.config_dir {
+        Some(dir) => config::set_config_dir(dir.clone()),
+        None => config::migrate_legacy_config(),
+    }
+    // the flag must work or we stop; a stored folder that doesn't falls back
+    // to the platform one with a warning
+    let mut cache_dir_warning = None;
+    if let Some(dir) = &cli.cache_dir {
+        if let Err(e) = config::prepare_cache_dir(dir) {
+            eprintln!("error: --cache-dir: {e}");
+            std::process::exit(2);
+        }
+        config::set_cache_dir(dir.clone());
+    } else {
+        let stored = config::load_config().cache_dir;
+        if !stored.is_empty() {
+            match config::prepare_cache_dir(std::path::Path::new(&stored)) {
+                Ok(()) => config::set_cache_dir(std::path::PathBuf::from(stored)),
+                Err(e) => {
+                    eprintln!("Cache folder setting ignored: {e}");
+                    cache_dir_warning = Some(format!("Cache folder not usable, using the default: {e}"));
+                }
+            }
+        }
+    }
+    cache::migrate_legacy_cache();
+
     let app = MainWindow::new()?;
 
     // Shared state owned by Rust
@@ -129,6 +1310,14 @@ fn main() -> Result<(), slint::PlatformError> {
         current_page: Page::Weather,
         clock_text: "12:34:56".to_string(),
         current_user: Some("guest".into()),
+        news_known: HashSet::new(),
+        news_unread: 0,
+        last_activity: None,
+        last_pointer: (0.0, 0.0),
+        locked_user: None,
+        sessions: HashMap::new(),
+        force_weather_refresh: false,
+        force_news_refresh: false,
     }));
 
     // Initial UI
@@ -146,6 +1335,56 @@ fn main() -> Result<(), slint::PlatformError> {
         let state_for_nav = state.clone();
         app.on_nav_selected(move |page: Page| {
             set_page(&state_for_nav, &app_weak, page);
+            if page == Page::Notes {
+                if let Some(app) = app_weak.upgrade() {
+                    push_notes(&app, &current_user(&state_for_nav), &app.get_notes_query());
+                }
+            }
+            if page == Page::Stocks {
+                if let Some(app) = app_weak.upgrade() {
+                    app.invoke_refresh_stocks(false);
+                }
+            }
+            if page == Page::Podcasts {
+                if let Some(app) = app_weak.upgrade() {
+                    app.invoke_refresh_podcasts(false);
+                }
+            }
+            if page == Page::Mail {
+                if let Some(app) = app_weak.upgrade() {
+                    // the account may have been edited in Settings
+                    push_mail_account(&app, &current_user(&state_for_nav));
+                    app.invoke_refresh_mail();
+                }
+            }
+            if page == Page::Calendar {
+                if let Some(app) = app_weak.upgrade() {
+                    let user = current_user(&state_for_nav);
+                    push_calendar_account(&app, &user);
+                    push_calendar(&app, &user);
+                    app.invoke_sync_calendar();
+                }
+            }
+            if page == Page::Clock {
+                if let Some(app) = app_weak.upgrade() {
+                    let user = current_user(&state_for_nav);
+                    push_clock(&app, &user);
+                    push_world(&app, &user);
+                }
+            }
+            if page == Page::News {
+                // opening News clears the badge; show what the poll fetched
+                let had_unread = state_for_nav
+                    .lock()
+                    .map(|mut s| std::mem::take(&mut s.news_unread))
+                    .unwrap_or(0);
+                if let Some(app) = app_weak.upgrade() {
+                    app.set_news_unread(0);
+                    if had_unread > 0 {
+                        app.invoke_news_tab_selected(app.get_news_topic());
+                    }
+                }
+            }
         });
     }
 
@@ -167,87 +1406,491 @@ fn main() -> Result<(), slint::PlatformError> {
             let mut tick = interval(Duration::from_secs(1));
             loop {
                 tick.tick().await;
-                let now = chrono::Local::now().format("%H:%M:%S").to_string();
+                let now = chrono::Local::now();
                 let aw = app_weak.clone();
                 let st = state_for_clock.clone();
-                set_clock(&st, &aw, now);
+                set_clock(&st, &aw, timefmt::clock(&now));
+
+                // alarms and timers: ring what's due, keep the countdowns moving
+                let user = current_user(&st);
+                let rings = alarms::due(&user, &now);
+                let counting = !alarms::list(&user).timers.is_empty();
+                let items: Vec<RingItem> = rings
+                    .iter()
+                    .map(|r| RingItem { timer: r.timer, id: r.id.to_string().into(), label: r.label.clone().into() })
+                    .collect();
+                let ringing = !items.is_empty();
+                let u = user.clone();
+                ui(&aw, move |app| {
+                    if ringing {
+                        let mut all: Vec<RingItem> = app.get_clock_rings().iter().collect();
+                        all.extend(items);
+                        app.set_clock_rings(slint::ModelRc::new(slint::VecModel::from(all)));
+                    }
+                    if ringing || counting {
+                        push_clock(&app, &u);
+                    }
+                    // the world clock only needs redrawing while it's on screen
+                    if app.get_current_page() == Page::Clock {
+                        push_world(&app, &u);
+                    }
+                });
+                if ringing {
+                    let sound = alarms::SOUND.flag(&load_config_for(&user));
+                    tokio::task::spawn_blocking(move || {
+                        for r in &rings {
+                            let summary = if r.timer { "Timer done" } else { "Alarm" };
+                            let body = if r.label.is_empty() { timefmt::clock(&now) } else { r.label.clone() };
+                            notify::show(summary, &body);
+                        }
+                        if sound {
+                            notify::chime();
+                        }
+                    });
+                }
             }
         });
     }
 
-    // Splash auto-hide
+    // Idle auto-lock: back to the login overlay (pages stay as they are) until the PIN is entered
     {
         let app_weak = app.as_weak();
         let h = handle.clone();
+        let state_for_idle = state.clone();
         h.spawn(async move {
-            use tokio::time::{sleep, Duration};
-            sleep(Duration::from_millis(1200)).await;
-            let _ = slint::invoke_from_event_loop(move || {
-                if let Some(app) = app_weak.upgrade() {
-                    app.set_show_splash(false);
-                }
-            });
-        });
-    }
+            use tokio::time::{interval, Duration};
+            let mut tick = interval(Duration::from_secs(5));
+            loop {
+                tick.tick().await;
+                let st = state_for_idle.clone();
+                ui(&app_weak, move |app| {
+                    let Ok(mut s) = st.lock() else { return };
+                    let now = std::time::Instant::now();
+                    let pointer = (app.get_pointer_x(), app.get_pointer_y());
+                    let minutes = app.get_lock_after_minutes().max(0) as u64;
+                    if pointer != s.last_pointer || !app.get_is_logged_in() || minutes == 0 {
+                        s.last_pointer = pointer;
+                        s.last_activity = Some(now);
+                        return;
+                    }
+                    let idle = now.duration_since(*s.last_activity.get_or_insert(now));
+                    if idle >= Duration::from_secs(minutes * 60) {
+                        lock_session(&app, &mut s);
+                    }
+                });
+            }
+        });
+    }
 
-    // Load settings (config.json) and push to UI
-    let cfg = load_config();
-    app.set_weather_city(cfg.city.into());
-    app.set_news_topic(cfg.news_topic.into());
-    app.set_use_celsius(cfg.units_celsius);
+    // Splash auto-hide, once the first weather and news loads are in (or SPLASH_MAX)
+    WARMING.store(WARM_WEATHER | WARM_NEWS, std::sync::atomic::Ordering::Relaxed);
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        h.spawn(async move {
+            use tokio::time::{sleep, Duration, Instant};
+            let shown = Instant::now();
+            sleep(SPLASH_MIN).await;
+            while WARMING.load(std::sync::atomic::Ordering::Relaxed) != 0 && shown.elapsed() < SPLASH_MAX {
+                sleep(Duration::from_millis(100)).await;
+            }
+            let _ = slint::invoke_from_event_loop(move || {
+                if let Some(app) = app_weak.upgrade() {
+                    app.set_show_splash(false);
+                }
+            });
+        });
+    }
+
+    // Account backend (register & login); handlers clone this handle
+    let auth = Auth::new().expect("auth storage");
+
+    // "Remember me": resume the last account without the login box (the PIN is never stored)
+    // --user / APP_USER only fills in the login box; another account than the
+    // remembered one still has to sign in with its PIN
+    let users = handle.block_on(auth.run(|a| a.list_users())).unwrap_or_default();
+    if let Some(u) = cli.user.as_ref().filter(|u| *u != "guest" && !users.contains(u)) {
+        eprintln!("--user {u}: no such account, ignoring");
+    }
+    let preselect = cli.user.clone().filter(|u| users.contains(u));
+    let remembered = load_session()
+        .remembered_user
+        .filter(|u| users.contains(u) && preselect.as_ref().is_none_or(|p| p == u));
+    // an encrypted cache only opens with the PIN, so such an account signs in again
+    let remembered = match remembered {
+        Some(user) if cachecrypt::enabled(&user) => {
+            app.set_login_user(user.as_str().into());
+            app.set_login_error_text("Enter your PIN to open your encrypted cache".into());
+            None
+        }
+        remembered => {
+            if let Some(user) = preselect.as_ref().filter(|_| remembered.is_none()) {
+                app.set_login_user(user.as_str().into());
+            }
+            remembered
+        }
+    };
+
+    // Load settings (config.json, or the remembered user's) and push to UI
+    let mut cfg = match &remembered {
+        Some(user) => {
+            if let Ok(mut s) = state.lock() {
+                s.current_user = Some(user.clone());
+                s.is_logged_in = true;
+            }
+            app.set_current_user(user.as_str().into());
+            app.set_is_logged_in(true);
+            load_config_for(user)
+        }
+        None => load_config(),
+    };
+    // --city / --page layer over the stored settings for this run
+    if let Some(city) = &cli.city {
+        cfg.city = city.clone();
+    }
+    apply_config(&app, &cfg);
+    // before the start page is picked: a remembered child without News opens elsewhere
+    let me = current_user(&state);
+    apply_profile(&app, handle.block_on(auth.run(move |a| Ok(profile_view(a, &me)))).unwrap_or_default());
+    if let Some(warning) = cache_dir_warning {
+        app.set_pin_status(warning.into());
+    }
+    let page = match cli.page {
+        Some(p) => start_page(&app, &AppConfig { start_page: p.label().into(), ..Default::default() }),
+        None => start_page(&app, &cfg),
+    };
+    set_page(&state, &app.as_weak(), page);
+    // read the other cached cities and pinned tabs into memory behind the splash
+    {
+        let user = current_user(&state);
+        let query = news_query(&app);
+        let keys: Vec<String> = cfg.pinned_topics.iter().map(|t| news::feed_key(t, query)).collect();
+        handle.spawn_blocking(move || cache::warm(&user, &keys));
+    }
     app.invoke_refresh_weather();
     app.invoke_refresh_news();
 
 
-    // Local auth (register & login)
-    let auth = LocalAuth::new().expect("auth storage");
-    push_users_to_ui(&app.as_weak(), &auth);
+    // Background news poll: refreshes the pinned topics into the in-memory cache,
+    // bumps the unread badge and raises desktop alerts for watched keywords
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_poll = state.clone();
+        let auth = auth.clone();
+        h.spawn(async move {
+            use tokio::time::{sleep, Duration};
+            loop {
+                let minutes = load_config_for(&current_user(&state_for_poll)).news_poll_minutes;
+                if minutes == 0 {
+                    // disabled: look again in a minute in case the setting changes
+                    sleep(Duration::from_secs(60)).await;
+                    continue;
+                }
+                sleep(Duration::from_secs(minutes as u64 * 60)).await;
+                if offline() {
+                    continue;
+                }
+
+                let user = current_user(&state_for_poll);
+                let cfg = load_config_for(&user);
+                if cfg.news_poll_minutes == 0 {
+                    continue;
+                }
+                let (role, rules) = profile_of(&auth, &user).await;
+                if role == Role::Child && !rules.allow_news {
+                    continue;
+                }
+                let filter = news_filter(&cfg, role == Role::Child);
+                let query = news_query_for(&cfg);
+                let page_size = news::PAGE_SIZE.number(&cfg) as usize;
+
+                let mut topics = cfg.pinned_topics.clone();
+                if !topics.iter().any(|t| news::topic_key(t) == news::topic_key(&cfg.news_topic)) {
+                    topics.push(cfg.news_topic.clone());
+                }
+                // merged / author feeds are built separately, never searched by name
+                topics.retain(|t| !news::is_virtual_topic(t));
+
+                let mut headlines: Vec<(String, String)> = Vec::new();
+                for topic in &topics {
+                    match news::fetch_news(topic, page_size, &filter, query).await {
+                        Ok(fetched) => headlines.extend(fetched.rows.into_iter().map(|a| (a.title, a.url))),
+                        Err(e) => eprintln!("News poll error ({topic}): {e}"),
+                    }
+                }
+                // (author, title, url) of the latest posts by followed authors
+                let mut followed: Vec<(String, String, String)> = Vec::new();
+                if !cfg.followed_authors.is_empty() {
+                    let mut q = query;
+                    q.sort = news::NewsSort::Newest;
+                    match news::fetch_following(&cfg.followed_authors, page_size, &filter, q).await {
+                        Ok(fetched) => followed.extend(fetched.rows.into_iter().map(|a| (a.author, a.title, a.url))),
+                        Err(e) => eprintln!("News poll error (following): {e}"),
+                    }
+                }
+                headlines.extend(followed.iter().map(|(_, t, u)| (t.clone(), u.clone())));
+
+                // unread = polled stories the user hasn't had on screen yet
+                let unread = match state_for_poll.lock() {
+                    Ok(mut s) => {
+                        for (_, url) in &headlines {
+                            if s.news_known.insert(news::canonical_url(url)) {
+                                s.news_unread += 1;
+                            }
+                        }
+                        s.news_unread
+                    }
+                    Err(_) => 0,
+                };
+                ui(&app_weak, move |app| app.set_news_unread(unread as i32));
+
+                if cfg.watch_keywords.is_empty() && !cfg.notify_followed {
+                    continue;
+                }
+
+                // first poll only seeds the seen-set, so we don't alert on the whole backlog
+                let mut seen = load_seen_for(&user);
+                let seeding = seen.urls.is_empty();
+                let mut alerts = Vec::new();
+                for (title, url) in headlines {
+                    if !seen.insert(news::canonical_url(&url)) || seeding {
+                        continue;
+                    }
+                    if cfg.notify_followed {
+                        if let Some((author, _, _)) = followed.iter().find(|(_, _, u)| *u == url) {
+                            alerts.push((format!("New post by {author}"), title));
+                            continue;
+                        }
+                    }
+                    if let Some(k) = news::first_match(&title, &cfg.watch_keywords) {
+                        alerts.push((format!("News alert: {k}"), title));
+                    }
+                }
+                let _ = save_seen_for(&user, &seen);
+
+                if !alerts.is_empty() {
+                    let _ = tokio::task::spawn_blocking(move || {
+                        for (summary, body) in alerts {
+                            notify::show(&summary, &body);
+                        }
+                    }).await;
+                }
+            }
+        });
+    }
+
+    // Background mail check: keeps the unread badge current (0 = only when the
+    // Mail page is opened or refreshed)
+    {
+        let app_weak = app.as_weak();
+        let state_for_mail = state.clone();
+        handle.spawn(async move {
+            use tokio::time::{sleep, Duration};
+            loop {
+                let minutes = mail::POLL_MINUTES.number(&load_config_for(&current_user(&state_for_mail)));
+                // disabled: look again in a minute in case the setting changes
+                sleep(Duration::from_secs(minutes.max(1) as u64 * 60)).await;
+                if minutes > 0 {
+                    ui(&app_weak, |app| app.invoke_refresh_mail());
+                }
+            }
+        });
+    }
+
+    // Background calendar sync (0 = only when the Calendar page is opened or synced)
+    {
+        let app_weak = app.as_weak();
+        let state_for_calendar = state.clone();
+        handle.spawn(async move {
+            use tokio::time::{sleep, Duration};
+            loop {
+                let minutes = calendar::SYNC_MINUTES.number(&load_config_for(&current_user(&state_for_calendar)));
+                sleep(Duration::from_secs(minutes.max(1) as u64 * 60)).await;
+                if minutes > 0 {
+                    ui(&app_weak, |app| app.invoke_sync_calendar());
+                }
+            }
+        });
+    }
+
+    // Auto-refresh: reload the Weather / News pages on the user's intervals
+    // (0 = manual only); the clock restarts after every auto refresh
+    {
+        let app_weak = app.as_weak();
+        let state_for_refresh = state.clone();
+        let auth = auth.clone();
+        handle.spawn(async move {
+            use tokio::time::{interval, Duration, Instant};
+            let mut tick = interval(Duration::from_secs(30));
+            let (mut last_weather, mut last_news) = (Instant::now(), Instant::now());
+            loop {
+                tick.tick().await;
+                let user = current_user(&state_for_refresh);
+                let cfg = load_config_for(&user);
+                let due = |last: Instant, minutes: u32| minutes > 0 && last.elapsed() >= Duration::from_secs(minutes as u64 * 60);
+
+                let weather = due(last_weather, cfg.weather_refresh_minutes);
+                let mut news = due(last_news, cfg.news_refresh_minutes);
+                if let (Role::Child, Restrictions { allow_news: false, .. }) = profile_of(&auth, &user).await {
+                    news = false;
+                }
+                if weather {
+                    last_weather = Instant::now();
+                }
+                if news {
+                    last_news = Instant::now();
+                }
+                if !weather && !news {
+                    continue;
+                }
+                ui(&app_weak, move |app| {
+                    // nothing to show behind the lock screen; bookmarks aren't a fetched list
+                    if app.get_locked() {
+                        return;
+                    }
+                    if weather {
+                        app.invoke_refresh_weather();
+                    }
+                    if news && !app.get_news_showing_bookmarks() {
+                        app.invoke_force_refresh_news();
+                    }
+                });
+            }
+        });
+    }
+
+    // Follow the OS dark-mode switch live while the user's theme is "System"
+    {
+        let app_weak = app.as_weak();
+        let state_for_theme = state.clone();
+        handle.spawn(async move {
+            let mut last = None;
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                // the portal / registry query may block briefly
+                let dark = tokio::task::spawn_blocking(theme::system_is_dark).await.unwrap_or(true);
+                if last == Some(dark) {
+                    continue;
+                }
+                last = Some(dark);
+                if Theme::from_label(&load_config_for(&current_user(&state_for_theme)).theme) == Theme::System {
+                    ui(&app_weak, move |app| theme::apply(&app, dark));
+                }
+            }
+        });
+    }
+
+    // Hot reload: settings edited by hand (or synced in) show up without a restart
+    {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        match watch::watch_user_configs(tx) {
+            Ok(watcher) => {
+                let app_weak = app.as_weak();
+                let state_for_watch = state.clone();
+                handle.spawn(async move {
+                    let _watcher = watcher; // dropping it stops the events
+                    let mut last = load_config_for(&current_user(&state_for_watch));
+                    while let Some(first) = rx.recv().await {
+                        // editors write in several steps: let them finish, then take the batch
+                        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+                        let mut paths = vec![first];
+                        while let Ok(p) = rx.try_recv() {
+                            paths.push(p);
+                        }
+                        let user = current_user(&state_for_watch);
+                        let external = paths.iter().any(|p| {
+                            watch::settings_owner(p).as_deref() == Some(user.as_str()) && !config::is_own_write(p)
+                        });
+                        if !external {
+                            continue;
+                        }
+                        let cfg = load_config_for(&user);
+                        let refetch_weather = cfg.city != last.city || cfg.units_celsius != last.units_celsius;
+                        let refetch_news = cfg.news_topic != last.news_topic;
+                        last = cfg.clone();
+                        ui(&app_weak, move |app| {
+                            apply_config(&app, &cfg);
+                            if refetch_weather {
+                                app.invoke_refresh_weather();
+                            }
+                            if refetch_news {
+                                app.invoke_refresh_news();
+                            }
+                        });
+                    }
+                });
+            }
+            Err(e) => eprintln!("Config watcher error: {e:?}"),
+        }
+    }
+
+    handle.spawn(push_users_to_ui(app.as_weak(), auth.clone()));
+    let storage = handle.block_on(auth.run(|a| {
+        let local = a.local().ok_or(AuthError::Unsupported)?;
+        Ok((local.secret_backend()?, local.storage()))
+    }));
+    if let Ok((secrets, users_in)) = storage {
+        app.set_pins_in_keyring(secrets == SecretBackend::Keyring);
+        app.set_users_in_sqlite(users_in == UserStorage::Sqlite);
+    }
 
     // REGISTER
     {
         let app_weak = app.as_weak();
-        let auth_reg = LocalAuth { path: auth.path.clone() };
+        let auth_reg = auth.clone();
         let h_register = handle.clone();
         let state_for_reg = state.clone();
 
         app.on_register_requested(move |user, pin| {
             let user = user.to_string();
-            let pin = pin.to_string();
-            let user_for_auth = user.clone();
-            let pin_for_auth = pin.clone();
+            // wiped on drop, once hashed
+            let pin = Zeroizing::new(pin.to_string());
             let aw = app_weak.clone();
             let st = state_for_reg.clone();
-            let auth_path = auth_reg.path.clone();
-            let auth = LocalAuth { path: auth_path.clone() };
+            let auth = auth_reg.clone();
             let h = h_register.clone();
 
             // clear any previous error immediately
             set_login_error(&aw, "".to_string());
 
             h.spawn(async move {
-                // CPU-bound hashing off the reactor
-                let res = tokio::task::spawn_blocking(move || auth.register_user(&user_for_auth, &pin_for_auth)).await;
+                // CPU-bound hashing off the reactor; a new account gets its recovery codes right away
+                let u = user.clone();
+                let res = auth
+                    .run(move |a| {
+                        a.register_user(&u, &pin)?;
+                        Ok(a.generate_recovery_codes(&u).unwrap_or_else(|e| {
+                            eprintln!("Recovery codes error: {e:?}");
+                            Vec::new()
+                        }))
+                    })
+                    .await;
                 match res {
-                    Ok(Ok(())) => {
-                        // 1) remember who is logged in (Rust state)
+                    Ok(codes) => {
+                        audit::record(AuditEvent::Register, &user, "");
+
+                        // 1) remember who is logged in (Rust state); a new account ends any lock
                         if let Ok(mut s) = st.lock() {
                             s.current_user = Some(user.clone());
+                            s.locked_user = None;
+                            s.sessions.insert(user.clone(), std::time::Instant::now());
                         }
 
                         // 2) update the current_user label in the UI
                         set_current_user(&st, &aw, Some(user.clone()));
 
                         // 3) refresh the users list (so the new account appears)
-                        let auth2 = LocalAuth { path: auth_path.clone() };
-                        push_users_to_ui(&aw, &auth2);
+                        push_users_to_ui(aw.clone(), auth.clone()).await;
 
                         // 4) load that user's config + push to UI
                         let user_for_ui = user.clone();
                         ui(&aw, move |app| {
+                            app.set_locked(false);
+                            app.set_recovery_codes(codes.join("\n").into());
                             let cfg = load_config_for(&user_for_ui);
-                            app.set_weather_city(cfg.city.into());
-                            app.set_news_topic(cfg.news_topic.into());
-                            app.set_use_celsius(cfg.units_celsius);
+                            apply_config(&app, &cfg);
+                            app.set_current_page(start_page(&app, &cfg));
                             app.set_login_error_text("".into());
                             app.set_is_logged_in(true);
                             app.invoke_refresh_weather();
@@ -255,9 +1898,11 @@ fn main() -> Result<(), slint::PlatformError> {
                         });
                     }
 
-                    Ok(Err(AuthError::AlreadyExists)) => set_login_error(&aw, "User already exists".to_string()),
-                    Ok(Err(e)) => set_login_error(&aw, format!("Register error: {:?}", e)),
-                    Err(join_err) => set_login_error(&aw, format!("Register task failed: {:?}", join_err)),
+                    Err(AuthError::AlreadyExists) => set_login_error(&aw, "User already exists".to_string()),
+                    Err(e @ (AuthError::PinTooShort(_) | AuthError::PinNotDigits | AuthError::PinTooCommon | AuthError::InvalidUsername)) => {
+                        set_login_error(&aw, e.to_string())
+                    }
+                    Err(e) => set_login_error(&aw, format!("Register error: {:?}", e)),
                 }
             });
         });
@@ -266,42 +1911,78 @@ fn main() -> Result<(), slint::PlatformError> {
     // LOGIN
     {
         let app_weak = app.as_weak();
-        let auth_log = LocalAuth { path: auth.path.clone() };
+        let auth_log = auth.clone();
         let h_login = handle.clone();
         let state_for_login = state.clone();
 
         app.on_login_requested(move |user, pin| {
             let user = user.to_string();
-            let pin = pin.to_string();
-            let user_for_auth = user.clone();
-            let pin_for_auth = pin.clone();
+            let pin = Zeroizing::new(pin.to_string());
             let aw = app_weak.clone();
             let st = state_for_login.clone();
-            let auth_path = auth_log.path.clone();
-            let auth = LocalAuth { path: auth_path.clone() };
+            let auth = auth_log.clone();
             let h = h_login.clone();
 
             // clear any previous error immediately
             set_login_error(&aw, "".to_string());
+            let remember = aw.upgrade().map(|app| app.get_login_remember()).unwrap_or(false);
+            // the lock screen also takes the quick-unlock PIN
+            let unlocking = st.lock().map(|s| s.locked_user.as_deref() == Some(user.as_str())).unwrap_or(false);
 
             h.spawn(async move {
-                let res = tokio::task::spawn_blocking(move || auth.verify_login(&user_for_auth, &pin_for_auth)).await;
+                let u = user.clone();
+                let res = auth
+                    .run(move |a| {
+                        if unlocking {
+                            return a.unlock(&u, &pin).map(|()| None);
+                        }
+                        a.verify_login(&u, &pin)?;
+                        // the sign-in PIN also opens an encrypted cache (quick-unlock PINs can't);
+                        // if it doesn't, the cache stays closed rather than starting over
+                        Ok(cachecrypt::unlock(&u, &pin).err().map(|e| {
+                            eprintln!("Cache key error for {u}: {e}");
+                            format!("Encrypted cache not opened: {e}")
+                        }))
+                    })
+                    .await;
                 match res {
-                    Ok(Ok(())) => {
-                        if let Ok(mut s) = st.lock() {
-                            s.current_user = Some(user.clone());
+                    Ok(cache_warning) => {
+                        audit::record(AuditEvent::Login, &user, "");
+
+                        // only the name is kept, never the PIN
+                        let session = Session { remembered_user: remember.then(|| user.clone()) };
+                        if let Err(e) = save_session(&session) {
+                            eprintln!("session save error: {e}");
                         }
-                        set_current_user(&st, &aw, Some(user.clone()));
 
-                        let auth2 = LocalAuth { path: auth_path.clone() };
-                        push_users_to_ui(&aw, &auth2);
+                        // unlocking the same account resumes the session as it was
+                        let resume = match st.lock() {
+                            Ok(mut s) => {
+                                s.current_user = Some(user.clone());
+                                s.sessions.insert(user.clone(), std::time::Instant::now());
+                                s.locked_user.take().as_deref() == Some(user.as_str())
+                            }
+                            Err(_) => false,
+                        };
+                        set_current_user(&st, &aw, Some(user.clone()));
+                        push_users_to_ui(aw.clone(), auth.clone()).await;
 
                         let user_for_ui = user.clone();
                         ui(&aw, move |app| {
+                            app.set_locked(false);
+                            if let Some(warning) = cache_warning {
+                                app.set_pin_status(warning.into());
+                            }
+                            if resume {
+                                app.set_login_pin("".into());
+                                app.set_login_error_text("".into());
+                                app.set_is_logged_in(true);
+                                return;
+                            }
                             let cfg = load_config_for(&user_for_ui);
-                            app.set_weather_city(cfg.city.into());
-                            app.set_news_topic(cfg.news_topic.into());
-                            app.set_use_celsius(cfg.units_celsius);
+                            apply_config(&app, &cfg);
+                            app.set_current_page(start_page(&app, &cfg));
+                            app.set_login_pin("".into());
                             app.set_login_error_text("".into());
                             app.set_is_logged_in(true);
                             app.invoke_refresh_weather();
@@ -309,345 +1990,2872 @@ fn main() -> Result<(), slint::PlatformError> {
                         });
                     }
 
-                    Ok(Err(AuthError::NotFound)) => set_login_error(&aw, "Unknown user".to_string()),
-                    Ok(Err(AuthError::InvalidPin)) => set_login_error(&aw, "Invalid PIN".to_string()),
-                    Ok(Err(e)) => set_login_error(&aw, format!("Login error: {:?}", e)),
-                    Err(join_err) => set_login_error(&aw, format!("Login task failed: {:?}", join_err)),
+                    Err(AuthError::NotFound) => {
+                        audit::record(AuditEvent::LoginFailed, &user, "unknown user");
+                        set_login_error(&aw, "Unknown user".to_string())
+                    }
+                    Err(AuthError::InvalidPin) => {
+                        audit::record(AuditEvent::LoginFailed, &user, "wrong PIN");
+                        set_login_error(&aw, "Invalid PIN".to_string())
+                    }
+                    Err(AuthError::WrongCredentials) => {
+                        audit::record(AuditEvent::LoginFailed, &user, "wrong user or PIN");
+                        set_login_error(&aw, AuthError::WrongCredentials.to_string())
+                    }
+                    Err(AuthError::QuickPinDisabled) => {
+                        audit::record(AuditEvent::LoginFailed, &user, "quick PIN turned off after wrong tries");
+                        set_login_error(&aw, AuthError::QuickPinDisabled.to_string())
+                    }
+                    Err(e) => set_login_error(&aw, format!("Login error: {:?}", e)),
                 }
             });
         });
     }
 
-    // LOG OUT
+    // CHANGE PIN (Settings): verify the current PIN, store a freshly salted hash
     {
         let app_weak = app.as_weak();
-        let state_for_logout = state.clone();
-        let auth_path = auth.path.clone();
-
-        app.on_logout(move || {
-            // flip auth state + UI
-            set_login(&state_for_logout, &app_weak, false);
-            set_current_user(&state_for_logout, &app_weak, None);
-
-            // refresh users list in the menu
-            let auth2 = LocalAuth { path: auth_path.clone() };
-            push_users_to_ui(&app_weak, &auth2);
+        let auth = auth.clone();
+        let h = handle.clone();
+        let state_for_pin = state.clone();
 
-            // clear lists on screen
-            ui(&app_weak, move |app| {
-                app.set_login_user("".into());
-                app.set_login_pin("".into());
-                app.set_login_error_text("".into());
-                app.set_weather_items(slint::ModelRc::new(slint::VecModel::from(Vec::<WeatherItem>::new())));
-                app.set_news_items(slint::ModelRc::new(slint::VecModel::from(Vec::<ArticleItem>::new())));
-                app.set_current_page(Page::Weather);
+        app.on_change_pin(move |old_pin, new_pin| {
+            let Some(user) = state_for_pin.lock().ok().and_then(|s| s.current_user.clone()) else {
+                ui(&app_weak, |app| app.set_pin_status("Log in to change your PIN".into()));
+                return;
+            };
+            if new_pin.is_empty() {
+                ui(&app_weak, |app| app.set_pin_status("Enter a new PIN".into()));
+                return;
+            }
+            let (old_pin, new_pin) = (Zeroizing::new(old_pin.to_string()), Zeroizing::new(new_pin.to_string()));
+            let auth = auth.clone();
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                // CPU-bound hashing off the reactor
+                let who = user.clone();
+                let res = auth
+                    .run(move |a| {
+                        // the cache key is resealed first, and put back if the PIN isn't changed
+                        a.confirm_pin(&user, &old_pin)?;
+                        cachecrypt::rewrap(&user, &old_pin, &new_pin).map_err(|e| AuthError::CacheKey(e.to_string()))?;
+                        a.change_pin(&user, &old_pin, &new_pin).inspect_err(|_| {
+                            if let Err(e) = cachecrypt::rewrap(&user, &new_pin, &old_pin) {
+                                eprintln!("Cache key error for {user}: {e}");
+                            }
+                        })
+                    })
+                    .await;
+                let changed = res.is_ok();
+                if changed {
+                    audit::record(AuditEvent::PinChanged, &who, "");
+                }
+                let status = match res {
+                    Ok(()) => "PIN changed".to_string(),
+                    Err(AuthError::InvalidPin) => "Current PIN is wrong".to_string(),
+                    Err(AuthError::NotFound) => "Unknown user".to_string(),
+                    Err(e @ (AuthError::PinTooShort(_) | AuthError::PinNotDigits | AuthError::PinTooCommon)) => e.to_string(),
+                    Err(e) => format!("PIN change error: {:?}", e),
+                };
+                ui(&aw, move |app| {
+                    if changed {
+                        app.set_pin_old("".into());
+                        app.set_pin_new("".into());
+                    }
+                    app.set_pin_status(status.into());
+                });
             });
-
         });
     }
 
-    // SWITCH ACCOUNT
+    // FORGOT PIN (login box): spend a recovery code on a new PIN, then sign in with it
     {
         let app_weak = app.as_weak();
-        let state_for_switch = state.clone();
-        let auth_path = auth.path.clone();
-
-        app.on_switch_account(move |u: slint::SharedString| {
-            let user = u.to_string();
+        let auth = auth.clone();
+        let h = handle.clone();
 
-            // mark active user in Rust + UI
-            set_current_user(&state_for_switch, &app_weak, Some(user.clone()));
-            set_login(&state_for_switch, &app_weak, true);
+        app.on_recover_requested(move |user, code, new_pin| {
+            let user = user.to_string();
+            let (code, new_pin) = (Zeroizing::new(code.to_string()), Zeroizing::new(new_pin.to_string()));
+            if user.is_empty() || code.is_empty() || new_pin.is_empty() {
+                set_login_error(&app_weak, "Enter username, recovery code and new PIN".to_string());
+                return;
+            }
+            set_login_error(&app_weak, "".to_string());
+            let auth = auth.clone();
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                // the new PIN comes back out for the sign-in instead of being cloned
+                let u = user.clone();
+                let res = auth
+                    .run(move |a| {
+                        let left = a.reset_pin_with_recovery(&u, &code, &new_pin)?;
+                        // an encrypted cache's key is sealed with the new PIN too
+                        let kept = cachecrypt::reseal(&u, &new_pin).map_err(|e| AuthError::CacheKey(e.to_string()))?;
+                        Ok((left, kept, new_pin))
+                    })
+                    .await;
+                match res {
+                    Ok((left, kept, new_pin)) => {
+                        audit::record(AuditEvent::PinReset, &user, &format!("{left} codes left"));
+                        let status = if kept {
+                            format!("PIN reset with a recovery code ({left} left)")
+                        } else {
+                            format!("PIN reset with a recovery code ({left} left); the encrypted cache was started over")
+                        };
+                        ui(&aw, move |app| {
+                            app.set_login_recovering(false);
+                            app.set_login_recovery_code("".into());
+                            app.set_login_new_pin("".into());
+                            app.set_pin_status(status.into());
+                            app.invoke_login_requested(user.into(), new_pin.as_str().into());
+                        })
+                    }
+                    Err(AuthError::NotFound) => set_login_error(&aw, "Unknown user".to_string()),
+                    Err(AuthError::InvalidRecoveryCode) => {
+                        audit::record(AuditEvent::LoginFailed, &user, "invalid recovery code");
+                        set_login_error(&aw, AuthError::InvalidRecoveryCode.to_string())
+                    }
+                    Err(e @ (AuthError::PinTooShort(_) | AuthError::PinNotDigits | AuthError::PinTooCommon)) => {
+                        set_login_error(&aw, e.to_string())
+                    }
+                    Err(e) => set_login_error(&aw, format!("Reset error: {:?}", e)),
+                }
+            });
+        });
+    }
 
-            // refresh users list (so menu shows up-to-date entries)
-            let auth2 = LocalAuth { path: auth_path.clone() };
-            push_users_to_ui(&app_weak, &auth2);
+    // FAMILY (Settings, admin only): make an account a restricted child profile or lift it
+    {
+        let app_weak = app.as_weak();
+        let auth = auth.clone();
+        let h = handle.clone();
+        let state_for_family = state.clone();
 
-            // load that user's config and trigger refreshes
-            let cfg = load_config_for(&user);
-            ui(&app_weak, move |app| {
-                app.set_weather_city(cfg.city.into());
-                app.set_use_celsius(cfg.units_celsius);
-                app.set_news_topic(cfg.news_topic.into());
-                app.set_current_page(Page::Weather);
-                app.invoke_refresh_weather();
-                app.invoke_refresh_news();
+        app.on_family_apply(move |target, child, allow_news, allow_links| {
+            let me = current_user(&state_for_family);
+            let target = target.trim().to_string();
+            if target.is_empty() {
+                ui(&app_weak, |app| app.set_pin_status("Enter the account to manage".into()));
+                return;
+            }
+            let role = if child { Role::Child } else { Role::Standard };
+            let auth = auth.clone();
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let t = target.clone();
+                let res = auth.run(move |a| a.set_profile(&me, &t, role, Restrictions { allow_news, allow_links })).await;
+                let status = match res {
+                    Ok(()) if target == "guest" && child => "Signed-out sessions now have child limits".to_string(),
+                    Ok(()) if target == "guest" => "Signed-out sessions are no longer limited".to_string(),
+                    Ok(()) if child => format!("{target} is now a child profile"),
+                    Ok(()) => format!("{target} is now a standard account"),
+                    Err(AuthError::NotFound) => format!("No account named {target}"),
+                    Err(e) => e.to_string(),
+                };
+                push_users_to_ui(aw.clone(), auth).await;
+                ui(&aw, move |app| app.set_pin_status(status.into()));
             });
         });
     }
 
-    // DELETE ACCOUNT
+    // FAMILY: installs from before roles have no admin; the signed-in user may claim it
     {
         let app_weak = app.as_weak();
-        let state_for_del = state.clone();
-        let auth_path = auth.path.clone();
+        let auth = auth.clone();
+        let h = handle.clone();
+        let state_for_claim = state.clone();
 
-        app.on_delete_account(move |u: slint::SharedString| {
-            let user = u.to_string();
+        app.on_claim_admin(move || {
+            let me = current_user(&state_for_claim);
+            let auth = auth.clone();
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let m = me.clone();
+                let status = match auth.run(move |a| a.set_profile(&m, &m, Role::Admin, Restrictions::default())).await {
+                    Ok(()) => format!("{me} is now the admin"),
+                    Err(e) => e.to_string(),
+                };
+                push_users_to_ui(aw.clone(), auth).await;
+                ui(&aw, move |app| app.set_pin_status(status.into()));
+            });
+        });
+    }
 
-            // delete from users.json (auth), config dir and cache dir
-            let auth2 = LocalAuth { path: auth_path.clone() };
-            let _ = auth2.delete_user(&user);
-            let _ = config::delete_user_tree(&user);
+    // PASSWORD STRENGTH (login box + Settings): zxcvbn score for the meter
+    app.on_password_strength(|secret, user| {
+        let (score, label) = auth::password_strength(&secret, &user);
+        Strength { score: score as i32, label: label.into() }
+    });
 
-            // if we deleted the current user, log out to "guest"
-            let active = current_user(&state_for_del);
-            if active == user {
-                set_login(&state_for_del, &app_weak, false);
-                set_current_user(&state_for_del, &app_weak, None);
-                ui(&app_weak, move |app| {
-                    app.set_login_user("".into());
-                    app.set_login_pin("".into());
-                    app.set_weather_items(slint::ModelRc::new(slint::VecModel::from(Vec::<WeatherItem>::new())));
-                    app.set_news_items(slint::ModelRc::new(slint::VecModel::from(Vec::<ArticleItem>::new())));
-                    app.set_current_page(Page::Weather);
-                });
-            }
+    // QUICK PIN (Settings): numeric PIN that only opens the lock screen
+    {
+        let app_weak = app.as_weak();
+        let auth = auth.clone();
+        let h = handle.clone();
+        let state_for_quick = state.clone();
 
-            // refresh users list
-            push_users_to_ui(&app_weak, &auth2);
+        app.on_set_quick_pin(move |password, quick_pin| {
+            let user = current_user(&state_for_quick);
+            if user == "guest" {
+                ui(&app_weak, |app| app.set_pin_status("Log in to set a quick PIN".into()));
+                return;
+            }
+            let (password, quick_pin) = (Zeroizing::new(password.to_string()), Zeroizing::new(quick_pin.to_string()));
+            let auth = auth.clone();
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let removing = quick_pin.is_empty();
+                let res = auth.run(move |a| a.set_quick_pin(&user, &password, &quick_pin)).await;
+                let changed = res.is_ok();
+                let status = match res {
+                    Ok(()) if removing => "Quick PIN removed".to_string(),
+                    Ok(()) => "Quick PIN set (unlocks the lock screen)".to_string(),
+                    Err(AuthError::InvalidPin) => "Current password is wrong".to_string(),
+                    Err(e @ (AuthError::PinTooShort(_) | AuthError::PinNotDigits | AuthError::PinTooCommon)) => format!("Quick PIN: {e}"),
+                    Err(e) => format!("Quick PIN error: {:?}", e),
+                };
+                ui(&aw, move |app| {
+                    if changed {
+                        app.set_pin_old("".into());
+                        app.set_quick_pin("".into());
+                    }
+                    app.set_pin_status(status.into());
+                });
+            });
         });
     }
 
-    // WEATHER: register a refresh handler
+    // NEW RECOVERY CODES (Settings): needs the current PIN; the old codes stop working
     {
         let app_weak = app.as_weak();
+        let auth = auth.clone();
         let h = handle.clone();
-        let state_for_weather = state.clone();
-
-        app.on_refresh_weather(move || {
-            let user = current_user(&state_for_weather);
+        let state_for_codes = state.clone();
 
-            // read UI:
-            let (city, use_celsius) = if let Some(app) = app_weak.upgrade() {
-                app.set_weather_status("Loading…".into());
-                (app.get_weather_city().to_string(), app.get_use_celsius())
-            } else {
-                ("Bucharest".to_string(), true)
+        app.on_new_recovery_codes(move |pin| {
+            let Some(user) = state_for_codes.lock().ok().and_then(|s| s.current_user.clone()) else {
+                ui(&app_weak, |app| app.set_pin_status("Log in to get recovery codes".into()));
+                return;
             };
-
-            // Try per-user cache first (text-only; no icons)
-            if let Some(c) = load_weather_for(&user) {
-                let want = if use_celsius { "C" } else { "F" };
-                if is_fresh(c.ts, 15 * 60) && c.units == want && c.city == city.to_lowercase() {
-                    if let Some(app) = app_weak.upgrade() {
-                        let items: Vec<WeatherItem> = c.rows
-                            .into_iter()
-                            .map(|r| WeatherItem {
-                                time: r.time.into(),
-                                temp: r.temp.into(),
-                                summary: r.summary.into(),
-                                icon: slint::Image::default(),   // cache has no icon info
-                            })
-                            .collect();
-                        let model = slint::VecModel::from(items);
-                        app.set_weather_items(slint::ModelRc::new(model));
-                        app.set_weather_status(format!(
-                            "Cached ({}) • updated {}m ago",
-                            if use_celsius { "°C" } else { "°F" },
-                            age_minutes(c.ts)
-                        ).into());
-                    }
-                }
-            }
-
-            // Network fetch
+            let pin = Zeroizing::new(pin.to_string());
+            let auth = auth.clone();
             let aw = app_weak.clone();
-            let user_for_save = user.clone();
-            let city_for_err = city.clone();
-
             h.spawn(async move {
-                // 1) Resolve city -> coords
-                let fetched = match fetch_coords(&city).await {
-                    Ok((lat, lon, label)) => {
+                let who = user.clone();
+                let res = auth
+                    .run(move |a| {
+                        a.confirm_pin(&user, &pin)?;
+                        a.generate_recovery_codes(&user)
+                    })
+                    .await;
+                if res.is_ok() {
+                    audit::record(AuditEvent::RecoveryCodes, &who, "");
+                }
+                match res {
+                    Ok(codes) => ui(&aw, move |app| {
+                        app.set_pin_old("".into());
+                        app.set_pin_status("New recovery codes issued".into());
+                        app.set_recovery_codes(codes.join("\n").into());
+                    }),
+                    Err(AuthError::InvalidPin) => ui(&aw, |app| app.set_pin_status("Current PIN is wrong".into())),
+                    Err(e) => ui(&aw, move |app| app.set_pin_status(format!("Recovery codes error: {:?}", e).into())),
+                }
+            });
+        });
+    }
+
+    // SETTINGS PROFILES (Settings + menu): switch, add or delete without logging out
+    {
+        let app_weak = app.as_weak();
+        let state_for_profile = state.clone();
+        app.on_switch_profile(move |name| {
+            let user = current_user(&state_for_profile);
+            let res = config::switch_profile(&user, &name).map(|()| format!("Using profile {name}"));
+            reload_profile(&app_weak, &user, res);
+        });
+
+        let app_weak = app.as_weak();
+        let state_for_profile = state.clone();
+        app.on_create_profile(move |name| {
+            let name = name.trim().to_string();
+            let user = current_user(&state_for_profile);
+            let res = config::create_profile(&user, &name).map(|()| format!("Created profile {name}"));
+            reload_profile(&app_weak, &user, res);
+        });
+
+        let app_weak = app.as_weak();
+        let state_for_profile = state.clone();
+        app.on_delete_profile(move |name| {
+            let user = current_user(&state_for_profile);
+            let res = config::delete_profile(&user, &name).map(|()| format!("Deleted profile {name}"));
+            reload_profile(&app_weak, &user, res);
+        });
+    }
+
+    // PAGE SETTINGS (Settings): keep edits in the rows until Save
+    {
+        let app_weak = app.as_weak();
+        app.on_page_setting_changed(move |section, key, value| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let rows = app.get_page_settings();
+            if let Some(i) = rows.iter().position(|r| r.section == section && r.key == key) {
+                if let Some(mut row) = rows.row_data(i) {
+                    row.value = value;
+                    rows.set_row_data(i, row);
+                }
+            }
+        });
+    }
+
+    // API KEYS (Settings): saved as soon as they're set, outside config.json
+    {
+        let app_weak = app.as_weak();
+        let state_for_keys = state.clone();
+        app.on_store_api_key(move |id, key| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let Some(service) = ApiService::from_id(&id) else { return };
+            let user = current_user(&state_for_keys);
+            let key = Zeroizing::new(key.to_string());
+            match secrets::set_api_key(&user, service, &key) {
+                Ok(()) if key.trim().is_empty() => app.set_pin_status("API key removed".into()),
+                Ok(()) => app.set_pin_status("API key saved".into()),
+                Err(e) => app.set_pin_status(format!("Could not save API key: {e}").into()),
+            }
+            push_api_keys(&app, &user);
+        });
+
+        let app_weak = app.as_weak();
+        let state_for_keys = state.clone();
+        app.on_move_api_keys(move |to_keyring| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_keys);
+            let to = if to_keyring { SecretBackend::Keyring } else { SecretBackend::File };
+            match secrets::set_api_key_backend(&user, to) {
+                Ok(()) if to_keyring => app.set_pin_status("API keys moved to the OS keyring".into()),
+                Ok(()) => app.set_pin_status("API keys moved to secrets.json".into()),
+                Err(e) => app.set_pin_status(format!("Could not move API keys: {e}").into()),
+            }
+            push_api_keys(&app, &user);
+        });
+    }
+
+    // SETTINGS BACKUPS (Settings): restore one, or reset to the defaults
+    {
+        let app_weak = app.as_weak();
+        let state_for_backup = state.clone();
+        app.on_restore_settings(move |index| {
+            let user = current_user(&state_for_backup);
+            let res = config::restore_backup(&user, index.max(0) as usize).map(|()| "Settings restored".to_string());
+            reload_profile(&app_weak, &user, res);
+        });
+
+        let app_weak = app.as_weak();
+        let state_for_backup = state.clone();
+        app.on_reset_settings(move || {
+            let user = current_user(&state_for_backup);
+            let res = config::reset_config(&user).map(|()| "Settings reset to defaults (the old ones are in Backups)".to_string());
+            reload_profile(&app_weak, &user, res);
+        });
+    }
+
+    // RENAME ACCOUNT (Settings): record, keyring entry, config and cache folders
+    {
+        let app_weak = app.as_weak();
+        let auth = auth.clone();
+        let h = handle.clone();
+        let state_for_rename = state.clone();
+
+        app.on_rename_account(move |new_name| {
+            let new_name = new_name.trim().to_string();
+            let old = current_user(&state_for_rename);
+            if old == "guest" {
+                ui(&app_weak, |app| app.set_pin_status("Log in to rename your account".into()));
+                return;
+            }
+            if new_name.is_empty() || new_name == old {
+                ui(&app_weak, |app| app.set_pin_status("Enter a new username".into()));
+                return;
+            }
+            let auth = auth.clone();
+            let aw = app_weak.clone();
+            let st = state_for_rename.clone();
+            h.spawn(async move {
+                // moves folders and keyring entries: off the UI thread
+                let (o, n) = (old.clone(), new_name.clone());
+                match auth.run(move |a| a.rename_user(&o, &n)).await {
+                    Ok(()) => {
+                        audit::record(AuditEvent::Renamed, &new_name, &format!("from {old}"));
+                        if load_session().remembered_user.as_deref() == Some(old.as_str()) {
+                            let session = Session { remembered_user: Some(new_name.clone()) };
+                            if let Err(e) = save_session(&session) {
+                                eprintln!("session save error: {e}");
+                            }
+                        }
+                        set_current_user(&st, &aw, Some(new_name.clone()));
+                        push_users_to_ui(aw.clone(), auth.clone()).await;
                         ui(&aw, move |app| {
-                            app.set_weather_status(format!("Loading… ({label})").into());
+                            app.set_rename_to("".into());
+                            app.set_pin_status(format!("Renamed {old} to {new_name}").into());
                         });
-                        // NOTE: this call is expected to return Vec<HourForecast>
-                        // with fields: time, temp, description, real_feel, precip, icon_url
-                        fetch_next_hours_at(lat, lon, 8, use_celsius).await
                     }
-                    Err(_) => {
+                    Err(e) => {
+                        let msg = match e {
+                            AuthError::AlreadyExists => format!("{new_name} is taken"),
+                            e => format!("Rename error: {e}"),
+                        };
+                        ui(&aw, move |app| app.set_pin_status(msg.into()));
+                    }
+                }
+            });
+        });
+    }
+
+    // EXPORT ACCOUNT (Settings): record + config + cache in one file, sealed with the PIN
+    {
+        let app_weak = app.as_weak();
+        let auth = auth.clone();
+        let h = handle.clone();
+        let state_for_export = state.clone();
+
+        app.on_export_account(move |pin| {
+            let user = current_user(&state_for_export);
+            if user == "guest" {
+                ui(&app_weak, |app| app.set_pin_status("Log in to export your profile".into()));
+                return;
+            }
+            if pin.is_empty() {
+                ui(&app_weak, |app| app.set_pin_status("Enter your current PIN to export".into()));
+                return;
+            }
+            let pin = Zeroizing::new(pin.to_string());
+            let auth = auth.clone();
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let Some(file) = rfd::AsyncFileDialog::new()
+                    .set_file_name(format!("{user}.account.json"))
+                    .add_filter("Account bundle", &["json"])
+                    .save_file()
+                    .await
+                else {
+                    return; // cancelled
+                };
+                let path = file.path().to_path_buf();
+                let res = auth
+                    .run(move |a| {
+                        a.confirm_pin(&user, &pin)?;
+                        Ok(bundle::export_account(a.local().ok_or(AuthError::Unsupported)?, &user, &pin, &path))
+                    })
+                    .await;
+                let msg = match res {
+                    Ok(Ok(())) => "Profile exported".to_string(),
+                    Ok(Err(e)) => format!("Export failed: {e}"),
+                    Err(AuthError::InvalidPin) => "Current PIN is wrong".to_string(),
+                    Err(e) => format!("Export failed: {e}"),
+                };
+                ui(&aw, move |app| {
+                    app.set_pin_old("".into());
+                    app.set_pin_status(msg.into());
+                });
+            });
+        });
+    }
+
+    // IMPORT ACCOUNT (login box): recreate an exported profile, then sign in with the PIN
+    {
+        let app_weak = app.as_weak();
+        let auth = auth.clone();
+        let h = handle.clone();
+
+        app.on_import_account(move |pin| {
+            if pin.is_empty() {
+                set_login_error(&app_weak, "Enter the profile's PIN first".to_string());
+                return;
+            }
+            let pin = Zeroizing::new(pin.to_string());
+            let auth = auth.clone();
+            let aw = app_weak.clone();
+            set_login_error(&aw, "".to_string());
+            h.spawn(async move {
+                let Some(file) = rfd::AsyncFileDialog::new()
+                    .add_filter("Account bundle", &["json"])
+                    .pick_file()
+                    .await
+                else {
+                    return; // cancelled
+                };
+                let path = file.path().to_path_buf();
+                let res = auth
+                    .run(move |a| Ok(bundle::import_account(a.local().ok_or(AuthError::Unsupported)?, &path, &pin).map(|user| (user, pin))))
+                    .await;
+                match res {
+                    Ok(Ok((user, pin))) => {
+                        audit::record(AuditEvent::Register, &user, "imported");
                         ui(&aw, move |app| {
-                            app.set_weather_status(format!("City not found: {}", city_for_err).into());
+                            app.set_login_user(user.as_str().into());
+                            app.invoke_login_requested(user.into(), pin.as_str().into());
                         });
-                        return;
                     }
+                    Ok(Err(e)) => set_login_error(&aw, format!("Import failed: {e}")),
+                    Err(e) => set_login_error(&aw, format!("Import failed: {e}")),
+                }
+            });
+        });
+    }
+
+    // SECURITY ACTIVITY (Settings): newest audit.log entries
+    {
+        let app_weak = app.as_weak();
+        app.on_load_security_activity(move || {
+            let items: Vec<AuditItem> = audit::recent(50)
+                .into_iter()
+                .map(|e| {
+                    let when = chrono::DateTime::from_timestamp(e.ts, 0)
+                        .map(|t| timefmt::date_time(&t.with_timezone(&chrono::Local)))
+                        .unwrap_or_default();
+                    let mut text = format!("{} · {}", e.event.label(), e.user);
+                    if !e.detail.is_empty() {
+                        text.push_str(&format!(" ({})", e.detail));
+                    }
+                    AuditItem { when: when.into(), text: text.into() }
+                })
+                .collect();
+            ui(&app_weak, move |app| {
+                app.set_security_activity(slint::ModelRc::new(slint::VecModel::from(items)));
+            });
+        });
+    }
+
+    // PIN STORAGE (Settings): move all PIN hashes between users.json and the OS keyring
+    {
+        let app_weak = app.as_weak();
+        let auth = auth.clone();
+        let h = handle.clone();
+        let state_for_secrets = state.clone();
+
+        app.on_set_pin_storage(move |keyring| {
+            if state_for_secrets.lock().ok().and_then(|s| s.current_user.clone()).is_none() {
+                ui(&app_weak, |app| app.set_pin_status("Log in to change PIN storage".into()));
+                return;
+            }
+            let to = if keyring { SecretBackend::Keyring } else { SecretBackend::File };
+            let auth = auth.clone();
+            let aw = app_weak.clone();
+            let who = current_user(&state_for_secrets);
+            h.spawn(async move {
+                let res = auth.run(move |a| a.local().ok_or(AuthError::Unsupported)?.set_secret_backend(to)).await;
+                if res.is_ok() {
+                    audit::record(AuditEvent::StorageChanged, &who, if keyring { "PINs to OS keyring" } else { "PINs to users.json" });
+                }
+                let (in_keyring, status) = match res {
+                    Ok(()) if keyring => (true, "PIN hashes moved to the OS keyring".to_string()),
+                    Ok(()) => (false, "PIN hashes moved to users.json".to_string()),
+                    Err(e) => (!keyring, format!("PIN storage error: {}", e)),
+                };
+                ui(&aw, move |app| {
+                    app.set_pins_in_keyring(in_keyring);
+                    app.set_pin_status(status.into());
+                });
+            });
+        });
+    }
+
+    // USER DB (Settings): move the user records between users.json and SQLite
+    {
+        let app_weak = app.as_weak();
+        let auth = auth.clone();
+        let h = handle.clone();
+        let state_for_store = state.clone();
+
+        app.on_set_user_storage(move |sqlite| {
+            if state_for_store.lock().ok().and_then(|s| s.current_user.clone()).is_none() {
+                ui(&app_weak, |app| app.set_pin_status("Log in to change the user database".into()));
+                return;
+            }
+            let to = if sqlite { UserStorage::Sqlite } else { UserStorage::Json };
+            let auth = auth.clone();
+            let aw = app_weak.clone();
+            let who = current_user(&state_for_store);
+            h.spawn(async move {
+                let res = auth.run(move |a| a.local().ok_or(AuthError::Unsupported)?.set_storage(to)).await;
+                let now = auth
+                    .run(|a| Ok(a.local().ok_or(AuthError::Unsupported)?.storage()))
+                    .await
+                    .unwrap_or(UserStorage::Json);
+                if res.is_ok() {
+                    audit::record(AuditEvent::StorageChanged, &who, if sqlite { "users to users.db" } else { "users to users.json" });
+                }
+                let status = match res {
+                    Ok(()) if now == UserStorage::Sqlite => "Users stored in users.db".to_string(),
+                    Ok(()) => "Users stored in users.json".to_string(),
+                    Err(e) => format!("User database error: {}", e),
+                };
+                ui(&aw, move |app| {
+                    app.set_users_in_sqlite(now == UserStorage::Sqlite);
+                    app.set_pin_status(status.into());
+                });
+            });
+        });
+    }
+
+    // CLEAR CACHE (Settings): the current user's weather / news cache, or everything
+    // cached (including the icons all users share); reports the space freed
+    {
+        let clear = {
+            let app_weak = app.as_weak();
+            let h = handle.clone();
+            let state_for_clear_cache = state.clone();
+            move |scope: cache::ClearScope| {
+                let user = current_user(&state_for_clear_cache);
+                let aw = app_weak.clone();
+                h.spawn(async move {
+                    let res = tokio::task::spawn_blocking(move || cache::clear_cache(&user, scope))
+                        .await
+                        .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+                    if scope != cache::ClearScope::Weather {
+                        news::invalidate_all().await;
+                    }
+                    let what = match scope {
+                        cache::ClearScope::Weather => "weather cache",
+                        cache::ClearScope::News => "news cache",
+                        cache::ClearScope::All => "all cached data",
+                    };
+                    let status = match res {
+                        Ok(freed) => format!("Cleared {what} • {} freed", cache::human_size(freed)),
+                        Err(e) => format!("Could not clear {what}: {e}"),
+                    };
+                    ui(&aw, move |app| app.set_pin_status(status.into()));
+                });
+            }
+        };
+        let c = clear.clone();
+        app.on_clear_weather_cache(move || c(cache::ClearScope::Weather));
+        let c = clear.clone();
+        app.on_clear_news_cache(move || c(cache::ClearScope::News));
+        app.on_clear_all_cache(move || clear(cache::ClearScope::All));
+    }
+
+    // CACHE STATS (Settings → Diagnostics): what the current user has cached
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_stats = state.clone();
+        app.on_show_cache_stats(move || {
+            let user = current_user(&state_for_stats);
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let lines = tokio::task::spawn_blocking(move || {
+                    let storage = match cache::cache_storage(&user) {
+                        cachestore::CacheStorage::Sqlite => "cache.db",
+                        cachestore::CacheStorage::Files => "JSON files",
+                    };
+                    let limit = match load_config_for(&user).cache_limit_mb {
+                        0 => "no limit".to_string(),
+                        mb => format!("limit {mb} MB"),
+                    };
+                    let mut lines = vec![format!(
+                        "{user}: {} in {storage} ({limit})",
+                        cache::human_size(cache::user_cache_size(&user))
+                    )];
+                    lines.extend(cache::cache_stats(&user).iter().map(|s| s.summary()));
+                    lines
+                })
+                .await
+                .unwrap_or_default();
+                ui(&aw, move |app| {
+                    let lines: Vec<slint::SharedString> = lines.into_iter().map(Into::into).collect();
+                    app.set_cache_stats(slint::ModelRc::new(slint::VecModel::from(lines)));
+                });
+            });
+        });
+    }
+
+    // CACHE STORAGE (Settings): the current user's cache documents in JSON files or cache.db
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_cache_store = state.clone();
+        app.on_set_cache_storage(move |sqlite| {
+            let user = current_user(&state_for_cache_store);
+            let to = if sqlite { cachestore::CacheStorage::Sqlite } else { cachestore::CacheStorage::Files };
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let u = user.clone();
+                let res = tokio::task::spawn_blocking(move || cache::set_cache_storage(&u, to))
+                    .await
+                    .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+                let now = cache::cache_storage(&user);
+                let status = match res {
+                    Ok(()) if now == cachestore::CacheStorage::Sqlite => "Cache stored in cache.db".to_string(),
+                    Ok(()) => "Cache stored in JSON files".to_string(),
+                    Err(e) => format!("Cache storage error: {e}"),
+                };
+                ui(&aw, move |app| {
+                    app.set_cache_in_sqlite(now == cachestore::CacheStorage::Sqlite);
+                    app.set_pin_status(status.into());
+                });
+            });
+        });
+    }
+
+    // CACHE ENCRYPTION (Settings): the current PIN seals the cache key; every entry is rewritten
+    {
+        let app_weak = app.as_weak();
+        let auth = auth.clone();
+        let h = handle.clone();
+        let state_for_crypt = state.clone();
+        app.on_set_cache_encryption(move |pin, on| {
+            let user = current_user(&state_for_crypt);
+            if user == "guest" {
+                ui(&app_weak, |app| app.set_pin_status("Log in to encrypt your cache".into()));
+                return;
+            }
+            if pin.is_empty() {
+                ui(&app_weak, |app| app.set_pin_status("Enter your current PIN to change cache encryption".into()));
+                return;
+            }
+            let pin = Zeroizing::new(pin.to_string());
+            let auth = auth.clone();
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let u = user.clone();
+                let res = auth
+                    .run(move |a| {
+                        a.confirm_pin(&u, &pin)?;
+                        Ok(cache::set_cache_encryption(&u, &pin, on))
+                    })
+                    .await;
+                let now = cachecrypt::enabled(&user);
+                httpcache::set_private(now);
+                if now {
+                    // plain copies of what this user looked up may be in the shared store
+                    httpcache::clear();
+                }
+                let msg = match res {
+                    Ok(Ok(())) if now => "Cache encrypted".to_string(),
+                    Ok(Ok(())) => "Cache no longer encrypted".to_string(),
+                    Ok(Err(e)) => format!("Cache encryption error: {e}"),
+                    Err(AuthError::InvalidPin) => "Current PIN is wrong".to_string(),
+                    Err(e) => format!("Cache encryption error: {e}"),
+                };
+                ui(&aw, move |app| {
+                    app.set_pin_old("".into());
+                    app.set_cache_encrypted(now);
+                    app.set_pin_status(msg.into());
+                });
+            });
+        });
+    }
+
+    // LOG OUT
+    {
+        let app_weak = app.as_weak();
+        let state_for_logout = state.clone();
+        let auth = auth.clone();
+        let h = handle.clone();
+
+        app.on_logout(move || {
+            let user = current_user(&state_for_logout);
+            audit::record(AuditEvent::Logout, &user, "");
+            if let Ok(mut s) = state_for_logout.lock() {
+                s.sessions.remove(&user);
+            }
+            cachecrypt::lock(&user);
+            forget_session(None);
+
+            // flip auth state + UI
+            set_login(&state_for_logout, &app_weak, false);
+            set_current_user(&state_for_logout, &app_weak, None);
+
+            // refresh users list in the menu (and the guest profile), then clear lists on screen
+            let (aw, auth) = (app_weak.clone(), auth.clone());
+            h.spawn(async move {
+                push_users_to_ui(aw.clone(), auth).await;
+                ui(&aw, move |app| {
+                    app.set_login_user("".into());
+                    app.set_login_pin("".into());
+                    app.set_login_error_text("".into());
+                    app.set_weather_items(slint::ModelRc::new(slint::VecModel::from(Vec::<WeatherItem>::new())));
+                    app.set_news_items(slint::ModelRc::new(slint::VecModel::from(Vec::<ArticleItem>::new())));
+                    app.set_current_page(Page::Weather);
+                });
+            });
+        });
+    }
+
+    // LOCK NOW (menu)
+    {
+        let state_for_lock = state.clone();
+        let app_weak = app.as_weak();
+
+        app.on_lock_now(move || {
+            let st = state_for_lock.clone();
+            ui(&app_weak, move |app| {
+                if let Ok(mut s) = st.lock() {
+                    lock_session(&app, &mut s);
+                }
+            });
+        });
+    }
+
+    // SWITCH ACCOUNT
+    {
+        let app_weak = app.as_weak();
+        let state_for_switch = state.clone();
+        let auth = auth.clone();
+        let h = handle.clone();
+
+        app.on_switch_account(move |u: slint::SharedString| {
+            let user = u.to_string();
+
+            // no PIN needed only for trusted accounts with a live session
+            let fast = state_for_switch.lock().map(|s| can_fast_switch(&s, &user)).unwrap_or(false);
+            if !fast {
+                let st = state_for_switch.clone();
+                ui(&app_weak, move |app| {
+                    if let Ok(mut s) = st.lock() {
+                        prompt_switch(&app, &mut s, &user);
+                    }
+                });
+                return;
+            }
+
+            // mark active user in Rust + UI
+            set_current_user(&state_for_switch, &app_weak, Some(user.clone()));
+            set_login(&state_for_switch, &app_weak, true);
+
+            // refresh users list (so menu shows up-to-date entries) and the
+            // profile, then load that user's config and trigger refreshes
+            let cfg = load_config_for(&user);
+            let (aw, auth) = (app_weak.clone(), auth.clone());
+            h.spawn(async move {
+                push_users_to_ui(aw.clone(), auth).await;
+                ui(&aw, move |app| {
+                    apply_config(&app, &cfg);
+                    app.set_current_page(start_page(&app, &cfg));
+                    app.invoke_refresh_weather();
+                    app.invoke_refresh_news();
+                });
+            });
+        });
+    }
+
+    // DELETE ACCOUNT
+    {
+        let app_weak = app.as_weak();
+        let state_for_del = state.clone();
+        let auth = auth.clone();
+        let h = handle.clone();
+
+        app.on_delete_account(move |u: slint::SharedString| {
+            let user = u.to_string();
+            let (aw, st, auth) = (app_weak.clone(), state_for_del.clone(), auth.clone());
+            h.spawn(async move {
+                // delete from users.json (auth), config dir and cache dir
+                let u = user.clone();
+                if auth.run(move |a| a.delete_user(&u)).await.is_ok() {
+                    audit::record(AuditEvent::Deleted, &user, "");
+                }
+                if let Ok(mut s) = st.lock() {
+                    s.sessions.remove(&user);
+                }
+                secrets::delete_api_keys(&user);
+                cachecrypt::lock(&user);
+                let _ = config::delete_user_tree(&user);
+                alarms::forget();
+                worldclock::forget();
+                podcasts::forget();
+                calendar::forget();
+                if let Err(e) = cache::delete_user_cache(&user) {
+                    eprintln!("Failed to remove cache for {}: {}", user, e);
+                }
+                forget_session(Some(&user));
+
+                // if we deleted the current user, log out to "guest"
+                let active = current_user(&st);
+                if active == user {
+                    set_login(&st, &aw, false);
+                    set_current_user(&st, &aw, None);
+                    ui(&aw, move |app| {
+                        app.set_login_user("".into());
+                        app.set_login_pin("".into());
+                        app.set_weather_items(slint::ModelRc::new(slint::VecModel::from(Vec::<WeatherItem>::new())));
+                        app.set_news_items(slint::ModelRc::new(slint::VecModel::from(Vec::<ArticleItem>::new())));
+                        app.set_current_page(Page::Weather);
+                    });
+                }
+
+                // refresh users list
+                push_users_to_ui(aw, auth).await;
+            });
+        });
+    }
+
+    // AVATAR: pick an image file, store a square copy in the user's config dir
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_avatar = state.clone();
+        let auth = auth.clone();
+
+        app.on_pick_avatar(move || {
+            let user = current_user(&state_for_avatar);
+            let aw = app_weak.clone();
+            let auth = auth.clone();
+            h.spawn(async move {
+                let Some(file) = rfd::AsyncFileDialog::new()
+                    .add_filter("Images", &["png", "jpg", "jpeg", "gif", "bmp", "webp"])
+                    .pick_file()
+                    .await
+                else {
+                    return; // cancelled
                 };
+                let path = file.path().to_path_buf();
+                let res = tokio::task::spawn_blocking(move || avatar::save_avatar(&user, &path)).await;
+                match res {
+                    Ok(Ok(())) => push_users_to_ui(aw, auth).await,
+                    Ok(Err(e)) => eprintln!("Avatar error: {e:?}"),
+                    Err(e) => eprintln!("Avatar task failed: {e:?}"),
+                }
+            });
+        });
+    }
+
+    // AVATAR: back to the plain name chip
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_avatar = state.clone();
+        let auth = auth.clone();
+
+        app.on_remove_avatar(move || {
+            let user = current_user(&state_for_avatar);
+            if let Err(e) = avatar::remove_avatar(&user) {
+                eprintln!("Avatar error: {e:?}");
+            }
+            h.spawn(push_users_to_ui(app_weak.clone(), auth.clone()));
+        });
+    }
+
+    // WEATHER: register a refresh handler
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_weather = state.clone();
+
+        app.on_refresh_weather(move || {
+            let warmed = Warmed(WARM_WEATHER);
+            let user = current_user(&state_for_weather);
+
+            // read UI:
+            let (city, use_celsius) = if let Some(app) = app_weak.upgrade() {
+                app.set_weather_status("Loading…".into());
+                (app.get_weather_city().to_string(), app.get_use_celsius())
+            } else {
+                ("Bucharest".to_string(), true)
+            };
+
+            let units = if use_celsius { "C" } else { "F" };
+            let unit_label = if use_celsius { "°C" } else { "°F" };
+            let cfg = load_config_for(&user);
+            let key = cache::WeatherKey { user: &user, city: &city, units };
+
+            // cached rows are text-only (no icons)
+            let show_cached = |c: cache::Tagged<cache::WeatherCache>| {
+                let Some(app) = app_weak.upgrade() else { return };
+                let items: Vec<WeatherItem> = c.entry.rows
+                    .into_iter()
+                    .map(|r| WeatherItem {
+                        time: timefmt::hour_label(&r.time).into(),
+                        temp: r.temp.into(),
+                        summary: r.summary.into(),
+                        icon: slint::Image::default(),
+                    })
+                    .collect();
+                app.set_weather_items(slint::ModelRc::new(slint::VecModel::from(items)));
+                app.set_weather_status(format!(
+                    "Cached ({unit_label}) • updated {}m ago{}",
+                    age_minutes(c.entry.ts),
+                    if c.stale { STALE_NOTE } else { "" }
+                ).into());
+            };
+
+            if offline() {
+                match key.load() {
+                    Some(entry) => show_cached(cache::Tagged { entry, stale: false }),
+                    None => ui(&app_weak, |app| app.set_weather_status("Offline mode • nothing cached".into())),
+                }
+                return;
+            }
+
+            // cached rows at once; a network fetch unless they're fresh
+            let ttl = if take_flag(&state_for_weather, |s| &mut s.force_weather_refresh) { 0 } else { cfg.weather_ttl_secs() };
+            let aw = app_weak.clone();
+            let city_for_fetch = city.clone();
+            let hours = weather::HOURS.number(&cfg) as usize;
+            let lang = weather::PLACE_LANGUAGE.text(&cfg);
+            let prefer = weather::PREFER_COUNTRY.text(&cfg);
+            let choice = cfg.city_choices.get(&geocode::query_key(&city)).cloned();
+            let user_for_recent = user.clone();
+            let (cached, refresh) = cache::cached_or_refresh(&h, &key, ttl, move || async move {
+                // 1) Resolve city -> coords (a remembered pick wins), 2) hourly forecast there
+                let (place, others) = match choice {
+                    Some(place) => (place, Vec::new()),
+                    None => {
+                        let places = fetch_places(&user_for_recent, &city_for_fetch, &lang, &prefer)
+                            .await
+                            .map_err(|_| format!("City not found: {city_for_fetch}"))?;
+                        // ambiguous: show the best match, offer the rest
+                        let others = if places.len() > 1 { places.clone() } else { Vec::new() };
+                        (places[0].clone(), others)
+                    }
+                };
+                let mut locations = cache::load_locations_for(&user_for_recent);
+                locations.note(cache::SavedLocation { city: city_for_fetch.clone(), place: place.clone() });
+                if let Err(e) = cache::save_locations_for(&user_for_recent, &locations) {
+                    eprintln!("Save locations error: {e:?}");
+                }
+                let Place { lat, lon, label, timezone } = place;
+                ui(&aw, move |app| {
+                    app.set_weather_status(format!("Loading… ({label})").into());
+                    push_locations(&app, &locations);
+                    let items: Vec<PlaceItem> = others
+                        .into_iter()
+                        .map(|p| PlaceItem {
+                            label: p.label.into(),
+                            lat: p.lat as f32,
+                            lon: p.lon as f32,
+                            timezone: p.timezone.into(),
+                        })
+                        .collect();
+                    app.set_weather_places(slint::ModelRc::new(slint::VecModel::from(items)));
+                });
+                fetch_next_hours_at(lat, lon, &timezone, hours, use_celsius).await.map_err(|err| format!("Failed to load: {err}"))
+            });
+            if let Some(c) = cached {
+                show_cached(c);
+            }
+            let Some(refresh) = refresh else { return };
+
+            let aw = app_weak.clone();
+            let user_for_save = user.clone();
+            h.spawn(async move {
+                let _warmed = warmed;
+                let Ok(fetched) = refresh.await else { return };
+                match fetched {
+                    // Build cache (text-only) and UI (with icons loaded on the UI thread)
+                    Ok(rows) => {
+                        // Save simplified rows to cache (compatible with old format)
+                        let rows_for_cache: Vec<(String, String, String)> = rows.iter()
+                            .map(|r| {
+                                let summary = format!("{} • {} • {}", r.description, r.real_feel, r.precip);
+                                (r.time.clone(), r.temp.clone(), summary)
+                            })
+                            .collect();
+
+                        let _ = save_weather_for(
+                            &user_for_save,
+                            &rows_for_cache,
+                            if use_celsius { "C" } else { "F" },
+                            &city,
+                        );
+
+                        // Prepare data for UI: download icons -> keep only file paths (Send)
+                        struct GuiRow {
+                            time: String,
+                            temp: String,
+                            summary: String,
+                            icon_path: Option<std::path::PathBuf>,
+                        }
+
+                        let mut gui_rows: Vec<GuiRow> = Vec::with_capacity(rows.len());
+                        for r in rows {
+                            let icon_path = cache_icon_to_path(&r.icon_url).await;  // async download/cache
+                            let summary = format!("{} • {} • {}", r.description, r.real_feel, r.precip);
+                            gui_rows.push(GuiRow {
+                                time: r.time,
+                                temp: r.temp,
+                                summary,
+                                icon_path,
+                            });
+                        }
+
+                        // Hop to UI thread: construct slint::Image here (not across threads)
+                        ui(&aw, move |app| {
+                            let items: Vec<WeatherItem> = gui_rows
+                                .into_iter()
+                                .map(|g| {
+                                    let img = g.icon_path
+                                        .as_ref()
+                                        .and_then(|p| slint::Image::load_from_path(p.as_path()).ok())
+                                        .unwrap_or_default();
+
+                                    WeatherItem {
+                                        time: timefmt::hour_label(&g.time).into(),
+                                        temp: g.temp.into(),
+                                        summary: g.summary.into(),
+                                        icon: img,
+                                    }
+                                })
+                                .collect();
+
+                            let model = slint::VecModel::from(items);
+                            app.set_weather_items(slint::ModelRc::new(model));
+                            app.set_weather_status(
+                                format!("Updated ({})", if use_celsius { "°C" } else { "°F" }).into()
+                            );
+                        });
+                    }
+
+                    // Error handling: keep showing the cached rows if there are any
+                    Err(msg) => {
+                        ui(&aw, move |app| {
+                            let s = app.get_weather_status().to_string();
+                            if s.starts_with("Cached") {
+                                app.set_weather_status(format!("Offline • {}", s.trim_end_matches(STALE_NOTE)).into());
+                            } else {
+                                app.set_weather_status(msg.into());
+                            }
+                        });
+                    }
+                }
+            });
+        });
+    }
+
+
+    // NEWS
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_news = state.clone();
+
+        app.on_refresh_news(move || {
+            let warmed = Warmed(WARM_NEWS);
+            let user = current_user(&state_for_news);
+
+            let (topic, query) = if let Some(app) = app_weak.upgrade() {
+                app.set_news_status("Loading…".into());
+                app.set_news_showing_bookmarks(false);
+                (app.get_news_topic().to_string(), news_query(&app))
+            } else {
+                ("Top Stories".to_string(), news::NewsQuery::default())
+            };
+            let key = news::feed_key(&topic, query);
+            let cfg = load_config_for(&user);
+            // apply_profile has run for this user by the time anything refreshes
+            let filter = news_filter(&cfg, app_weak.upgrade().is_some_and(|app| app.get_is_child()));
+            let following = news::topic_key(&topic) == news::topic_key(news::FOLLOWING);
+            if following && cfg.followed_authors.is_empty() {
+                if let Some(app) = app_weak.upgrade() {
+                    app.set_news_items(slint::ModelRc::new(slint::VecModel::from(Vec::<ArticleItem>::new())));
+                    app.set_news_hidden_count(0);
+                    app.set_news_status("Add authors to follow in Settings".into());
+                }
+                return;
+            }
+
+            let entry_key = cache::NewsKey { user: &user, topic: &key };
+            let show_cached = |c: cache::Tagged<cache::NewsCache>| {
+                let Some(app) = app_weak.upgrade() else { return };
+                let ts = c.entry.ts;
+                mark_news_seen(&state_for_news, c.entry.rows.iter().map(|r| r.url.as_str()));
+                let total = c.entry.rows.len();
+                let items: Vec<ArticleItem> = c.entry.rows.into_iter()
+                    .filter(|r| filter.keeps(&r.source, &r.title))
+                    .map(|r| article_item(r.into_article(&user)))
+                    .collect();
+                app.set_news_hidden_count((total - items.len()) as i32);
+                let items = CardMarks::load(&user).apply(items);
+                app.set_news_items(slint::ModelRc::new(slint::VecModel::from(items)));
+                app.set_news_status(format!(
+                    "Cached • updated {}m ago{}",
+                    age_minutes(ts),
+                    if c.stale { STALE_NOTE } else { "" }
+                ).into());
+            };
+
+            if offline() {
+                match entry_key.load() {
+                    Some(entry) => show_cached(cache::Tagged { entry, stale: false }),
+                    None => ui(&app_weak, |app| app.set_news_status("Offline mode • nothing cached".into())),
+                }
+                return;
+            }
+
+            // cached stories at once; a network fetch unless they're fresh
+            let ttl = if take_flag(&state_for_news, |s| &mut s.force_news_refresh) { 0 } else { cfg.news_ttl_secs() };
+            let page_size = news::PAGE_SIZE.number(&cfg) as usize;
+            let stats = load_open_stats_for(&user);
+            let (pinned, authors, fetch_filter) = (cfg.pinned_topics.clone(), cfg.followed_authors.clone(), filter.clone());
+            let (cached, refresh) = cache::cached_or_refresh(&h, &entry_key, ttl, move || async move {
+                if news::topic_key(&topic) == news::topic_key(news::FOR_YOU) {
+                    news::fetch_for_you(&pinned, page_size, &fetch_filter, query, &stats).await
+                } else if following {
+                    news::fetch_following(&authors, page_size, &fetch_filter, query).await
+                } else {
+                    news::fetch_news(&topic, page_size, &fetch_filter, query).await
+                }
+            });
+            if let Some(c) = cached {
+                show_cached(c);
+            }
+            let Some(refresh) = refresh else { return };
+
+            // per-user save of whatever the network returned
+            let aw = app_weak.clone();
+            let user_for_save = user.clone();
+            let st = state_for_news.clone();
+            h.spawn(async move {
+                let _warmed = warmed;
+                let Ok(res) = refresh.await else { return };
+                match res {
+                    Ok(fetched) => {
+                        let news::FetchedNews { rows, hidden } = fetched;
+                        let _ = save_news_for(&user_for_save, &key, &rows); // <-- per-user save
+                        mark_news_seen(&st, rows.iter().map(|a| a.url.as_str()));
+                        let marks = CardMarks::load(&user_for_save);
+                        ui(&aw, move |app| {
+                            let items = marks.apply(rows.into_iter().map(article_item).collect());
+                            let model = slint::VecModel::from(items);
+                            app.set_news_items(slint::ModelRc::new(model));
+                            app.set_news_hidden_count(hidden as i32);
+                            app.set_news_status("".into());
+                        });
+                    }
+                    Err(err) => {
+                        ui(&aw, move |app| {
+                            let s = app.get_news_status().to_string();
+                            if s.starts_with("Cached") {
+                                app.set_news_status(format!("Offline • {}", s.trim_end_matches(STALE_NOTE)).into());
+                            } else {
+                                app.set_news_status(format!("Failed to load: {:?}", err).into());
+                            }
+                        });
+                    }
+                }
+            });
+        });
+    }
+
+    // Open a news link in the default browser
+
+    {
+        let h = handle.clone();
+        let app_weak = app.as_weak();
+        let state_for_open = state.clone();
+        app.on_open_news(move |url: slint::SharedString| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_open);
+            // "Reader first": list taps go to the reader view; its "Open original" still opens the browser
+            if !app.get_reader_open() && news::OPEN_IN_READER.flag(&load_config_for(&user)) {
+                if let Some(item) = app.get_news_items().iter().find(|a| a.url == url) {
+                    app.invoke_open_reader(item);
+                    return;
+                }
+            }
+            open_url(&h, url.to_string());
+            mark_read(&app, &user, &url);
+
+            // count the open towards its topic(s) and source for the "For You" ranking
+            let current = app.get_news_topic().to_string();
+            let source = app.get_news_items().iter().find(|a| a.url == url).map(|a| a.source.to_string());
+            let url = url.to_string();
+            h.spawn(async move {
+                let mut topics = news::topics_with(&url).await;
+                if topics.is_empty() && !news::is_virtual_topic(&current) {
+                    topics.push(current);
+                }
+                let mut stats = load_open_stats_for(&user);
+                stats.record(&topics, source.as_deref().unwrap_or(""));
+                if let Err(e) = save_open_stats_for(&user, &stats) {
+                    eprintln!("Save open stats error: {e:?}");
+                }
+            });
+        });
+    }
+
+    // Open the HN discussion for a story
+    {
+        let h = handle.clone();
+        app.on_open_comments(move |object_id: slint::SharedString| {
+            if object_id.is_empty() {
+                return;
+            }
+            open_url(&h, news::discussion_url(&object_id));
+        });
+    }
+
+    // Ambiguous city: remember the place the user picked for it, then reload
+    {
+        let app_weak = app.as_weak();
+        let state_for_place = state.clone();
+        app.on_weather_place_picked(move |item: PlaceItem| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_place);
+            let mut cfg = load_config_for(&user);
+            let place = Place {
+                lat: item.lat as f64,
+                lon: item.lon as f64,
+                label: item.label.to_string(),
+                timezone: item.timezone.to_string(),
+            };
+            cfg.city_choices.insert(geocode::query_key(&app.get_weather_city()), place);
+            if let Err(e) = save_config_for(&user, &cfg) {
+                eprintln!("Save config error: {e:?}");
+            }
+            app.set_weather_places(slint::ModelRc::new(slint::VecModel::from(Vec::<PlaceItem>::new())));
+            app.invoke_force_refresh_weather();
+        });
+    }
+
+    // Favorite / recent location chips: switch to one (as if typed in Settings), or star it
+    {
+        let app_weak = app.as_weak();
+        let state_for_loc = state.clone();
+        app.on_weather_location_picked(move |item: LocationItem| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_loc);
+            let Some(loc) = cache::load_locations_for(&user).find(&item.label).cloned() else { return };
+            let mut cfg = load_config_for(&user);
+            cfg.city = loc.city.clone();
+            // the place it resolved to back then, even if the name is ambiguous
+            cfg.city_choices.insert(geocode::query_key(&loc.city), loc.place);
+            if let Err(e) = save_config_for(&user, &cfg) {
+                eprintln!("Save config error: {e:?}");
+            }
+            app.set_weather_city(loc.city.into());
+            app.set_weather_places(slint::ModelRc::new(slint::VecModel::from(Vec::<PlaceItem>::new())));
+            app.invoke_refresh_weather();
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_loc = state.clone();
+        app.on_weather_location_starred(move |item: LocationItem| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_loc);
+            let mut store = cache::load_locations_for(&user);
+            let Some(loc) = store.find(&item.label).cloned() else { return };
+            store.toggle_favorite(loc);
+            if let Err(e) = cache::save_locations_for(&user, &store) {
+                eprintln!("Save locations error: {e:?}");
+            }
+            push_locations(&app, &store);
+        });
+    }
+
+    // NOTES: one Markdown file per note; edits are saved once typing pauses
+    {
+        let app_weak = app.as_weak();
+        let state_for_notes = state.clone();
+        app.on_notes_search(move |query| {
+            let Some(app) = app_weak.upgrade() else { return };
+            push_notes(&app, &current_user(&state_for_notes), &query);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_notes = state.clone();
+        app.on_note_open(move |id| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_notes);
+            save_open_note(&app, &user);
+            match notes::load(&user, &id) {
+                Ok(text) => {
+                    app.set_note_preview(slint::ModelRc::new(slint::VecModel::from(note_blocks(&text))));
+                    app.set_note_text(text.into());
+                    app.set_note_id(id);
+                    app.set_notes_status("".into());
+                }
+                Err(e) => app.set_notes_status(format!("Could not open the note: {e}").into()),
+            }
+            push_notes(&app, &user, &app.get_notes_query());
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_notes = state.clone();
+        app.on_note_new(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_notes);
+            save_open_note(&app, &user);
+            match notes::create(&user) {
+                Ok(id) => {
+                    app.set_note_id(id.into());
+                    app.set_note_text("".into());
+                    app.set_note_previewing(false);
+                    app.set_notes_query("".into());
+                    app.set_notes_status("".into());
+                }
+                Err(e) => app.set_notes_status(format!("Could not create a note: {e}").into()),
+            }
+            push_notes(&app, &user, "");
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_notes = state.clone();
+        app.on_note_edited(move |text| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let edit = NOTE_EDITS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            let (user, id, text) = (current_user(&state_for_notes), app.get_note_id().to_string(), text.to_string());
+            app.set_notes_status("Editing…".into());
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                tokio::time::sleep(NOTE_AUTOSAVE).await;
+                if NOTE_EDITS.load(std::sync::atomic::Ordering::SeqCst) != edit {
+                    return;
+                }
+                let res = notes::save(&user, &id, &text);
+                ui(&aw, move |app| {
+                    match res {
+                        Ok(()) => app.set_notes_status("Saved".into()),
+                        Err(e) => app.set_notes_status(format!("Not saved: {e}").into()),
+                    }
+                    push_notes(&app, &user, &app.get_notes_query());
+                });
+            });
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_notes = state.clone();
+        app.on_note_delete(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_notes);
+            // a pending autosave must not bring it back
+            NOTE_EDITS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            match notes::delete(&user, &app.get_note_id()) {
+                Ok(()) => {
+                    app.set_note_id("".into());
+                    app.set_note_text("".into());
+                    app.set_notes_status("".into());
+                }
+                Err(e) => app.set_notes_status(format!("Could not delete the note: {e}").into()),
+            }
+            push_notes(&app, &user, &app.get_notes_query());
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        app.on_note_preview_requested(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            let blocks = note_blocks(&app.get_note_text());
+            app.set_note_preview(slint::ModelRc::new(slint::VecModel::from(blocks)));
+        });
+    }
+
+    // CLOCK: alarms and timers (they ring from the clock task)
+    {
+        let app_weak = app.as_weak();
+        let state_for_clock = state.clone();
+        app.on_alarm_add(move |time, label, daily| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let Some((hour, minute)) = alarms::parse_time(&time) else {
+                app.set_clock_status(format!("\"{time}\" isn't a time; try 07:30 or 7:30 pm").into());
+                return;
+            };
+            let user = current_user(&state_for_clock);
+            match alarms::add_alarm(&user, hour, minute, &label, daily) {
+                Ok(()) => app.set_clock_status("".into()),
+                Err(e) => app.set_clock_status(format!("Could not save the alarm: {e}").into()),
+            }
+            push_clock(&app, &user);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_clock = state.clone();
+        app.on_alarm_toggled(move |id| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_clock);
+            if let Err(e) = alarms::toggle_alarm(&user, id.parse().unwrap_or_default()) {
+                app.set_clock_status(format!("Could not save the alarm: {e}").into());
+            }
+            push_clock(&app, &user);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_clock = state.clone();
+        app.on_alarm_deleted(move |id| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_clock);
+            if let Err(e) = alarms::delete_alarm(&user, id.parse().unwrap_or_default()) {
+                app.set_clock_status(format!("Could not delete the alarm: {e}").into());
+            }
+            push_clock(&app, &user);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_clock = state.clone();
+        app.on_timer_add(move |length, label| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let Some(seconds) = alarms::parse_duration(&length) else {
+                app.set_clock_status(format!("\"{length}\" isn't a length; try 10, 90s, 1h30m or 2:30").into());
+                return;
+            };
+            if seconds > alarms::MAX_TIMER {
+                app.set_clock_status("Timers run for 24 hours at most".into());
+                return;
+            }
+            let user = current_user(&state_for_clock);
+            match alarms::add_timer(&user, seconds, &label) {
+                Ok(()) => app.set_clock_status("".into()),
+                Err(e) => app.set_clock_status(format!("Could not save the timer: {e}").into()),
+            }
+            push_clock(&app, &user);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_clock = state.clone();
+        app.on_timer_cancelled(move |id| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_clock);
+            if let Err(e) = alarms::cancel_timer(&user, id.parse().unwrap_or_default()) {
+                app.set_clock_status(format!("Could not cancel the timer: {e}").into());
+            }
+            push_clock(&app, &user);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_clock = state.clone();
+        app.on_ring_snoozed(move |ring| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_clock);
+            let minutes = alarms::SNOOZE_MINUTES.number(&load_config_for(&user));
+            let again = alarms::Ring { timer: ring.timer, id: ring.id.parse().unwrap_or_default(), label: ring.label.to_string() };
+            if let Err(e) = alarms::snooze(&user, &again, minutes) {
+                app.set_clock_status(format!("Could not snooze: {e}").into());
+            }
+            drop_ring(&app, &ring);
+            push_clock(&app, &user);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        app.on_ring_dismissed(move |ring| {
+            let Some(app) = app_weak.upgrade() else { return };
+            drop_ring(&app, &ring);
+        });
+    }
+
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_clock = state.clone();
+        app.on_world_add(move |query| {
+            let query = query.trim().to_string();
+            if query.is_empty() {
+                return;
+            }
+            let user = current_user(&state_for_clock);
+            let cfg = load_config_for(&user);
+            let (lang, prefer) = (weather::PLACE_LANGUAGE.text(&cfg), weather::PREFER_COUNTRY.text(&cfg));
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let res = match worldclock::resolve(&user, &query, &lang, &prefer).await {
+                    Ok(zone) => worldclock::add(&user, zone).map_err(anyhow::Error::from),
+                    Err(e) => Err(e),
+                };
+                ui(&aw, move |app| {
+                    match res {
+                        Ok(()) => {
+                            app.set_world_query("".into());
+                            app.set_clock_status("".into());
+                        }
+                        Err(e) => app.set_clock_status(format!("{e:#}").into()),
+                    }
+                    push_world(&app, &user);
+                });
+            });
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_clock = state.clone();
+        app.on_world_removed(move |label| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_clock);
+            if let Err(e) = worldclock::remove(&user, &label) {
+                app.set_clock_status(format!("Could not remove {label}: {e}").into());
+            }
+            push_world(&app, &user);
+        });
+    }
+
+    // STOCKS: cached quotes at once, stale symbols refetched (Refresh refetches all)
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_stocks = state.clone();
+        app.on_refresh_stocks(move |force| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_stocks);
+            let cfg = load_config_for(&user);
+            let symbols = stocks::watchlist(&cfg);
+            push_stocks(&app, &user, &symbols);
+            push_currency(&app, &user);
+            if offline() {
+                app.set_stocks_status("Offline mode • cached quotes".into());
+                return;
+            }
+
+            // exchange rates: one document, refetched when stale like the quotes
+            let rates_ttl = if force { 0 } else { currency::RATES_TTL_SECS };
+            let (_, rates) = cache::cached_or_refresh(&h, &cache::RatesKey { user: &user }, rates_ttl, currency::fetch_rates);
+            if let Some(rx) = rates {
+                let aw = app_weak.clone();
+                let u = user.clone();
+                h.spawn(async move {
+                    match rx.await {
+                        Ok(Ok((date, rates))) => {
+                            if let Err(e) = cache::save_rates_for(&u, &date, rates) {
+                                eprintln!("Save rates error: {e:?}");
+                            }
+                        }
+                        Ok(Err(e)) => eprintln!("Exchange rates: {e:#}"),
+                        Err(_) => return,
+                    }
+                    ui(&aw, move |app| push_currency(&app, &u));
+                });
+            }
+
+            let ttl = if force { 0 } else { stocks::TTL_MINUTES.number(&cfg) * 60 };
+            let mut pending = Vec::new();
+            for symbol in &symbols {
+                let key = cache::StockKey { user: &user, symbol: symbol.as_str() };
+                let s = symbol.clone();
+                let (_, refresh) = cache::cached_or_refresh(&h, &key, ttl, move || async move { stocks::fetch_history(&s).await });
+                if let Some(rx) = refresh {
+                    pending.push((symbol.clone(), rx));
+                }
+            }
+            if pending.is_empty() {
+                let oldest = symbols.iter().filter_map(|s| cache::load_stock_for(&user, s)).map(|c| c.ts).min();
+                app.set_stocks_status(oldest.map(|ts| format!("Cached • updated {}m ago", age_minutes(ts))).unwrap_or_default().into());
+                return;
+            }
+
+            app.set_stocks_status("Updating…".into());
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let mut failed = Vec::new();
+                for (symbol, rx) in pending {
+                    match rx.await {
+                        Ok(Ok(days)) => {
+                            if let Err(e) = cache::save_stock_for(&user, &symbol, days) {
+                                eprintln!("Save stocks error: {e:?}");
+                            }
+                        }
+                        Ok(Err(e)) => {
+                            eprintln!("Stock quotes for {symbol}: {e:#}");
+                            failed.push(symbol);
+                        }
+                        Err(_) => {}
+                    }
+                }
+                ui(&aw, move |app| {
+                    push_stocks(&app, &user, &symbols);
+                    let status = if failed.is_empty() {
+                        "Updated just now".to_string()
+                    } else {
+                        format!("Couldn't update {}", failed.join(", "))
+                    };
+                    app.set_stocks_status(status.into());
+                });
+            });
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_stocks = state.clone();
+        let change = move |input: &str, add: bool| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let symbol = match stocks::clean_symbol(input) {
+                Ok(s) => s,
+                Err(e) => {
+                    app.set_stocks_status(e.to_string().into());
+                    return;
+                }
+            };
+            let user = current_user(&state_for_stocks);
+            let mut cfg = load_config_for(&user);
+            let list = stocks::with_symbol(&cfg, &symbol, add);
+            stocks::WATCHLIST.set(&mut cfg, serde_json::Value::from(list));
+            if let Err(e) = save_config_for(&user, &cfg) {
+                app.set_stocks_status(format!("Could not save the watchlist: {e}").into());
+                return;
+            }
+            if add {
+                app.set_stock_symbol("".into());
+            }
+            app.invoke_refresh_stocks(false);
+        };
+        let remove = change.clone();
+        app.on_stock_add(move |symbol| change(&symbol, true));
+        app.on_stock_remove(move |symbol| remove(&symbol, false));
+    }
+
+    // CURRENCY (Stocks page): convert from the cached rates, star / pick favorite pairs
+    {
+        let app_weak = app.as_weak();
+        let state_for_currency = state.clone();
+        app.on_convert_currency(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            push_currency(&app, &current_user(&state_for_currency));
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_currency = state.clone();
+        app.on_star_currency_pair(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_currency);
+            let mut cfg = load_config_for(&user);
+            let pairs = currency::toggle_pair(&cfg, &app.get_conv_from(), &app.get_conv_to());
+            currency::FAVORITE_PAIRS.set(&mut cfg, serde_json::Value::from(pairs));
+            if let Err(e) = save_config_for(&user, &cfg) {
+                app.set_rates_note(format!("Could not save the pairs: {e}").into());
+                return;
+            }
+            push_currency(&app, &user);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_currency = state.clone();
+        app.on_pick_currency_pair(move |from, to| {
+            let Some(app) = app_weak.upgrade() else { return };
+            app.set_conv_from(from);
+            app.set_conv_to(to);
+            push_currency(&app, &current_user(&state_for_currency));
+        });
+    }
+
+    // MAIL: sign in (server / username to the settings, password to the keyring),
+    // check the inbox, reply or write in the default mail client
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_mail = state.clone();
+        app.on_refresh_mail(move || refresh_mail(&h, &app_weak, current_user(&state_for_mail)));
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_mail = state.clone();
+        app.on_mail_sign_in(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_mail);
+            let (server, username) = (app.get_mail_server().trim().to_string(), app.get_mail_username().trim().to_string());
+            let password = Zeroizing::new(app.get_mail_password().to_string());
+            if server.is_empty() || username.is_empty() || password.is_empty() {
+                app.set_mail_status("Fill in the server, username and password".into());
+                return;
+            }
+            let mut cfg = load_config_for(&user);
+            mail::SERVER.set(&mut cfg, serde_json::Value::from(server));
+            mail::USERNAME.set(&mut cfg, serde_json::Value::from(username));
+            if let Err(e) = save_config_for(&user, &cfg) {
+                app.set_mail_status(format!("Could not save the account: {e}").into());
+                return;
+            }
+            if let Err(e) = secrets::set_mail_password(&user, &password) {
+                app.set_mail_status(format!("Could not store the password in the keyring: {e}").into());
+                return;
+            }
+            app.set_mail_password("".into());
+            push_mail_account(&app, &user);
+            app.invoke_refresh_mail();
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_mail = state.clone();
+        app.on_mail_sign_out(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_mail);
+            if let Err(e) = secrets::set_mail_password(&user, "") {
+                app.set_mail_status(format!("Could not remove the password: {e}").into());
+                return;
+            }
+            MAIL_FETCHES.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            app.set_mail_items(slint::ModelRc::default());
+            app.set_mail_unread(0);
+            app.set_mail_status("Signed out".into());
+            app.set_mail_signed_in(false);
+            push_mail_account(&app, &user);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        app.on_mail_reply(move |item: MailItem| {
+            let Some(app) = app_weak.upgrade() else { return };
+            match mail::reply_link(&item.address, &item.subject) {
+                Ok(link) => open_url(&h, link),
+                Err(e) => app.set_mail_status(format!("Can't reply: {e}").into()),
+            }
+        });
+    }
+    {
+        let h = handle.clone();
+        app.on_mail_compose(move || open_url(&h, "mailto:".to_string()));
+    }
+
+    // PODCASTS: subscribe to feeds, download and play episodes; where each one
+    // got to is kept per user
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_podcasts = state.clone();
+        app.on_refresh_podcasts(move |force| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_podcasts);
+            push_podcasts(&app, &user);
+            push_player(&app);
+            if offline() {
+                app.set_podcasts_status("Offline mode • downloaded episodes still play".into());
+                return;
+            }
+            let now = chrono::Utc::now().timestamp();
+            let stale: Vec<String> = podcasts::list(&user)
+                .feeds
+                .into_iter()
+                .filter(|f| force || now - f.fetched >= podcasts::FEED_TTL_SECS)
+                .map(|f| f.url)
+                .collect();
+            if stale.is_empty() {
+                return;
+            }
+            app.set_podcasts_status("Updating feeds…".into());
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let mut failed = Vec::new();
+                for url in stale {
+                    match podcasts::fetch_feed(&url).await {
+                        Ok(feed) => {
+                            if let Err(e) = podcasts::refreshed(&user, feed) {
+                                eprintln!("Save podcasts error: {e:?}");
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("Podcast feed {url}: {e:#}");
+                            failed.push(url);
+                        }
+                    }
+                }
+                ui(&aw, move |app| {
+                    push_podcasts(&app, &user);
+                    app.set_podcasts_status(
+                        if failed.is_empty() { String::new() } else { format!("Couldn't update {}", failed.join(", ")) }.into(),
+                    );
+                });
+            });
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_podcasts = state.clone();
+        app.on_podcast_subscribe(move |url| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let url = url.trim().to_string();
+            if url.is_empty() {
+                return;
+            }
+            if offline() {
+                app.set_podcasts_status("Offline mode • can't fetch the feed".into());
+                return;
+            }
+            let user = current_user(&state_for_podcasts);
+            app.set_podcasts_status("Fetching the feed…".into());
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let result = podcasts::fetch_feed(&url).await;
+                ui(&aw, move |app| match result {
+                    Ok(feed) => {
+                        let title = feed.title.clone();
+                        if let Err(e) = podcasts::subscribe(&user, feed) {
+                            app.set_podcasts_status(format!("Could not save the subscription: {e}").into());
+                            return;
+                        }
+                        app.set_podcast_url("".into());
+                        app.set_podcast_feed(url.into());
+                        app.set_podcasts_status(format!("Subscribed to {title}").into());
+                        push_podcasts(&app, &user);
+                    }
+                    Err(e) => app.set_podcasts_status(format!("Can't subscribe: {e:#}").into()),
+                });
+            });
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_podcasts = state.clone();
+        app.on_podcast_unsubscribe(move |url| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_podcasts);
+            let all = podcasts::list(&user);
+            let playing = audio::now_playing().filter(|n| n.user == user).map(|n| n.episode);
+            if let Some(feed) = all.feeds.iter().find(|f| f.url == url.as_str()) {
+                if feed.episodes.iter().any(|e| playing.as_deref() == Some(e.id.as_str())) {
+                    stop_playback();
+                    push_player(&app);
+                }
+            }
+            if let Err(e) = podcasts::unsubscribe(&user, &url) {
+                app.set_podcasts_status(format!("Could not unsubscribe: {e}").into());
+            }
+            push_podcasts(&app, &user);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_podcasts = state.clone();
+        app.on_podcast_selected(move |url| {
+            let Some(app) = app_weak.upgrade() else { return };
+            app.set_podcast_feed(url);
+            push_podcasts(&app, &current_user(&state_for_podcasts));
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_podcasts = state.clone();
+        app.on_episode_play(move |id| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_podcasts);
+            // the episode that's on: pause / resume
+            if audio::now_playing().is_some_and(|n| n.user == user && n.episode == id.as_str()) {
+                if !audio::toggle() {
+                    if let Some(now) = audio::now_playing() {
+                        remember_progress(&now);
+                    }
+                }
+                push_player(&app);
+                push_podcasts(&app, &user);
+                return;
+            }
+            let all = podcasts::list(&user);
+            let Some(episode) = all.episode(&id).cloned() else { return };
+            let progress = all.progress(&episode.id);
+            let from = std::time::Duration::from_secs(if progress.listened { 0 } else { progress.position });
+            stop_playback();
+            push_player(&app);
+            let downloaded = podcasts::downloaded(&user, &episode).is_some();
+            if !downloaded && offline() {
+                app.set_podcasts_status("Offline mode • only downloaded episodes play".into());
+                push_podcasts(&app, &user);
+                return;
+            }
+            // streaming goes through the download folder, so it plays offline next time
+            app.set_podcasts_status(if downloaded { String::new() } else { format!("Downloading {}…", episode.title) }.into());
+            let aw = app_weak.clone();
+            let handle = h.clone();
+            h.spawn(async move {
+                let started = match podcasts::download(&user, &episode).await {
+                    Ok(path) => {
+                        let u = user.clone();
+                        tokio::task::spawn_blocking(move || {
+                            audio::play(&path, from, &u, &episode.id, &episode.title, std::time::Duration::from_secs(episode.duration))
+                        })
+                        .await
+                        .map_err(anyhow::Error::from)
+                        .and_then(|r| r)
+                    }
+                    Err(e) => Err(e),
+                };
+                ui(&aw, move |app| {
+                    match started {
+                        Ok(()) => {
+                            app.set_podcasts_status("".into());
+                            watch_playback(&handle, &app.as_weak());
+                        }
+                        Err(e) => app.set_podcasts_status(format!("Can't play: {e:#}").into()),
+                    }
+                    push_player(&app);
+                    push_podcasts(&app, &user);
+                });
+            });
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_podcasts = state.clone();
+        app.on_episode_download(move |id| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_podcasts);
+            let Some(episode) = podcasts::list(&user).episode(&id).cloned() else { return };
+            if podcasts::downloaded(&user, &episode).is_some() {
+                if audio::now_playing().is_some_and(|n| n.user == user && n.episode == episode.id) {
+                    stop_playback();
+                    push_player(&app);
+                }
+                if let Err(e) = podcasts::delete_download(&user, &episode) {
+                    app.set_podcasts_status(format!("Could not delete the download: {e}").into());
+                }
+                push_podcasts(&app, &user);
+                return;
+            }
+            if offline() {
+                app.set_podcasts_status("Offline mode • can't download".into());
+                return;
+            }
+            app.set_podcasts_status(format!("Downloading {}…", episode.title).into());
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let result = podcasts::download(&user, &episode).await;
+                ui(&aw, move |app| {
+                    app.set_podcasts_status(
+                        match result {
+                            Ok(_) => format!("Downloaded {}", episode.title),
+                            Err(e) => format!("Download failed: {e:#}"),
+                        }
+                        .into(),
+                    );
+                    push_podcasts(&app, &user);
+                });
+            });
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_podcasts = state.clone();
+        app.on_episode_mark(move |id| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_podcasts);
+            if audio::now_playing().is_some_and(|n| n.user == user && n.episode == id.as_str()) {
+                stop_playback();
+                push_player(&app);
+            }
+            // played <-> unplayed; either way it starts over next time
+            let listened = !podcasts::list(&user).progress(&id).listened;
+            if let Err(e) = podcasts::set_progress(&user, &id, podcasts::Progress { position: 0, listened }) {
+                app.set_podcasts_status(format!("Could not save: {e}").into());
+            }
+            push_podcasts(&app, &user);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_podcasts = state.clone();
+        app.on_player_toggle(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            if !audio::toggle() {
+                if let Some(now) = audio::now_playing() {
+                    remember_progress(&now);
+                }
+            }
+            push_player(&app);
+            push_podcasts(&app, &current_user(&state_for_podcasts));
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        app.on_player_seek(move |secs| {
+            let Some(app) = app_weak.upgrade() else { return };
+            audio::seek_by(i64::from(secs));
+            push_player(&app);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_podcasts = state.clone();
+        app.on_player_stop(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            stop_playback();
+            push_player(&app);
+            push_podcasts(&app, &current_user(&state_for_podcasts));
+        });
+    }
+
+    // STOPWATCH (Clock page): measured in Rust, redrawn every frame while it runs
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        app.on_stopwatch_toggle(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            let running = stopwatch::with(|w| {
+                w.toggle();
+                w.running()
+            });
+            app.set_stopwatch_note("".into());
+            push_stopwatch(&app);
+            if !running {
+                return;
+            }
+            let run = STOPWATCH_RUNS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let mut frame = tokio::time::interval(STOPWATCH_FRAME);
+                frame.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+                loop {
+                    frame.tick().await;
+                    if STOPWATCH_RUNS.load(std::sync::atomic::Ordering::SeqCst) != run || !stopwatch::with(|w| w.running()) {
+                        break;
+                    }
+                    let text = stopwatch::with(|w| stopwatch::format(w.elapsed()));
+                    ui(&aw, move |app| app.set_stopwatch_text(text.into()));
+                }
+            });
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        app.on_stopwatch_lap(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            stopwatch::with(|w| w.lap());
+            push_stopwatch(&app);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        app.on_stopwatch_reset(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            stopwatch::with(|w| w.reset());
+            app.set_stopwatch_note("".into());
+            push_stopwatch(&app);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        // keep one clipboard handle alive: on X11 the text is lost when its owner drops
+        let clipboard: std::cell::RefCell<Option<arboard::Clipboard>> = std::cell::RefCell::new(None);
+        app.on_laps_copy(move || {
+            let mut slot = clipboard.borrow_mut();
+            if slot.is_none() {
+                *slot = arboard::Clipboard::new().ok();
+            }
+            let text = stopwatch::with(|w| w.to_text());
+            let res = match slot.as_mut() {
+                Some(cb) => cb.set_text(text),
+                None => Err(arboard::Error::ClipboardNotSupported),
+            };
+            let msg = match res {
+                Ok(()) => "Laps copied".to_string(),
+                Err(e) => format!("Copy failed: {e}"),
+            };
+            if let Some(app) = app_weak.upgrade() {
+                app.set_stopwatch_note(msg.into());
+            }
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        app.on_laps_save(move || {
+            let text = stopwatch::with(|w| w.to_text());
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let Some(file) = rfd::AsyncFileDialog::new()
+                    .set_file_name("laps.txt")
+                    .add_filter("Text", &["txt"])
+                    .save_file()
+                    .await
+                else {
+                    return; // cancelled
+                };
+                let path = file.path().to_path_buf();
+                let msg = match std::fs::write(&path, text) {
+                    Ok(()) => format!("Saved to {}", path.display()),
+                    Err(e) => format!("Save failed: {e}"),
+                };
+                ui(&aw, move |app| app.set_stopwatch_note(msg.into()));
+            });
+        });
+    }
+
+    // Refresh buttons: refetch even if the cache is fresh (news also drops the in-memory copy)
+    {
+        let app_weak = app.as_weak();
+        let state_for_force = state.clone();
+        app.on_force_refresh_weather(move || {
+            if let Ok(mut s) = state_for_force.lock() {
+                s.force_weather_refresh = true;
+            }
+            if let Some(app) = app_weak.upgrade() {
+                app.invoke_refresh_weather();
+            }
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_force = state.clone();
+        app.on_force_refresh_news(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            if let Ok(mut s) = state_for_force.lock() {
+                s.force_news_refresh = true;
+            }
+            let key = news::feed_key(&app.get_news_topic(), news_query(&app));
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                news::invalidate(&key).await;
+                ui(&aw, |app| app.invoke_refresh_news());
+            });
+        });
+    }
+
+    // News tab switched: reuse the in-memory copy for that topic, fetch only on a miss
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_tab = state.clone();
+        app.on_news_tab_selected(move |topic: slint::SharedString| {
+            let Some(app) = app_weak.upgrade() else { return };
+            app.set_news_topic(topic.clone());
+            app.set_news_showing_bookmarks(false);
+            let key = news::feed_key(&topic, news_query(&app));
+            let user = current_user(&state_for_tab);
+            let filter = news_filter(&load_config_for(&user), app.get_is_child());
+            let marks = CardMarks::load(&user);
+            let aw = app_weak.clone();
+            let st = state_for_tab.clone();
+            h.spawn(async move {
+                match news::cached_topic(&key).await {
+                    Some(rows) => ui(&aw, move |app| {
+                        mark_news_seen(&st, rows.iter().map(|a| a.url.as_str()));
+                        let total = rows.len();
+                        let items: Vec<ArticleItem> = rows.into_iter()
+                            .filter(|a| filter.keeps(&a.source, &a.title))
+                            .map(article_item)
+                            .collect();
+                        app.set_news_hidden_count((total - items.len()) as i32);
+                        let items = marks.apply(items);
+                        app.set_news_items(slint::ModelRc::new(slint::VecModel::from(items)));
+                        app.set_news_status("".into());
+                    }),
+                    None => ui(&aw, |app| app.invoke_refresh_news()),
+                }
+            });
+        });
+    }
+
+    // Topic search box: suggestions from history, submit records + refreshes
+    {
+        let app_weak = app.as_weak();
+        let state_for_edit = state.clone();
+        app.on_news_topic_edited(move |query: slint::SharedString| {
+            let cfg = load_config_for(&current_user(&state_for_edit));
+            if let Some(app) = app_weak.upgrade() {
+                set_topic_suggestions(&app, &cfg.topic_suggestions(&query));
+            }
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_submit = state.clone();
+        app.on_news_topic_submitted(move |topic: slint::SharedString| {
+            let topic = topic.trim().to_string();
+            if topic.is_empty() {
+                return;
+            }
+            let user = current_user(&state_for_submit);
+            let mut cfg = load_config_for(&user);
+            cfg.remember_topic(&topic);
+            if let Err(e) = save_config_for(&user, &cfg) {
+                eprintln!("Save config error: {e:?}");
+            }
+            if let Some(app) = app_weak.upgrade() {
+                app.set_news_topic_query("".into());
+                set_topic_suggestions(&app, &cfg.topic_suggestions(""));
+                app.set_news_topic(topic.into());
+                app.invoke_refresh_news();
+            }
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_clear = state.clone();
+        app.on_news_clear_topic_history(move || {
+            let user = current_user(&state_for_clear);
+            let mut cfg = load_config_for(&user);
+            cfg.topic_history.clear();
+            if let Err(e) = save_config_for(&user, &cfg) {
+                eprintln!("Save config error: {e:?}");
+            }
+            if let Some(app) = app_weak.upgrade() {
+                set_topic_suggestions(&app, &[]);
+            }
+        });
+    }
+
+    // News date range changed: persist per user and refetch
+    {
+        let app_weak = app.as_weak();
+        let state_for_range = state.clone();
+        app.on_news_range_changed(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_range);
+            let mut cfg = load_config_for(&user);
+            cfg.news_range = app.get_news_range().to_string();
+            cfg.news_range_from = app.get_news_range_from().trim().to_string();
+            cfg.news_range_to = app.get_news_range_to().trim().to_string();
+            if let Err(e) = save_config_for(&user, &cfg) {
+                eprintln!("Save config error: {e:?}");
+            }
+            app.invoke_refresh_news();
+        });
+    }
+
+    // Offline search over the user's cached stories and bookmarks (no network),
+    // through their news filter; empty query restores the feed
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_search = state.clone();
+        app.on_news_offline_search(move |query: slint::SharedString| {
+            let query = query.trim().to_string();
+            if query.is_empty() {
+                if let Some(app) = app_weak.upgrade() {
+                    app.invoke_refresh_news();
+                }
+                return;
+            }
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_search);
+            let filter = news_filter(&load_config_for(&user), app.get_is_child());
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                // the disk cache first (newer points / comments), then the bookmarks
+                let cached: Vec<news::Article> = load_all_news_for(&user)
+                    .into_iter()
+                    .flat_map(|c| c.rows.into_iter().map(|r| r.into_article(&user)))
+                    .collect();
+                let saved: Vec<news::Article> = load_bookmarks_for(&user).items.iter().map(|b| b.to_article(&user)).collect();
+                let matching: Vec<news::Article> = news::merge_articles(cached, saved)
+                    .into_iter()
+                    .filter(|a| news::matches_query(&a.title, &a.source, &query))
+                    .collect();
+                let total = matching.len();
+                let found: Vec<news::Article> = matching.into_iter().filter(|a| filter.keeps(&a.source, &a.title)).collect();
+                let marks = CardMarks::load(&user);
+
+                ui(&aw, move |app| {
+                    let status = format!("Offline search: {} match(es) for “{}”", found.len(), query);
+                    let hidden = total - found.len();
+                    let mut items: Vec<ArticleItem> = found.into_iter().map(article_item).collect();
+                    marks.flag(&mut items);
+                    app.set_news_items(slint::ModelRc::new(slint::VecModel::from(items)));
+                    app.set_news_hidden_count(hidden as i32);
+                    app.set_news_status(status.into());
+                });
+            });
+        });
+    }
+
+    // News category chip changed: persist per user and refetch
+    {
+        let app_weak = app.as_weak();
+        let state_for_category = state.clone();
+        app.on_news_category_changed(move |label: slint::SharedString| {
+            let user = current_user(&state_for_category);
+            let mut cfg = load_config_for(&user);
+            cfg.news_category = label.to_string();
+            if let Err(e) = save_config_for(&user, &cfg) {
+                eprintln!("Save config error: {e:?}");
+            }
+            if let Some(app) = app_weak.upgrade() {
+                app.invoke_refresh_news();
+            }
+        });
+    }
+
+    // Minimum points changed: persist per user and refetch
+    {
+        let app_weak = app.as_weak();
+        let state_for_points = state.clone();
+        app.on_news_min_points_changed(move |points: i32| {
+            let user = current_user(&state_for_points);
+            let mut cfg = load_config_for(&user);
+            cfg.news_min_points = points.max(0) as u32;
+            if let Err(e) = save_config_for(&user, &cfg) {
+                eprintln!("Save config error: {e:?}");
+            }
+            if let Some(app) = app_weak.upgrade() {
+                app.invoke_refresh_news();
+            }
+        });
+    }
+
+    // News sort changed: persist per user and refetch
+    {
+        let app_weak = app.as_weak();
+        let state_for_sort = state.clone();
+        app.on_news_sort_changed(move |label: slint::SharedString| {
+            let user = current_user(&state_for_sort);
+            let mut cfg = load_config_for(&user);
+            cfg.news_sort = label.to_string();
+            if let Err(e) = save_config_for(&user, &cfg) {
+                eprintln!("Save config error: {e:?}");
+            }
+            if let Some(app) = app_weak.upgrade() {
+                app.invoke_refresh_news();
+            }
+        });
+    }
+
+    // Share: copy the article link to the clipboard
+    {
+        let app_weak = app.as_weak();
+        // keep one clipboard handle alive: on X11 the text is lost when its owner drops
+        let clipboard: std::cell::RefCell<Option<arboard::Clipboard>> = std::cell::RefCell::new(None);
+        app.on_copy_link(move |url: slint::SharedString| {
+            let mut slot = clipboard.borrow_mut();
+            if slot.is_none() {
+                *slot = arboard::Clipboard::new().ok();
+            }
+            let res = match slot.as_mut() {
+                Some(cb) => cb.set_text(url.to_string()),
+                None => Err(arboard::Error::ClipboardNotSupported),
+            };
+            let msg = match res {
+                Ok(()) => "Link copied".to_string(),
+                Err(e) => format!("Copy failed: {e}"),
+            };
+            if let Some(app) = app_weak.upgrade() {
+                app.set_news_status(msg.into());
+            }
+        });
+    }
 
-                match fetched {
-                    // Build cache (text-only) and UI (with icons loaded on the UI thread)
-                    Ok(rows) => {
-                        // Save simplified rows to cache (compatible with old format)
-                        let rows_for_cache: Vec<(String, String, String)> = rows.iter()
-                            .map(|r| {
-                                let summary = format!("{} • {} • {}", r.description, r.real_feel, r.precip);
-                                (r.time.clone(), r.temp.clone(), summary)
-                            })
-                            .collect();
+    // Share: compose an email with the title as subject and the link as body
+    {
+        let h = handle.clone();
+        app.on_share_email(move |title: slint::SharedString, url: slint::SharedString| {
+            let mailto = format!(
+                "mailto:?subject={}&body={}",
+                urlencoding::encode(&title),
+                urlencoding::encode(&url),
+            );
+            open_url(&h, mailto);
+        });
+    }
 
-                        let _ = save_weather_for(
-                            &user_for_save,
-                            &rows_for_cache,
-                            if use_celsius { "C" } else { "F" },
-                            &city,
-                        );
+    // Expand / collapse the related stories of a cluster lead
+    {
+        let app_weak = app.as_weak();
+        app.on_news_toggle_cluster(move |lead_url: slint::SharedString| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let items = app.get_news_items();
+            let Some(i) = items.iter().position(|a| a.url == lead_url) else { return };
+            let Some(mut lead) = items.row_data(i) else { return };
+            lead.expanded = !lead.expanded;
+            let expanded = lead.expanded;
+            items.set_row_data(i, lead);
+            for j in 0..items.row_count() {
+                if let Some(mut a) = items.row_data(j) {
+                    if a.group == lead_url {
+                        a.expanded = expanded;
+                        items.set_row_data(j, a);
+                    }
+                }
+            }
+        });
+    }
 
-                        // Prepare data for UI: download icons -> keep only file paths (Send)
-                        struct GuiRow {
-                            time: String,
-                            temp: String,
-                            summary: String,
-                            icon_path: Option<std::path::PathBuf>,
-                        }
+    // Top-comments preview: expand loads the first few comments under the card
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        app.on_news_toggle_comments(move |item: ArticleItem| {
+            let Some(app) = app_weak.upgrade() else { return };
+            let items = app.get_news_items();
+            let Some(i) = items.iter().position(|a| a.url == item.url) else { return };
+            let Some(mut row) = items.row_data(i) else { return };
+            row.comments_open = !row.comments_open;
+            let needs_fetch = row.comments_open && row.top_comments.row_count() == 0;
+            if needs_fetch {
+                row.comments_note = "Loading comments…".into();
+            }
+            items.set_row_data(i, row);
+            if !needs_fetch {
+                return;
+            }
 
-                        let mut gui_rows: Vec<GuiRow> = Vec::with_capacity(rows.len());
-                        for r in rows {
-                            let icon_path = cache_icon_to_path(&r.icon_url).await;  // async download/cache
-                            let summary = format!("{} • {} • {}", r.description, r.real_feel, r.precip);
-                            gui_rows.push(GuiRow {
-                                time: r.time,
-                                temp: r.temp,
-                                summary,
-                                icon_path,
-                            });
+            let aw = app_weak.clone();
+            let url = item.url.clone();
+            let object_id = item.object_id.to_string();
+            h.spawn(async move {
+                let res = news::fetch_top_comments(&object_id, 3).await;
+                ui(&aw, move |app| {
+                    let items = app.get_news_items();
+                    let Some(i) = items.iter().position(|a| a.url == url) else { return };
+                    let Some(mut row) = items.row_data(i) else { return };
+                    match res {
+                        Ok(list) => {
+                            row.comments_note = if list.is_empty() { "No comments yet".into() } else { "".into() };
+                            let list: Vec<CommentItem> = list.into_iter()
+                                .map(|c| CommentItem { author: c.author.into(), text: c.text.into() })
+                                .collect();
+                            row.top_comments = slint::ModelRc::new(slint::VecModel::from(list));
                         }
+                        Err(e) => row.comments_note = format!("Failed to load comments: {e}").into(),
+                    }
+                    items.set_row_data(i, row);
+                });
+            });
+        });
+    }
 
-                        // Hop to UI thread: construct slint::Image here (not across threads)
-                        ui(&aw, move |app| {
-                            let items: Vec<WeatherItem> = gui_rows
-                                .into_iter()
-                                .map(|g| {
-                                    let img = g.icon_path
-                                        .as_ref()
-                                        .and_then(|p| slint::Image::load_from_path(p.as_path()).ok())
-                                        .unwrap_or_default();
+    // Bookmark toggle: add/remove the story; optionally store an offline reader copy
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_mark = state.clone();
+        app.on_toggle_bookmark(move |item: ArticleItem| {
+            let user = current_user(&state_for_mark);
+            let url = item.url.to_string();
+            let mut store = load_bookmarks_for(&user);
+            let bookmarked = if store.remove(&url) {
+                remove_offline_for(&user, &url);
+                false
+            } else {
+                store.add(Bookmark {
+                    title: item.title.to_string(),
+                    source: item.source.to_string(),
+                    published: item.stamp.to_string(),
+                    url: url.clone(),
+                    object_id: item.object_id.to_string(),
+                    saved_at: chrono::Utc::now().timestamp(),
+                    offline: false,
+                    paywalled: item.paywalled,
+                });
+                true
+            };
+            if let Err(e) = save_bookmarks_for(&user, &store) {
+                eprintln!("Save bookmarks error: {e:?}");
+            }
 
-                                    WeatherItem {
-                                        time: g.time.into(),
-                                        temp: g.temp.into(),
-                                        summary: g.summary.into(),
-                                        icon: img,
-                                    }
-                                })
-                                .collect();
+            let Some(app) = app_weak.upgrade() else { return };
+            let items = app.get_news_items();
+            if app.get_news_showing_bookmarks() && !bookmarked {
+                // un-bookmarking from the bookmarks view drops the card
+                let rest: Vec<ArticleItem> = items.iter().filter(|a| a.url != item.url).collect();
+                app.set_news_items(slint::ModelRc::new(slint::VecModel::from(rest)));
+            } else if let Some(i) = items.iter().position(|a| a.url == item.url) {
+                let mut row = item.clone();
+                row.bookmarked = bookmarked;
+                items.set_row_data(i, row);
+            }
 
-                            let model = slint::VecModel::from(items);
-                            app.set_weather_items(slint::ModelRc::new(model));
-                            app.set_weather_status(
-                                format!("Updated ({})", if use_celsius { "°C" } else { "°F" }).into()
-                            );
-                        });
-                    }
+            if bookmarked && load_config_for(&user).offline_bookmarks {
+                app.set_news_status("Saving offline copy…".into());
+                let aw = app_weak.clone();
+                h.spawn(async move {
+                    let msg = match reader::download_offline(&user, &url).await {
+                        Ok(_) => {
+                            let mut store = load_bookmarks_for(&user);
+                            if let Some(b) = store.items.iter_mut().find(|b| b.url == url) {
+                                b.offline = true;
+                            }
+                            if let Err(e) = save_bookmarks_for(&user, &store) {
+                                eprintln!("Save bookmarks error: {e:?}");
+                            }
+                            "Saved for offline reading".to_string()
+                        }
+                        Err(e) => format!("Offline copy failed: {e}"),
+                    };
+                    ui(&aw, move |app| app.set_news_status(msg.into()));
+                });
+            }
+        });
+    }
+
+    // Bookmarks view: list the saved stories (newest first)
+    {
+        let app_weak = app.as_weak();
+        let state_for_list = state.clone();
+        app.on_show_bookmarks(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_list);
+            let marks = CardMarks::load(&user);
+            let mut items: Vec<ArticleItem> = marks.bookmarks.items.iter()
+                .map(|b| article_item(b.to_article(&user)))
+                .collect();
+            marks.flag(&mut items);
+            app.set_news_status(format!("{} bookmark(s)", items.len()).into());
+            app.set_news_hidden_count(0);
+            app.set_news_items(slint::ModelRc::new(slint::VecModel::from(items)));
+            app.set_news_showing_bookmarks(true);
+        });
+    }
 
+    // Reader view: offline copy first, otherwise extract the page live
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_reader = state.clone();
+        app.on_open_reader(move |item: ArticleItem| {
+            let Some(app) = app_weak.upgrade() else { return };
+            app.set_reader_title(item.title.clone());
+            app.set_reader_source(item.source.clone());
+            app.set_reader_url(item.url.clone());
+            app.set_reader_paragraphs(slint::ModelRc::new(slint::VecModel::from(Vec::<slint::SharedString>::new())));
+            app.set_reader_images(slint::ModelRc::new(slint::VecModel::from(Vec::<Image>::new())));
+            app.invoke_reader_speech_stop();   // don't keep reading the previous story
+            app.set_reader_status("Loading…".into());
+            app.set_reader_open(true);
 
-                    // Error handling
-                    Err(err) => {
+            let user = current_user(&state_for_reader);
+            mark_read(&app, &user, &item.url);
+            let known_paywall = item.paywalled
+                || news::is_paywalled_host(&item.source, &load_config_for(&user).paywalled_sources);
+            let summary: Vec<slint::SharedString> = load_summary_for(&user, &item.url)
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect();
+            app.set_reader_summary(slint::ModelRc::new(slint::VecModel::from(summary)));
+            let url = item.url.to_string();
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let (doc, status) = match load_offline_for(&user, &url) {
+                    Some(doc) => (Ok(doc), "Offline copy".to_string()),
+                    None => (reader::fetch_article(&url).await, String::new()),
+                };
+                match doc {
+                    Ok(doc) => {
+                        let images = reader::load_images(&user, &doc).await;
                         ui(&aw, move |app| {
-                            let s = app.get_weather_status().to_string();
-                            if s.starts_with("Cached") {
-                                app.set_weather_status(format!("Offline • {}", s).into());
+                            // the user may have opened another story meanwhile
+                            if app.get_reader_url() != doc.url.as_str() {
+                                return;
+                            }
+                            let status = if doc.paywalled || known_paywall {
+                                flag_paywalled(&app, &doc.url);
+                                let lock = "🔒 Paywalled, text may be incomplete";
+                                if status.is_empty() { lock.to_string() } else { format!("{lock} • {status}") }
                             } else {
-                                app.set_weather_status(format!("Failed to load: {}", err).into());
+                                status
+                            };
+                            if !doc.title.is_empty() {
+                                app.set_reader_title(doc.title.into());
                             }
+                            let paras: Vec<slint::SharedString> = doc.paragraphs.into_iter().map(Into::into).collect();
+                            let imgs: Vec<Image> = images.into_iter().map(Image::from_rgba8).collect();
+                            app.set_reader_paragraphs(slint::ModelRc::new(slint::VecModel::from(paras)));
+                            app.set_reader_images(slint::ModelRc::new(slint::VecModel::from(imgs)));
+                            app.set_reader_status(status.into());
                         });
                     }
+                    Err(e) => ui(&aw, move |app| {
+                        if app.get_reader_url() == url.as_str() {
+                            app.set_reader_status(format!("Failed to load: {e}").into());
+                        }
+                    }),
                 }
             });
         });
     }
 
-
-    // NEWS
+    // Reader view: read the article aloud (play / pause / stop)
     {
         let app_weak = app.as_weak();
-        let h = handle.clone();
-        let state_for_news = state.clone();
+        let speaker = speech::Speaker::spawn(move |state| {
+            ui(&app_weak, move |app| app.set_reader_speech(state.label().into()));
+        });
 
-        app.on_refresh_news(move || {
-            let user = current_user(&state_for_news);
+        let app_weak = app.as_weak();
+        let sp = speaker.clone();
+        app.on_reader_speech_play(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            if app.get_reader_speech() == "paused" {
+                sp.resume();
+                return;
+            }
+            let text: Vec<String> = std::iter::once(app.get_reader_title().to_string())
+                .chain(app.get_reader_paragraphs().iter().map(|p| p.to_string()))
+                .collect();
+            sp.play(text);
+        });
 
-            let topic = if let Some(app) = app_weak.upgrade() {
-                app.set_news_status("Loading…".into());
-                app.get_news_topic().to_string()
-            } else {
-                "Top Stories".to_string()
-            };
+        let sp = speaker.clone();
+        app.on_reader_speech_pause(move || sp.pause());
 
-            // Try per-user cache first (was: load_news())
-            if let Some(c) = load_news_for(&user) {
-                if is_fresh(c.ts, 15 * 60) {
-                    if let Some(app) = app_weak.upgrade() {
-                      //  let path = Path::new("assets/no_image.png");
-                        let items: Vec<ArticleItem> = c.rows.into_iter()
-                            .map(|r| ArticleItem {
-                                title: r.title.into(),
-                                source: r.source.into(),
-                                published: r.published.into(),
-                                url: r.url.into(),
-                                thumbnail: Image::from_rgba8(SharedPixelBuffer::new(10, 10)),
-                            })
-                            .collect();
-                        let model = slint::VecModel::from(items);
-                        app.set_news_items(slint::ModelRc::new(model));
-                        app.set_news_status(format!("Cached • updated {}m ago", age_minutes(c.ts)).into());
-                    }
-                }
-            }
+        let sp = speaker.clone();
+        app.on_reader_speech_stop(move || sp.stop());
+    }
 
-            // Network fetch + per-user save
+    // Reader view: 3-bullet summary from the configured endpoint (cached per URL)
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_summary = state.clone();
+        app.on_summarize_reader(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_summary);
+            let url = app.get_reader_url().to_string();
+            let text = app.get_reader_paragraphs().iter().map(|p| p.to_string()).collect::<Vec<_>>().join("\n\n");
+            if text.is_empty() {
+                return;
+            }
+            let cfg = load_config_for(&user);
+            let api_key = secrets::api_key(&user, ApiService::Summarizer).unwrap_or_default();
+            app.set_reader_status("Summarizing…".into());
             let aw = app_weak.clone();
-            let user_for_save = user.clone();
             h.spawn(async move {
-                match news::fetch_news(&topic, 8).await {
-                    Ok(rows) => {
-                        let _ = save_news_for(&user_for_save, &rows); // <-- per-user save
-                        ui(&aw, move |app| {
-                            let items: Vec<ArticleItem> = rows.into_iter()
-                                .map(|(title, source, published, url, thumbnail)| ArticleItem {
-                                    title: title.into(),
-                                    source: source.into(),
-                                    published: published.into(),
-                                    url: url.into(),
-                                    thumbnail: Image::from_rgba8(thumbnail),
-                                })
-                                .collect();
-                            let model = slint::VecModel::from(items);
-                            app.set_news_items(slint::ModelRc::new(model));
-                            app.set_news_status("".into());
-                        });
-                    }
-                    Err(err) => {
-                        ui(&aw, move |app| {
-                            let s = app.get_news_status().to_string();
-                            if s.starts_with("Cached") {
-                                app.set_news_status(format!("Offline • {}", s).into());
-                            } else {
-                                app.set_news_status(format!("Failed to load: {:?}", err).into());
-                            }
-                        });
+                let res = summarize::summarize(&cfg.summary_endpoint, &api_key, &cfg.summary_model, &text).await;
+                if let Ok(bullets) = &res {
+                    if let Err(e) = save_summary_for(&user, &url, bullets) {
+                        eprintln!("Save summary error: {e:?}");
                     }
                 }
+                ui(&aw, move |app| {
+                    if app.get_reader_url() != url.as_str() {
+                        return;
+                    }
+                    match res {
+                        Ok(bullets) => {
+                            let bullets: Vec<slint::SharedString> = bullets.into_iter().map(Into::into).collect();
+                            app.set_reader_summary(slint::ModelRc::new(slint::VecModel::from(bullets)));
+                            app.set_reader_status("".into());
+                        }
+                        Err(e) => app.set_reader_status(format!("Summary failed: {e}").into()),
+                    }
+                });
             });
         });
     }
 
-    // Open a news link in the default browser
+    // "Hide read" toggle: persist per user and redraw the current list
+    {
+        let app_weak = app.as_weak();
+        let state_for_hide_read = state.clone();
+        app.on_news_hide_read_changed(move |hide: bool| {
+            let user = current_user(&state_for_hide_read);
+            let mut cfg = load_config_for(&user);
+            cfg.hide_read = hide;
+            if let Err(e) = save_config_for(&user, &cfg) {
+                eprintln!("Save config error: {e:?}");
+            }
+            if let Some(app) = app_weak.upgrade() {
+                if !app.get_news_showing_bookmarks() {
+                    app.invoke_news_tab_selected(app.get_news_topic());
+                }
+            }
+        });
+    }
+
+    // "Mark all as read": every story in the current list
+    {
+        let app_weak = app.as_weak();
+        let state_for_all_read = state.clone();
+        app.on_news_mark_all_read(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_all_read);
+            let mut marks = CardMarks::load(&user);
+            let items: Vec<ArticleItem> = app.get_news_items().iter().collect();
+            for a in &items {
+                marks.read.insert(&a.url);
+            }
+            if let Err(e) = save_read_for(&user, &marks.read) {
+                eprintln!("Save read list error: {e:?}");
+            }
+            let items = if app.get_news_showing_bookmarks() {
+                let mut items = items;
+                marks.flag(&mut items);
+                items
+            } else {
+                marks.apply(items)
+            };
+            app.set_news_items(slint::ModelRc::new(slint::VecModel::from(items)));
+        });
+    }
 
+    // Export the current list (or every bookmark) to CSV / JSON via a save dialog
     {
+        let app_weak = app.as_weak();
         let h = handle.clone();
-        app.on_open_news(move |url: slint::SharedString| {
-            let url = url.to_string();
-            let h2 = h.clone();
-            // run off the UI thread; opening can block a bit
-            h2.spawn(async move {
-                let _ = tokio::task::spawn_blocking(move || {
-                    let _ = open::that(url);
-                }).await;
+        let state_for_export = state.clone();
+        app.on_export_news(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_export);
+            let bookmarks = app.get_news_showing_bookmarks();
+            let rows: Vec<export::ExportRow> = if bookmarks {
+                let read = load_read_for(&user);
+                load_bookmarks_for(&user).items.into_iter().map(|b| export::ExportRow {
+                    read: read.contains(&b.url),
+                    title: b.title,
+                    source: b.source,
+                    url: b.url,
+                    published: b.published,
+                }).collect()
+            } else {
+                app.get_news_items().iter().map(|a| export::ExportRow {
+                    title: a.title.to_string(),
+                    source: a.source.to_string(),
+                    url: a.url.to_string(),
+                    published: a.stamp.to_string(),
+                    read: a.read,
+                }).collect()
+            };
+            if rows.is_empty() {
+                app.set_news_status("Nothing to export".into());
+                return;
+            }
+
+            let name = if bookmarks { "bookmarks.csv" } else { "news.csv" };
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let Some(file) = rfd::AsyncFileDialog::new()
+                    .set_file_name(name)
+                    .add_filter("CSV", &["csv"])
+                    .add_filter("JSON", &["json"])
+                    .save_file()
+                    .await
+                else {
+                    return; // cancelled
+                };
+                let path = file.path().to_path_buf();
+                let msg = match export::write_export(&path, &rows) {
+                    Ok(()) => format!("Exported {} stories to {}", rows.len(), path.display()),
+                    Err(e) => format!("Export failed: {e}"),
+                };
+                ui(&aw, move |app| app.set_news_status(msg.into()));
             });
         });
     }
 
+    // Hide a news source: append its host to the user's blocklist
+    {
+        let app_weak = app.as_weak();
+        let state_for_hide = state.clone();
+        app.on_hide_source(move |source: slint::SharedString| {
+            let host = news::normalize_host(&source);
+            if host.is_empty() {
+                return;
+            }
+            let user = current_user(&state_for_hide);
+            let mut cfg = load_config_for(&user);
+            if !cfg.blocked_sources.contains(&host) {
+                cfg.blocked_sources.push(host);
+                if let Err(e) = save_config_for(&user, &cfg) {
+                    eprintln!("Save config error: {e:?}");
+                }
+            }
+
+            // drop the now-hidden cards without refetching
+            if let Some(app) = app_weak.upgrade() {
+                let filter = news_filter(&cfg, app.get_is_child());
+                let items: Vec<ArticleItem> = app.get_news_items()
+                    .iter()
+                    .filter(|a| filter.allows_source(&a.source))
+                    .collect();
+                let items = cluster_items(items);
+                app.set_news_items(slint::ModelRc::new(slint::VecModel::from(items)));
+                app.set_news_blocked_sources(cfg.blocked_sources.join(", ").into());
+            }
+        });
+    }
+
 // Handle save from settings
 
     {
@@ -655,19 +4863,206 @@ fn main() -> Result<(), slint::PlatformError> {
         let state_for_save = state.clone();
         app.on_save_settings(move || {
             if let Some(app) = app_weak.upgrade() {
-                let cfg = AppConfig {
-                    city: app.get_weather_city().to_string(),
-                    news_topic: app.get_news_topic().to_string(),
-                    units_celsius: app.get_use_celsius(),
-                };
                 let user = current_user(&state_for_save);          // <-- get active user
+                // start from the stored config so fields without a UI control survive
+                let mut cfg = load_config_for(&user);
+                cfg.city = app.get_weather_city().to_string();
+                cfg.news_topic = app.get_news_topic().to_string();
+                cfg.units_celsius = app.get_use_celsius();
+                cfg.theme = app.get_theme().to_string();
+                cfg.start_page = app.get_start_page().to_string();
+                cfg.time_format = app.get_time_format().to_string();
+                cfg.date_format = app.get_date_format().to_string();
+                cfg.accent_color = app.get_accent_color().trim().to_string();
+                cfg.pinned_topics = split_list(&app.get_news_pinned_topics());
+                cfg.blocked_sources = split_list(&app.get_news_blocked_sources());
+                cfg.muted_keywords = split_list(&app.get_news_muted_keywords());
+                cfg.watch_keywords = split_list(&app.get_news_watch_keywords());
+                cfg.languages = split_list(&app.get_news_languages());
+                cfg.paywalled_sources = split_list(&app.get_news_paywalled_sources());
+                cfg.followed_authors = split_list(&app.get_news_followed_authors())
+                    .into_iter()
+                    .map(|a| a.trim_start_matches('@').to_string())
+                    .collect();
+                cfg.notify_followed = app.get_news_notify_followed();
+                cfg.news_poll_minutes = app.get_news_poll_minutes().max(0) as u32;
+                cfg.weather_refresh_minutes = app.get_weather_refresh_minutes().max(0) as u32;
+                cfg.news_refresh_minutes = app.get_news_refresh_minutes().max(0) as u32;
+                cfg.weather_ttl_minutes = app.get_weather_ttl_minutes().max(0) as u32;
+                cfg.news_ttl_minutes = app.get_news_ttl_minutes().max(0) as u32;
+                cfg.cache_limit_mb = app.get_cache_limit_mb().max(0) as u32;
+                cfg.cache_dir = app.get_cache_dir().trim().to_string();
+                cfg.lock_after_minutes = app.get_lock_after_minutes().max(0) as u32;
+                cfg.fast_switch = app.get_fast_switch();
+                cfg.offline_bookmarks = app.get_news_offline_bookmarks();
+                cfg.summary_endpoint = app.get_summary_endpoint().trim().to_string();
+                cfg.summary_model = app.get_summary_model().trim().to_string();
+                for row in app.get_page_settings().iter() {
+                    let Some(d) = settings::find(&row.section, &row.key) else { continue };
+                    if let Some(v) = d.parse(&row.value) {
+                        d.set(&mut cfg, v);
+                    }
+                }
+
+                // refuse to save bad values; say why next to each field
+                let errors = config::validate(&cfg);
+                app.set_settings_errors(settings_errors(&errors));
+                if !errors.is_empty() {
+                    app.set_pin_status("Settings not saved: check the highlighted fields".into());
+                    return;
+                }
+                cfg.accent_color = theme::normalize_accent(&cfg.accent_color);
+                // install-wide, so only the admin may move it; goes to the machine
+                // config before the user layer is diffed against it
+                let cache_dir_changed = cfg.cache_dir != load_config().cache_dir;
+                if cache_dir_changed {
+                    let refused = if !app.get_is_admin() {
+                        Some("Only an admin can change the cache folder".to_string())
+                    } else {
+                        config::save_cache_dir(&cfg.cache_dir).err().map(|e| format!("Not usable: {e}"))
+                    };
+                    if let Some(message) = refused {
+                        let errors = [config::FieldError { field: config::SettingsField::CacheDir, message }];
+                        app.set_settings_errors(settings_errors(&errors));
+                        app.set_pin_status("Settings not saved: check the highlighted fields".into());
+                        return;
+                    }
+                }
                 if let Err(e) = save_config_for(&user, &cfg) {
                     eprintln!("Save config error: {e:?}");
                 }
+                apply_config(&app, &cfg);   // re-push normalised lists (tabs, filters)
+                cache::enforce_size_limit(&user);   // a lower limit applies right away
                 app.invoke_refresh_weather();
                 app.invoke_refresh_news();
+                if cache_dir_changed {
+                    app.set_pin_status("Cache folder saved; it's used from the next start".into());
+                }
+            }
+        });
+    }
+
+    // CALENDAR: import .ics files, sync with a CalDAV calendar
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_calendar = state.clone();
+        app.on_sync_calendar(move || sync_calendar(&h, &app_weak, current_user(&state_for_calendar)));
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_calendar = state.clone();
+        app.on_calendar_sign_in(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_calendar);
+            let (url, username) = (app.get_calendar_url().trim().to_string(), app.get_calendar_username().trim().to_string());
+            let password = Zeroizing::new(app.get_calendar_password().to_string());
+            if url.is_empty() || username.is_empty() || password.is_empty() {
+                app.set_calendar_status("Fill in the calendar URL, username and password".into());
+                return;
             }
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                app.set_calendar_status("The calendar URL starts with https://".into());
+                return;
+            }
+            let mut cfg = load_config_for(&user);
+            calendar::URL.set(&mut cfg, serde_json::Value::from(url));
+            calendar::USERNAME.set(&mut cfg, serde_json::Value::from(username));
+            if let Err(e) = save_config_for(&user, &cfg) {
+                app.set_calendar_status(format!("Could not save the account: {e}").into());
+                return;
+            }
+            if let Err(e) = secrets::set_caldav_password(&user, &password) {
+                app.set_calendar_status(format!("Could not store the password in the keyring: {e}").into());
+                return;
+            }
+            app.set_calendar_password("".into());
+            push_calendar_account(&app, &user);
+            app.invoke_sync_calendar();
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let state_for_calendar = state.clone();
+        app.on_calendar_sign_out(move || {
+            let Some(app) = app_weak.upgrade() else { return };
+            let user = current_user(&state_for_calendar);
+            if let Err(e) = secrets::set_caldav_password(&user, "") {
+                app.set_calendar_status(format!("Could not remove the password: {e}").into());
+                return;
+            }
+            if let Err(e) = calendar::clear_server(&user) {
+                eprintln!("Calendar for {user}: {e}");
+            }
+            app.set_calendar_status("Signed out".into());
+            app.set_calendar_signed_in(false);
+            push_calendar_account(&app, &user);
+            push_calendar(&app, &user);
+        });
+    }
+    {
+        let app_weak = app.as_weak();
+        let h = handle.clone();
+        let state_for_calendar = state.clone();
+        app.on_calendar_import(move || {
+            let user = current_user(&state_for_calendar);
+            let aw = app_weak.clone();
+            h.spawn(async move {
+                let Some(file) = rfd::AsyncFileDialog::new()
+                    .add_filter("Calendar", &["ics"])
+                    .pick_file()
+                    .await
+                else {
+                    return; // cancelled
+                };
+                let name = file.file_name();
+                let result = std::fs::read_to_string(file.path())
+                    .map_err(anyhow::Error::from)
+                    .and_then(|ics| calendar::import(&user, &ics));
+                ui(&aw, move |app| {
+                    let status = match result {
+                        Ok(n) => format!("Imported {n} events from {name}"),
+                        Err(e) => format!("Import failed: {e:#}"),
+                    };
+                    if app.get_current_user() == user.as_str() {
+                        push_calendar(&app, &user);
+                    }
+                    app.set_calendar_status(status.into());
+                });
+            });
+        });
+    }
+
+    // Window geometry (install-wide): restore the last placement, save it on close
+    if let Some(g) = config::load_window_geometry() {
+        let window = app.window();
+        window.set_size(slint::PhysicalSize::new(g.width, g.height));
+        window.set_position(slint::PhysicalPosition::new(g.x, g.y));
+        window.set_maximized(g.maximized);
+    }
+    {
+        let app_weak = app.as_weak();
+        app.window().on_close_requested(move || {
+            if let Some(app) = app_weak.upgrade() {
+                let window = app.window();
+                let maximized = window.is_maximized();
+                let saved = config::load_window_geometry();
+                // minimized windows report bogus positions; keep the last good one
+                let geometry = if maximized || window.is_minimized() {
+                    saved.map(|g| config::WindowGeometry { maximized, ..g })
+                } else {
+                    let (pos, size) = (window.position(), window.size());
+                    Some(config::WindowGeometry { x: pos.x, y: pos.y, width: size.width, height: size.height, maximized })
+                };
+                if let Some(g) = geometry {
+                    if let Err(e) = config::save_window_geometry(&g) {
+                        eprintln!("Save window geometry error: {e:?}");
+                    }
+                }
+            }
+            slint::CloseRequestResponse::HideWindow
         });
     }
+
     app.run()
 }
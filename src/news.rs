@@ -1,11 +1,11 @@
-    use serde::Deserialize;
+    use serde::{Deserialize, Serialize};
     use reqwest::{Client, Url};
     use scraper::{Html, Selector};
     use slint::{Rgba8Pixel, SharedPixelBuffer};
-    use std::collections::HashMap;
-    use tokio::sync::Mutex;
+    use std::collections::{HashMap, HashSet, VecDeque};
+    use tokio::sync::{Mutex, Semaphore};
     use lazy_static::lazy_static;
-    use futures::stream::{FuturesUnordered, StreamExt};
+    use futures::stream::{FuturesOrdered, StreamExt};
 
 
     #[derive(Debug)]
@@ -14,7 +14,29 @@
         Json(serde_json::Error),
     }
 
-    use std::{fmt, time::Duration};
+    use std::{fmt, path::PathBuf, sync::atomic::{AtomicU64, Ordering}, time::{Duration, Instant}};
+    use crate::settings::{DefaultValue, Descriptor, Kind};
+
+    /// Stories fetched per tab / feed.
+    pub const PAGE_SIZE: Descriptor = Descriptor {
+        section: "news",
+        key: "page_size",
+        label: "Stories",
+        kind: Kind::Number { min: 4, max: 20 },
+        default: DefaultValue::Number(8),
+    };
+
+    /// Open story links in the reader view instead of the browser.
+    pub const OPEN_IN_READER: Descriptor = Descriptor {
+        section: "news",
+        key: "open_in_reader",
+        label: "Reader first",
+        kind: Kind::Toggle,
+        default: DefaultValue::Toggle(false),
+    };
+
+    /// News settings for the registry in `settings.rs`.
+    pub const SETTINGS: &[Descriptor] = &[PAGE_SIZE, OPEN_IN_READER];
 
     impl fmt::Display for NewsFetchError {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -42,16 +64,117 @@
         fn from(e: serde_json::Error) -> Self { Self::Json(e) }
     }
 
-    #[derive(Deserialize)]
+    #[derive(Deserialize, Clone)]
     struct SearchResponse {
         hits: Vec<Hit>,
     }
-    #[derive(Deserialize)]
+    #[derive(Deserialize, Clone)]
     struct Hit {
         title: Option<String>,
         url: Option<String>,
         created_at: Option<String>,
-        object_id: Option<String>,
+        #[serde(rename = "objectID")] object_id: Option<String>,
+        #[serde(default)] created_at_i: Option<i64>,
+        #[serde(default)] points: Option<i64>,
+        #[serde(default)] num_comments: Option<i64>,
+        #[serde(default)] author: Option<String>,
+    }
+
+    /// How stories are ordered after fetching.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum NewsSort {
+        #[default]
+        Relevance,
+        Newest,
+        Points,
+        Comments,
+    }
+
+    impl NewsSort {
+        pub const LABELS: [&'static str; 4] = ["Relevance", "Newest", "Most points", "Most comments"];
+
+        /// Parse a UI/config label; unknown labels fall back to `Relevance`.
+        pub fn from_label(label: &str) -> Self {
+            match label {
+                "Newest" => NewsSort::Newest,
+                "Most points" => NewsSort::Points,
+                "Most comments" => NewsSort::Comments,
+                _ => NewsSort::Relevance,
+            }
+        }
+
+        fn apply(self, hits: &mut [Hit]) {
+            match self {
+                NewsSort::Relevance => {}
+                NewsSort::Newest => hits.sort_by_key(|h| std::cmp::Reverse(h.created_at_i.unwrap_or(0))),
+                NewsSort::Points => hits.sort_by_key(|h| std::cmp::Reverse(h.points.unwrap_or(0))),
+                NewsSort::Comments => hits.sort_by_key(|h| std::cmp::Reverse(h.num_comments.unwrap_or(0))),
+            }
+        }
+    }
+
+    /// HN item type, sent to Algolia as the `tags=` filter.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum NewsCategory {
+        #[default]
+        All,
+        Ask,
+        Show,
+        Jobs,
+        Polls,
+    }
+
+    impl NewsCategory {
+        pub const LABELS: [&'static str; 5] = ["All", "Ask HN", "Show HN", "Jobs", "Polls"];
+
+        /// Parse a UI/config label; unknown labels fall back to `All`.
+        pub fn from_label(label: &str) -> Self {
+            match label {
+                "Ask HN" => NewsCategory::Ask,
+                "Show HN" => NewsCategory::Show,
+                "Jobs" => NewsCategory::Jobs,
+                "Polls" => NewsCategory::Polls,
+                _ => NewsCategory::All,
+            }
+        }
+
+        fn tag(self) -> Option<&'static str> {
+            match self {
+                NewsCategory::All => None,
+                NewsCategory::Ask => Some("ask_hn"),
+                NewsCategory::Show => Some("show_hn"),
+                NewsCategory::Jobs => Some("job"),
+                NewsCategory::Polls => Some("poll"),
+            }
+        }
+    }
+
+    /// Everything besides the topic that shapes a search.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct NewsQuery {
+        pub sort: NewsSort,
+        pub range: DateRange,
+        pub category: NewsCategory,
+        /// Only stories with more than this many points (0 = no threshold).
+        pub min_points: u32,
+    }
+
+    /// Cache key for `topic` under `query`: each category and score threshold
+    /// gets its own entry.
+    pub fn feed_key(topic: &str, query: NewsQuery) -> String {
+        let mut key = topic_key(topic);
+        if let Some(tag) = query.category.tag() {
+            key.push_str(&format!(" [{tag}]"));
+        }
+        if query.min_points > 0 {
+            key.push_str(&format!(" [>{}]", query.min_points));
+        }
+        key
+    }
+
+    /// Topic part of a `feed_key`.
+    fn topic_of_key(key: &str) -> &str {
+        key.split(" [").next().unwrap_or(key)
     }
 
     fn host_from_url(url: &str) -> String {
@@ -60,51 +183,619 @@
         s.split('/').next().unwrap_or("").to_string()
     }
 
+    /// Lowercase host without a leading `www.`; accepts bare hosts or full URLs.
+    pub fn normalize_host(s: &str) -> String {
+        let host = host_from_url(s.trim()).to_lowercase();
+        host.strip_prefix("www.").unwrap_or(&host).to_string()
+    }
+
+    /// Case-insensitive phrase match on word boundaries ("rust" doesn't match "trust").
+    pub fn contains_phrase(text: &str, phrase: &str) -> bool {
+        let text = text.to_lowercase();
+        let phrase = phrase.trim().to_lowercase();
+        if phrase.is_empty() {
+            return false;
+        }
+        text.match_indices(&phrase).any(|(i, _)| {
+            let before = text[..i].chars().next_back();
+            let after = text[i + phrase.len()..].chars().next();
+            !before.is_some_and(|c| c.is_alphanumeric()) && !after.is_some_and(|c| c.is_alphanumeric())
+        })
+    }
+
+    /// Per-user news filter: source block/allow lists (entries match the host
+    /// itself and any subdomain), muted title keywords and wanted title languages.
+    #[derive(Clone, Debug, Default)]
+    pub struct NewsFilter {
+        pub blocked: Vec<String>,
+        pub allowed: Vec<String>,
+        pub muted: Vec<String>,
+        /// Language names or ISO 639-3 codes ("English", "deu"); empty = any.
+        pub languages: Vec<String>,
+        /// Child profiles: also drop titles with a `SAFE_BLOCKLIST` term.
+        pub safe: bool,
+    }
+
+    /// Terms that keep a story off a child profile's feed (whole-word, any case).
+    pub const SAFE_BLOCKLIST: [&str; 18] = [
+        "porn", "sex", "nsfw", "nude", "nudity", "xxx", "onlyfans", "gambling", "casino", "betting",
+        "cocaine", "heroin", "murder", "suicide", "gore", "massacre", "shooting", "terrorist",
+    ];
+
+    /// True if `host` is `entry` or one of its subdomains.
+    fn host_matches(host: &str, entry: &str) -> bool {
+        let host = normalize_host(host);
+        let entry = normalize_host(entry);
+        !entry.is_empty() && (host == entry || host.ends_with(&format!(".{entry}")))
+    }
+
+    impl NewsFilter {
+        pub fn allows_source(&self, host: &str) -> bool {
+            if self.blocked.iter().any(|e| host_matches(host, e)) {
+                return false;
+            }
+            self.allowed.is_empty() || self.allowed.iter().any(|e| host_matches(host, e))
+        }
+
+        pub fn is_muted(&self, title: &str) -> bool {
+            self.muted.iter().any(|k| contains_phrase(title, k))
+                || (self.safe && SAFE_BLOCKLIST.iter().any(|k| contains_phrase(title, k)))
+        }
+
+        /// False only if the title is reliably detected as a language the user didn't pick.
+        /// Titles are short, so uncertain detections are always kept.
+        pub fn wants_language(&self, title: &str) -> bool {
+            if self.languages.is_empty() {
+                return true;
+            }
+            let Some(info) = whatlang::detect(title).filter(|i| i.is_reliable()) else {
+                return true;
+            };
+            let lang = info.lang();
+            self.languages.iter().any(|l| {
+                let l = l.trim();
+                l.eq_ignore_ascii_case(lang.code()) || l.eq_ignore_ascii_case(lang.eng_name())
+            })
+        }
+
+        /// True if a story from `host` titled `title` should be shown.
+        pub fn keeps(&self, host: &str, title: &str) -> bool {
+            self.allows_source(host) && !self.is_muted(title) && self.wants_language(title)
+        }
+    }
+
+    /// Sites known to put most articles behind a paywall (users can add more).
+    const PAYWALLED_HOSTS: [&str; 14] = [
+        "nytimes.com", "wsj.com", "ft.com", "economist.com", "bloomberg.com",
+        "washingtonpost.com", "theatlantic.com", "newyorker.com", "wired.com",
+        "businessinsider.com", "theinformation.com", "thetimes.co.uk", "telegraph.co.uk", "hbr.org",
+    ];
+
+    /// Markers publishers put on subscriber-only pages (matched on lowercased,
+    /// whitespace-free HTML).
+    const PAYWALL_MARKERS: [&str; 6] = [
+        "\"isaccessibleforfree\":false",
+        "\"isaccessibleforfree\":\"false\"",
+        "class=\"paywall",
+        "id=\"paywall",
+        "data-paywall",
+        "subscriber-only",
+    ];
+
+    /// True if `host` is a known paywalled site or listed in `extra`.
+    pub fn is_paywalled_host(host: &str, extra: &[String]) -> bool {
+        PAYWALLED_HOSTS.iter().any(|e| host_matches(host, e)) || extra.iter().any(|e| host_matches(host, e))
+    }
+
+    /// Heuristic paywall check on an article page.
+    pub fn looks_paywalled(html: &str) -> bool {
+        let compact: String = html.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect();
+        PAYWALL_MARKERS.iter().any(|m| compact.contains(m))
+    }
+
+    /// One story, ready for the UI/cache.
+    #[derive(Clone)]
+    pub struct Article {
+        pub title: String,
+        pub source: String,
+        pub published: String,
+        pub url: String,
+        /// HN item id, used for the discussion link.
+        pub object_id: String,
+        pub points: i64,
+        pub comments: i64,
+        pub thumbnail: SharedPixelBuffer<Rgba8Pixel>,
+        pub favicon: Option<SharedPixelBuffer<Rgba8Pixel>>,
+        /// Known paywalled site, or the page looked paywalled when fetched.
+        pub paywalled: bool,
+        /// HN username of the submitter ("" if unknown).
+        pub author: String,
+    }
+
+    /// Result of a news fetch: the rows to show plus how many hits the filter dropped.
+    pub struct FetchedNews {
+        pub rows: Vec<Article>,
+        pub hidden: usize,
+    }
+
+    /// Query parameters that only track clicks and never change the page.
+    const TRACKING_PARAMS: [&str; 7] = ["fbclid", "gclid", "mc_cid", "mc_eid", "ref_src", "igshid", "cmpid"];
+
+    /// Canonical form used for de-duplication: https, lowercase host without `www.`,
+    /// no fragment, no tracking params, no trailing slash.
+    pub fn canonical_url(url: &str) -> String {
+        let Ok(u) = Url::parse(url.trim()) else {
+            return url.trim().to_lowercase();
+        };
+        let host = u.host_str().unwrap_or("").to_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        let path = u.path().trim_end_matches('/');
+
+        let query: Vec<String> = u
+            .query_pairs()
+            .filter(|(k, _)| !k.starts_with("utm_") && !TRACKING_PARAMS.contains(&k.as_ref()))
+            .map(|(k, v)| if v.is_empty() { k.into_owned() } else { format!("{k}={v}") })
+            .collect();
+
+        if query.is_empty() {
+            format!("https://{host}{path}")
+        } else {
+            format!("https://{host}{path}?{}", query.join("&"))
+        }
+    }
+
+    /// Append `extra` to `primary`, skipping stories whose canonical URL is already present.
+    pub fn merge_articles(primary: Vec<Article>, extra: Vec<Article>) -> Vec<Article> {
+        let mut seen: HashSet<String> = HashSet::new();
+        primary
+            .into_iter()
+            .chain(extra)
+            .filter(|a| seen.insert(canonical_url(&a.url)))
+            .collect()
+    }
+
+    fn hit_url(hit: &Hit) -> String {
+        hit.url.clone().unwrap_or_else(|| {
+            hit.object_id
+                .as_ref()
+                .map(|id| discussion_url(id))
+                .unwrap_or_else(|| "https://news.ycombinator.com/".to_string())
+        })
+    }
+
+    /// How many article pages/thumbnails may be downloaded at the same time.
+    const MAX_CONCURRENT_THUMBNAILS: usize = 4;
+    /// Upper bound for a single thumbnail lookup (HTML + image download).
+    const THUMBNAIL_TIMEOUT: Duration = Duration::from_secs(10);
+    /// Per-request timeout for the HTTP client.
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(8);
+    /// Size caps so a huge page or image can't blow up memory.
+    pub const MAX_HTML_BYTES: usize = 2 * 1024 * 1024;
+    pub const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+    /// In-memory per-topic cache limits: entries expire after the TTL (the user's
+    /// news TTL, see `set_cache_ttl`) and the least recently used topic is evicted
+    /// beyond `NEWS_CACHE_MAX_TOPICS`.
+    static NEWS_CACHE_TTL_SECS: AtomicU64 = AtomicU64::new(15 * 60);
+    const NEWS_CACHE_MAX_TOPICS: usize = 8;
+
+    struct TopicEntry {
+        rows: Vec<Article>,
+        fetched_at: Instant,
+        last_used: Instant,
+    }
+
+    impl TopicEntry {
+        fn is_fresh(&self) -> bool {
+            self.fetched_at.elapsed() <= Duration::from_secs(NEWS_CACHE_TTL_SECS.load(Ordering::Relaxed))
+        }
+    }
+
     lazy_static! {
-        static ref NEWS_CACHE: Mutex<HashMap<String, Vec<(String,String,String,String,SharedPixelBuffer<Rgba8Pixel>)>>> =
+        static ref THUMBNAIL_SLOTS: Semaphore = Semaphore::new(MAX_CONCURRENT_THUMBNAILS);
+        static ref NEWS_CACHE: Mutex<HashMap<String, TopicEntry>> =
+            Mutex::new(HashMap::new());
+        static ref HOST_NEXT_SLOT: Mutex<HashMap<String, Instant>> = Mutex::new(HashMap::new());
+        static ref ROBOTS_CACHE: Mutex<HashMap<String, RobotsRules>> = Mutex::new(HashMap::new());
+        static ref COMMENTS_CACHE: Mutex<HashMap<String, (Instant, Vec<CommentPreview>)>> =
             Mutex::new(HashMap::new());
     }
 
 
+    /// Publication window, sent to Algolia as `numericFilters=created_at_i…`.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum DateRange {
+        #[default]
+        Any,
+        Today,
+        Week,
+        Month,
+        /// Unix seconds, either bound optional.
+        Custom { from: Option<i64>, to: Option<i64> },
+    }
+
+    impl DateRange {
+        pub const LABELS: [&'static str; 5] = ["Any time", "Today", "This week", "This month", "Custom"];
+
+        /// Parse a UI/config label; `from`/`to` are `YYYY-MM-DD` (inclusive, local time)
+        /// and only used for "Custom".
+        pub fn from_label(label: &str, from: &str, to: &str) -> Self {
+            match label {
+                "Today" => DateRange::Today,
+                "This week" => DateRange::Week,
+                "This month" => DateRange::Month,
+                "Custom" => DateRange::Custom {
+                    from: local_day_start(from, 0),
+                    to: local_day_start(to, 1),
+                },
+                _ => DateRange::Any,
+            }
+        }
+
+        fn numeric_filter(self) -> Option<String> {
+            let now = chrono::Local::now();
+            let since = |secs: i64| Some(format!("created_at_i>{}", now.timestamp() - secs));
+            match self {
+                DateRange::Any => None,
+                DateRange::Today => {
+                    let midnight = now.date_naive().and_hms_opt(0, 0, 0)?;
+                    let ts = midnight.and_local_timezone(chrono::Local).earliest()?.timestamp();
+                    Some(format!("created_at_i>{ts}"))
+                }
+                DateRange::Week => since(7 * 24 * 3600),
+                DateRange::Month => since(30 * 24 * 3600),
+                DateRange::Custom { from, to } => {
+                    let parts: Vec<String> = [
+                        from.map(|f| format!("created_at_i>{f}")),
+                        to.map(|t| format!("created_at_i<{t}")),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                    if parts.is_empty() { None } else { Some(parts.join(",")) }
+                }
+            }
+        }
+    }
+
+    /// Unix timestamp of local midnight for `YYYY-MM-DD` shifted by `plus_days`.
+    fn local_day_start(date: &str, plus_days: i64) -> Option<i64> {
+        let day = chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok()?;
+        let day = day.checked_add_signed(chrono::Duration::days(plus_days))?;
+        let midnight = day.and_hms_opt(0, 0, 0)?;
+        Some(midnight.and_local_timezone(chrono::Local).earliest()?.timestamp())
+    }
+
+    /// Normalised key for per-topic caches.
+    pub fn topic_key(topic: &str) -> String {
+        topic.trim().to_lowercase()
+    }
+
+    /// In-memory copy of the last fetch for `topic` (a topic or `feed_key`,
+    /// thumbnails included); `None` if missing or expired.
+    pub async fn cached_topic(topic: &str) -> Option<Vec<Article>> {
+        let key = topic_key(topic);
+        let mut cache = NEWS_CACHE.lock().await;
+        match cache.get_mut(&key) {
+            Some(entry) if entry.is_fresh() => {
+                entry.last_used = Instant::now();
+                Some(entry.rows.clone())
+            }
+            Some(_) => {
+                cache.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Cached topics (keys, without category) whose current list contains `url`.
+    pub async fn topics_with(url: &str) -> Vec<String> {
+        let target = canonical_url(url);
+        let mut topics: Vec<String> = NEWS_CACHE
+            .lock()
+            .await
+            .iter()
+            .filter(|(k, e)| !is_virtual_topic(topic_of_key(k)) && e.rows.iter().any(|a| canonical_url(&a.url) == target))
+            .map(|(k, _)| topic_of_key(k).to_string())
+            .collect();
+        topics.sort();
+        topics.dedup();
+        topics
+    }
+
+    /// How long fetched topics stay in the in-memory cache.
+    pub fn set_cache_ttl(ttl: Duration) {
+        NEWS_CACHE_TTL_SECS.store(ttl.as_secs(), Ordering::Relaxed);
+    }
+
+    /// Drop the in-memory copy for `topic` (used when the user forces a refresh).
+    pub async fn invalidate(topic: &str) {
+        NEWS_CACHE.lock().await.remove(&topic_key(topic));
+    }
+
+    /// Drop every in-memory topic (used when the user clears the news cache).
+    pub async fn invalidate_all() {
+        NEWS_CACHE.lock().await.clear();
+    }
+
+    async fn store_topic(topic: &str, rows: Vec<Article>) {
+        let mut cache = NEWS_CACHE.lock().await;
+        cache.retain(|_, e| e.is_fresh());
+        let now = Instant::now();
+        cache.insert(topic_key(topic), TopicEntry { rows, fetched_at: now, last_used: now });
+
+        while cache.len() > NEWS_CACHE_MAX_TOPICS {
+            let oldest = cache
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone());
+            match oldest {
+                Some(k) => { cache.remove(&k); }
+                None => break,
+            }
+        }
+    }
+
+    fn words(s: &str) -> impl Iterator<Item = String> + '_ {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_lowercase())
+    }
+
+    /// Offline search: every query word must prefix-match a word of the title or source.
+    pub fn matches_query(title: &str, source: &str, query: &str) -> bool {
+        let haystack: Vec<String> = words(title).chain(words(source)).collect();
+        let mut any = false;
+        for q in words(query) {
+            any = true;
+            if !haystack.iter().any(|w| w.starts_with(&q)) {
+                return false;
+            }
+        }
+        any
+    }
+
+    /// Words that say nothing about which event a title covers.
+    const STOPWORDS: [&str; 24] = [
+        "the", "and", "for", "with", "from", "into", "about", "after", "over", "how", "why", "what",
+        "are", "was", "its", "new", "you", "your", "this", "that", "show", "ask", "now", "says",
+    ];
+    /// Share of title words two stories must have in common to be clustered.
+    const CLUSTER_MIN_OVERLAP: f32 = 0.5;
+
+    fn title_tokens(title: &str) -> HashSet<String> {
+        words(title)
+            .filter(|w| w.chars().count() >= 3 && !STOPWORDS.contains(&w.as_str()))
+            .collect()
+    }
+
+    /// Host + last path segment, when the segment is a real article slug.
+    fn host_slug(url: &str) -> Option<(String, String)> {
+        let u = Url::parse(url).ok()?;
+        let slug = u.path_segments()?.rev().find(|s| !s.is_empty())?.to_lowercase();
+        if slug.len() < 12 {
+            return None;
+        }
+        Some((normalize_host(u.host_str()?), slug))
+    }
+
+    /// Near-duplicate coverage: same host and article slug, or mostly the same title words.
+    fn same_story(a: (&str, &str), b: (&str, &str)) -> bool {
+        if let (Some(x), Some(y)) = (host_slug(a.1), host_slug(b.1)) {
+            if x == y {
+                return true;
+            }
+        }
+        let (ta, tb) = (title_tokens(a.0), title_tokens(b.0));
+        let shared = ta.intersection(&tb).count();
+        let union = ta.union(&tb).count();
+        shared >= 2 && union > 0 && shared as f32 / union as f32 >= CLUSTER_MIN_OVERLAP
+    }
+
+    /// Group `(title, url)` stories into clusters of the same event. Each group lists
+    /// indices into `stories`, lead story first; groups keep first-seen order.
+    pub fn cluster_stories(stories: &[(&str, &str)]) -> Vec<Vec<usize>> {
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for (i, story) in stories.iter().enumerate() {
+            match groups.iter_mut().find(|g| same_story(stories[g[0]], *story)) {
+                Some(g) => g.push(i),
+                None => groups.push(vec![i]),
+            }
+        }
+        groups
+    }
+
+    /// Raw Algolia search for top stories (topic == "Top Stories") or `topic`,
+    /// ordered by `query.sort` (Newest goes through the `search_by_date` endpoint),
+    /// limited to `query.range` and, if set, to one item type (Ask HN, jobs, …).
+    async fn search_hits(topic: &str, query: NewsQuery) -> Result<Vec<Hit>, NewsFetchError> {
+        let top = topic.trim().is_empty() || topic.eq_ignore_ascii_case("Top Stories");
+        let tags = query.category.tag().unwrap_or(if top { "front_page" } else { "story" });
+        algolia_hits((!top).then_some(topic), tags, query).await
+    }
+
+    /// Stories submitted by any of `authors` (`tags=story,(author_a,author_b)`).
+    async fn author_hits(authors: &[String], query: NewsQuery) -> Result<Vec<Hit>, NewsFetchError> {
+        let list: Vec<String> = authors.iter().map(|a| format!("author_{}", a.trim())).collect();
+        let tags = format!("{},({})", query.category.tag().unwrap_or("story"), list.join(","));
+        algolia_hits(None, &tags, query).await
+    }
+
+    async fn algolia_hits(text: Option<&str>, tags: &str, query: NewsQuery) -> Result<Vec<Hit>, NewsFetchError> {
+        let NewsQuery { sort, range, category, min_points } = query;
+        let endpoint = if sort == NewsSort::Newest { "search_by_date" } else { "search" };
+        let mut url = format!("https://hn.algolia.com/api/v1/{endpoint}?tags={}", urlencoding::encode(tags));
+        if let Some(text) = text {
+            url.push_str(&format!("&query={}", urlencoding::encode(text)));
+        }
+        // job posts carry no points, so the score threshold would hide them all
+        let points = (min_points > 0 && category != NewsCategory::Jobs).then(|| format!("points>{min_points}"));
+        let filters: Vec<String> = range.numeric_filter().into_iter().chain(points).collect();
+        if !filters.is_empty() {
+            url.push_str(&format!("&numericFilters={}", urlencoding::encode(&filters.join(","))));
+        }
+
+        let client = Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+        let data: SearchResponse = crate::httpcache::get(&client, &url).await?.json()?;
+        let mut hits = data.hits;
+        sort.apply(&mut hits);
+        Ok(hits)
+    }
+
+    /// HN discussion page for a story id.
+    pub fn discussion_url(object_id: &str) -> String {
+        format!("https://news.ycombinator.com/item?id={object_id}")
+    }
+
+    /// How long a story's comment preview is reused before it is fetched again.
+    const COMMENTS_TTL: Duration = Duration::from_secs(15 * 60);
+    /// Preview text is cut to this many characters.
+    const MAX_COMMENT_CHARS: usize = 280;
+
+    #[derive(Deserialize)]
+    struct ItemNode {
+        #[serde(default)] author: Option<String>,
+        #[serde(default)] text: Option<String>,
+        #[serde(default)] children: Vec<ItemNode>,
+    }
+
+    /// One top-level HN comment, as plain text.
+    #[derive(Clone, Debug)]
+    pub struct CommentPreview {
+        pub author: String,
+        pub text: String,
+    }
+
+    /// Comment HTML to plain text: paragraphs joined by blank lines, then shortened.
+    fn comment_text(html: &str) -> String {
+        let frag = Html::parse_fragment(&html.replace("<p>", "\n\n"));
+        let text: String = frag.root_element().text().collect();
+        let text = text.trim();
+        if text.chars().count() <= MAX_COMMENT_CHARS {
+            return text.to_string();
+        }
+        let cut: String = text.chars().take(MAX_COMMENT_CHARS).collect();
+        format!("{}…", cut.trim_end())
+    }
+
+    /// The first `n` top-level comments of a story (HN's own ranking order), from the
+    /// Algolia items API. Cached per story id for `COMMENTS_TTL`.
+    pub async fn fetch_top_comments(object_id: &str, n: usize) -> anyhow::Result<Vec<CommentPreview>> {
+        if let Some((at, list)) = COMMENTS_CACHE.lock().await.get(object_id) {
+            if at.elapsed() <= COMMENTS_TTL {
+                return Ok(list.clone());
+            }
+        }
+
+        let url = format!("https://hn.algolia.com/api/v1/items/{}", urlencoding::encode(object_id));
+        let client = Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+        let item: ItemNode = client.get(&url).send().await?.error_for_status()?.json().await?;
+        let list: Vec<CommentPreview> = item
+            .children
+            .into_iter()
+            // deleted / flagged comments come back without author or text
+            .filter_map(|c| Some(CommentPreview { author: c.author?, text: comment_text(&c.text?) }))
+            .filter(|c| !c.text.is_empty())
+            .take(n)
+            .collect();
+
+        COMMENTS_CACHE.lock().await.insert(object_id.to_string(), (Instant::now(), list.clone()));
+        Ok(list)
+    }
+
+    /// First keyword from `keywords` that appears in `title`, if any.
+    pub fn first_match<'a>(title: &str, keywords: &'a [String]) -> Option<&'a str> {
+        keywords.iter().find(|k| contains_phrase(title, k)).map(|k| k.as_str())
+    }
+
+    /// Name of the tab listing stories by followed authors.
+    pub const FOLLOWING: &str = "Following";
+
+    /// Tabs assembled from other feeds or settings rather than searched by name.
+    pub fn is_virtual_topic(topic: &str) -> bool {
+        let key = topic_key(topic);
+        key == topic_key(FOR_YOU) || key == topic_key(FOLLOWING)
+    }
+
     /// Fetch top stories (topic == "Top Stories") or a search for `topic`
-    /// Returns Vec<(title, source, published, url)>
+    /// Hits rejected by `filter` are dropped (and counted) before any thumbnail download.
+    /// Rows come back in `sort` order.
     pub async fn fetch_news(
     topic: &str,
-    count: usize
-) -> Result<Vec<(String,String,String,String,SharedPixelBuffer<Rgba8Pixel>)>, NewsFetchError> {
-
-    let url = if topic.trim().is_empty() || topic.eq_ignore_ascii_case("Top Stories") { 
-        "https://hn.algolia.com/api/v1/search?tags=front_page".to_string() 
-    } else { 
-        format!( "https://hn.algolia.com/api/v1/search?query={}&tags=story", urlencoding::encode(topic) ) 
-    }; 
+    count: usize,
+    filter: &NewsFilter,
+    query: NewsQuery,
+) -> Result<FetchedNews, NewsFetchError> {
+    let hits = search_hits(topic, query).await?;
+    let fetched = build_articles(hits, count, filter).await;
+    store_topic(&feed_key(topic, query), fetched.rows.clone()).await;
+    Ok(fetched)
+}
 
-    let resp = reqwest::Client::new().get(&url).send().await?.error_for_status()?; 
-    let data: SearchResponse = resp.json().await?; 
+    /// Latest stories by the followed `authors`, stored as the "Following" feed.
+    pub async fn fetch_following(
+        authors: &[String],
+        count: usize,
+        filter: &NewsFilter,
+        query: NewsQuery,
+    ) -> Result<FetchedNews, NewsFetchError> {
+        let hits = author_hits(authors, query).await?;
+        let fetched = build_articles(hits, count, filter).await;
+        store_topic(&feed_key(FOLLOWING, query), fetched.rows.clone()).await;
+        Ok(fetched)
+    }
 
-    let hits = data.hits.into_iter().take(count).collect::<Vec<_>>();
+/// Dedupe, filter and cap `hits`, then load thumbnails and favicons (order kept).
+async fn build_articles(hits: Vec<Hit>, count: usize, filter: &NewsFilter) -> FetchedNews {
+    let mut hidden = 0usize;
+    // re-posts / mirror submissions share a canonical URL: keep the first one
+    let mut seen: HashSet<String> = HashSet::new();
+    let hits = hits
+        .into_iter()
+        .filter(|hit| seen.insert(canonical_url(&hit_url(hit))))
+        .filter(|hit| {
+            let keep = filter.keeps(&host_from_url(&hit_url(hit)), hit.title.as_deref().unwrap_or(""));
+            if !keep { hidden += 1; }
+            keep
+        })
+        .take(count)
+        .collect::<Vec<_>>();
 
-    // Spawn all thumbnail fetches concurrently
-    let mut futures = FuturesUnordered::new();
+    // Spawn all thumbnail fetches concurrently (bounded by THUMBNAIL_SLOTS);
+    // FuturesOrdered yields them back in the sorted order
+    let mut futures = FuturesOrdered::new();
 
     for hit in hits.into_iter() {
-        futures.push(async move {
+        futures.push_back(async move {
+            let url = hit_url(&hit);
             let title = hit.title.unwrap_or_else(|| "Untitled".to_string());
-            let url = hit.url.unwrap_or_else(|| {
-                hit.object_id
-                    .map(|id| format!("https://news.ycombinator.com/item?id={id}"))
-                    .unwrap_or_else(|| "https://news.ycombinator.com/".to_string())
-            });
             let source = host_from_url(&url);
            let published = hit.created_at
     .as_ref()
     .and_then(|ts| chrono::DateTime::parse_from_rfc3339(ts).ok())
-    .map(|dt| dt.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+    .map(|dt| dt.with_timezone(&chrono::Local).format(crate::timefmt::STORED_STAMP).to_string())
     .unwrap_or_else(|| hit.created_at.clone().unwrap_or_default());
 
-            let thumbnail = fetch_thumbnail_or_placeholder(&url).await;
+            let ((thumbnail, page_paywalled), favicon) = futures::join!(
+                fetch_thumbnail_or_placeholder(&url),
+                fetch_favicon(&source),
+            );
+            let paywalled = page_paywalled || is_paywalled_host(&source, &[]);
 
-            (title, source, published, url, thumbnail)
+            Article {
+                title,
+                source,
+                published,
+                url,
+                object_id: hit.object_id.unwrap_or_default(),
+                points: hit.points.unwrap_or(0),
+                comments: hit.num_comments.unwrap_or(0),
+                thumbnail,
+                paywalled,
+                favicon,
+                author: hit.author.unwrap_or_default(),
+            }
         });
     }
 
@@ -113,26 +804,168 @@
         out.push(res);
     }
 
-    Ok(out)
+    FetchedNews { rows: out, hidden }
 }
 
 
+    /// Read a response body, bailing out as soon as it grows past `limit` bytes.
+    pub async fn read_capped(mut resp: reqwest::Response, limit: usize) -> anyhow::Result<Vec<u8>> {
+        if let Some(len) = resp.content_length() {
+            if len as usize > limit {
+                anyhow::bail!("body too large ({} bytes, limit {})", len, limit);
+            }
+        }
+
+        let mut buf = Vec::new();
+        while let Some(chunk) = resp.chunk().await? {
+            if buf.len() + chunk.len() > limit {
+                anyhow::bail!("body exceeded {} bytes", limit);
+            }
+            buf.extend_from_slice(&chunk);
+        }
+        Ok(buf)
+    }
+
+    /// Minimum gap between two scraping requests to the same host.
+    const HOST_MIN_INTERVAL: Duration = Duration::from_millis(750);
+    /// Upper bound for a site's `Crawl-delay`, so one slow site can't stall the cards.
+    const MAX_CRAWL_DELAY: Duration = Duration::from_secs(5);
+    const MAX_ROBOTS_BYTES: usize = 128 * 1024;
+    /// Names we answer to in robots.txt (besides `*`).
+    const ROBOTS_AGENTS: [&str; 2] = ["news-thumbs", "news-reader"];
+
+    /// The parts of a robots.txt group we honor.
+    #[derive(Clone, Default)]
+    struct RobotsRules {
+        allow: Vec<String>,
+        disallow: Vec<String>,
+        crawl_delay: Option<Duration>,
+    }
+
+    impl RobotsRules {
+        /// Longest matching prefix wins; `Allow` wins ties. `*` and `$` are matched literally.
+        fn allows(&self, path: &str) -> bool {
+            let longest = |rules: &[String]| {
+                rules.iter().filter(|r| path.starts_with(r.as_str())).map(|r| r.len()).max()
+            };
+            match (longest(&self.allow), longest(&self.disallow)) {
+                (_, None) => true,
+                (None, Some(_)) => false,
+                (Some(a), Some(d)) => a >= d,
+            }
+        }
+
+        /// Rules for our agents: a group naming us, else the `*` group.
+        fn parse(body: &str) -> Self {
+            let mut ours: Option<RobotsRules> = None;
+            let mut any: Option<RobotsRules> = None;
+            let mut agents: Vec<String> = Vec::new();
+            let mut current = RobotsRules::default();
+            let mut in_rules = false;
+
+            let mut close = |agents: &[String], rules: RobotsRules| {
+                if agents.iter().any(|a| ROBOTS_AGENTS.iter().any(|name| name.starts_with(a.as_str()))) {
+                    ours.get_or_insert(rules);
+                } else if agents.iter().any(|a| a == "*") {
+                    any.get_or_insert(rules);
+                }
+            };
+
+            for line in body.lines() {
+                let line = line.split('#').next().unwrap_or("").trim();
+                let Some((key, value)) = line.split_once(':') else { continue };
+                let (key, value) = (key.trim().to_lowercase(), value.trim());
+                match key.as_str() {
+                    "user-agent" => {
+                        if in_rules {
+                            close(&agents, std::mem::take(&mut current));
+                            agents.clear();
+                            in_rules = false;
+                        }
+                        agents.push(value.to_lowercase());
+                    }
+                    "allow" | "disallow" | "crawl-delay" => {
+                        in_rules = true;
+                        if key == "crawl-delay" {
+                            current.crawl_delay = value.parse::<f64>().ok()
+                                .filter(|s| s.is_finite() && *s > 0.0)
+                                .map(|s| Duration::from_secs_f64(s).min(MAX_CRAWL_DELAY));
+                        } else if !value.is_empty() {
+                            // an empty Disallow means "allow everything"
+                            let list = if key == "allow" { &mut current.allow } else { &mut current.disallow };
+                            list.push(value.trim_end_matches(['*', '$']).to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            close(&agents, current);
+            ours.or(any).unwrap_or_default()
+        }
+    }
+
+    /// robots.txt rules for the URL's origin, fetched once per host per session.
+    /// An unreachable or missing robots.txt allows everything.
+    async fn robots_for(client: &Client, url: &Url) -> RobotsRules {
+        let origin = url.origin().ascii_serialization();
+        if let Some(rules) = ROBOTS_CACHE.lock().await.get(&origin) {
+            return rules.clone();
+        }
+        let body = match client.get(format!("{origin}/robots.txt")).send().await {
+            Ok(resp) if resp.status().is_success() => read_capped(resp, MAX_ROBOTS_BYTES).await.ok(),
+            _ => None,
+        };
+        let rules = body
+            .map(|b| RobotsRules::parse(&String::from_utf8_lossy(&b)))
+            .unwrap_or_default();
+        ROBOTS_CACHE.lock().await.insert(origin, rules.clone());
+        rules
+    }
+
+    /// Reserve the next request slot for `host` and sleep until it comes up,
+    /// so many cards from one domain are spread out instead of fired at once.
+    async fn wait_for_host(host: &str, gap: Duration) {
+        let now = Instant::now();
+        let start = {
+            let mut slots = HOST_NEXT_SLOT.lock().await;
+            let start = slots.get(host).copied().filter(|t| *t > now).unwrap_or(now);
+            slots.insert(host.to_string(), start + gap);
+            start
+        };
+        tokio::time::sleep(start - now).await;
+    }
+
+    /// GET for the scraping paths (article pages and their images): honors the
+    /// host's robots.txt and spaces out requests to the same host.
+    pub async fn polite_get(client: &Client, url: &str) -> anyhow::Result<reqwest::Response> {
+        let parsed = Url::parse(url)?;
+        let host = parsed.host_str().unwrap_or_default().to_string();
+        let rules = robots_for(client, &parsed).await;
+        if !rules.allows(parsed.path()) {
+            anyhow::bail!("disallowed by robots.txt: {}", url);
+        }
+        let gap = rules.crawl_delay.unwrap_or(HOST_MIN_INTERVAL).max(HOST_MIN_INTERVAL);
+        wait_for_host(&host, gap).await;
+        Ok(client.get(parsed).send().await?.error_for_status()?)
+    }
+
+    /// Thumbnail from the page's preview-image metadata. `paywalled` is set as soon
+    /// as the HTML is in, so it survives a missing or broken image.
     pub async fn fetch_thumbnail_buffer(
         article_url: &str,
+        paywalled: &mut bool,
     ) -> anyhow::Result<SharedPixelBuffer<Rgba8Pixel>> {
         let client = Client::builder()
-            .timeout(Duration::from_secs(8))
+            .timeout(REQUEST_TIMEOUT)
+            .connect_timeout(Duration::from_secs(4))
             .user_agent("news-thumbs/1.0") // be a good citizen
             .build()?;
 
-        // 1) download HTML
-        let html = client
-            .get(article_url)
-            .send()
-            .await?
-            .error_for_status()?
-            .text()
-            .await?;
+        // 1) download HTML (capped)
+        let resp = polite_get(&client, article_url).await?;
+        let html_bytes = read_capped(resp, MAX_HTML_BYTES).await?;
+        let html = String::from_utf8_lossy(&html_bytes).into_owned();
+        *paywalled = looks_paywalled(&html);
 
         // 2) parse synchronously — no awaits inside this block
         let img_url: Option<String> = {
@@ -176,14 +1009,9 @@
         img_url.query_pairs_mut().append_pair("w", "300").append_pair("h", "150");
         eprintln!("Resolved thumbnail URL: {}", img_url);
 
-        // 4) download image bytes
-        let bytes = client
-            .get(img_url)
-            .send()
-            .await?
-            .error_for_status()?
-            .bytes()
-            .await?;
+        // 4) download image bytes (capped)
+        let resp = polite_get(&client, img_url.as_str()).await?;
+        let bytes = read_capped(resp, MAX_IMAGE_BYTES).await?;
 
         eprintln!("Downloaded {} bytes for thumbnail", bytes.len());
 
@@ -200,11 +1028,74 @@
 
 
 
+    const MAX_FAVICON_BYTES: usize = 256 * 1024;
+
+    fn favicon_dir() -> PathBuf {
+        crate::cache::icons_dir().join("favicons")
+    }
+
+    fn favicon_path(host: &str) -> PathBuf {
+        favicon_dir().join(format!("{host}.png"))
+    }
+
+    /// Favicon for `host` from the disk cache, if it was fetched before.
+    pub fn cached_favicon(host: &str) -> Option<SharedPixelBuffer<Rgba8Pixel>> {
+        let rgba = image::open(favicon_path(&normalize_host(host))).ok()?.to_rgba8();
+        let (w, h) = rgba.dimensions();
+        Some(SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(rgba.as_raw(), w, h))
+    }
+
+    /// Favicon for `host`: disk cache, then `/favicon.ico`, then a favicon service.
+    /// Fetched icons are shrunk to 32px and stored as PNG under `icons/favicons` in the cache folder.
+    pub async fn fetch_favicon(host: &str) -> Option<SharedPixelBuffer<Rgba8Pixel>> {
+        let host = normalize_host(host);
+        if host.is_empty() {
+            return None;
+        }
+        if let Some(buf) = cached_favicon(&host) {
+            return Some(buf);
+        }
+
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent("news-thumbs/1.0")
+            .build()
+            .ok()?;
+        let candidates = [
+            format!("https://{host}/favicon.ico"),
+            format!("https://www.google.com/s2/favicons?domain={host}&sz=32"),
+        ];
+        for url in candidates {
+            let Ok(resp) = client.get(&url).send().await.and_then(|r| r.error_for_status()) else { continue };
+            let Ok(bytes) = read_capped(resp, MAX_FAVICON_BYTES).await else { continue };
+            let Ok(img) = image::load_from_memory(&bytes) else { continue };
+
+            let icon = img.thumbnail(32, 32).to_rgba8();
+            let _ = std::fs::create_dir_all(favicon_dir());
+            if let Err(e) = icon.save(favicon_path(&host)) {
+                eprintln!("Failed to cache favicon for {}: {:?}", host, e);
+            }
+            let (w, h) = icon.dimensions();
+            return Some(SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(icon.as_raw(), w, h));
+        }
+        None
+    }
+
     /// Convenience: try to fetch a thumbnail, otherwise load a bundled placeholder.
+    /// Also reports whether the page looked paywalled.
     pub async fn fetch_thumbnail_or_placeholder(
         article_url: &str,
-    ) -> SharedPixelBuffer<Rgba8Pixel> {
-        match fetch_thumbnail_buffer(article_url).await {
+    ) -> (SharedPixelBuffer<Rgba8Pixel>, bool) {
+        // wait for a free slot; the permit is released when this function returns
+        let _permit = THUMBNAIL_SLOTS.acquire().await.ok();
+
+        let mut paywalled = false;
+        let res = match tokio::time::timeout(THUMBNAIL_TIMEOUT, fetch_thumbnail_buffer(article_url, &mut paywalled)).await {
+            Ok(res) => res,
+            Err(_) => Err(anyhow::anyhow!("timed out after {:?}", THUMBNAIL_TIMEOUT)),
+        };
+
+        let buf = match res {
             Ok(buf) => buf,
             Err(err) => {
                 eprintln!("Thumbnail fetch failed for {}: {:?}", article_url, err);
@@ -223,5 +1114,108 @@
                     }
                 }
             }
+        };
+        (buf, paywalled)
+    }
+
+    /// Name of the merged feed tab.
+    pub const FOR_YOU: &str = "For You";
+
+    /// How often the user opened stories per topic key / source host (drives "For You").
+    #[derive(Serialize, Deserialize, Default, Clone, Debug)]
+    pub struct OpenStats {
+        #[serde(default)] pub topics: HashMap<String, u32>,
+        #[serde(default)] pub sources: HashMap<String, u32>,
+    }
+
+    impl OpenStats {
+        pub fn record(&mut self, topics: &[String], source: &str) {
+            for t in topics {
+                *self.topics.entry(topic_key(t)).or_default() += 1;
+            }
+            let host = normalize_host(source);
+            if !host.is_empty() {
+                *self.sources.entry(host).or_default() += 1;
+            }
+        }
+    }
+
+    struct FeedQueue {
+        weight: i64,
+        current: i64,
+        rows: VecDeque<Article>,
+    }
+
+    /// Interleave per-topic feeds into one list. Topics take turns by smooth weighted
+    /// round-robin (weight = 1 + stories opened from that topic); within a topic,
+    /// stories from often-opened sources move up. Duplicate URLs are dropped.
+    pub fn rank_for_you(feeds: Vec<(String, Vec<Article>)>, stats: &OpenStats) -> Vec<Article> {
+        let mut queues: Vec<FeedQueue> = feeds
+            .into_iter()
+            .map(|(topic, mut rows)| {
+                rows.sort_by_key(|a| std::cmp::Reverse(stats.sources.get(&normalize_host(&a.source)).copied().unwrap_or(0)));
+                FeedQueue {
+                    weight: 1 + stats.topics.get(&topic_key(&topic)).copied().unwrap_or(0) as i64,
+                    current: 0,
+                    rows: rows.into(),
+                }
+            })
+            .collect();
+
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut out = Vec::new();
+        loop {
+            queues.retain(|q| !q.rows.is_empty());
+            let total: i64 = queues.iter().map(|q| q.weight).sum();
+            for q in queues.iter_mut() {
+                q.current += q.weight;
+            }
+            // first queue wins ties, so pinned-tab order breaks them
+            let Some(pick) = queues.iter_mut().rev().max_by_key(|q| q.current) else { break };
+            pick.current -= total;
+            if let Some(a) = pick.rows.pop_front() {
+                if seen.insert(canonical_url(&a.url)) {
+                    out.push(a);
+                }
+            }
         }
+        out
+    }
+
+    /// Merged feed over `topics`: reuses fresh in-memory topic lists, fetches the rest,
+    /// then ranks them with `rank_for_you`. Fails only if no topic could be loaded.
+    pub async fn fetch_for_you(
+        topics: &[String],
+        count: usize,
+        filter: &NewsFilter,
+        query: NewsQuery,
+        stats: &OpenStats,
+    ) -> Result<FetchedNews, NewsFetchError> {
+        let mut feeds = Vec::new();
+        let mut hidden = 0usize;
+        let mut last_err = None;
+        for topic in topics.iter().filter(|t| !is_virtual_topic(t)) {
+            match cached_topic(&feed_key(topic, query)).await {
+                Some(rows) => {
+                    let total = rows.len();
+                    let rows: Vec<Article> = rows.into_iter().filter(|a| filter.keeps(&a.source, &a.title)).collect();
+                    hidden += total - rows.len();
+                    feeds.push((topic.clone(), rows));
+                }
+                None => match fetch_news(topic, count, filter, query).await {
+                    Ok(f) => {
+                        hidden += f.hidden;
+                        feeds.push((topic.clone(), f.rows));
+                    }
+                    Err(e) => last_err = Some(e),
+                },
+            }
+        }
+        if let Some(e) = last_err.filter(|_| feeds.is_empty()) {
+            return Err(e);
+        }
+
+        let rows = rank_for_you(feeds, stats);
+        store_topic(&feed_key(FOR_YOU, query), rows.clone()).await;
+        Ok(FetchedNews { rows, hidden })
     }
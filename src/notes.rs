@@ -0,0 +1,274 @@
+use crate::config::user_root;
+use std::{fs, io, path::PathBuf, time::SystemTime};
+
+// Notes: one Markdown file per note in the user's `notes/` folder, named after
+// the time it was created. The first line is the title; nothing else is stored.
+
+fn notes_dir(user: &str) -> io::Result<PathBuf> {
+    let dir = user_root(user)?.join("notes");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn note_path(user: &str, id: &str) -> io::Result<PathBuf> {
+    // ids are creation times; anything else could point outside the folder
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("bad note id {id:?}")));
+    }
+    Ok(notes_dir(user)?.join(format!("{id}.md")))
+}
+
+pub struct Note {
+    pub id: String,
+    pub text: String,
+    /// Unix seconds of the last save.
+    pub modified: i64,
+}
+
+impl Note {
+    pub fn title(&self) -> String {
+        title(&self.text)
+    }
+}
+
+/// First non-empty line without its heading marks ("Untitled" for an empty note).
+pub fn title(text: &str) -> String {
+    text.lines()
+        .map(|l| l.trim().trim_start_matches('#').trim())
+        .find(|l| !l.is_empty())
+        .map(strip_inline)
+        .unwrap_or_else(|| "Untitled".to_string())
+}
+
+/// Every note of `user`, most recently saved first.
+pub fn list(user: &str) -> Vec<Note> {
+    let Ok(dir) = notes_dir(user) else { return Vec::new() };
+    let mut notes: Vec<Note> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            let id = name.strip_suffix(".md")?.to_string();
+            let modified = e
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs() as i64);
+            Some(Note { id, text: fs::read_to_string(e.path()).ok()?, modified })
+        })
+        .collect();
+    notes.sort_by(|a, b| b.modified.cmp(&a.modified).then(b.id.cmp(&a.id)));
+    notes
+}
+
+/// Notes containing every word of `query` (case-insensitive), most recent first.
+pub fn search(user: &str, query: &str) -> Vec<Note> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    let mut notes = list(user);
+    if !words.is_empty() {
+        notes.retain(|n| {
+            let text = n.text.to_lowercase();
+            words.iter().all(|w| text.contains(w.as_str()))
+        });
+    }
+    notes
+}
+
+/// A line to show under the title in the list: the first match of `query`, else
+/// the start of the body.
+pub fn snippet(text: &str, query: &str) -> String {
+    let mut body = text.lines().map(str::trim).filter(|l| !l.is_empty()).skip(1);
+    let first_word = query.split_whitespace().next().map(str::to_lowercase);
+    let line = match first_word {
+        Some(w) => text.lines().find(|l| l.to_lowercase().contains(&w)).map(str::trim),
+        None => None,
+    }
+    .or_else(|| body.next())
+    .unwrap_or_default();
+    let line = strip_inline(line.trim_start_matches(['#', '>', '-', '*', '+', ' ']));
+    match line.char_indices().nth(80) {
+        Some((i, _)) => format!("{}…", &line[..i]),
+        None => line,
+    }
+}
+
+pub fn load(user: &str, id: &str) -> io::Result<String> {
+    fs::read_to_string(note_path(user, id)?)
+}
+
+/// Start an empty note; returns its id.
+pub fn create(user: &str) -> io::Result<String> {
+    let mut id = chrono::Utc::now().timestamp_millis();
+    // two notes in the same millisecond: take the next free one
+    while note_path(user, &id.to_string())?.exists() {
+        id += 1;
+    }
+    let id = id.to_string();
+    fs::write(note_path(user, &id)?, "")?;
+    Ok(id)
+}
+
+pub fn save(user: &str, id: &str, text: &str) -> io::Result<()> {
+    fs::write(note_path(user, id)?, text)
+}
+
+pub fn delete(user: &str, id: &str) -> io::Result<()> {
+    match fs::remove_file(note_path(user, id)?) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+// Preview: Markdown turned into styled blocks the UI lays out one under the
+// other (headings, paragraphs, list items, quotes, code, rules). Slint's Text
+// has no inline styling, so emphasis and link markup are dropped from the text.
+
+/// One preview block; `kind` is "h1", "h2", "h3", "p", "item", "quote", "code" or "rule".
+pub struct Block {
+    pub kind: &'static str,
+    pub text: String,
+}
+
+pub fn render(markdown: &str) -> Vec<Block> {
+    let mut out = Vec::new();
+    let mut para: Vec<&str> = Vec::new();
+    let mut quote: Vec<&str> = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+
+    fn flush(out: &mut Vec<Block>, lines: &mut Vec<&str>, kind: &'static str) {
+        if !lines.is_empty() {
+            out.push(Block { kind, text: strip_inline(&lines.join(" ")) });
+            lines.clear();
+        }
+    }
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if let Some(lines) = code.as_mut() {
+            if trimmed.starts_with("```") {
+                out.push(Block { kind: "code", text: lines.join("\n") });
+                code = None;
+            } else {
+                lines.push(line);
+            }
+            continue;
+        }
+        if !trimmed.starts_with('>') {
+            flush(&mut out, &mut quote, "quote");
+        }
+        if trimmed.is_empty() {
+            flush(&mut out, &mut para, "p");
+        } else if trimmed.starts_with("```") {
+            flush(&mut out, &mut para, "p");
+            code = Some(Vec::new());
+        } else if let Some(rest) = trimmed.strip_prefix('>') {
+            flush(&mut out, &mut para, "p");
+            quote.push(rest.trim());
+        } else if is_rule(trimmed) {
+            flush(&mut out, &mut para, "p");
+            out.push(Block { kind: "rule", text: String::new() });
+        } else if let Some((level, rest)) = heading(trimmed) {
+            flush(&mut out, &mut para, "p");
+            out.push(Block { kind: ["h1", "h2", "h3"][level.min(3) - 1], text: strip_inline(rest) });
+        } else if let Some(item) = list_item(line) {
+            flush(&mut out, &mut para, "p");
+            out.push(Block { kind: "item", text: item });
+        } else {
+            para.push(trimmed);
+        }
+    }
+    flush(&mut out, &mut quote, "quote");
+    flush(&mut out, &mut para, "p");
+    if let Some(lines) = code {
+        out.push(Block { kind: "code", text: lines.join("\n") });
+    }
+    out
+}
+
+/// "---", "***", "_ _ _"
+fn is_rule(line: &str) -> bool {
+    let marks: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3 && ['-', '*', '_'].iter().any(|m| marks.iter().all(|c| c == m))
+}
+
+/// "## Title" -> (2, "Title")
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then_some((level, rest.trim()))
+}
+
+/// "- text", "* text", "1. text", "- [x] text", indented two spaces per level.
+fn list_item(line: &str) -> Option<String> {
+    let indent = line.len() - line.trim_start().len();
+    let trimmed = line.trim_start();
+    let (marker, rest) = if let Some(rest) = trimmed.strip_prefix(['-', '*', '+']).and_then(|r| r.strip_prefix(' ')) {
+        ("•".to_string(), rest)
+    } else {
+        let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+        let rest = trimmed[digits..].strip_prefix(['.', ')']).and_then(|r| r.strip_prefix(' '));
+        match (digits, rest) {
+            (1..=9, Some(rest)) => (format!("{}.", &trimmed[..digits]), rest),
+            _ => return None,
+        }
+    };
+    let (marker, rest) = match rest.get(..4) {
+        Some("[ ] ") => ("☐".to_string(), &rest[4..]),
+        Some("[x] " | "[X] ") => ("☑".to_string(), &rest[4..]),
+        _ => (marker, rest),
+    };
+    Some(format!("{}{marker} {}", "    ".repeat(indent / 2), strip_inline(rest.trim())))
+}
+
+/// Drop inline markup: `**bold**`, `__bold__`, `~~struck~~`, `` `code` ``,
+/// `*italic*` and `[text](url)` links / images keep only their text.
+fn strip_inline(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let chars: Vec<char> = s.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '*' | '_' | '~' if next == Some(c) => i += 2,
+            '`' => i += 1,
+            '*' => {
+                // only emphasis marks: "*word*", not "2 * 3"
+                let prev_space = i == 0 || chars[i - 1].is_whitespace();
+                let next_space = next.is_none_or(char::is_whitespace);
+                if prev_space == next_space {
+                    out.push(c);
+                }
+                i += 1;
+            }
+            '!' if next == Some('[') => i += 1,
+            '[' => match link(&chars[i..]) {
+                Some((text, len)) => {
+                    out.push_str(&text);
+                    i += len;
+                }
+                None => {
+                    out.push(c);
+                    i += 1;
+                }
+            },
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// `[text](url)` at the start of `chars`: the text and how many chars it spans.
+fn link(chars: &[char]) -> Option<(String, usize)> {
+    let close = chars.iter().position(|&c| c == ']')?;
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+    let end = close + 2 + chars[close + 2..].iter().position(|&c| c == ')')?;
+    Some((strip_inline(&chars[1..close].iter().collect::<String>()), end + 1))
+}
@@ -0,0 +1,41 @@
+/// Show a desktop notification. Blocking; call from a blocking task.
+pub fn show(summary: &str, body: &str) {
+    if let Err(e) = notify_rust::Notification::new()
+        .appname("slint_rust")
+        .summary(summary)
+        .body(body)
+        .show()
+    {
+        eprintln!("Notification error: {e:?}");
+    }
+}
+
+/// Three short beeps for an alarm or timer. Blocking; call from a blocking task.
+#[cfg(feature = "sound")]
+pub fn chime() {
+    use rodio::{source::{SineWave, Zero}, Sink, Source};
+    use std::time::Duration;
+
+    let Some(output) = crate::audio::output() else {
+        eprintln!("Alarm sound: no audio output");
+        return;
+    };
+    let sink = match Sink::try_new(&output) {
+        Ok(sink) => sink,
+        Err(e) => {
+            eprintln!("Alarm sound error: {e:?}");
+            return;
+        }
+    };
+    for _ in 0..3 {
+        sink.append(SineWave::new(880.0).take_duration(Duration::from_millis(250)).amplify(0.25));
+        sink.append(Zero::<f32>::new(1, 48_000).take_duration(Duration::from_millis(200)));
+    }
+    sink.sleep_until_end();
+}
+
+/// Built without the `sound` feature: the notification has to do.
+#[cfg(not(feature = "sound"))]
+pub fn chime() {
+    eprintln!("Alarm sound: built without the `sound` feature");
+}
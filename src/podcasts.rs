@@ -0,0 +1,311 @@
+use crate::cache::fnv1a;
+use crate::config::user_root;
+use anyhow::{bail, Context};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::PathBuf, sync::Mutex};
+use tokio::io::AsyncWriteExt;
+
+// Podcast subscriptions: RSS feeds with audio enclosures, per user in
+// `podcasts.json` next to their settings, along with how far each episode was
+// listened to. Downloaded episodes go to `podcasts/` in the user's cache folder
+// and play from there, so they work offline; like the rest of the cache they
+// count against its size limit, are sealed when it's encrypted and go with
+// Clear cache. Playback goes through `audio.rs`.
+
+const FILE: &str = "podcasts.json";
+/// Folder of downloaded episodes, in the user's cache folder.
+pub const DOWNLOADS: &str = "podcasts";
+
+/// Episodes kept per feed, newest first.
+const MAX_EPISODES: usize = 100;
+
+/// A subscription's episode list counts as fresh this long.
+pub const FEED_TTL_SECS: i64 = 6 * 3600;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Episode {
+    /// The item's guid, or its enclosure URL when it has none.
+    pub id: String,
+    pub title: String,
+    /// Unix seconds, 0 if the feed didn't say.
+    #[serde(default)]
+    pub published: i64,
+    /// The audio file.
+    pub url: String,
+    /// Seconds, 0 if the feed didn't say.
+    #[serde(default)]
+    pub duration: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Feed {
+    pub url: String,
+    pub title: String,
+    /// Newest first.
+    pub episodes: Vec<Episode>,
+    /// Unix seconds of the last fetch.
+    #[serde(default)]
+    pub fetched: i64,
+}
+
+/// How far an episode was listened to.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+pub struct Progress {
+    /// Seconds into the episode.
+    #[serde(default)]
+    pub position: u64,
+    /// Played to the end (or marked so).
+    #[serde(default)]
+    pub listened: bool,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct Podcasts {
+    #[serde(default)]
+    pub feeds: Vec<Feed>,
+    /// By episode id.
+    #[serde(default)]
+    pub progress: BTreeMap<String, Progress>,
+}
+
+impl Podcasts {
+    pub fn progress(&self, episode: &str) -> Progress {
+        self.progress.get(episode).copied().unwrap_or_default()
+    }
+
+    pub fn episode(&self, id: &str) -> Option<&Episode> {
+        self.feeds.iter().flat_map(|f| &f.episodes).find(|e| e.id == id)
+    }
+}
+
+/// The loaded user's podcasts, so the player doesn't read the file every tick.
+static LOADED: Mutex<Option<(String, Podcasts)>> = Mutex::new(None);
+
+fn path(user: &str) -> io::Result<PathBuf> {
+    Ok(user_root(user)?.join(FILE))
+}
+
+fn read(user: &str) -> Podcasts {
+    let Ok(text) = path(user).and_then(fs::read_to_string) else { return Podcasts::default() };
+    serde_json::from_str(&text).unwrap_or_else(|e| {
+        eprintln!("Podcasts for {user}: {e}; starting empty");
+        Podcasts::default()
+    })
+}
+
+fn with<R>(user: &str, f: impl FnOnce(&mut Podcasts) -> R) -> R {
+    let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+    if loaded.as_ref().is_none_or(|(u, _)| u != user) {
+        move_old_downloads(user);
+        *loaded = Some((user.to_string(), read(user)));
+    }
+    let (_, podcasts) = loaded.as_mut().expect("just loaded");
+    f(podcasts)
+}
+
+fn update<R>(user: &str, f: impl FnOnce(&mut Podcasts) -> R) -> io::Result<R> {
+    with(user, |podcasts| {
+        let out = f(podcasts);
+        fs::write(path(user)?, serde_json::to_string_pretty(podcasts)?)?;
+        Ok(out)
+    })
+}
+
+/// A copy of `user`'s subscriptions (in the order they were added) and progress.
+pub fn list(user: &str) -> Podcasts {
+    with(user, |podcasts| podcasts.clone())
+}
+
+/// Forget the loaded copy (account renamed or deleted).
+pub fn forget() {
+    *LOADED.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Add `feed`, or replace the subscription with the same URL.
+pub fn subscribe(user: &str, feed: Feed) -> io::Result<()> {
+    update(user, |p| match p.feeds.iter_mut().find(|f| f.url == feed.url) {
+        Some(f) => *f = feed,
+        None => p.feeds.push(feed),
+    })
+}
+
+/// Store a refetched feed, unless it was unsubscribed in the meantime.
+pub fn refreshed(user: &str, feed: Feed) -> io::Result<()> {
+    update(user, |p| {
+        if let Some(f) = p.feeds.iter_mut().find(|f| f.url == feed.url) {
+            *f = feed;
+        }
+    })
+}
+
+/// Drop a subscription with its downloads and progress.
+pub fn unsubscribe(user: &str, url: &str) -> io::Result<()> {
+    let removed = update(user, |p| {
+        let Some(i) = p.feeds.iter().position(|f| f.url == url) else { return Vec::new() };
+        let feed = p.feeds.remove(i);
+        for e in &feed.episodes {
+            p.progress.remove(&e.id);
+        }
+        feed.episodes
+    })?;
+    for e in &removed {
+        delete_download(user, e)?;
+    }
+    Ok(())
+}
+
+pub fn set_progress(user: &str, episode: &str, progress: Progress) -> io::Result<()> {
+    update(user, |p| {
+        if progress.position == 0 && !progress.listened {
+            p.progress.remove(episode);
+        } else {
+            p.progress.insert(episode.to_string(), progress);
+        }
+    })
+}
+
+/// Fetch and parse a feed; only items with an audio enclosure count as episodes.
+pub async fn fetch_feed(url: &str) -> anyhow::Result<Feed> {
+    let body = crate::httpcache::get(&reqwest::Client::new(), url).await?;
+    parse_feed(url, body.text.as_bytes())
+}
+
+fn parse_feed(url: &str, xml: &[u8]) -> anyhow::Result<Feed> {
+    let channel = rss::Channel::read_from(xml).context("not an RSS feed")?;
+    let mut episodes: Vec<Episode> = channel
+        .items()
+        .iter()
+        .filter_map(|item| {
+            let enclosure = item.enclosure()?;
+            let mime = enclosure.mime_type();
+            if !(mime.is_empty() || mime.starts_with("audio/")) || enclosure.url().is_empty() {
+                return None;
+            }
+            Some(Episode {
+                id: item.guid().map(|g| g.value().to_string()).unwrap_or_else(|| enclosure.url().to_string()),
+                title: item.title().unwrap_or("Untitled episode").trim().to_string(),
+                published: item
+                    .pub_date()
+                    .and_then(|d| chrono::DateTime::parse_from_rfc2822(d.trim()).ok())
+                    .map_or(0, |d| d.timestamp()),
+                url: enclosure.url().to_string(),
+                duration: item.itunes_ext().and_then(|i| i.duration()).and_then(parse_duration).unwrap_or(0),
+            })
+        })
+        .collect();
+    if episodes.is_empty() {
+        bail!("no audio episodes in this feed");
+    }
+    episodes.sort_by_key(|e| std::cmp::Reverse(e.published));
+    episodes.truncate(MAX_EPISODES);
+    Ok(Feed { url: url.to_string(), title: channel.title().trim().to_string(), episodes, fetched: chrono::Utc::now().timestamp() })
+}
+
+/// `<itunes:duration>`: "3723", "62:03" or "1:02:03" -> seconds; `None` if it
+/// doesn't parse or doesn't fit.
+fn parse_duration(s: &str) -> Option<u64> {
+    let parts: Vec<u64> = s.trim().split(':').map(|p| p.trim().parse().ok()).collect::<Option<_>>()?;
+    match parts[..] {
+        [s] => Some(s),
+        [m, s] => m.checked_mul(60)?.checked_add(s),
+        [h, m, s] => h.checked_mul(3600)?.checked_add(m.checked_mul(60)?)?.checked_add(s),
+        _ => None,
+    }
+}
+
+/// "12:05", "1:02:03"
+pub fn format_secs(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 { format!("{h}:{m:02}:{s:02}") } else { format!("{m}:{s:02}") }
+}
+
+fn downloads_dir(user: &str) -> PathBuf {
+    crate::cache::users_cache_base().join(user).join(DOWNLOADS)
+}
+
+/// Older versions downloaded next to the settings; move those into the cache
+/// (or drop them when that fails: they can be downloaded again).
+fn move_old_downloads(user: &str) {
+    let Ok(old) = user_root(user).map(|root| root.join(DOWNLOADS)) else { return };
+    if !old.is_dir() {
+        return;
+    }
+    let new = downloads_dir(user);
+    let moved = !new.exists()
+        && new.parent().is_some_and(|parent| fs::create_dir_all(parent).is_ok())
+        && fs::rename(&old, &new).is_ok();
+    if !moved {
+        let _ = fs::remove_dir_all(&old);
+    }
+}
+
+/// Where `episode` is (or would be) downloaded for `user`.
+fn download_path(user: &str, episode: &Episode) -> PathBuf {
+    let name = episode.url.split(['?', '#']).next().unwrap_or_default();
+    let ext = name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .filter(|ext| (2..=4).contains(&ext.len()) && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+        .unwrap_or_else(|| "mp3".to_string());
+    downloads_dir(user).join(format!("{:016x}.{ext}", fnv1a(&episode.id)))
+}
+
+/// The downloaded file, if there is one.
+pub fn downloaded(user: &str, episode: &Episode) -> Option<PathBuf> {
+    Some(download_path(user, episode)).filter(|p| p.is_file())
+}
+
+/// Download `episode` (a no-op if it's there already); a partial file is only
+/// renamed into place once complete. Each download writes its own partial file,
+/// so a Play and a Download of the same episode don't interleave. For an
+/// encrypted cache the episode is kept in memory and only its sealed bytes are
+/// written. Either way it counts as just used, for the cache size limit.
+pub async fn download(user: &str, episode: &Episode) -> anyhow::Result<PathBuf> {
+    let path = download_path(user, episode);
+    if path.is_file() {
+        crate::cache::touch_download(user, &path);
+        return Ok(path);
+    }
+    if crate::cachecrypt::enabled(user) && !crate::cachecrypt::is_open(user) {
+        bail!("sign in with your PIN to download to your encrypted cache");
+    }
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let part = path.with_extension(format!("{:08x}.part", OsRng.next_u32()));
+    let fetched = async {
+        let mut resp = reqwest::Client::new().get(&episode.url).send().await?.error_for_status()?;
+        if crate::cachecrypt::enabled(user) {
+            let mut body = Vec::new();
+            while let Some(chunk) = resp.chunk().await? {
+                body.extend_from_slice(&chunk);
+            }
+            tokio::fs::write(&part, crate::cachecrypt::seal_bytes(user, &body)?).await?;
+        } else {
+            let mut file = tokio::fs::File::create(&part).await?;
+            while let Some(chunk) = resp.chunk().await? {
+                file.write_all(&chunk).await?;
+            }
+            file.flush().await?;
+        }
+        tokio::fs::rename(&part, &path).await?;
+        anyhow::Ok(())
+    }
+    .await;
+    if fetched.is_err() {
+        let _ = tokio::fs::remove_file(&part).await;
+    }
+    fetched?;
+    crate::cache::touch_download(user, &path);
+    let u = user.to_string();
+    let _ = tokio::task::spawn_blocking(move || crate::cache::enforce_size_limit(&u)).await;
+    Ok(path)
+}
+
+pub fn delete_download(user: &str, episode: &Episode) -> io::Result<()> {
+    match fs::remove_file(download_path(user, episode)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,167 @@
+use crate::cache::{encode_png, offline_dir_for, read_file_for, save_offline_for, write_file_for};
+use crate::news::{looks_paywalled, polite_get, read_capped, MAX_HTML_BYTES, MAX_IMAGE_BYTES};
+use reqwest::{Client, Url};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use slint::{Rgba8Pixel, SharedPixelBuffer};
+use std::time::Duration;
+
+/// Most images kept per article (lead image first).
+const MAX_READER_IMAGES: usize = 6;
+/// Images wider than this are shrunk before display / storage.
+const MAX_READER_IMAGE_WIDTH: u32 = 800;
+/// Paragraphs shorter than this are usually captions, bylines or buttons.
+const MIN_PARAGRAPH_CHARS: usize = 40;
+
+/// Readable text extracted from an article page.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ReaderDoc {
+    pub url: String,
+    pub title: String,
+    pub paragraphs: Vec<String>,
+    /// Image URLs, or local PNG paths for an offline copy.
+    pub images: Vec<String>,
+    /// The page carried paywall markers (the text is likely just a teaser).
+    #[serde(default)]
+    pub paywalled: bool,
+}
+
+fn client() -> reqwest::Result<Client> {
+    Client::builder()
+        .timeout(Duration::from_secs(10))
+        .connect_timeout(Duration::from_secs(4))
+        .user_agent("news-reader/1.0")
+        .build()
+}
+
+fn collapse_ws(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Pull title, body paragraphs and images out of an HTML page (no awaits: `Html` is !Send).
+fn extract(html: &str, url: &str) -> ReaderDoc {
+    let doc = Html::parse_document(html);
+    let base = Url::parse(url).ok();
+    let sel = |css: &str| Selector::parse(css).ok();
+
+    let title = ["meta[property=\"og:title\"]", "title"]
+        .iter()
+        .filter_map(|css| sel(css))
+        .find_map(|s| {
+            let el = doc.select(&s).next()?;
+            let t = el.value().attr("content").map(str::to_string).unwrap_or_else(|| el.text().collect());
+            Some(collapse_ws(&t)).filter(|t| !t.is_empty())
+        })
+        .unwrap_or_default();
+
+    // body text: the first container that actually yields paragraphs
+    let paragraphs: Vec<String> = ["article p", "main p", "p"]
+        .iter()
+        .filter_map(|css| sel(css))
+        .map(|s| {
+            doc.select(&s)
+                .map(|p| collapse_ws(&p.text().collect::<String>()))
+                .filter(|t| t.chars().count() >= MIN_PARAGRAPH_CHARS)
+                .collect::<Vec<_>>()
+        })
+        .find(|ps| !ps.is_empty())
+        .unwrap_or_default();
+
+    let mut images: Vec<String> = Vec::new();
+    let candidates = sel("meta[property=\"og:image\"]")
+        .into_iter()
+        .flat_map(|s| doc.select(&s).filter_map(|e| e.value().attr("content")).collect::<Vec<_>>())
+        .chain(
+            sel("article img, main img")
+                .into_iter()
+                .flat_map(|s| doc.select(&s).filter_map(|e| e.value().attr("src")).collect::<Vec<_>>()),
+        );
+    for src in candidates {
+        if src.starts_with("data:") {
+            continue;
+        }
+        let Some(abs) = Url::parse(src).ok().or_else(|| base.as_ref()?.join(src).ok()) else { continue };
+        let abs = abs.to_string();
+        if !images.contains(&abs) {
+            images.push(abs);
+        }
+        if images.len() >= MAX_READER_IMAGES {
+            break;
+        }
+    }
+
+    ReaderDoc { url: url.to_string(), title, paragraphs, images, paywalled: looks_paywalled(html) }
+}
+
+/// Download an article page and extract its readable content.
+pub async fn fetch_article(url: &str) -> anyhow::Result<ReaderDoc> {
+    let resp = polite_get(&client()?, url).await?;
+    let html = String::from_utf8_lossy(&read_capped(resp, MAX_HTML_BYTES).await?).into_owned();
+    let doc = extract(&html, url);
+    if doc.paragraphs.is_empty() {
+        anyhow::bail!("no readable text found");
+    }
+    Ok(doc)
+}
+
+async fn fetch_image(client: &Client, url: &str) -> anyhow::Result<image::RgbaImage> {
+    let resp = polite_get(client, url).await?;
+    let bytes = read_capped(resp, MAX_IMAGE_BYTES).await?;
+    let mut img = image::load_from_memory(&bytes)?;
+    if img.width() > MAX_READER_IMAGE_WIDTH {
+        img = img.thumbnail(MAX_READER_IMAGE_WIDTH, u32::MAX);
+    }
+    Ok(img.to_rgba8())
+}
+
+fn to_buffer(img: &image::RgbaImage) -> SharedPixelBuffer<Rgba8Pixel> {
+    let (w, h) = img.dimensions();
+    SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(img.as_raw(), w, h)
+}
+
+/// Load the images of a document: `user`'s local PNGs for offline copies, downloads
+/// otherwise. Images that fail are skipped.
+pub async fn load_images(user: &str, doc: &ReaderDoc) -> Vec<SharedPixelBuffer<Rgba8Pixel>> {
+    let client = client().ok();
+    let mut out = Vec::new();
+    for src in &doc.images {
+        let img = if src.starts_with("http://") || src.starts_with("https://") {
+            let Some(c) = client.as_ref() else { continue };
+            fetch_image(c, src).await.ok()
+        } else {
+            read_file_for(user, std::path::Path::new(src))
+                .and_then(|png| image::load_from_memory(&png).ok())
+                .map(|i| i.to_rgba8())
+        };
+        if let Some(img) = img {
+            out.push(to_buffer(&img));
+        }
+    }
+    out
+}
+
+/// Fetch an article and store its text plus images under the user's offline cache,
+/// so the reader view can open it without a network connection.
+pub async fn download_offline(user: &str, url: &str) -> anyhow::Result<ReaderDoc> {
+    let mut doc = fetch_article(url).await?;
+    let dir = offline_dir_for(user, url)?;
+    let client = client()?;
+
+    let mut local = Vec::new();
+    for (i, src) in doc.images.iter().enumerate() {
+        match fetch_image(&client, src).await {
+            Ok(img) => {
+                let path = dir.join(format!("{i}.png"));
+                match encode_png(&img).and_then(|png| write_file_for(user, &path, &png)) {
+                    Ok(()) => local.push(path.to_string_lossy().into_owned()),
+                    Err(e) => eprintln!("Failed to store offline image {}: {:?}", src, e),
+                }
+            }
+            Err(e) => eprintln!("Offline image fetch failed for {}: {:?}", src, e),
+        }
+    }
+    doc.images = local;
+
+    save_offline_for(user, &doc)?;
+    Ok(doc)
+}
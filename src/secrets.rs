@@ -0,0 +1,286 @@
+use crate::config;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+/// Service name used for every entry in the OS credential store.
+const SERVICE: &str = "slint_rust";
+
+/// Where secrets (PIN hashes, API keys) are kept.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretBackend {
+    /// Inline in the JSON file that owns them (users.json for PIN hashes).
+    #[default]
+    File,
+    /// OS credential store (Secret Service, macOS Keychain, Windows Credential Manager).
+    Keyring,
+}
+
+/// Minimal key/value secret storage.
+pub trait SecretStore {
+    fn get(&self, key: &str) -> anyhow::Result<Option<String>>;
+    fn set(&self, key: &str, value: &str) -> anyhow::Result<()>;
+    /// Removing a missing key is not an error.
+    fn delete(&self, key: &str) -> anyhow::Result<()>;
+}
+
+/// `SecretStore` backed by keyring-rs.
+pub struct KeyringStore;
+
+impl SecretStore for KeyringStore {
+    fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        match keyring::Entry::new(SERVICE, key)?.get_password() {
+            Ok(v) => Ok(Some(v)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) -> anyhow::Result<()> {
+        keyring::Entry::new(SERVICE, key)?.set_password(value)?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match keyring::Entry::new(SERVICE, key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Keyring key holding a user's PIN hash.
+pub fn pin_key(username: &str) -> String {
+    format!("pin:{username}")
+}
+
+/// Third-party services a user can keep an API key for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiService {
+    NewsApi,
+    OpenWeatherMap,
+    Summarizer,
+}
+
+impl ApiService {
+    pub const ALL: [ApiService; 3] = [ApiService::NewsApi, ApiService::OpenWeatherMap, ApiService::Summarizer];
+
+    /// Stable name used in `secrets.json` and keyring entries.
+    pub fn id(self) -> &'static str {
+        match self {
+            ApiService::NewsApi => "newsapi",
+            ApiService::OpenWeatherMap => "openweathermap",
+            ApiService::Summarizer => "summarizer",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.id() == id)
+    }
+}
+
+/// A user's `secrets.json`: which backend holds their API keys, and the keys
+/// themselves when that backend is the file. Kept apart from `config.json` so
+/// settings can be shared or synced without leaking keys.
+#[derive(Serialize, Deserialize, Default)]
+struct ApiKeyFile {
+    #[serde(default)]
+    backend: SecretBackend,
+    #[serde(default)]
+    keys: BTreeMap<String, String>,
+}
+
+/// In the user's config folder.
+const API_KEYS_FILE: &str = "secrets.json";
+
+fn api_keys_path(user: &str) -> std::io::Result<PathBuf> {
+    Ok(config::user_root(user)?.join(API_KEYS_FILE))
+}
+
+/// Keyring key holding one of a user's API keys.
+fn api_keyring_key(user: &str, service: ApiService) -> String {
+    format!("api:{user}:{}", service.id())
+}
+
+fn load_api_keys(user: &str) -> ApiKeyFile {
+    api_keys_path(user)
+        .ok()
+        .and_then(|p| fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_api_keys(user: &str, file: &ApiKeyFile) -> anyhow::Result<()> {
+    let path = api_keys_path(user)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    config::write_private(&path, serde_json::to_string_pretty(file)?.as_bytes())?;
+    Ok(())
+}
+
+/// The one place providers get their API key from; `None` if the user hasn't set one.
+pub fn api_key(user: &str, service: ApiService) -> Option<String> {
+    let file = load_api_keys(user);
+    let key = match file.backend {
+        SecretBackend::File => file.keys.get(service.id()).cloned(),
+        SecretBackend::Keyring => KeyringStore.get(&api_keyring_key(user, service)).unwrap_or_else(|e| {
+            eprintln!("Keyring read error ({}): {e:?}", service.id());
+            None
+        }),
+    };
+    key.filter(|k| !k.is_empty())
+}
+
+/// Store (or with an empty `value`, remove) a user's key for `service`.
+pub fn set_api_key(user: &str, service: ApiService, value: &str) -> anyhow::Result<()> {
+    let mut file = load_api_keys(user);
+    let value = value.trim();
+    match file.backend {
+        SecretBackend::File => {
+            if value.is_empty() {
+                file.keys.remove(service.id());
+            } else {
+                file.keys.insert(service.id().to_string(), value.to_string());
+            }
+            save_api_keys(user, &file)
+        }
+        SecretBackend::Keyring if value.is_empty() => KeyringStore.delete(&api_keyring_key(user, service)),
+        SecretBackend::Keyring => KeyringStore.set(&api_keyring_key(user, service), value),
+    }
+}
+
+pub fn api_key_backend(user: &str) -> SecretBackend {
+    load_api_keys(user).backend
+}
+
+/// Move a user's API keys between `secrets.json` and the OS credential store.
+pub fn set_api_key_backend(user: &str, to: SecretBackend) -> anyhow::Result<()> {
+    let mut file = load_api_keys(user);
+    if file.backend == to {
+        return Ok(());
+    }
+    let keys: Vec<(ApiService, String)> =
+        ApiService::ALL.into_iter().filter_map(|s| api_key(user, s).map(|k| (s, k))).collect();
+    match to {
+        SecretBackend::Keyring => {
+            for (service, key) in &keys {
+                KeyringStore.set(&api_keyring_key(user, *service), key)?;
+            }
+            file.keys.clear();
+        }
+        SecretBackend::File => {
+            file.keys = keys.iter().map(|(s, k)| (s.id().to_string(), k.clone())).collect();
+        }
+    }
+    file.backend = to;
+    save_api_keys(user, &file)?;
+    if to == SecretBackend::File {
+        for (service, _) in &keys {
+            if let Err(e) = KeyringStore.delete(&api_keyring_key(user, *service)) {
+                eprintln!("Failed to remove keyring entry for {}: {e:?}", service.id());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Re-key a renamed user's keyring entries (`secrets.json` moves with their folder).
+pub fn rename_api_keys(old: &str, new: &str) -> anyhow::Result<()> {
+    for key in [mail_keyring_key, caldav_keyring_key] {
+        if let Some(password) = KeyringStore.get(&key(old))? {
+            KeyringStore.set(&key(new), &password)?;
+            KeyringStore.delete(&key(old))?;
+        }
+    }
+    if load_api_keys(old).backend != SecretBackend::Keyring {
+        return Ok(());
+    }
+    for service in ApiService::ALL {
+        if let Some(key) = KeyringStore.get(&api_keyring_key(old, service))? {
+            KeyringStore.set(&api_keyring_key(new, service), &key)?;
+            KeyringStore.delete(&api_keyring_key(old, service))?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove a user's keyring entries; call before their folder is deleted.
+pub fn delete_api_keys(user: &str) {
+    if let Err(e) = KeyringStore.delete(&mail_keyring_key(user)) {
+        eprintln!("Failed to remove keyring entry for mail: {e:?}");
+    }
+    if let Err(e) = KeyringStore.delete(&caldav_keyring_key(user)) {
+        eprintln!("Failed to remove keyring entry for the calendar: {e:?}");
+    }
+    if load_api_keys(user).backend != SecretBackend::Keyring {
+        return;
+    }
+    for service in ApiService::ALL {
+        if let Err(e) = KeyringStore.delete(&api_keyring_key(user, service)) {
+            eprintln!("Failed to remove keyring entry for {}: {e:?}", service.id());
+        }
+    }
+}
+
+/// Keyring key holding a user's mail (IMAP) password. Unlike the API keys it
+/// never goes in `secrets.json`, whichever backend they use.
+fn mail_keyring_key(user: &str) -> String {
+    format!("mail:{user}")
+}
+
+/// The user's IMAP password, `None` if they haven't signed in to a mailbox.
+pub fn mail_password(user: &str) -> Option<String> {
+    KeyringStore
+        .get(&mail_keyring_key(user))
+        .unwrap_or_else(|e| {
+            eprintln!("Keyring read error (mail): {e:?}");
+            None
+        })
+        .filter(|p| !p.is_empty())
+}
+
+/// Store (or with an empty `value`, remove) the user's IMAP password.
+pub fn set_mail_password(user: &str, value: &str) -> anyhow::Result<()> {
+    if value.is_empty() {
+        KeyringStore.delete(&mail_keyring_key(user))
+    } else {
+        KeyringStore.set(&mail_keyring_key(user), value)
+    }
+}
+
+/// Keyring key holding a user's CalDAV password; unlike API keys it never
+/// goes to `secrets.json`.
+fn caldav_keyring_key(user: &str) -> String {
+    format!("caldav:{user}")
+}
+
+/// The user's CalDAV password, `None` if they haven't signed in to a calendar.
+pub fn caldav_password(user: &str) -> Option<String> {
+    KeyringStore
+        .get(&caldav_keyring_key(user))
+        .unwrap_or_else(|e| {
+            eprintln!("Keyring read error (calendar): {e:?}");
+            None
+        })
+        .filter(|p| !p.is_empty())
+}
+
+/// Store (or with an empty `value`, remove) the user's CalDAV password.
+pub fn set_caldav_password(user: &str, value: &str) -> anyhow::Result<()> {
+    if value.is_empty() {
+        KeyringStore.delete(&caldav_keyring_key(user))
+    } else {
+        KeyringStore.set(&caldav_keyring_key(user), value)
+    }
+}
+
+/// A key as shown in the UI: only the last four characters survive.
+pub fn mask(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 4 {
+        return "••••".to_string();
+    }
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("••••{tail}")
+}
@@ -0,0 +1,107 @@
+use crate::config::AppConfig;
+use serde_json::Value;
+
+/// What kind of control a registered setting gets, and which values it accepts.
+#[derive(Clone, Copy, Debug)]
+pub enum Kind {
+    Toggle,
+    Number { min: i64, max: i64 },
+    Text,
+    Choice(&'static [&'static str]),
+}
+
+/// Default value of a registered setting (const-friendly).
+#[derive(Clone, Copy, Debug)]
+pub enum DefaultValue {
+    Toggle(bool),
+    Number(i64),
+    Text(&'static str),
+}
+
+/// One setting contributed by a module. Values live in the per-user config under
+/// `sections.<section>.<key>`; Settings renders every descriptor in `registry()`
+/// with a generic row, so a new page only has to list its descriptors there.
+#[derive(Clone, Copy, Debug)]
+pub struct Descriptor {
+    pub section: &'static str,
+    pub key: &'static str,
+    pub label: &'static str,
+    pub kind: Kind,
+    pub default: DefaultValue,
+}
+
+/// Every registered setting, grouped by page in display order.
+pub fn registry() -> Vec<&'static Descriptor> {
+    crate::weather::SETTINGS.iter().chain(crate::news::SETTINGS).chain(crate::stocks::SETTINGS).chain(crate::currency::SETTINGS).chain(crate::mail::SETTINGS).chain(crate::calendar::SETTINGS).chain(crate::alarms::SETTINGS).collect()
+}
+
+/// Look up a descriptor by its section and key.
+pub fn find(section: &str, key: &str) -> Option<&'static Descriptor> {
+    registry().into_iter().find(|d| d.section == section && d.key == key)
+}
+
+impl Descriptor {
+    pub fn default_value(&self) -> Value {
+        match self.default {
+            DefaultValue::Toggle(b) => Value::Bool(b),
+            DefaultValue::Number(n) => Value::from(n),
+            DefaultValue::Text(s) => Value::from(s),
+        }
+    }
+
+    /// Whether `v` is a valid value for this setting.
+    pub fn accepts(&self, v: &Value) -> bool {
+        match (self.kind, v) {
+            (Kind::Toggle, Value::Bool(_)) => true,
+            (Kind::Number { min, max }, Value::Number(n)) => n.as_i64().is_some_and(|n| (min..=max).contains(&n)),
+            (Kind::Text, Value::String(_)) => true,
+            (Kind::Choice(options), Value::String(s)) => options.contains(&s.as_str()),
+            _ => false,
+        }
+    }
+
+    /// The user's value, or the default when it's missing or doesn't fit.
+    pub fn value(&self, cfg: &AppConfig) -> Value {
+        cfg.sections
+            .get(self.section)
+            .and_then(|s| s.get(self.key))
+            .filter(|v| self.accepts(v))
+            .cloned()
+            .unwrap_or_else(|| self.default_value())
+    }
+
+    pub fn flag(&self, cfg: &AppConfig) -> bool {
+        self.value(cfg).as_bool().unwrap_or(false)
+    }
+
+    pub fn number(&self, cfg: &AppConfig) -> i64 {
+        self.value(cfg).as_i64().unwrap_or(0)
+    }
+
+    pub fn text(&self, cfg: &AppConfig) -> String {
+        self.value(cfg).as_str().unwrap_or_default().to_string()
+    }
+
+    /// Parse a value typed or picked in the generic settings row.
+    pub fn parse(&self, raw: &str) -> Option<Value> {
+        let v = match self.kind {
+            Kind::Toggle => Value::Bool(raw == "true"),
+            Kind::Number { .. } => Value::from(raw.trim().parse::<i64>().ok()?),
+            Kind::Text | Kind::Choice(_) => Value::from(raw.trim()),
+        };
+        self.accepts(&v).then_some(v)
+    }
+
+    /// Store `v` for this setting; values equal to the default are dropped.
+    pub fn set(&self, cfg: &mut AppConfig, v: Value) {
+        let section = cfg.sections.entry(self.section.to_string()).or_default();
+        if v == self.default_value() {
+            section.remove(self.key);
+        } else {
+            section.insert(self.key.to_string(), v);
+        }
+        if section.is_empty() {
+            cfg.sections.remove(self.section);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::sync::mpsc;
+#[cfg(feature = "speech")]
+use std::sync::mpsc::RecvTimeoutError;
+#[cfg(feature = "speech")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "speech")]
+use tts::Tts;
+
+/// What the reader's text-to-speech is doing (mirrored into the UI).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(not(feature = "speech"), allow(dead_code))]
+pub enum SpeechState {
+    Playing,
+    Paused,
+    Stopped,
+    Unavailable,
+}
+
+impl SpeechState {
+    pub fn label(self) -> &'static str {
+        match self {
+            SpeechState::Playing => "playing",
+            SpeechState::Paused => "paused",
+            SpeechState::Stopped => "stopped",
+            SpeechState::Unavailable => "unavailable",
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "speech"), allow(dead_code))]
+enum Cmd {
+    Play(Vec<String>),
+    Pause,
+    Resume,
+    Stop,
+}
+
+/// How often the worker checks whether the current paragraph is done.
+#[cfg(feature = "speech")]
+const POLL: Duration = Duration::from_millis(200);
+/// Grace period after `speak` before trusting `is_speaking() == false`.
+#[cfg(feature = "speech")]
+const START_GRACE: Duration = Duration::from_millis(600);
+
+/// Start speaking `text` (interrupting anything else); returns the start time.
+#[cfg(feature = "speech")]
+fn speak(tts: &mut Tts, text: &str) -> Instant {
+    if let Err(e) = tts.speak(text, true) {
+        eprintln!("Speech error: {e:?}");
+    }
+    Instant::now()
+}
+
+/// Handle to a TTS worker thread. The backend is created and used only on that
+/// thread (some platforms' speech objects aren't `Send`). Text is spoken one
+/// paragraph at a time, so pause/resume restarts the current paragraph.
+#[derive(Clone)]
+pub struct Speaker {
+    tx: mpsc::Sender<Cmd>,
+}
+
+impl Speaker {
+    pub fn spawn(on_state: impl Fn(SpeechState) + Send + 'static) -> Self {
+        let (tx, rx) = mpsc::channel::<Cmd>();
+        std::thread::spawn(move || run(rx, on_state));
+        Self { tx }
+    }
+
+    pub fn play(&self, paragraphs: Vec<String>) {
+        let _ = self.tx.send(Cmd::Play(paragraphs));
+    }
+
+    pub fn pause(&self) {
+        let _ = self.tx.send(Cmd::Pause);
+    }
+
+    pub fn resume(&self) {
+        let _ = self.tx.send(Cmd::Resume);
+    }
+
+    pub fn stop(&self) {
+        let _ = self.tx.send(Cmd::Stop);
+    }
+}
+
+/// The worker: speaks the paragraphs it's sent until the `Speaker` is dropped.
+#[cfg(feature = "speech")]
+fn run(rx: mpsc::Receiver<Cmd>, on_state: impl Fn(SpeechState)) {
+    let mut tts = match Tts::default() {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("Text-to-speech unavailable: {e:?}");
+            on_state(SpeechState::Unavailable);
+            return;
+        }
+    };
+
+    let mut queue: Vec<String> = Vec::new();
+    let mut pos = 0usize;
+    let mut playing = false;
+    let mut started = Instant::now();
+
+    loop {
+        let cmd = if playing {
+            rx.recv_timeout(POLL)
+        } else {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        };
+        match cmd {
+            Ok(Cmd::Play(paragraphs)) => {
+                queue = paragraphs;
+                pos = 0;
+                playing = !queue.is_empty();
+                if playing {
+                    started = speak(&mut tts, &queue[0]);
+                    on_state(SpeechState::Playing);
+                }
+            }
+            Ok(Cmd::Pause) => {
+                if playing {
+                    playing = false;
+                    let _ = tts.stop();
+                    on_state(SpeechState::Paused);
+                }
+            }
+            Ok(Cmd::Resume) => {
+                if !playing && pos < queue.len() {
+                    playing = true;
+                    started = speak(&mut tts, &queue[pos]);
+                    on_state(SpeechState::Playing);
+                }
+            }
+            Ok(Cmd::Stop) => {
+                playing = false;
+                queue.clear();
+                pos = 0;
+                let _ = tts.stop();
+                on_state(SpeechState::Stopped);
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if started.elapsed() < START_GRACE || tts.is_speaking().unwrap_or(false) {
+                    continue;
+                }
+                pos += 1;
+                if pos < queue.len() {
+                    started = speak(&mut tts, &queue[pos]);
+                } else {
+                    playing = false;
+                    queue.clear();
+                    pos = 0;
+                    on_state(SpeechState::Stopped);
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+/// Built without the `speech` feature: there's nothing to speak with.
+#[cfg(not(feature = "speech"))]
+fn run(_rx: mpsc::Receiver<Cmd>, on_state: impl Fn(SpeechState)) {
+    eprintln!("Text-to-speech unavailable: built without the `speech` feature");
+    on_state(SpeechState::Unavailable);
+}
@@ -0,0 +1,161 @@
+use crate::cache::StockDay;
+use crate::config::{split_list, AppConfig};
+use crate::settings::{DefaultValue, Descriptor, Kind};
+use anyhow::bail;
+use slint::{Rgba8Pixel, SharedPixelBuffer};
+
+// Stocks watchlist: daily closes from Stooq's CSV download, one request per
+// symbol, cached like the weather (per user, per symbol, with its own TTL).
+// Plain tickers are US listings ("AAPL" -> "aapl.us"); anything with a market
+// suffix ("vow3.de") or an index ("^spx") is passed through.
+
+/// Symbols on the Stocks page, comma-separated.
+pub const WATCHLIST: Descriptor = Descriptor {
+    section: "stocks",
+    key: "watchlist",
+    label: "Watchlist",
+    kind: Kind::Text,
+    default: DefaultValue::Text("AAPL, MSFT, ^SPX"),
+};
+
+/// Minutes a fetched quote counts as fresh.
+pub const TTL_MINUTES: Descriptor = Descriptor {
+    section: "stocks",
+    key: "ttl_minutes",
+    label: "Quotes fresh for (min)",
+    kind: Kind::Number { min: 1, max: 1440 },
+    default: DefaultValue::Number(15),
+};
+
+/// Stocks settings for the registry in `settings.rs`.
+pub const SETTINGS: &[Descriptor] = &[WATCHLIST, TTL_MINUTES];
+
+/// How many days of closes are fetched (enough for a month of trading days).
+const HISTORY_DAYS: i64 = 45;
+
+/// The user's watchlist, uppercased, without repeats.
+pub fn watchlist(cfg: &AppConfig) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for s in split_list(&WATCHLIST.text(cfg)) {
+        let s = s.to_uppercase();
+        if !out.contains(&s) {
+            out.push(s);
+        }
+    }
+    out
+}
+
+/// The watchlist with `symbol` added (or removed), as the setting stores it.
+pub fn with_symbol(cfg: &AppConfig, symbol: &str, add: bool) -> String {
+    let symbol = symbol.trim().to_uppercase();
+    let mut list: Vec<String> = watchlist(cfg).into_iter().filter(|s| *s != symbol).collect();
+    if add && !symbol.is_empty() {
+        list.push(symbol);
+    }
+    list.join(", ")
+}
+
+/// Stooq's name for a watchlist symbol.
+fn stooq_symbol(symbol: &str) -> String {
+    let s = symbol.trim().to_lowercase();
+    if s.contains('.') || s.starts_with('^') { s } else { format!("{s}.us") }
+}
+
+/// Daily closes for `symbol`, oldest first.
+pub async fn fetch_history(symbol: &str) -> anyhow::Result<Vec<StockDay>> {
+    let to = chrono::Utc::now().date_naive();
+    let from = to - chrono::Duration::days(HISTORY_DAYS);
+    let url = format!(
+        "https://stooq.com/q/d/l/?s={}&i=d&d1={}&d2={}",
+        urlencoding::encode(&stooq_symbol(symbol)),
+        from.format("%Y%m%d"),
+        to.format("%Y%m%d"),
+    );
+    let body = crate::httpcache::get(&reqwest::Client::new(), &url).await?;
+    let days = parse_csv(&body.text);
+    if days.is_empty() {
+        bail!("no quotes for {symbol}");
+    }
+    Ok(days)
+}
+
+/// "Date,Open,High,Low,Close,Volume" rows -> (date, close); anything else
+/// (Stooq answers "No data" for unknown symbols) gives nothing.
+fn parse_csv(text: &str) -> Vec<StockDay> {
+    let mut lines = text.lines();
+    let Some(header) = lines.next() else { return Vec::new() };
+    let Some(close_col) = header.split(',').position(|h| h.trim().eq_ignore_ascii_case("close")) else {
+        return Vec::new();
+    };
+    lines
+        .filter_map(|l| {
+            let fields: Vec<&str> = l.split(',').collect();
+            let close = fields.get(close_col)?.trim().parse::<f64>().ok()?;
+            Some(StockDay { date: fields.first()?.trim().to_string(), close })
+        })
+        .collect()
+}
+
+/// Last close and its change from the one before: (price, change, percent).
+pub fn quote(days: &[StockDay]) -> Option<(f64, f64, f64)> {
+    let last = days.last()?.close;
+    let prev = days.len().checked_sub(2).map(|i| days[i].close).filter(|p| *p != 0.0);
+    let change = prev.map_or(0.0, |p| last - p);
+    let pct = prev.map_or(0.0, |p| change / p * 100.0);
+    Some((last, change, pct))
+}
+
+/// "+1.23 (+0.85%)"
+pub fn change_label(change: f64, pct: f64) -> String {
+    format!("{change:+.2} ({pct:+.2}%)")
+}
+
+pub const SPARK_WIDTH: u32 = 120;
+pub const SPARK_HEIGHT: u32 = 32;
+
+/// A sparkline of `closes` (green when the last close is up on the first, red
+/// when down) on a transparent background.
+pub fn sparkline(closes: &[f64]) -> SharedPixelBuffer<Rgba8Pixel> {
+    let (w, h) = (SPARK_WIDTH, SPARK_HEIGHT);
+    let mut buf = SharedPixelBuffer::<Rgba8Pixel>::new(w, h);
+    if closes.len() < 2 {
+        return buf;
+    }
+    let up = closes[closes.len() - 1] >= closes[0];
+    let color = if up { Rgba8Pixel { r: 34, g: 197, b: 94, a: 255 } } else { Rgba8Pixel { r: 239, g: 68, b: 68, a: 255 } };
+    let (min, max) = closes.iter().fold((f64::MAX, f64::MIN), |(lo, hi), &c| (lo.min(c), hi.max(c)));
+    let span = if max > min { max - min } else { 1.0 };
+    // one row per column, 2px inset so the line isn't clipped
+    let row = |x: u32| {
+        let pos = f64::from(x) / f64::from(w - 1) * (closes.len() - 1) as f64;
+        let (i, frac) = (pos.floor() as usize, pos.fract());
+        let v = closes[i] + (closes[(i + 1).min(closes.len() - 1)] - closes[i]) * frac;
+        let y = (max - v) / span * f64::from(h - 5) + 2.0;
+        y.round() as u32
+    };
+    let pixels = buf.make_mut_slice();
+    let mut previous = row(0);
+    for x in 0..w {
+        let y = row(x);
+        // join to the previous column so steep moves stay connected; 2px thick
+        let (top, bottom) = (y.min(previous), y.max(previous) + 1);
+        for yy in top..=bottom.min(h - 1) {
+            pixels[(yy * w + x) as usize] = color;
+        }
+        previous = y;
+    }
+    buf
+}
+
+/// A symbol as typed in the page's box, or an error to show.
+pub fn clean_symbol(input: &str) -> anyhow::Result<String> {
+    let s = input.trim().to_uppercase();
+    let ok = !s.is_empty()
+        && s.len() <= 16
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '^' | '-' | '_'));
+    if !ok {
+        bail!("\"{}\" doesn't look like a ticker symbol", input.trim());
+    }
+    Ok(s)
+}
+
@@ -0,0 +1,91 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// The Clock page's stopwatch: one per run, shared by every account and not
+// saved. Time comes from `Instant`, so it doesn't jump when the system clock
+// is changed; the UI only samples it.
+
+pub struct Stopwatch {
+    /// Set while running.
+    started: Option<Instant>,
+    /// Time from earlier runs (before a pause).
+    banked: Duration,
+    /// Total elapsed at each lap, oldest first.
+    laps: Vec<Duration>,
+}
+
+static WATCH: Mutex<Stopwatch> = Mutex::new(Stopwatch { started: None, banked: Duration::ZERO, laps: Vec::new() });
+
+pub fn with<R>(f: impl FnOnce(&mut Stopwatch) -> R) -> R {
+    f(&mut WATCH.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// One recorded lap.
+pub struct Lap {
+    pub number: usize,
+    /// Time since the lap before.
+    pub split: Duration,
+    /// Time since the start.
+    pub total: Duration,
+}
+
+impl Stopwatch {
+    pub fn running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.banked + self.started.map_or(Duration::ZERO, |s| s.elapsed())
+    }
+
+    /// Start, or pause if it's running.
+    pub fn toggle(&mut self) {
+        match self.started.take() {
+            Some(s) => self.banked += s.elapsed(),
+            None => self.started = Some(Instant::now()),
+        }
+    }
+
+    /// Record a lap (only while running).
+    pub fn lap(&mut self) {
+        if self.running() {
+            self.laps.push(self.elapsed());
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Stopwatch { started: None, banked: Duration::ZERO, laps: Vec::new() };
+    }
+
+    /// Laps, oldest first.
+    pub fn laps(&self) -> Vec<Lap> {
+        let mut previous = Duration::ZERO;
+        self.laps
+            .iter()
+            .enumerate()
+            .map(|(i, &total)| {
+                let lap = Lap { number: i + 1, split: total - previous, total };
+                previous = total;
+                lap
+            })
+            .collect()
+    }
+
+    /// The laps as a tab-separated table (lap, split, total) with the current
+    /// total at the end, for the clipboard or a text file.
+    pub fn to_text(&self) -> String {
+        let mut out = String::from("Lap\tSplit\tTotal\n");
+        for lap in self.laps() {
+            out.push_str(&format!("{}\t{}\t{}\n", lap.number, format(lap.split), format(lap.total)));
+        }
+        out.push_str(&format!("Total\t\t{}\n", format(self.elapsed())));
+        out
+    }
+}
+
+/// "00:12.345", "1:02:03.456"
+pub fn format(d: Duration) -> String {
+    let ms = d.as_millis();
+    let (h, m, s, ms) = (ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000);
+    if h > 0 { format!("{h}:{m:02}:{s:02}.{ms:03}") } else { format!("{m:02}:{s:02}.{ms:03}") }
+}
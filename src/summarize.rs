@@ -0,0 +1,70 @@
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Longest article text sent to the endpoint (characters); keeps requests cheap.
+const MAX_INPUT_CHARS: usize = 12_000;
+const SUMMARY_BULLETS: usize = 3;
+
+const PROMPT: &str = "Summarize the following news article in exactly 3 short bullet points. \
+Reply with the bullets only, one per line.";
+
+#[derive(Deserialize)]
+struct ChatResponse { choices: Vec<Choice> }
+#[derive(Deserialize)]
+struct Choice { message: Message }
+#[derive(Deserialize)]
+struct Message { content: String }
+
+/// `https://host/v1` -> `https://host/v1/chat/completions` (full URLs are kept as-is).
+fn completions_url(endpoint: &str) -> String {
+    let base = endpoint.trim().trim_end_matches('/');
+    if base.ends_with("/chat/completions") {
+        base.to_string()
+    } else {
+        format!("{base}/chat/completions")
+    }
+}
+
+/// Strip list markers ("-", "*", "•", "1.") from a reply line.
+fn bullet_text(line: &str) -> &str {
+    line.trim()
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim_start_matches(['-', '*', '•', '.', ')'])
+        .trim()
+}
+
+/// Ask an OpenAI-compatible chat endpoint for a 3-bullet summary of `text`.
+pub async fn summarize(endpoint: &str, api_key: &str, model: &str, text: &str) -> anyhow::Result<Vec<String>> {
+    if endpoint.trim().is_empty() {
+        anyhow::bail!("no summarizer endpoint configured");
+    }
+    let input: String = text.chars().take(MAX_INPUT_CHARS).collect();
+    let body = serde_json::json!({
+        "model": model,
+        "messages": [
+            { "role": "system", "content": PROMPT },
+            { "role": "user", "content": input },
+        ],
+        "temperature": 0.2,
+    });
+
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(60)).build()?;
+    let mut req = client.post(completions_url(endpoint)).json(&body);
+    if !api_key.trim().is_empty() {
+        req = req.bearer_auth(api_key.trim());
+    }
+    let resp: ChatResponse = req.send().await?.error_for_status()?.json().await?;
+
+    let content = resp.choices.into_iter().next().map(|c| c.message.content).unwrap_or_default();
+    let bullets: Vec<String> = content
+        .lines()
+        .map(bullet_text)
+        .filter(|l| !l.is_empty())
+        .take(SUMMARY_BULLETS)
+        .map(str::to_string)
+        .collect();
+    if bullets.is_empty() {
+        anyhow::bail!("empty summary");
+    }
+    Ok(bullets)
+}
@@ -0,0 +1,111 @@
+use crate::{MainWindow, Palette};
+use slint::{Color, ComponentHandle};
+
+/// Theme preference as stored in the user's config ("System", "Light", "Dark").
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Theme {
+    System,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub const LABELS: [&'static str; 3] = ["System", "Light", "Dark"];
+
+    /// Empty or unknown labels follow the system.
+    pub fn from_label(label: &str) -> Self {
+        match label {
+            "Light" => Theme::Light,
+            "Dark" => Theme::Dark,
+            _ => Theme::System,
+        }
+    }
+
+    /// Whether the dark palette applies; `System` asks the OS.
+    pub fn is_dark(self) -> bool {
+        match self {
+            Theme::Dark => true,
+            Theme::Light => false,
+            Theme::System => system_is_dark(),
+        }
+    }
+}
+
+/// OS dark-mode preference; unknown counts as dark (the app's original look).
+pub fn system_is_dark() -> bool {
+    !matches!(dark_light::detect(), dark_light::Mode::Light)
+}
+
+fn hex(rgb: u32) -> Color {
+    Color::from_rgb_u8((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+}
+
+/// Accent used when the configured one doesn't parse.
+pub const DEFAULT_ACCENT: &str = "#2563eb";
+
+/// `#rrggbb` or `#rgb` (the `#` is optional) as a color.
+pub fn parse_hex(s: &str) -> Option<Color> {
+    let digits = s.trim().trim_start_matches('#');
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let full = match digits.len() {
+        3 => digits.chars().flat_map(|c| [c, c]).collect(),
+        6 => digits.to_string(),
+        _ => return None,
+    };
+    u32::from_str_radix(&full, 16).ok().map(hex)
+}
+
+/// Normalized `#rrggbb` form of `s`, or the default accent if it isn't a color.
+pub fn normalize_accent(s: &str) -> String {
+    match parse_hex(s) {
+        Some(c) => format!("#{:02x}{:02x}{:02x}", c.red(), c.green(), c.blue()),
+        None => DEFAULT_ACCENT.to_string(),
+    }
+}
+
+/// Set the accent brush used by the nav bar, buttons and highlights.
+pub fn apply_accent(app: &MainWindow, accent: &str) {
+    let color = parse_hex(accent).or_else(|| parse_hex(DEFAULT_ACCENT)).unwrap_or_default();
+    app.global::<Palette>().set_accent(color);
+}
+
+/// Push the dark or light colors into the UI's `Palette` global.
+pub fn apply(app: &MainWindow, dark: bool) {
+    let p = app.global::<Palette>();
+    if dark {
+        p.set_window_bg(hex(0x0b1022));
+        p.set_page_bg(hex(0x0f172a));
+        p.set_surface(hex(0x111827));
+        p.set_surface_alt(hex(0x1e293b));
+        p.set_nav_bg(hex(0x1f2a44));
+        p.set_card(hex(0x212d5a));
+        p.set_card_alt(hex(0x1b2550));
+        p.set_border(hex(0x334155));
+        p.set_control(hex(0x374151));
+        p.set_text(hex(0xffffff));
+        p.set_text_secondary(hex(0xcbd5e1));
+        p.set_text_muted(hex(0x94a3b8));
+        p.set_text_soft(hex(0xe2e8f0));
+        p.set_link(hex(0x93c5fd));
+        p.set_error(hex(0xfca5a5));
+    } else {
+        p.set_window_bg(hex(0xe2e8f0));
+        p.set_page_bg(hex(0xf8fafc));
+        p.set_surface(hex(0xffffff));
+        p.set_surface_alt(hex(0xe2e8f0));
+        // the nav bar stays dark so its white labels keep their contrast
+        p.set_nav_bg(hex(0x334155));
+        p.set_card(hex(0xffffff));
+        p.set_card_alt(hex(0xeef2ff));
+        p.set_border(hex(0xcbd5e1));
+        p.set_control(hex(0x64748b));
+        p.set_text(hex(0x0f172a));
+        p.set_text_secondary(hex(0x334155));
+        p.set_text_muted(hex(0x64748b));
+        p.set_text_soft(hex(0x1e293b));
+        p.set_link(hex(0x1d4ed8));
+        p.set_error(hex(0xb91c1c));
+    }
+}
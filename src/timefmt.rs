@@ -0,0 +1,114 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use std::sync::Mutex;
+
+/// How timestamps are kept in caches and bookmarks, whatever the display settings.
+pub const STORED_STAMP: &str = "%Y-%m-%d %H:%M";
+
+/// Date orders offered in Settings; the labels are what's stored in the config.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateStyle {
+    Iso,
+    DayMonth,
+    MonthDay,
+}
+
+impl DateStyle {
+    pub const LABELS: [&'static str; 3] = ["YYYY-MM-DD", "DD.MM.YYYY", "MM/DD/YYYY"];
+
+    /// Unknown labels fall back to ISO.
+    pub fn from_label(label: &str) -> Self {
+        match label {
+            "DD.MM.YYYY" => DateStyle::DayMonth,
+            "MM/DD/YYYY" => DateStyle::MonthDay,
+            _ => DateStyle::Iso,
+        }
+    }
+
+    fn pattern(self) -> &'static str {
+        match self {
+            DateStyle::Iso => "%Y-%m-%d",
+            DateStyle::DayMonth => "%d.%m.%Y",
+            DateStyle::MonthDay => "%m/%d/%Y",
+        }
+    }
+}
+
+/// The user's time and date display preferences.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeFormat {
+    pub hour12: bool,
+    pub date: DateStyle,
+}
+
+/// Current preferences; `apply_config` sets them, every formatter below reads them.
+static CURRENT: Mutex<TimeFormat> = Mutex::new(TimeFormat { hour12: false, date: DateStyle::Iso });
+
+pub fn set(format: TimeFormat) {
+    if let Ok(mut current) = CURRENT.lock() {
+        *current = format;
+    }
+}
+
+fn current() -> TimeFormat {
+    CURRENT.lock().map(|c| *c).unwrap_or(TimeFormat { hour12: false, date: DateStyle::Iso })
+}
+
+fn time_pattern(f: TimeFormat, seconds: bool) -> &'static str {
+    match (f.hour12, seconds) {
+        (false, false) => "%H:%M",
+        (false, true) => "%H:%M:%S",
+        (true, false) => "%-I:%M %p",
+        (true, true) => "%-I:%M:%S %p",
+    }
+}
+
+/// Header clock, with seconds.
+pub fn clock(t: &DateTime<Local>) -> String {
+    t.format(time_pattern(current(), true)).to_string()
+}
+
+/// Time of day without seconds (weather hours).
+pub fn time(t: NaiveTime) -> String {
+    t.format(time_pattern(current(), false)).to_string()
+}
+
+/// Date and time in the local zone (news stamps, security activity).
+pub fn date_time(t: &DateTime<Local>) -> String {
+    let f = current();
+    t.format(&format!("{} {}", f.date.pattern(), time_pattern(f, false))).to_string()
+}
+
+/// Local date and time with seconds (settings backups, which can be seconds apart).
+pub fn date_time_secs(t: NaiveDateTime) -> String {
+    let f = current();
+    t.format(&format!("{} {}", f.date.pattern(), time_pattern(f, true))).to_string()
+}
+
+/// Date only (podcast episodes, calendar events running over several days).
+pub fn date(t: &DateTime<Local>) -> String {
+    t.format(current().date.pattern()).to_string()
+}
+
+/// A day with its weekday (calendar headings).
+pub fn weekday_date(d: NaiveDate) -> String {
+    d.format(&format!("%a {}", current().date.pattern())).to_string()
+}
+
+/// Re-render a `STORED_STAMP` for display; anything else is shown as it is.
+pub fn stored_stamp(s: &str) -> String {
+    match NaiveDateTime::parse_from_str(s, STORED_STAMP) {
+        Ok(t) => {
+            let f = current();
+            t.format(&format!("{} {}", f.date.pattern(), time_pattern(f, false))).to_string()
+        }
+        Err(_) => s.to_string(),
+    }
+}
+
+/// Re-render an `HH:MM` hour label (weather rows, cached or fresh); "Now" passes through.
+pub fn hour_label(s: &str) -> String {
+    match NaiveTime::parse_from_str(s, "%H:%M") {
+        Ok(t) => time(t),
+        Err(_) => s.to_string(),
+    }
+}
@@ -0,0 +1,225 @@
+use crate::auth::{AuthError, UserRecord};
+use crate::secrets::SecretBackend;
+use fs2::FileExt;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf, time::Duration};
+
+/// Storage for the user records. `LocalAuth` only talks to this trait.
+pub trait UserStore {
+    /// Where the PIN hashes of this store live.
+    fn secret_backend(&self) -> Result<SecretBackend, AuthError>;
+    fn get(&self, username: &str) -> Result<Option<UserRecord>, AuthError>;
+    fn list(&self) -> Result<Vec<UserRecord>, AuthError>;
+    /// Fails with `NotFound` if the user is missing.
+    fn delete(&self, username: &str) -> Result<(), AuthError>;
+    /// Replace everything in one go (backend switches / migrations).
+    fn replace_all(&self, secrets: SecretBackend, users: &[UserRecord]) -> Result<(), AuthError>;
+    /// Load every record, let `f` change them and save the result, holding the
+    /// store's write lock throughout so another instance can't slip a change in
+    /// between. Nothing is saved if `f` fails.
+    fn update_with(&self, f: &mut dyn FnMut(&mut Vec<UserRecord>) -> Result<(), AuthError>) -> Result<(), AuthError>;
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct UsersFile {
+    /// Where the PIN hashes live.
+    #[serde(default)]
+    secrets: SecretBackend,
+    users: Vec<UserRecord>,
+}
+
+/// The original flat file: every change rewrites the whole users.json, under an
+/// advisory lock (`users.json.lock`) so concurrent instances don't lose updates.
+pub struct JsonUserStore {
+    path: PathBuf,
+}
+
+impl JsonUserStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn load(&self) -> Result<UsersFile, AuthError> {
+        if !self.path.exists() {
+            return Ok(UsersFile::default());
+        }
+        let data = fs::read_to_string(&self.path)?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Write to a sibling temp file, then rename over users.json, so a crash
+    /// mid-write never leaves a truncated user database behind.
+    fn save(&self, uf: &UsersFile) -> Result<(), AuthError> {
+        let data = serde_json::to_string_pretty(uf)?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    /// Run a load-modify-save cycle while holding the exclusive lock. Readers
+    /// need no lock: the rename swaps in complete files only.
+    fn modify<T>(&self, f: impl FnOnce(&mut UsersFile) -> Result<T, AuthError>) -> Result<T, AuthError> {
+        let lock = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.path.with_extension("json.lock"))?;
+        lock.lock_exclusive()?;
+        let res = self.load().and_then(|mut uf| {
+            let out = f(&mut uf)?;
+            self.save(&uf)?;
+            Ok(out)
+        });
+        // also released when `lock` is dropped, but say so explicitly
+        let _ = lock.unlock();
+        res
+    }
+}
+
+impl UserStore for JsonUserStore {
+    fn secret_backend(&self) -> Result<SecretBackend, AuthError> {
+        Ok(self.load()?.secrets)
+    }
+
+    fn get(&self, username: &str) -> Result<Option<UserRecord>, AuthError> {
+        Ok(self.load()?.users.into_iter().find(|u| u.username == username))
+    }
+
+    fn list(&self) -> Result<Vec<UserRecord>, AuthError> {
+        Ok(self.load()?.users)
+    }
+
+    fn delete(&self, username: &str) -> Result<(), AuthError> {
+        self.modify(|uf| {
+            let before = uf.users.len();
+            uf.users.retain(|u| u.username != username);
+            if uf.users.len() == before {
+                return Err(AuthError::NotFound);
+            }
+            Ok(())
+        })
+    }
+
+    fn replace_all(&self, secrets: SecretBackend, users: &[UserRecord]) -> Result<(), AuthError> {
+        self.modify(|uf| {
+            *uf = UsersFile { secrets, users: users.to_vec() };
+            Ok(())
+        })
+    }
+
+    fn update_with(&self, f: &mut dyn FnMut(&mut Vec<UserRecord>) -> Result<(), AuthError>) -> Result<(), AuthError> {
+        self.modify(|uf| f(&mut uf.users))
+    }
+}
+
+/// SQLite store (users.db): one row per user, in a file several app instances
+/// can share; changes run in write transactions.
+pub struct SqliteUserStore {
+    path: PathBuf,
+}
+
+impl SqliteUserStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn open(&self) -> Result<Connection, AuthError> {
+        let conn = Connection::open(&self.path)?;
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS users (
+                 username   TEXT PRIMARY KEY,
+                 created_at TEXT NOT NULL,
+                 record     TEXT NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS meta (
+                 key   TEXT PRIMARY KEY,
+                 value TEXT NOT NULL
+             );",
+        )?;
+        Ok(conn)
+    }
+
+    fn row(rec: &UserRecord) -> Result<(String, String, String), AuthError> {
+        Ok((rec.username.clone(), rec.created_at.clone(), serde_json::to_string(rec)?))
+    }
+
+    fn records(conn: &Connection) -> Result<Vec<UserRecord>, AuthError> {
+        let mut stmt = conn.prepare("SELECT record FROM users ORDER BY created_at, username")?;
+        let rows = stmt.query_map([], |r| r.get::<_, String>(0))?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(serde_json::from_str(&r?)?);
+        }
+        Ok(out)
+    }
+
+    fn insert_all(conn: &Connection, users: &[UserRecord]) -> Result<(), AuthError> {
+        for rec in users {
+            let (name, created, record) = Self::row(rec)?;
+            conn.execute(
+                "INSERT INTO users (username, created_at, record) VALUES (?1, ?2, ?3)",
+                params![name, created, record],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl UserStore for SqliteUserStore {
+    fn secret_backend(&self) -> Result<SecretBackend, AuthError> {
+        let value: Option<String> = self
+            .open()?
+            .query_row("SELECT value FROM meta WHERE key = 'secrets'", [], |r| r.get(0))
+            .optional()?;
+        Ok(value.and_then(|v| serde_json::from_str(&v).ok()).unwrap_or_default())
+    }
+
+    fn get(&self, username: &str) -> Result<Option<UserRecord>, AuthError> {
+        let record: Option<String> = self
+            .open()?
+            .query_row("SELECT record FROM users WHERE username = ?1", params![username], |r| r.get(0))
+            .optional()?;
+        Ok(record.map(|r| serde_json::from_str(&r)).transpose()?)
+    }
+
+    fn list(&self) -> Result<Vec<UserRecord>, AuthError> {
+        Self::records(&self.open()?)
+    }
+
+    fn delete(&self, username: &str) -> Result<(), AuthError> {
+        let n = self.open()?.execute("DELETE FROM users WHERE username = ?1", params![username])?;
+        if n == 0 {
+            return Err(AuthError::NotFound);
+        }
+        Ok(())
+    }
+
+    fn replace_all(&self, secrets: SecretBackend, users: &[UserRecord]) -> Result<(), AuthError> {
+        let mut conn = self.open()?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM users", [])?;
+        Self::insert_all(&tx, users)?;
+        tx.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('secrets', ?1)",
+            params![serde_json::to_string(&secrets)?],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn update_with(&self, f: &mut dyn FnMut(&mut Vec<UserRecord>) -> Result<(), AuthError>) -> Result<(), AuthError> {
+        let mut conn = self.open()?;
+        // IMMEDIATE takes the write lock up front, before anything is read
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let mut users = Self::records(&tx)?;
+        f(&mut users)?;
+        tx.execute("DELETE FROM users", [])?;
+        Self::insert_all(&tx, &users)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use crate::config::users_base_dir;
+use ::notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Component, Path, PathBuf};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Watch every user's config folder and send the paths of changed files to `tx`.
+/// Keep the returned watcher alive for as long as events are wanted.
+pub fn watch_user_configs(tx: UnboundedSender<PathBuf>) -> ::notify::Result<RecommendedWatcher> {
+    let base = users_base_dir().map_err(::notify::Error::io)?;
+    let mut watcher = ::notify::recommended_watcher(move |res: ::notify::Result<Event>| {
+        let Ok(event) = res else { return };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        for path in event.paths {
+            let _ = tx.send(path);
+        }
+    })?;
+    watcher.watch(&base, RecursiveMode::Recursive)?;
+    Ok(watcher)
+}
+
+/// The user whose settings `path` belongs to: `users/<user>/config.json` or a file
+/// in `users/<user>/profiles/`. Avatars and other files don't count.
+pub fn settings_owner(path: &Path) -> Option<String> {
+    let base = users_base_dir().ok()?;
+    let rel = path.strip_prefix(&base).ok()?;
+    let parts: Vec<&str> = rel
+        .components()
+        .map(|c| match c {
+            Component::Normal(s) => s.to_str(),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    match parts.as_slice() {
+        [user, "config.json"] | [user, "profiles", _] => Some(user.to_string()),
+        _ => None,
+    }
+}
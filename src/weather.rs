@@ -1,3 +1,4 @@
+use crate::settings::{DefaultValue, Descriptor, Kind};
 use serde::Deserialize;
 use std::{collections::HashMap, fmt, fs::File, io, io::BufReader};
 
@@ -39,8 +40,11 @@ impl From<std::io::Error> for WeatherFetchError {
     fn from(e: std::io::Error) -> Self { Self::Io(e) }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 struct Forecast {
+    /// Offset of the forecast's time zone (the city's), which `hourly.time` is in.
+    #[serde(default)]
+    utc_offset_seconds: i64,
     hourly: Hourly,
 }
 #[derive(Deserialize, Debug)]
@@ -64,6 +68,37 @@ struct Hourly {
     #[serde(rename = "weather_code")]         weather_code: Vec<u8>,
     #[serde(rename = "is_day")]               is_day: Vec<u8>,
 }
+
+/// How many hours the Weather page lists.
+pub const HOURS: Descriptor = Descriptor {
+    section: "weather",
+    key: "hours",
+    label: "Hours shown",
+    kind: Kind::Number { min: 1, max: 24 },
+    default: DefaultValue::Number(8),
+};
+
+/// Language of place names from the geocoders (ISO 639-1).
+pub const PLACE_LANGUAGE: Descriptor = Descriptor {
+    section: "weather",
+    key: "place_language",
+    label: "Place names",
+    kind: Kind::Choice(&["en", "de", "fr", "es", "it", "pt", "nl", "pl", "ro", "ru", "tr", "ja", "zh"]),
+    default: DefaultValue::Text("en"),
+};
+
+/// Country (ISO code, "RO") searched first for city names; empty = anywhere.
+pub const PREFER_COUNTRY: Descriptor = Descriptor {
+    section: "weather",
+    key: "prefer_country",
+    label: "Prefer country",
+    kind: Kind::Text,
+    default: DefaultValue::Text(""),
+};
+
+/// Weather settings for the registry in `settings.rs`.
+pub const SETTINGS: &[Descriptor] = &[HOURS, PLACE_LANGUAGE, PREFER_COUNTRY];
+
 #[derive(Clone, Debug)]
 pub struct HourForecast {
     pub time: String,
@@ -74,27 +109,42 @@ pub struct HourForecast {
     pub icon_url: String,
 }
 
+#[derive(Deserialize, Clone)]
+struct ZoneOnly {
+    timezone: String,
+}
+
+/// IANA zone at `lat`/`lon`, for places the geocoder gave without one.
+pub async fn fetch_timezone(lat: f64, lon: f64) -> Result<String, WeatherFetchError> {
+    let url = format!("https://api.open-meteo.com/v1/forecast?latitude={lat}&longitude={lon}&timezone=auto&forecast_days=1");
+    let data: ZoneOnly = crate::httpcache::get(&reqwest::Client::new(), &url).await?.json()?;
+    Ok(data.timezone)
+}
+
+/// The next `count` hours at `lat`/`lon`, labelled in the place's local time.
+/// `timezone` is the geocoder's IANA zone; empty lets the API pick it.
 pub async fn fetch_next_hours_at(
     lat: f64,
     lon: f64,
+    timezone: &str,
     count: usize,
     use_celsius: bool,
 ) -> Result<Vec<HourForecast>, WeatherFetchError> {
     let unit = if use_celsius { "celsius" } else { "fahrenheit" };
+    let tz = if timezone.is_empty() { "auto".into() } else { urlencoding::encode(timezone) };
     let url = format!(
-        "https://api.open-meteo.com/v1/forecast?latitude={lat}&longitude={lon}&hourly=temperature_2m,apparent_temperature,precipitation_probability,weather_code,is_day&timezone=auto&forecast_days=1&temperature_unit={unit}"
+        "https://api.open-meteo.com/v1/forecast?latitude={lat}&longitude={lon}&hourly=temperature_2m,apparent_temperature,precipitation_probability,weather_code,is_day&timezone={tz}&forecast_days=1&temperature_unit={unit}"
     );
 
-    let resp = reqwest::Client::new().get(&url).send().await?.error_for_status()?;
-    let data: Forecast = resp.json().await?;
+    let data: Forecast = crate::httpcache::get(&reqwest::Client::new(), &url).await?.json()?;
 
     // Load weather code -> (day/night) mapping
     let codes_file = File::open("weather_codes.json")?;
     let reader = BufReader::new(codes_file);
     let code_map: HashMap<String, DayNight> = serde_json::from_reader(reader)?;
 
-    // Find current hour index
-    let now = chrono::Local::now().naive_local();
+    // Find current hour index (in the city's time, not this machine's)
+    let now = (chrono::Utc::now() + chrono::Duration::seconds(data.utc_offset_seconds)).naive_utc();
     let mut start_idx = 0usize;
     for (i, t) in data.hourly.time.iter().enumerate() {
         if let Ok(ts) = chrono::NaiveDateTime::parse_from_str(t, "%Y-%m-%dT%H:%M") {
@@ -0,0 +1,161 @@
+use crate::config::user_root;
+use crate::geocode::fetch_places;
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Datelike, Offset, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf, sync::Mutex};
+
+// World clock: places (or bare IANA zones) a user pinned, in `worldclock.json`
+// next to their settings. Times come from the zone database built into the
+// binary, so the list keeps ticking offline; only adding a city needs the
+// geocoder.
+
+const FILE: &str = "worldclock.json";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Zone {
+    /// "Tokyo, Japan", or the zone name for a bare zone
+    pub label: String,
+    /// IANA zone ("Asia/Tokyo")
+    pub timezone: String,
+    /// Where the sun is looked at for day / night; bare zones go by the hour.
+    #[serde(default)]
+    pub coords: Option<(f64, f64)>,
+}
+
+/// The loaded user's zones, so the clock task doesn't read the file every second.
+static LOADED: Mutex<Option<(String, Vec<Zone>)>> = Mutex::new(None);
+
+fn path(user: &str) -> io::Result<PathBuf> {
+    Ok(user_root(user)?.join(FILE))
+}
+
+fn read(user: &str) -> Vec<Zone> {
+    let Ok(text) = path(user).and_then(fs::read_to_string) else { return Vec::new() };
+    serde_json::from_str(&text).unwrap_or_else(|e| {
+        eprintln!("World clock for {user}: {e}; starting empty");
+        Vec::new()
+    })
+}
+
+fn with<R>(user: &str, f: impl FnOnce(&mut Vec<Zone>) -> R) -> R {
+    let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+    if loaded.as_ref().is_none_or(|(u, _)| u != user) {
+        *loaded = Some((user.to_string(), read(user)));
+    }
+    let (_, zones) = loaded.as_mut().expect("just loaded");
+    f(zones)
+}
+
+fn update(user: &str, f: impl FnOnce(&mut Vec<Zone>)) -> io::Result<()> {
+    with(user, |zones| {
+        f(zones);
+        fs::write(path(user)?, serde_json::to_string_pretty(zones)?)
+    })
+}
+
+/// `user`'s pinned zones, in the order they were added.
+pub fn list(user: &str) -> Vec<Zone> {
+    with(user, |zones| zones.clone())
+}
+
+/// Forget the loaded copy (account renamed or deleted).
+pub fn forget() {
+    *LOADED.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Pin `zone` (a place already pinned under the same label is replaced).
+pub fn add(user: &str, zone: Zone) -> io::Result<()> {
+    update(user, |zones| match zones.iter_mut().find(|z| z.label == zone.label) {
+        Some(z) => *z = zone,
+        None => zones.push(zone),
+    })
+}
+
+pub fn remove(user: &str, label: &str) -> io::Result<()> {
+    update(user, |zones| zones.retain(|z| z.label != label))
+}
+
+/// "Springfield — Illinois, United States" -> "Springfield, United States"
+fn short_label(label: &str) -> String {
+    match label.split_once(" — ") {
+        Some((name, rest)) => format!("{name}, {}", rest.rsplit(", ").next().unwrap_or(rest)),
+        None => label.to_string(),
+    }
+}
+
+/// Find what the user typed: an IANA zone name as it is ("Asia/Tokyo", "UTC"),
+/// anything else through the geocoder (same language / country preference as
+/// the weather search), asking the forecast API for the zone if the geocoder
+/// didn't say.
+pub async fn resolve(user: &str, query: &str, lang: &str, prefer: &str) -> anyhow::Result<Zone> {
+    let query = query.trim();
+    if let Ok(tz) = query.parse::<Tz>() {
+        return Ok(Zone { label: tz.name().replace('_', " "), timezone: tz.name().to_string(), coords: None });
+    }
+    let place = fetch_places(user, query, lang, prefer)
+        .await
+        .ok()
+        .and_then(|places| places.into_iter().next())
+        .ok_or_else(|| anyhow!("Place not found: {query}"))?;
+    let timezone = if place.timezone.is_empty() {
+        crate::weather::fetch_timezone(place.lat, place.lon).await.context("no time zone for this place")?
+    } else {
+        place.timezone
+    };
+    timezone.parse::<Tz>().map_err(|_| anyhow!("Unknown time zone {timezone}"))?;
+    Ok(Zone { label: short_label(&place.label), timezone, coords: Some((place.lat, place.lon)) })
+}
+
+/// What a pinned zone shows right now.
+pub struct Reading {
+    pub local: DateTime<Tz>,
+    /// Seconds ahead of this computer's zone (negative = behind).
+    pub ahead: i64,
+    /// Days ahead of this computer's date: -1, 0 or 1.
+    pub day_shift: i64,
+    pub daytime: bool,
+}
+
+pub fn read_zone(zone: &Zone, now: &DateTime<Utc>) -> Option<Reading> {
+    let tz: Tz = zone.timezone.parse().ok()?;
+    let local = tz.from_utc_datetime(&now.naive_utc());
+    let here = now.with_timezone(&chrono::Local);
+    let ahead = i64::from(local.offset().fix().local_minus_utc() - here.offset().fix().local_minus_utc());
+    let day_shift = (local.date_naive() - here.date_naive()).num_days();
+    let daytime = match zone.coords {
+        Some((lat, lon)) => sun_up(lat, lon, now),
+        None => (7..19).contains(&local.hour()),
+    };
+    Some(Reading { local, ahead, day_shift, daytime })
+}
+
+/// "+3h", "-5:30h", "" for the same offset as here.
+pub fn offset_label(ahead: i64) -> String {
+    if ahead == 0 {
+        return String::new();
+    }
+    let sign = if ahead > 0 { '+' } else { '-' };
+    let (h, m) = (ahead.abs() / 3600, ahead.abs() / 60 % 60);
+    if m == 0 { format!("{sign}{h}h") } else { format!("{sign}{h}:{m:02}h") }
+}
+
+/// Whether the sun is above the horizon at `lat`/`lon` (NOAA's approximation,
+/// good to a few minutes around sunrise and sunset).
+fn sun_up(lat: f64, lon: f64, now: &DateTime<Utc>) -> bool {
+    use std::f64::consts::PI;
+    let hours = f64::from(now.hour()) + f64::from(now.minute()) / 60.0;
+    let g = 2.0 * PI / 365.0 * (f64::from(now.ordinal()) - 1.0 + (hours - 12.0) / 24.0);
+    let decl = 0.006918 - 0.399912 * g.cos() + 0.070257 * g.sin() - 0.006758 * (2.0 * g).cos()
+        + 0.000907 * (2.0 * g).sin()
+        - 0.002697 * (3.0 * g).cos()
+        + 0.00148 * (3.0 * g).sin();
+    let eqtime = 229.18
+        * (0.000075 + 0.001868 * g.cos() - 0.032077 * g.sin() - 0.014615 * (2.0 * g).cos() - 0.040849 * (2.0 * g).sin());
+    let hour_angle = ((hours * 60.0 + eqtime + 4.0 * lon) / 4.0 - 180.0).to_radians();
+    let lat = lat.to_radians();
+    let elevation = (lat.sin() * decl.sin() + lat.cos() * decl.cos() * hour_angle.cos()).asin();
+    // refraction and the sun's radius: it "rises" at -0.833°
+    elevation > (-0.833f64).to_radians()
+}
@@ -0,0 +1,69 @@
+#!/usr/bin/env python3
+"""Rebuild cities.tsv (the offline gazetteer, see src/gazetteer.rs) from GeoNames.
+
+Download and unzip these from https://download.geonames.org/export/dump/ into
+one folder:
+
+    cities5000.txt          (from cities5000.zip: every place over 5000 people)
+    admin1CodesASCII.txt    (region names)
+    countryInfo.txt         (country names)
+
+then run
+
+    python3 tools/cities.py <folder> [count] > cities.tsv
+
+`count` (default 50000) keeps the largest places. GeoNames data is CC BY 4.0.
+"""
+
+import csv
+import sys
+
+HEADER = """\
+# Offline gazetteer (see src/gazetteer.rs): name, region, country, latitude, longitude, country code.
+# Largest first, so the first match for a name is the likeliest. Columns are tab-separated.
+# Built by tools/cities.py from GeoNames (https://www.geonames.org, CC BY 4.0).
+"""
+
+
+def rows(path):
+    with open(path, encoding="utf-8", newline="") as f:
+        for row in csv.reader(f, delimiter="\t", quoting=csv.QUOTE_NONE):
+            if row and not row[0].startswith("#"):
+                yield row
+
+
+def clean(s):
+    return s.replace("\t", " ").strip()
+
+
+def main():
+    if len(sys.argv) not in (2, 3):
+        sys.exit(__doc__)
+    folder = sys.argv[1].rstrip("/")
+    count = int(sys.argv[2]) if len(sys.argv) == 3 else 50000
+
+    countries = {r[0]: r[4] for r in rows(f"{folder}/countryInfo.txt")}
+    regions = {r[0]: r[1] for r in rows(f"{folder}/admin1CodesASCII.txt")}
+
+    cities = []
+    for r in rows(f"{folder}/cities5000.txt"):
+        name, lat, lon, country, admin1, population = r[1], r[4], r[5], r[8], r[10], r[14]
+        cities.append((
+            int(population or 0),
+            clean(name),
+            clean(regions.get(f"{country}.{admin1}", "")),
+            clean(countries.get(country, country)),
+            float(lat),
+            float(lon),
+            country,
+        ))
+    cities.sort(key=lambda c: -c[0])
+
+    out = sys.stdout
+    out.write(HEADER)
+    for _, name, region, country, lat, lon, code in cities[:count]:
+        out.write(f"{name}\t{region}\t{country}\t{lat:.4f}\t{lon:.4f}\t{code}\n")
+
+
+if __name__ == "__main__":
+    main()